@@ -0,0 +1,170 @@
+// __awaiter
+export function awaiter(thisArg, _arguments, P, generator) {
+  function adopt(value) {
+    return value instanceof P
+      ? value
+      : new P(function(resolve) {
+          resolve(value);
+        });
+  }
+  return new (P || (P = Promise))(function(resolve, reject) {
+    function fulfilled(value) {
+      try {
+        step(generator.next(value));
+      } catch (e) {
+        reject(e);
+      }
+    }
+    function rejected(value) {
+      try {
+        step(generator['throw'](value));
+      } catch (e) {
+        reject(e);
+      }
+    }
+    function step(result) {
+      result.done ? resolve(result.value) : adopt(result.value).then(fulfilled, rejected);
+    }
+    step((generator = generator.apply(thisArg, _arguments || [])).next());
+  });
+}
+// __rest
+export function rest(s, e) {
+  var t = {};
+  for (var p in s) if (Object.prototype.hasOwnProperty.call(s, p) && e.indexOf(p) < 0) t[p] = s[p];
+  if (s != null && typeof Object.getOwnPropertySymbols === 'function')
+    for (var i = 0, p = Object.getOwnPropertySymbols(s); i < p.length; i++) {
+      if (e.indexOf(p[i]) < 0 && Object.prototype.propertyIsEnumerable.call(s, p[i])) t[p[i]] = s[p[i]];
+    }
+  return t;
+}
+// __classPrivateFieldGet
+export function classPrivateFieldGet(receiver, state, kind, f) {
+  if (kind === 'a' && !f) throw new TypeError('Private accessor was defined without a getter');
+  if (typeof state === 'function' ? receiver !== state || !f : !state.has(receiver))
+    throw new TypeError('Cannot read private member from an object whose class did not declare it');
+  return kind === 'm' ? f : kind === 'a' ? f.call(receiver) : f ? f.value : state.get(receiver);
+}
+// __classPrivateFieldSet
+export function classPrivateFieldSet(receiver, state, value, kind, f) {
+  if (kind === 'm') throw new TypeError('Private method is not writable');
+  if (kind === 'a' && !f) throw new TypeError('Private accessor was defined without a setter');
+  if (typeof state === 'function' ? receiver !== state || !f : !state.has(receiver))
+    throw new TypeError('Cannot write private member to an object whose class did not declare it');
+  return kind === 'a' ? f.call(receiver, value) : f ? (f.value = value) : state.set(receiver, value), value;
+}
+// __classPrivateFieldIn
+export function classPrivateFieldIn(state, receiver) {
+  if (receiver === null || (typeof receiver !== 'object' && typeof receiver !== 'function'))
+    throw new TypeError("Cannot use 'in' operator on non-object");
+  return typeof state === 'function' ? receiver === state : state.has(receiver);
+}
+// __await
+export function awaitValue(v) {
+  return this instanceof awaitValue ? ((this.v = v), this) : new awaitValue(v);
+}
+// __asyncGenerator
+export function asyncGenerator(thisArg, _arguments, generator) {
+  if (!Symbol.asyncIterator) throw new TypeError('Symbol.asyncIterator is not defined.');
+  var g = generator.apply(thisArg, _arguments || []),
+    i,
+    q = [];
+  return (
+    (i = Object.create((typeof AsyncIterator === 'function' ? AsyncIterator : Object).prototype)),
+    verb('next'),
+    verb('throw'),
+    verb('return', awaitReturn),
+    (i[Symbol.asyncIterator] = function() {
+      return this;
+    }),
+    i
+  );
+  function awaitReturn(f) {
+    return function(v) {
+      return Promise.resolve(v).then(f, reject);
+    };
+  }
+  function verb(n, f) {
+    if (g[n]) {
+      i[n] = function(v) {
+        return new Promise(function(a, b) {
+          q.push([n, v, a, b]) > 1 || resume(n, v);
+        });
+      };
+      if (f) i[n] = f(i[n]);
+    }
+  }
+  function resume(n, v) {
+    try {
+      step(g[n](v));
+    } catch (e) {
+      settle(q[0][3], e);
+    }
+  }
+  function step(r) {
+    r.value instanceof awaitValue ? Promise.resolve(r.value.v).then(fulfill, reject) : settle(q[0][2], r);
+  }
+  function fulfill(value) {
+    resume('next', value);
+  }
+  function reject(value) {
+    resume('throw', value);
+  }
+  function settle(f, v) {
+    if ((f(v), q.shift(), q.length)) resume(q[0][0], q[0][1]);
+  }
+}
+// __asyncDelegator
+export function asyncDelegator(o) {
+  var i, p;
+  return (
+    (i = {}),
+    verb('next'),
+    verb('throw', function(e) {
+      throw e;
+    }),
+    verb('return'),
+    (i[Symbol.iterator] = function() {
+      return this;
+    }),
+    i
+  );
+  function verb(n, f) {
+    i[n] = o[n]
+      ? function(v) {
+          return (p = !p) ? { value: awaitValue(o[n](v)), done: false } : f ? f(v) : v;
+        }
+      : f;
+  }
+}
+// __asyncValues
+export function asyncValues(o) {
+  if (!Symbol.asyncIterator) throw new TypeError('Symbol.asyncIterator is not defined.');
+  var m = o[Symbol.asyncIterator],
+    i;
+  return m
+    ? m.call(o)
+    : ((o = o[Symbol.iterator]()),
+      (i = {}),
+      verb('next'),
+      verb('throw'),
+      verb('return'),
+      (i[Symbol.asyncIterator] = function() {
+        return this;
+      }),
+      i);
+  function verb(n) {
+    i[n] =
+      o[n] &&
+      function(v) {
+        return new Promise(function(resolve, reject) {
+          (v = o[n](v)), settle(resolve, reject, v.done, v.value);
+        });
+      };
+  }
+  function settle(resolve, reject, d, v) {
+    Promise.resolve(v).then(function(v) {
+      resolve({ value: v, done: d });
+    }, reject);
+  }
+}
@@ -131,21 +131,21 @@ fn bench_real_world(c: &mut Criterion) {
     
     group.bench_function("small_file_50_lines", |b| {
         b.iter(|| {
-            let imports = extract_imports_internal(black_box(&small_file));
+            let _imports = extract_imports_internal(black_box(&small_file));
             transform_internal(black_box(&small_file), "small.tsx", &options)
         })
     });
     
     group.bench_function("medium_file_500_lines", |b| {
         b.iter(|| {
-            let imports = extract_imports_internal(black_box(&medium_file));
+            let _imports = extract_imports_internal(black_box(&medium_file));
             transform_internal(black_box(&medium_file), "medium.tsx", &options)
         })
     });
     
     group.bench_function("large_file_2500_lines", |b| {
         b.iter(|| {
-            let imports = extract_imports_internal(black_box(&large_file));
+            let _imports = extract_imports_internal(black_box(&large_file));
             transform_internal(black_box(&large_file), "large.tsx", &options)
         })
     });
@@ -0,0 +1,2126 @@
+//! Syntax tree for JavaScript, TypeScript and JSX
+//!
+//! Produced by `js_parser`. Every node keeps the byte span it was parsed from,
+//! so consumers rewrite the original text in place instead of printing the
+//! tree back out. TypeScript types are kept only as far as the transformer
+//! needs them; everything else about a type is its span.
+
+use crate::lexer::Comment;
+
+/// Byte range in the source
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    #[inline]
+    pub fn new(start: usize, end: usize) -> Self {
+        Self { start, end }
+    }
+
+    #[inline]
+    pub fn text<'a>(&self, source: &'a str) -> &'a str {
+        &source[self.start..self.end]
+    }
+
+    #[inline]
+    pub fn contains(&self, other: Span) -> bool {
+        self.start <= other.start && other.end <= self.end
+    }
+}
+
+/// A syntax error, with the parser recovering after it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub code: ErrorCode,
+    pub message: String,
+    pub span: Span,
+}
+
+/// Broad class of a syntax error, stable across message wording changes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorCode {
+    /// A token the grammar doesn't allow at this point
+    UnexpectedToken,
+    /// A string, template, regex, comment or JSX element that never ends
+    Unterminated,
+    /// A character that can't start any token
+    InvalidCharacter,
+    /// Valid tokens in an invalid arrangement, like `1 = x`
+    InvalidSyntax,
+    /// Nesting beyond what the parser handles
+    TooDeep,
+}
+
+impl ErrorCode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ErrorCode::UnexpectedToken => "unexpected-token",
+            ErrorCode::Unterminated => "unterminated",
+            ErrorCode::InvalidCharacter => "invalid-character",
+            ErrorCode::InvalidSyntax => "invalid-syntax",
+            ErrorCode::TooDeep => "too-deep",
+        }
+    }
+}
+
+/// A parsed source file
+#[derive(Debug, Clone)]
+pub struct Program {
+    pub span: Span,
+    /// `#!` line
+    pub hashbang: Option<Span>,
+    pub body: Vec<Stmt>,
+    /// All comments, in source order
+    pub comments: Vec<Comment>,
+    /// Syntax errors, in source order
+    pub errors: Vec<ParseError>,
+}
+
+// ---------------------------------------------------------------------------
+// Statements
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Clone)]
+pub enum Stmt {
+    Expr(ExprStmt),
+    Block(BlockStmt),
+    Empty(Span),
+    Debugger(Span),
+    With(WithStmt),
+    Return(ReturnStmt),
+    Labeled(LabeledStmt),
+    Break(JumpStmt),
+    Continue(JumpStmt),
+    If(IfStmt),
+    Switch(SwitchStmt),
+    Throw(ThrowStmt),
+    Try(TryStmt),
+    While(WhileStmt),
+    DoWhile(DoWhileStmt),
+    For(ForStmt),
+    ForIn(ForInStmt),
+    ForOf(ForOfStmt),
+    Var(VarDecl),
+    Function(Function),
+    Class(Class),
+    Import(ImportDecl),
+    ExportDecl(ExportDecl),
+    ExportDefault(ExportDefault),
+    ExportNamed(ExportNamed),
+    ExportAll(ExportAll),
+    TsInterface(TsInterfaceDecl),
+    TsTypeAlias(TsTypeAliasDecl),
+    TsEnum(TsEnumDecl),
+    TsModule(TsModuleDecl),
+    TsImportEquals(TsImportEqualsDecl),
+    /// `export = expr`
+    TsExportAssignment(TsExportAssignment),
+    /// `export as namespace Name`
+    TsNamespaceExport(TsNamespaceExport),
+}
+
+impl Stmt {
+    pub fn span(&self) -> Span {
+        match self {
+            Stmt::Expr(s) => s.span,
+            Stmt::Block(s) => s.span,
+            Stmt::Empty(span) | Stmt::Debugger(span) => *span,
+            Stmt::With(s) => s.span,
+            Stmt::Return(s) => s.span,
+            Stmt::Labeled(s) => s.span,
+            Stmt::Break(s) | Stmt::Continue(s) => s.span,
+            Stmt::If(s) => s.span,
+            Stmt::Switch(s) => s.span,
+            Stmt::Throw(s) => s.span,
+            Stmt::Try(s) => s.span,
+            Stmt::While(s) => s.span,
+            Stmt::DoWhile(s) => s.span,
+            Stmt::For(s) => s.span,
+            Stmt::ForIn(s) => s.span,
+            Stmt::ForOf(s) => s.span,
+            Stmt::Var(s) => s.span,
+            Stmt::Function(s) => s.span,
+            Stmt::Class(s) => s.span,
+            Stmt::Import(s) => s.span,
+            Stmt::ExportDecl(s) => s.span,
+            Stmt::ExportDefault(s) => s.span,
+            Stmt::ExportNamed(s) => s.span,
+            Stmt::ExportAll(s) => s.span,
+            Stmt::TsInterface(s) => s.span,
+            Stmt::TsTypeAlias(s) => s.span,
+            Stmt::TsEnum(s) => s.span,
+            Stmt::TsModule(s) => s.span,
+            Stmt::TsImportEquals(s) => s.span,
+            Stmt::TsExportAssignment(s) => s.span,
+            Stmt::TsNamespaceExport(s) => s.span,
+        }
+    }
+
+    /// Is this a declaration that only exists at the type level?
+    pub fn is_type_only(&self) -> bool {
+        match self {
+            Stmt::TsInterface(_) | Stmt::TsTypeAlias(_) | Stmt::TsNamespaceExport(_) => true,
+            Stmt::Var(d) => d.declare,
+            Stmt::Function(f) => f.declare || f.body.is_none(),
+            Stmt::Class(c) => c.declare,
+            Stmt::TsEnum(e) => e.declare,
+            Stmt::TsModule(m) => m.declare || m.body.as_ref().is_some_and(|b| b.body.iter().all(Stmt::is_type_only)),
+            Stmt::TsImportEquals(d) => d.type_only,
+            Stmt::Import(d) => d.type_only,
+            Stmt::ExportDecl(d) => d.decl.is_type_only(),
+            Stmt::ExportNamed(d) => d.type_only,
+            Stmt::ExportAll(d) => d.type_only,
+            Stmt::ExportDefault(d) => match &d.decl {
+                DefaultDecl::TsInterface(_) => true,
+                DefaultDecl::Function(f) => f.body.is_none(),
+                _ => false,
+            },
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ExprStmt {
+    pub span: Span,
+    pub expr: Expr,
+}
+
+#[derive(Debug, Clone)]
+pub struct BlockStmt {
+    pub span: Span,
+    pub stmts: Vec<Stmt>,
+}
+
+#[derive(Debug, Clone)]
+pub struct WithStmt {
+    pub span: Span,
+    pub object: Expr,
+    pub body: Box<Stmt>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ReturnStmt {
+    pub span: Span,
+    pub arg: Option<Expr>,
+}
+
+#[derive(Debug, Clone)]
+pub struct LabeledStmt {
+    pub span: Span,
+    pub label: Ident,
+    pub body: Box<Stmt>,
+}
+
+/// `break` / `continue`
+#[derive(Debug, Clone)]
+pub struct JumpStmt {
+    pub span: Span,
+    pub label: Option<Ident>,
+}
+
+#[derive(Debug, Clone)]
+pub struct IfStmt {
+    pub span: Span,
+    pub test: Expr,
+    pub cons: Box<Stmt>,
+    pub alt: Option<Box<Stmt>>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SwitchStmt {
+    pub span: Span,
+    pub discriminant: Expr,
+    pub cases: Vec<SwitchCase>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SwitchCase {
+    pub span: Span,
+    /// `None` for `default:`
+    pub test: Option<Expr>,
+    pub cons: Vec<Stmt>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ThrowStmt {
+    pub span: Span,
+    pub arg: Expr,
+}
+
+#[derive(Debug, Clone)]
+pub struct TryStmt {
+    pub span: Span,
+    pub block: BlockStmt,
+    pub handler: Option<CatchClause>,
+    pub finalizer: Option<BlockStmt>,
+}
+
+#[derive(Debug, Clone)]
+pub struct CatchClause {
+    pub span: Span,
+    pub param: Option<Pat>,
+    pub body: BlockStmt,
+}
+
+#[derive(Debug, Clone)]
+pub struct WhileStmt {
+    pub span: Span,
+    pub test: Expr,
+    pub body: Box<Stmt>,
+}
+
+#[derive(Debug, Clone)]
+pub struct DoWhileStmt {
+    pub span: Span,
+    pub body: Box<Stmt>,
+    pub test: Expr,
+}
+
+#[derive(Debug, Clone)]
+pub struct ForStmt {
+    pub span: Span,
+    pub init: Option<ForInit>,
+    pub test: Option<Expr>,
+    pub update: Option<Expr>,
+    pub body: Box<Stmt>,
+}
+
+#[derive(Debug, Clone)]
+pub enum ForInit {
+    Var(VarDecl),
+    Expr(Expr),
+}
+
+#[derive(Debug, Clone)]
+pub struct ForInStmt {
+    pub span: Span,
+    pub left: ForHead,
+    pub right: Expr,
+    pub body: Box<Stmt>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ForOfStmt {
+    pub span: Span,
+    pub is_await: bool,
+    pub left: ForHead,
+    pub right: Expr,
+    pub body: Box<Stmt>,
+}
+
+/// Left side of `for (... in/of ...)`
+#[derive(Debug, Clone)]
+pub enum ForHead {
+    Var(VarDecl),
+    Pat(Pat),
+}
+
+// ---------------------------------------------------------------------------
+// Declarations
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VarKind {
+    Var,
+    Let,
+    Const,
+    Using,
+    AwaitUsing,
+}
+
+#[derive(Debug, Clone)]
+pub struct VarDecl {
+    pub span: Span,
+    pub kind: VarKind,
+    /// TypeScript `declare`
+    pub declare: bool,
+    pub decls: Vec<VarDeclarator>,
+}
+
+#[derive(Debug, Clone)]
+pub struct VarDeclarator {
+    pub span: Span,
+    pub name: Pat,
+    pub init: Option<Expr>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Function {
+    pub span: Span,
+    pub id: Option<Ident>,
+    pub is_async: bool,
+    pub is_generator: bool,
+    /// TypeScript `declare`
+    pub declare: bool,
+    pub type_params: Option<TsTypeParams>,
+    /// Span of the parameter list, parentheses included
+    pub params_span: Span,
+    pub params: Vec<Param>,
+    pub return_type: Option<TsTypeAnn>,
+    /// `None` for TypeScript overload signatures and ambient functions
+    pub body: Option<BlockStmt>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Param {
+    pub span: Span,
+    pub decorators: Vec<Decorator>,
+    /// TypeScript parameter property modifiers (`private`, `readonly`, ...)
+    pub modifiers: Vec<Modifier>,
+    pub pat: Pat,
+}
+
+#[derive(Debug, Clone)]
+pub struct Decorator {
+    pub span: Span,
+    pub expr: Expr,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModifierKind {
+    Public,
+    Private,
+    Protected,
+    Readonly,
+    Static,
+    Abstract,
+    Override,
+    Declare,
+    Accessor,
+}
+
+impl ModifierKind {
+    pub fn from_keyword(word: &str) -> Option<Self> {
+        Some(match word {
+            "public" => Self::Public,
+            "private" => Self::Private,
+            "protected" => Self::Protected,
+            "readonly" => Self::Readonly,
+            "static" => Self::Static,
+            "abstract" => Self::Abstract,
+            "override" => Self::Override,
+            "declare" => Self::Declare,
+            "accessor" => Self::Accessor,
+            _ => return None,
+        })
+    }
+
+    /// Does this modifier exist only in TypeScript?
+    pub fn is_typescript(&self) -> bool {
+        !matches!(self, Self::Static | Self::Accessor)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Modifier {
+    pub span: Span,
+    pub kind: ModifierKind,
+}
+
+#[derive(Debug, Clone)]
+pub struct Class {
+    pub span: Span,
+    pub decorators: Vec<Decorator>,
+    /// `abstract`, `declare` and friends in front of `class`
+    pub modifiers: Vec<Modifier>,
+    pub id: Option<Ident>,
+    pub type_params: Option<TsTypeParams>,
+    pub super_class: Option<Box<Expr>>,
+    pub super_type_args: Option<TsTypeArgs>,
+    /// `implements A, B` clause
+    pub implements: Option<Span>,
+    /// Span of the body, braces included
+    pub body_span: Span,
+    pub body: Vec<ClassMember>,
+    /// TypeScript `declare class`
+    pub declare: bool,
+    pub is_abstract: bool,
+}
+
+#[derive(Debug, Clone)]
+pub enum ClassMember {
+    Method(ClassMethod),
+    Property(ClassProperty),
+    StaticBlock(StaticBlock),
+    /// TypeScript `[key: string]: T;`
+    IndexSignature(Span),
+    /// Stray `;`
+    Empty(Span),
+}
+
+impl ClassMember {
+    pub fn span(&self) -> Span {
+        match self {
+            ClassMember::Method(m) => m.span,
+            ClassMember::Property(p) => p.span,
+            ClassMember::StaticBlock(b) => b.span,
+            ClassMember::IndexSignature(span) | ClassMember::Empty(span) => *span,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MethodKind {
+    Method,
+    Getter,
+    Setter,
+    Constructor,
+}
+
+#[derive(Debug, Clone)]
+pub struct ClassMethod {
+    pub span: Span,
+    pub decorators: Vec<Decorator>,
+    pub modifiers: Vec<Modifier>,
+    pub is_static: bool,
+    pub kind: MethodKind,
+    pub key: PropKey,
+    /// `?` after the name
+    pub optional: Option<Span>,
+    pub function: Function,
+}
+
+#[derive(Debug, Clone)]
+pub struct ClassProperty {
+    pub span: Span,
+    pub decorators: Vec<Decorator>,
+    pub modifiers: Vec<Modifier>,
+    pub is_static: bool,
+    pub key: PropKey,
+    /// `?` after the name
+    pub optional: Option<Span>,
+    /// `!` after the name
+    pub definite: Option<Span>,
+    pub type_ann: Option<TsTypeAnn>,
+    pub value: Option<Expr>,
+}
+
+impl ClassProperty {
+    pub fn has_modifier(&self, kind: ModifierKind) -> bool {
+        self.modifiers.iter().any(|m| m.kind == kind)
+    }
+}
+
+impl ClassMethod {
+    pub fn has_modifier(&self, kind: ModifierKind) -> bool {
+        self.modifiers.iter().any(|m| m.kind == kind)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct StaticBlock {
+    pub span: Span,
+    pub body: BlockStmt,
+}
+
+/// Property name in objects, classes and patterns
+#[derive(Debug, Clone)]
+pub enum PropKey {
+    Ident(Ident),
+    Str(Lit),
+    Num(Lit),
+    BigInt(Lit),
+    Computed(ComputedKey),
+    Private(Ident),
+}
+
+impl PropKey {
+    pub fn span(&self) -> Span {
+        match self {
+            PropKey::Ident(i) | PropKey::Private(i) => i.span,
+            PropKey::Str(l) | PropKey::Num(l) | PropKey::BigInt(l) => l.span,
+            PropKey::Computed(c) => c.span,
+        }
+    }
+
+    /// Static name of the key (`None` for computed keys)
+    pub fn static_name(&self) -> Option<String> {
+        match self {
+            PropKey::Ident(i) => Some(i.name.clone()),
+            PropKey::Str(l) | PropKey::Num(l) | PropKey::BigInt(l) => l.value.as_key(),
+            PropKey::Private(_) | PropKey::Computed(_) => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ComputedKey {
+    /// Brackets included
+    pub span: Span,
+    pub expr: Box<Expr>,
+}
+
+// ---------------------------------------------------------------------------
+// Modules
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Clone)]
+pub struct ImportDecl {
+    pub span: Span,
+    /// `import type ...`
+    pub type_only: bool,
+    pub specifiers: Vec<ImportSpecifier>,
+    pub source: Lit,
+    pub attributes: Option<ImportAttributes>,
+}
+
+#[derive(Debug, Clone)]
+pub enum ImportSpecifier {
+    /// `import a from`
+    Default(Ident),
+    /// `import * as ns from`
+    Namespace { span: Span, local: Ident },
+    /// `import { a as b } from`
+    Named {
+        span: Span,
+        imported: ModuleExportName,
+        local: Ident,
+        type_only: bool,
+    },
+}
+
+impl ImportSpecifier {
+    pub fn span(&self) -> Span {
+        match self {
+            ImportSpecifier::Default(local) => local.span,
+            ImportSpecifier::Namespace { span, .. } | ImportSpecifier::Named { span, .. } => *span,
+        }
+    }
+
+    pub fn local(&self) -> &Ident {
+        match self {
+            ImportSpecifier::Default(local)
+            | ImportSpecifier::Namespace { local, .. }
+            | ImportSpecifier::Named { local, .. } => local,
+        }
+    }
+}
+
+/// Imported or exported name: an identifier or a string (`export { a as "b-c" }`)
+#[derive(Debug, Clone)]
+pub enum ModuleExportName {
+    Ident(Ident),
+    Str(Lit),
+}
+
+impl ModuleExportName {
+    pub fn span(&self) -> Span {
+        match self {
+            ModuleExportName::Ident(i) => i.span,
+            ModuleExportName::Str(l) => l.span,
+        }
+    }
+
+    pub fn name(&self) -> String {
+        match self {
+            ModuleExportName::Ident(i) => i.name.clone(),
+            ModuleExportName::Str(l) => l.value.as_key().unwrap_or_default(),
+        }
+    }
+}
+
+/// `with { type: "json" }` / `assert { type: "json" }`
+#[derive(Debug, Clone)]
+pub struct ImportAttributes {
+    pub span: Span,
+    /// Uses the deprecated `assert` keyword
+    pub is_assert: bool,
+    pub entries: Vec<ImportAttribute>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ImportAttribute {
+    pub span: Span,
+    pub key: ModuleExportName,
+    pub value: Lit,
+}
+
+/// `export var/let/const/function/class/...`
+#[derive(Debug, Clone)]
+pub struct ExportDecl {
+    pub span: Span,
+    pub decl: Box<Stmt>,
+}
+
+/// `export default ...`
+#[derive(Debug, Clone)]
+pub struct ExportDefault {
+    pub span: Span,
+    pub decl: DefaultDecl,
+}
+
+#[derive(Debug, Clone)]
+pub enum DefaultDecl {
+    Function(Function),
+    Class(Class),
+    TsInterface(TsInterfaceDecl),
+    Expr(Expr),
+}
+
+/// `export { a as b }` / `export { a } from "x"`
+#[derive(Debug, Clone)]
+pub struct ExportNamed {
+    pub span: Span,
+    pub type_only: bool,
+    pub specifiers: Vec<ExportSpecifier>,
+    pub source: Option<Lit>,
+    pub attributes: Option<ImportAttributes>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ExportSpecifier {
+    pub span: Span,
+    pub local: ModuleExportName,
+    pub exported: Option<ModuleExportName>,
+    pub type_only: bool,
+}
+
+impl ExportSpecifier {
+    /// Name other modules import
+    pub fn exported_name(&self) -> String {
+        self.exported.as_ref().unwrap_or(&self.local).name()
+    }
+}
+
+/// `export * from "x"` / `export * as ns from "x"`
+#[derive(Debug, Clone)]
+pub struct ExportAll {
+    pub span: Span,
+    pub type_only: bool,
+    pub exported: Option<ModuleExportName>,
+    pub source: Lit,
+    pub attributes: Option<ImportAttributes>,
+}
+
+// ---------------------------------------------------------------------------
+// TypeScript declarations
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Clone)]
+pub struct TsInterfaceDecl {
+    pub span: Span,
+    pub id: Ident,
+    pub declare: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct TsTypeAliasDecl {
+    pub span: Span,
+    pub id: Ident,
+    pub declare: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct TsEnumDecl {
+    pub span: Span,
+    pub id: Ident,
+    pub is_const: bool,
+    pub declare: bool,
+    pub members: Vec<TsEnumMember>,
+}
+
+#[derive(Debug, Clone)]
+pub struct TsEnumMember {
+    pub span: Span,
+    /// Identifier or string literal
+    pub id: PropKey,
+    pub init: Option<Expr>,
+}
+
+#[derive(Debug, Clone)]
+pub struct TsModuleDecl {
+    pub span: Span,
+    pub declare: bool,
+    pub name: TsModuleName,
+    /// `None` for `declare module "x";`
+    pub body: Option<TsModuleBlock>,
+}
+
+#[derive(Debug, Clone)]
+pub enum TsModuleName {
+    /// `namespace A.B.C`
+    Path(Vec<Ident>),
+    /// `declare module "x"`
+    Str(Lit),
+    /// `declare global`
+    Global(Span),
+}
+
+#[derive(Debug, Clone)]
+pub struct TsModuleBlock {
+    pub span: Span,
+    pub body: Vec<Stmt>,
+}
+
+#[derive(Debug, Clone)]
+pub struct TsImportEqualsDecl {
+    pub span: Span,
+    pub is_export: bool,
+    pub type_only: bool,
+    pub id: Ident,
+    pub module_ref: TsModuleRef,
+}
+
+#[derive(Debug, Clone)]
+pub enum TsModuleRef {
+    /// `require("x")`
+    Require(Lit),
+    /// `A.B.C`
+    Entity(Vec<Ident>),
+}
+
+#[derive(Debug, Clone)]
+pub struct TsExportAssignment {
+    pub span: Span,
+    pub expr: Expr,
+}
+
+#[derive(Debug, Clone)]
+pub struct TsNamespaceExport {
+    pub span: Span,
+    pub id: Ident,
+}
+
+// ---------------------------------------------------------------------------
+// TypeScript types
+// ---------------------------------------------------------------------------
+
+/// `: Type`, colon included in the span
+#[derive(Debug, Clone)]
+pub struct TsTypeAnn {
+    pub span: Span,
+    pub ty: TsType,
+}
+
+/// `<T, U extends X = Y>`
+#[derive(Debug, Clone)]
+pub struct TsTypeParams {
+    pub span: Span,
+    pub params: Vec<Ident>,
+}
+
+/// `<A, B>`
+#[derive(Debug, Clone)]
+pub struct TsTypeArgs {
+    pub span: Span,
+    pub args: Vec<TsType>,
+}
+
+#[derive(Debug, Clone)]
+pub struct TsType {
+    pub span: Span,
+    pub kind: TsTypeKind,
+}
+
+#[derive(Debug, Clone)]
+pub enum TsTypeKind {
+    /// `string`, `number`, `any`, `void`, ...
+    Keyword(String),
+    This,
+    /// `A.B<C>`
+    Reference(Vec<Ident>),
+    /// `"a"`, `1`, `true`, `` `a${B}` ``
+    Literal(LitKind),
+    Array(Box<TsType>),
+    Tuple,
+    Union(Vec<TsType>),
+    Intersection(Vec<TsType>),
+    Function,
+    Constructor,
+    /// `{ a: T }` and mapped types
+    TypeLiteral,
+    Paren(Box<TsType>),
+    /// `typeof x`
+    Query,
+    /// `as const`
+    Const,
+    /// Conditional, indexed access, `keyof`, predicates, ...
+    Other,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LitKind {
+    Str,
+    Num,
+    BigInt,
+    Bool,
+    Template,
+}
+
+// ---------------------------------------------------------------------------
+// Expressions
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Clone)]
+pub struct Ident {
+    pub span: Span,
+    /// Name with escapes decoded
+    pub name: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct Lit {
+    pub span: Span,
+    pub value: LitValue,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LitValue {
+    Str(String),
+    Num(f64),
+    BigInt(String),
+    Bool(bool),
+    Null,
+    Regex { pattern: String, flags: String },
+}
+
+impl LitValue {
+    /// The property key this literal names
+    pub fn as_key(&self) -> Option<String> {
+        match self {
+            LitValue::Str(s) => Some(s.clone()),
+            LitValue::Num(n) if n.fract() == 0.0 && n.abs() < 1e21 => Some(format!("{}", n)),
+            LitValue::Num(n) => Some(n.to_string()),
+            LitValue::BigInt(b) => Some(b.trim_end_matches('n').to_string()),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            LitValue::Str(s) => Some(s),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Expr {
+    Ident(Ident),
+    /// `#x` in `#x in obj`
+    PrivateName(Ident),
+    This(Span),
+    Super(Span),
+    Lit(Lit),
+    Template(TemplateLit),
+    TaggedTemplate(Box<TaggedTemplate>),
+    Array(ArrayLit),
+    Object(ObjectLit),
+    Function(Box<Function>),
+    Arrow(Box<ArrowFunction>),
+    Class(Box<Class>),
+    Unary(Box<UnaryExpr>),
+    Update(Box<UpdateExpr>),
+    Binary(Box<BinaryExpr>),
+    Assign(Box<AssignExpr>),
+    Cond(Box<CondExpr>),
+    Call(Box<CallExpr>),
+    New(Box<NewExpr>),
+    Member(Box<MemberExpr>),
+    /// Outermost node of an optional chain (`a?.b.c`)
+    OptChain(Box<OptChainExpr>),
+    Seq(SeqExpr),
+    Paren(Box<ParenExpr>),
+    Yield(Box<YieldExpr>),
+    Await(Box<AwaitExpr>),
+    /// `new.target` / `import.meta`
+    MetaProp(MetaProp),
+    /// `import("x")`
+    Import(Box<ImportCall>),
+    JsxElement(Box<JsxElement>),
+    JsxFragment(Box<JsxFragment>),
+    TsAs(Box<TsAsExpr>),
+    TsSatisfies(Box<TsAsExpr>),
+    TsNonNull(Box<TsNonNullExpr>),
+    /// `<T>expr`
+    TsTypeAssertion(Box<TsTypeAssertion>),
+    /// `f<T>` without a call
+    TsInstantiation(Box<TsInstantiation>),
+    /// Placeholder after a syntax error
+    Invalid(Span),
+}
+
+impl Expr {
+    pub fn span(&self) -> Span {
+        match self {
+            Expr::Ident(i) | Expr::PrivateName(i) => i.span,
+            Expr::This(span) | Expr::Super(span) | Expr::Invalid(span) => *span,
+            Expr::Lit(l) => l.span,
+            Expr::Template(t) => t.span,
+            Expr::TaggedTemplate(t) => t.span,
+            Expr::Array(a) => a.span,
+            Expr::Object(o) => o.span,
+            Expr::Function(f) => f.span,
+            Expr::Arrow(a) => a.span,
+            Expr::Class(c) => c.span,
+            Expr::Unary(e) => e.span,
+            Expr::Update(e) => e.span,
+            Expr::Binary(e) => e.span,
+            Expr::Assign(e) => e.span,
+            Expr::Cond(e) => e.span,
+            Expr::Call(e) => e.span,
+            Expr::New(e) => e.span,
+            Expr::Member(e) => e.span,
+            Expr::OptChain(e) => e.span,
+            Expr::Seq(e) => e.span,
+            Expr::Paren(e) => e.span,
+            Expr::Yield(e) => e.span,
+            Expr::Await(e) => e.span,
+            Expr::MetaProp(e) => e.span,
+            Expr::Import(e) => e.span,
+            Expr::JsxElement(e) => e.span,
+            Expr::JsxFragment(e) => e.span,
+            Expr::TsAs(e) | Expr::TsSatisfies(e) => e.span,
+            Expr::TsNonNull(e) => e.span,
+            Expr::TsTypeAssertion(e) => e.span,
+            Expr::TsInstantiation(e) => e.span,
+        }
+    }
+
+    /// The expression with parentheses and TypeScript-only wrappers removed
+    pub fn unwrapped(&self) -> &Expr {
+        match self {
+            Expr::Paren(e) => e.expr.unwrapped(),
+            Expr::TsAs(e) | Expr::TsSatisfies(e) => e.expr.unwrapped(),
+            Expr::TsNonNull(e) => e.expr.unwrapped(),
+            Expr::TsTypeAssertion(e) => e.expr.unwrapped(),
+            _ => self,
+        }
+    }
+
+    pub fn as_ident(&self) -> Option<&Ident> {
+        match self {
+            Expr::Ident(i) => Some(i),
+            _ => None,
+        }
+    }
+
+    /// Value of a string literal or a template without substitutions
+    pub fn as_static_string(&self, source: &str) -> Option<String> {
+        match self {
+            Expr::Lit(Lit { value: LitValue::Str(s), .. }) => Some(s.clone()),
+            Expr::Template(t) if t.exprs.is_empty() => {
+                t.quasis.first().map(|q| crate::lexer::decode_escapes(q.text(source)))
+            }
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TemplateLit {
+    pub span: Span,
+    /// Raw text of each part, delimiters excluded
+    pub quasis: Vec<Span>,
+    pub exprs: Vec<Expr>,
+}
+
+#[derive(Debug, Clone)]
+pub struct TaggedTemplate {
+    pub span: Span,
+    pub tag: Expr,
+    pub type_args: Option<TsTypeArgs>,
+    pub quasi: TemplateLit,
+}
+
+#[derive(Debug, Clone)]
+pub struct ArrayLit {
+    pub span: Span,
+    /// `None` for holes
+    pub elems: Vec<Option<ExprOrSpread>>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ExprOrSpread {
+    /// Span of `...`
+    pub spread: Option<Span>,
+    pub expr: Expr,
+}
+
+impl ExprOrSpread {
+    pub fn span(&self) -> Span {
+        let end = self.expr.span().end;
+        Span::new(self.spread.map_or(self.expr.span().start, |s| s.start), end)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ObjectLit {
+    pub span: Span,
+    pub props: Vec<Prop>,
+}
+
+#[derive(Debug, Clone)]
+pub enum Prop {
+    /// `{ a }`
+    Shorthand(Ident),
+    /// `{ a: 1 }`
+    KeyValue(KeyValueProp),
+    /// `{ a = 1 }`, only valid as a destructuring target
+    Assign(AssignProp),
+    /// `{ a() {} }`, getters and setters
+    Method(MethodProp),
+    /// `{ ...a }`
+    Spread(SpreadElement),
+}
+
+impl Prop {
+    pub fn span(&self) -> Span {
+        match self {
+            Prop::Shorthand(i) => i.span,
+            Prop::KeyValue(p) => p.span,
+            Prop::Assign(p) => p.span,
+            Prop::Method(p) => p.span,
+            Prop::Spread(p) => p.span,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct KeyValueProp {
+    pub span: Span,
+    pub key: PropKey,
+    pub value: Expr,
+}
+
+#[derive(Debug, Clone)]
+pub struct AssignProp {
+    pub span: Span,
+    pub key: Ident,
+    pub value: Expr,
+}
+
+#[derive(Debug, Clone)]
+pub struct MethodProp {
+    pub span: Span,
+    pub kind: MethodKind,
+    pub key: PropKey,
+    pub function: Function,
+}
+
+#[derive(Debug, Clone)]
+pub struct SpreadElement {
+    pub span: Span,
+    pub expr: Expr,
+}
+
+#[derive(Debug, Clone)]
+pub struct ArrowFunction {
+    pub span: Span,
+    pub is_async: bool,
+    pub type_params: Option<TsTypeParams>,
+    /// Span of the parameter list (a lone identifier or the parenthesized list)
+    pub params_span: Span,
+    pub params: Vec<Param>,
+    pub return_type: Option<TsTypeAnn>,
+    pub body: ArrowBody,
+}
+
+#[derive(Debug, Clone)]
+pub enum ArrowBody {
+    Block(BlockStmt),
+    Expr(Box<Expr>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnaryOp {
+    Minus,
+    Plus,
+    Not,
+    BitNot,
+    TypeOf,
+    Void,
+    Delete,
+}
+
+impl UnaryOp {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            UnaryOp::Minus => "-",
+            UnaryOp::Plus => "+",
+            UnaryOp::Not => "!",
+            UnaryOp::BitNot => "~",
+            UnaryOp::TypeOf => "typeof",
+            UnaryOp::Void => "void",
+            UnaryOp::Delete => "delete",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct UnaryExpr {
+    pub span: Span,
+    pub op: UnaryOp,
+    pub arg: Expr,
+}
+
+#[derive(Debug, Clone)]
+pub struct UpdateExpr {
+    pub span: Span,
+    /// `++` (as opposed to `--`)
+    pub increment: bool,
+    pub prefix: bool,
+    pub arg: Expr,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinaryOp {
+    EqEq,
+    NotEq,
+    EqEqEq,
+    NotEqEq,
+    Lt,
+    LtEq,
+    Gt,
+    GtEq,
+    LShift,
+    RShift,
+    ZeroFillRShift,
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Mod,
+    Exp,
+    BitOr,
+    BitXor,
+    BitAnd,
+    In,
+    InstanceOf,
+    LogicalOr,
+    LogicalAnd,
+    NullishCoalescing,
+}
+
+impl BinaryOp {
+    pub fn from_op(op: &str) -> Option<Self> {
+        Some(match op {
+            "==" => Self::EqEq,
+            "!=" => Self::NotEq,
+            "===" => Self::EqEqEq,
+            "!==" => Self::NotEqEq,
+            "<" => Self::Lt,
+            "<=" => Self::LtEq,
+            ">" => Self::Gt,
+            ">=" => Self::GtEq,
+            "<<" => Self::LShift,
+            ">>" => Self::RShift,
+            ">>>" => Self::ZeroFillRShift,
+            "+" => Self::Add,
+            "-" => Self::Sub,
+            "*" => Self::Mul,
+            "/" => Self::Div,
+            "%" => Self::Mod,
+            "**" => Self::Exp,
+            "|" => Self::BitOr,
+            "^" => Self::BitXor,
+            "&" => Self::BitAnd,
+            "in" => Self::In,
+            "instanceof" => Self::InstanceOf,
+            "||" => Self::LogicalOr,
+            "&&" => Self::LogicalAnd,
+            "??" => Self::NullishCoalescing,
+            _ => return None,
+        })
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::EqEq => "==",
+            Self::NotEq => "!=",
+            Self::EqEqEq => "===",
+            Self::NotEqEq => "!==",
+            Self::Lt => "<",
+            Self::LtEq => "<=",
+            Self::Gt => ">",
+            Self::GtEq => ">=",
+            Self::LShift => "<<",
+            Self::RShift => ">>",
+            Self::ZeroFillRShift => ">>>",
+            Self::Add => "+",
+            Self::Sub => "-",
+            Self::Mul => "*",
+            Self::Div => "/",
+            Self::Mod => "%",
+            Self::Exp => "**",
+            Self::BitOr => "|",
+            Self::BitXor => "^",
+            Self::BitAnd => "&",
+            Self::In => "in",
+            Self::InstanceOf => "instanceof",
+            Self::LogicalOr => "||",
+            Self::LogicalAnd => "&&",
+            Self::NullishCoalescing => "??",
+        }
+    }
+
+    /// Binding power (higher binds tighter)
+    pub fn precedence(&self) -> u8 {
+        match self {
+            Self::NullishCoalescing => 1,
+            Self::LogicalOr => 2,
+            Self::LogicalAnd => 3,
+            Self::BitOr => 4,
+            Self::BitXor => 5,
+            Self::BitAnd => 6,
+            Self::EqEq | Self::NotEq | Self::EqEqEq | Self::NotEqEq => 7,
+            Self::Lt | Self::LtEq | Self::Gt | Self::GtEq | Self::In | Self::InstanceOf => 8,
+            Self::LShift | Self::RShift | Self::ZeroFillRShift => 9,
+            Self::Add | Self::Sub => 10,
+            Self::Mul | Self::Div | Self::Mod => 11,
+            Self::Exp => 12,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct BinaryExpr {
+    pub span: Span,
+    pub op: BinaryOp,
+    /// Span of the operator token
+    pub op_span: Span,
+    pub left: Expr,
+    pub right: Expr,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AssignOp {
+    Assign,
+    AddAssign,
+    SubAssign,
+    MulAssign,
+    DivAssign,
+    ModAssign,
+    ExpAssign,
+    LShiftAssign,
+    RShiftAssign,
+    ZeroFillRShiftAssign,
+    BitOrAssign,
+    BitXorAssign,
+    BitAndAssign,
+    OrAssign,
+    AndAssign,
+    NullishAssign,
+}
+
+impl AssignOp {
+    pub fn from_op(op: &str) -> Option<Self> {
+        Some(match op {
+            "=" => Self::Assign,
+            "+=" => Self::AddAssign,
+            "-=" => Self::SubAssign,
+            "*=" => Self::MulAssign,
+            "/=" => Self::DivAssign,
+            "%=" => Self::ModAssign,
+            "**=" => Self::ExpAssign,
+            "<<=" => Self::LShiftAssign,
+            ">>=" => Self::RShiftAssign,
+            ">>>=" => Self::ZeroFillRShiftAssign,
+            "|=" => Self::BitOrAssign,
+            "^=" => Self::BitXorAssign,
+            "&=" => Self::BitAndAssign,
+            "||=" => Self::OrAssign,
+            "&&=" => Self::AndAssign,
+            "??=" => Self::NullishAssign,
+            _ => return None,
+        })
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Assign => "=",
+            Self::AddAssign => "+=",
+            Self::SubAssign => "-=",
+            Self::MulAssign => "*=",
+            Self::DivAssign => "/=",
+            Self::ModAssign => "%=",
+            Self::ExpAssign => "**=",
+            Self::LShiftAssign => "<<=",
+            Self::RShiftAssign => ">>=",
+            Self::ZeroFillRShiftAssign => ">>>=",
+            Self::BitOrAssign => "|=",
+            Self::BitXorAssign => "^=",
+            Self::BitAndAssign => "&=",
+            Self::OrAssign => "||=",
+            Self::AndAssign => "&&=",
+            Self::NullishAssign => "??=",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct AssignExpr {
+    pub span: Span,
+    pub op: AssignOp,
+    pub op_span: Span,
+    pub left: Pat,
+    pub right: Expr,
+}
+
+#[derive(Debug, Clone)]
+pub struct CondExpr {
+    pub span: Span,
+    pub test: Expr,
+    pub cons: Expr,
+    pub alt: Expr,
+}
+
+#[derive(Debug, Clone)]
+pub struct CallExpr {
+    pub span: Span,
+    /// `Expr::Super` for `super(...)`
+    pub callee: Expr,
+    pub type_args: Option<TsTypeArgs>,
+    /// Span of the argument list, parentheses included
+    pub args_span: Span,
+    pub args: Vec<ExprOrSpread>,
+    /// `f?.()`
+    pub optional: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct NewExpr {
+    pub span: Span,
+    pub callee: Expr,
+    pub type_args: Option<TsTypeArgs>,
+    /// `None` for `new X` without parentheses
+    pub args: Option<Vec<ExprOrSpread>>,
+}
+
+#[derive(Debug, Clone)]
+pub struct MemberExpr {
+    pub span: Span,
+    pub object: Expr,
+    pub property: MemberProp,
+    /// `a?.b`
+    pub optional: bool,
+}
+
+#[derive(Debug, Clone)]
+pub enum MemberProp {
+    Ident(Ident),
+    PrivateName(Ident),
+    Computed(ComputedKey),
+}
+
+impl MemberProp {
+    pub fn span(&self) -> Span {
+        match self {
+            MemberProp::Ident(i) | MemberProp::PrivateName(i) => i.span,
+            MemberProp::Computed(c) => c.span,
+        }
+    }
+
+    /// Name of a `.name` access (or `["name"]`)
+    pub fn static_name(&self) -> Option<&str> {
+        match self {
+            MemberProp::Ident(i) => Some(&i.name),
+            MemberProp::Computed(c) => match c.expr.as_ref() {
+                Expr::Lit(Lit { value: LitValue::Str(s), .. }) => Some(s),
+                _ => None,
+            },
+            MemberProp::PrivateName(_) => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct OptChainExpr {
+    pub span: Span,
+    pub expr: Expr,
+}
+
+#[derive(Debug, Clone)]
+pub struct SeqExpr {
+    pub span: Span,
+    pub exprs: Vec<Expr>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ParenExpr {
+    pub span: Span,
+    pub expr: Expr,
+}
+
+#[derive(Debug, Clone)]
+pub struct YieldExpr {
+    pub span: Span,
+    pub delegate: bool,
+    pub arg: Option<Expr>,
+}
+
+#[derive(Debug, Clone)]
+pub struct AwaitExpr {
+    pub span: Span,
+    pub arg: Expr,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MetaPropKind {
+    NewTarget,
+    ImportMeta,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct MetaProp {
+    pub span: Span,
+    pub kind: MetaPropKind,
+}
+
+#[derive(Debug, Clone)]
+pub struct ImportCall {
+    pub span: Span,
+    pub source: Expr,
+    /// Second argument: `import(x, { with: { type: "json" } })`
+    pub options: Option<Expr>,
+}
+
+#[derive(Debug, Clone)]
+pub struct TsAsExpr {
+    pub span: Span,
+    pub expr: Expr,
+    pub type_ann: TsType,
+}
+
+#[derive(Debug, Clone)]
+pub struct TsNonNullExpr {
+    pub span: Span,
+    pub expr: Expr,
+}
+
+#[derive(Debug, Clone)]
+pub struct TsTypeAssertion {
+    pub span: Span,
+    /// Angle brackets included
+    pub type_span: Span,
+    pub expr: Expr,
+}
+
+#[derive(Debug, Clone)]
+pub struct TsInstantiation {
+    pub span: Span,
+    pub expr: Expr,
+    pub type_args: TsTypeArgs,
+}
+
+// ---------------------------------------------------------------------------
+// JSX
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Clone)]
+pub struct JsxElement {
+    pub span: Span,
+    pub opening: JsxOpening,
+    pub children: Vec<JsxChild>,
+    /// `</name>`, absent for self-closing elements
+    pub closing: Option<Span>,
+}
+
+#[derive(Debug, Clone)]
+pub struct JsxOpening {
+    pub span: Span,
+    pub name: JsxName,
+    pub type_args: Option<TsTypeArgs>,
+    pub attrs: Vec<JsxAttrOrSpread>,
+    pub self_closing: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct JsxFragment {
+    pub span: Span,
+    pub opening: Span,
+    pub children: Vec<JsxChild>,
+    pub closing: Span,
+}
+
+#[derive(Debug, Clone)]
+pub enum JsxName {
+    /// `div`, `my-element`, `Foo`
+    Ident(Ident),
+    /// `svg:rect`
+    Namespaced { span: Span, ns: Ident, name: Ident },
+    /// `Foo.Bar`
+    Member { span: Span, object: Box<JsxName>, property: Ident },
+}
+
+impl JsxName {
+    pub fn span(&self) -> Span {
+        match self {
+            JsxName::Ident(i) => i.span,
+            JsxName::Namespaced { span, .. } | JsxName::Member { span, .. } => *span,
+        }
+    }
+
+    /// Name as written, without whitespace: `div`, `svg:rect`, `Foo.Bar`
+    pub fn name(&self) -> String {
+        match self {
+            JsxName::Ident(i) => i.name.clone(),
+            JsxName::Namespaced { ns, name, .. } => format!("{}:{}", ns.name, name.name),
+            JsxName::Member { object, property, .. } => format!("{}.{}", object.name(), property.name),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum JsxAttrOrSpread {
+    Attr(JsxAttr),
+    Spread(SpreadElement),
+}
+
+#[derive(Debug, Clone)]
+pub struct JsxAttr {
+    pub span: Span,
+    /// `JsxName::Ident` or `JsxName::Namespaced`
+    pub name: JsxName,
+    pub value: Option<JsxAttrValue>,
+}
+
+#[derive(Debug, Clone)]
+pub enum JsxAttrValue {
+    /// Quoted value; JSX strings have no escapes, so the value is the raw text
+    Str(Lit),
+    Expr(JsxExprContainer),
+    Element(Box<JsxElement>),
+    Fragment(Box<JsxFragment>),
+}
+
+#[derive(Debug, Clone)]
+pub struct JsxExprContainer {
+    /// Braces included
+    pub span: Span,
+    /// `None` for `{}` and `{/* comment */}`
+    pub expr: Option<Expr>,
+}
+
+#[derive(Debug, Clone)]
+pub enum JsxChild {
+    Text(Span),
+    Expr(JsxExprContainer),
+    Spread(SpreadElement),
+    Element(Box<JsxElement>),
+    Fragment(Box<JsxFragment>),
+}
+
+// ---------------------------------------------------------------------------
+// Patterns
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Clone)]
+pub enum Pat {
+    Ident(BindingIdent),
+    Array(ArrayPat),
+    Object(ObjectPat),
+    Rest(RestPat),
+    /// `a = 1` in a pattern
+    Assign(AssignPat),
+    /// Member expressions and other assignment targets
+    Expr(Box<Expr>),
+    Invalid(Span),
+}
+
+impl Pat {
+    pub fn span(&self) -> Span {
+        match self {
+            Pat::Ident(p) => p.span,
+            Pat::Array(p) => p.span,
+            Pat::Object(p) => p.span,
+            Pat::Rest(p) => p.span,
+            Pat::Assign(p) => p.span,
+            Pat::Expr(e) => e.span(),
+            Pat::Invalid(span) => *span,
+        }
+    }
+
+    /// Type annotation of the pattern, if any
+    pub fn type_ann(&self) -> Option<&TsTypeAnn> {
+        match self {
+            Pat::Ident(p) => p.type_ann.as_ref(),
+            Pat::Array(p) => p.type_ann.as_ref(),
+            Pat::Object(p) => p.type_ann.as_ref(),
+            Pat::Rest(p) => p.type_ann.as_ref(),
+            Pat::Assign(p) => p.left.type_ann(),
+            Pat::Expr(_) | Pat::Invalid(_) => None,
+        }
+    }
+
+    /// Names bound by the pattern, in source order
+    pub fn bound_names(&self) -> Vec<&Ident> {
+        let mut names = Vec::new();
+        self.collect_bound_names(&mut names);
+        names
+    }
+
+    fn collect_bound_names<'a>(&'a self, names: &mut Vec<&'a Ident>) {
+        match self {
+            Pat::Ident(p) => names.push(&p.id),
+            Pat::Array(p) => p.elems.iter().flatten().for_each(|e| e.collect_bound_names(names)),
+            Pat::Object(p) => {
+                for prop in &p.props {
+                    match prop {
+                        ObjectPatProp::KeyValue { value, .. } => value.collect_bound_names(names),
+                        ObjectPatProp::Assign { key, .. } => names.push(key),
+                        ObjectPatProp::Rest(rest) => rest.arg.collect_bound_names(names),
+                    }
+                }
+            }
+            Pat::Rest(p) => p.arg.collect_bound_names(names),
+            Pat::Assign(p) => p.left.collect_bound_names(names),
+            Pat::Expr(_) | Pat::Invalid(_) => {}
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct BindingIdent {
+    /// Covers the type annotation, if any
+    pub span: Span,
+    pub id: Ident,
+    /// `?` after the name
+    pub optional: Option<Span>,
+    /// `!` after the name
+    pub definite: Option<Span>,
+    pub type_ann: Option<TsTypeAnn>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ArrayPat {
+    pub span: Span,
+    pub elems: Vec<Option<Pat>>,
+    pub optional: Option<Span>,
+    pub type_ann: Option<TsTypeAnn>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ObjectPat {
+    pub span: Span,
+    pub props: Vec<ObjectPatProp>,
+    pub optional: Option<Span>,
+    pub type_ann: Option<TsTypeAnn>,
+}
+
+#[derive(Debug, Clone)]
+pub enum ObjectPatProp {
+    /// `{ a: b }`
+    KeyValue { span: Span, key: PropKey, value: Pat },
+    /// `{ a }` / `{ a = 1 }`
+    Assign { span: Span, key: Ident, value: Option<Expr> },
+    /// `{ ...rest }`
+    Rest(RestPat),
+}
+
+#[derive(Debug, Clone)]
+pub struct RestPat {
+    pub span: Span,
+    pub arg: Box<Pat>,
+    pub type_ann: Option<TsTypeAnn>,
+}
+
+#[derive(Debug, Clone)]
+pub struct AssignPat {
+    pub span: Span,
+    pub left: Box<Pat>,
+    pub right: Expr,
+}
+
+// ---------------------------------------------------------------------------
+// Traversal
+// ---------------------------------------------------------------------------
+
+/// Read-only traversal. Override a method to inspect a node, and call the
+/// matching `walk_*` function to keep descending.
+pub trait Visit {
+    fn visit_stmt(&mut self, stmt: &Stmt) {
+        walk_stmt(self, stmt);
+    }
+
+    fn visit_expr(&mut self, expr: &Expr) {
+        walk_expr(self, expr);
+    }
+
+    fn visit_pat(&mut self, pat: &Pat) {
+        walk_pat(self, pat);
+    }
+
+    fn visit_function(&mut self, function: &Function) {
+        walk_function(self, function);
+    }
+
+    fn visit_arrow(&mut self, arrow: &ArrowFunction) {
+        walk_arrow(self, arrow);
+    }
+
+    fn visit_class(&mut self, class: &Class) {
+        walk_class(self, class);
+    }
+
+    fn visit_jsx_element(&mut self, element: &JsxElement) {
+        walk_jsx_element(self, element);
+    }
+
+    fn visit_jsx_fragment(&mut self, fragment: &JsxFragment) {
+        walk_jsx_children(self, &fragment.children);
+    }
+}
+
+pub fn walk_program<V: Visit + ?Sized>(v: &mut V, program: &Program) {
+    for stmt in &program.body {
+        v.visit_stmt(stmt);
+    }
+}
+
+fn walk_block<V: Visit + ?Sized>(v: &mut V, block: &BlockStmt) {
+    for stmt in &block.stmts {
+        v.visit_stmt(stmt);
+    }
+}
+
+fn walk_var_decl<V: Visit + ?Sized>(v: &mut V, decl: &VarDecl) {
+    for d in &decl.decls {
+        v.visit_pat(&d.name);
+        if let Some(init) = &d.init {
+            v.visit_expr(init);
+        }
+    }
+}
+
+fn walk_for_head<V: Visit + ?Sized>(v: &mut V, head: &ForHead) {
+    match head {
+        ForHead::Var(decl) => walk_var_decl(v, decl),
+        ForHead::Pat(pat) => v.visit_pat(pat),
+    }
+}
+
+pub fn walk_stmt<V: Visit + ?Sized>(v: &mut V, stmt: &Stmt) {
+    match stmt {
+        Stmt::Expr(s) => v.visit_expr(&s.expr),
+        Stmt::Block(s) => walk_block(v, s),
+        Stmt::Empty(_) | Stmt::Debugger(_) => {}
+        Stmt::With(s) => {
+            v.visit_expr(&s.object);
+            v.visit_stmt(&s.body);
+        }
+        Stmt::Return(s) => {
+            if let Some(arg) = &s.arg {
+                v.visit_expr(arg);
+            }
+        }
+        Stmt::Labeled(s) => v.visit_stmt(&s.body),
+        Stmt::Break(_) | Stmt::Continue(_) => {}
+        Stmt::If(s) => {
+            v.visit_expr(&s.test);
+            v.visit_stmt(&s.cons);
+            if let Some(alt) = &s.alt {
+                v.visit_stmt(alt);
+            }
+        }
+        Stmt::Switch(s) => {
+            v.visit_expr(&s.discriminant);
+            for case in &s.cases {
+                if let Some(test) = &case.test {
+                    v.visit_expr(test);
+                }
+                for stmt in &case.cons {
+                    v.visit_stmt(stmt);
+                }
+            }
+        }
+        Stmt::Throw(s) => v.visit_expr(&s.arg),
+        Stmt::Try(s) => {
+            walk_block(v, &s.block);
+            if let Some(handler) = &s.handler {
+                if let Some(param) = &handler.param {
+                    v.visit_pat(param);
+                }
+                walk_block(v, &handler.body);
+            }
+            if let Some(finalizer) = &s.finalizer {
+                walk_block(v, finalizer);
+            }
+        }
+        Stmt::While(s) => {
+            v.visit_expr(&s.test);
+            v.visit_stmt(&s.body);
+        }
+        Stmt::DoWhile(s) => {
+            v.visit_stmt(&s.body);
+            v.visit_expr(&s.test);
+        }
+        Stmt::For(s) => {
+            match &s.init {
+                Some(ForInit::Var(decl)) => walk_var_decl(v, decl),
+                Some(ForInit::Expr(expr)) => v.visit_expr(expr),
+                None => {}
+            }
+            if let Some(test) = &s.test {
+                v.visit_expr(test);
+            }
+            if let Some(update) = &s.update {
+                v.visit_expr(update);
+            }
+            v.visit_stmt(&s.body);
+        }
+        Stmt::ForIn(s) => {
+            walk_for_head(v, &s.left);
+            v.visit_expr(&s.right);
+            v.visit_stmt(&s.body);
+        }
+        Stmt::ForOf(s) => {
+            walk_for_head(v, &s.left);
+            v.visit_expr(&s.right);
+            v.visit_stmt(&s.body);
+        }
+        Stmt::Var(decl) => walk_var_decl(v, decl),
+        Stmt::Function(f) => v.visit_function(f),
+        Stmt::Class(c) => v.visit_class(c),
+        Stmt::Import(_) | Stmt::ExportNamed(_) | Stmt::ExportAll(_) => {}
+        Stmt::ExportDecl(d) => v.visit_stmt(&d.decl),
+        Stmt::ExportDefault(d) => match &d.decl {
+            DefaultDecl::Function(f) => v.visit_function(f),
+            DefaultDecl::Class(c) => v.visit_class(c),
+            DefaultDecl::Expr(e) => v.visit_expr(e),
+            DefaultDecl::TsInterface(_) => {}
+        },
+        Stmt::TsInterface(_) | Stmt::TsTypeAlias(_) | Stmt::TsNamespaceExport(_) => {}
+        Stmt::TsEnum(e) => {
+            for member in &e.members {
+                if let Some(init) = &member.init {
+                    v.visit_expr(init);
+                }
+            }
+        }
+        Stmt::TsModule(m) => {
+            if let Some(body) = &m.body {
+                for stmt in &body.body {
+                    v.visit_stmt(stmt);
+                }
+            }
+        }
+        Stmt::TsImportEquals(_) => {}
+        Stmt::TsExportAssignment(s) => v.visit_expr(&s.expr),
+    }
+}
+
+fn walk_args<V: Visit + ?Sized>(v: &mut V, args: &[ExprOrSpread]) {
+    for arg in args {
+        v.visit_expr(&arg.expr);
+    }
+}
+
+pub fn walk_prop_key<V: Visit + ?Sized>(v: &mut V, key: &PropKey) {
+    if let PropKey::Computed(c) = key {
+        v.visit_expr(&c.expr);
+    }
+}
+
+pub fn walk_expr<V: Visit + ?Sized>(v: &mut V, expr: &Expr) {
+    match expr {
+        Expr::Ident(_)
+        | Expr::PrivateName(_)
+        | Expr::This(_)
+        | Expr::Super(_)
+        | Expr::Lit(_)
+        | Expr::MetaProp(_)
+        | Expr::Invalid(_) => {}
+        Expr::Template(t) => t.exprs.iter().for_each(|e| v.visit_expr(e)),
+        Expr::TaggedTemplate(t) => {
+            v.visit_expr(&t.tag);
+            t.quasi.exprs.iter().for_each(|e| v.visit_expr(e));
+        }
+        Expr::Array(a) => {
+            for elem in a.elems.iter().flatten() {
+                v.visit_expr(&elem.expr);
+            }
+        }
+        Expr::Object(o) => {
+            for prop in &o.props {
+                match prop {
+                    Prop::Shorthand(_) => {}
+                    Prop::KeyValue(p) => {
+                        walk_prop_key(v, &p.key);
+                        v.visit_expr(&p.value);
+                    }
+                    Prop::Assign(p) => v.visit_expr(&p.value),
+                    Prop::Method(p) => {
+                        walk_prop_key(v, &p.key);
+                        v.visit_function(&p.function);
+                    }
+                    Prop::Spread(p) => v.visit_expr(&p.expr),
+                }
+            }
+        }
+        Expr::Function(f) => v.visit_function(f),
+        Expr::Arrow(a) => v.visit_arrow(a),
+        Expr::Class(c) => v.visit_class(c),
+        Expr::Unary(e) => v.visit_expr(&e.arg),
+        Expr::Update(e) => v.visit_expr(&e.arg),
+        Expr::Binary(e) => {
+            v.visit_expr(&e.left);
+            v.visit_expr(&e.right);
+        }
+        Expr::Assign(e) => {
+            v.visit_pat(&e.left);
+            v.visit_expr(&e.right);
+        }
+        Expr::Cond(e) => {
+            v.visit_expr(&e.test);
+            v.visit_expr(&e.cons);
+            v.visit_expr(&e.alt);
+        }
+        Expr::Call(e) => {
+            v.visit_expr(&e.callee);
+            walk_args(v, &e.args);
+        }
+        Expr::New(e) => {
+            v.visit_expr(&e.callee);
+            if let Some(args) = &e.args {
+                walk_args(v, args);
+            }
+        }
+        Expr::Member(e) => {
+            v.visit_expr(&e.object);
+            if let MemberProp::Computed(c) = &e.property {
+                v.visit_expr(&c.expr);
+            }
+        }
+        Expr::OptChain(e) => v.visit_expr(&e.expr),
+        Expr::Seq(e) => e.exprs.iter().for_each(|e| v.visit_expr(e)),
+        Expr::Paren(e) => v.visit_expr(&e.expr),
+        Expr::Yield(e) => {
+            if let Some(arg) = &e.arg {
+                v.visit_expr(arg);
+            }
+        }
+        Expr::Await(e) => v.visit_expr(&e.arg),
+        Expr::Import(e) => {
+            v.visit_expr(&e.source);
+            if let Some(options) = &e.options {
+                v.visit_expr(options);
+            }
+        }
+        Expr::JsxElement(e) => v.visit_jsx_element(e),
+        Expr::JsxFragment(f) => v.visit_jsx_fragment(f),
+        Expr::TsAs(e) | Expr::TsSatisfies(e) => v.visit_expr(&e.expr),
+        Expr::TsNonNull(e) => v.visit_expr(&e.expr),
+        Expr::TsTypeAssertion(e) => v.visit_expr(&e.expr),
+        Expr::TsInstantiation(e) => v.visit_expr(&e.expr),
+    }
+}
+
+pub fn walk_pat<V: Visit + ?Sized>(v: &mut V, pat: &Pat) {
+    match pat {
+        Pat::Ident(_) | Pat::Invalid(_) => {}
+        Pat::Array(p) => p.elems.iter().flatten().for_each(|e| v.visit_pat(e)),
+        Pat::Object(p) => {
+            for prop in &p.props {
+                match prop {
+                    ObjectPatProp::KeyValue { key, value, .. } => {
+                        walk_prop_key(v, key);
+                        v.visit_pat(value);
+                    }
+                    ObjectPatProp::Assign { value, .. } => {
+                        if let Some(value) = value {
+                            v.visit_expr(value);
+                        }
+                    }
+                    ObjectPatProp::Rest(rest) => v.visit_pat(&rest.arg),
+                }
+            }
+        }
+        Pat::Rest(p) => v.visit_pat(&p.arg),
+        Pat::Assign(p) => {
+            v.visit_pat(&p.left);
+            v.visit_expr(&p.right);
+        }
+        Pat::Expr(e) => v.visit_expr(e),
+    }
+}
+
+fn walk_params<V: Visit + ?Sized>(v: &mut V, params: &[Param]) {
+    for param in params {
+        for decorator in &param.decorators {
+            v.visit_expr(&decorator.expr);
+        }
+        v.visit_pat(&param.pat);
+    }
+}
+
+pub fn walk_function<V: Visit + ?Sized>(v: &mut V, function: &Function) {
+    walk_params(v, &function.params);
+    if let Some(body) = &function.body {
+        walk_block(v, body);
+    }
+}
+
+pub fn walk_arrow<V: Visit + ?Sized>(v: &mut V, arrow: &ArrowFunction) {
+    walk_params(v, &arrow.params);
+    match &arrow.body {
+        ArrowBody::Block(block) => walk_block(v, block),
+        ArrowBody::Expr(expr) => v.visit_expr(expr),
+    }
+}
+
+pub fn walk_class<V: Visit + ?Sized>(v: &mut V, class: &Class) {
+    for decorator in &class.decorators {
+        v.visit_expr(&decorator.expr);
+    }
+    if let Some(super_class) = &class.super_class {
+        v.visit_expr(super_class);
+    }
+    for member in &class.body {
+        match member {
+            ClassMember::Method(m) => {
+                m.decorators.iter().for_each(|d| v.visit_expr(&d.expr));
+                walk_prop_key(v, &m.key);
+                v.visit_function(&m.function);
+            }
+            ClassMember::Property(p) => {
+                p.decorators.iter().for_each(|d| v.visit_expr(&d.expr));
+                walk_prop_key(v, &p.key);
+                if let Some(value) = &p.value {
+                    v.visit_expr(value);
+                }
+            }
+            ClassMember::StaticBlock(b) => walk_block(v, &b.body),
+            ClassMember::IndexSignature(_) | ClassMember::Empty(_) => {}
+        }
+    }
+}
+
+pub fn walk_jsx_element<V: Visit + ?Sized>(v: &mut V, element: &JsxElement) {
+    for attr in &element.opening.attrs {
+        match attr {
+            JsxAttrOrSpread::Attr(attr) => match &attr.value {
+                Some(JsxAttrValue::Expr(c)) => {
+                    if let Some(expr) = &c.expr {
+                        v.visit_expr(expr);
+                    }
+                }
+                Some(JsxAttrValue::Element(e)) => v.visit_jsx_element(e),
+                Some(JsxAttrValue::Fragment(f)) => v.visit_jsx_fragment(f),
+                Some(JsxAttrValue::Str(_)) | None => {}
+            },
+            JsxAttrOrSpread::Spread(spread) => v.visit_expr(&spread.expr),
+        }
+    }
+    walk_jsx_children(v, &element.children);
+}
+
+pub fn walk_jsx_children<V: Visit + ?Sized>(v: &mut V, children: &[JsxChild]) {
+    for child in children {
+        match child {
+            JsxChild::Text(_) => {}
+            JsxChild::Expr(c) => {
+                if let Some(expr) = &c.expr {
+                    v.visit_expr(expr);
+                }
+            }
+            JsxChild::Spread(s) => v.visit_expr(&s.expr),
+            JsxChild::Element(e) => v.visit_jsx_element(e),
+            JsxChild::Fragment(f) => v.visit_jsx_fragment(f),
+        }
+    }
+}
@@ -36,6 +36,12 @@ pub struct BundleGenerator {
     // No state needed
 }
 
+impl Default for BundleGenerator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 #[wasm_bindgen]
 impl BundleGenerator {
     #[wasm_bindgen(constructor)]
@@ -192,59 +198,9 @@ fn escape_string(s: &str) -> String {
      .replace('\r', "\\r")
 }
 
-/// Quick minification
+/// Minify the bundle with the default minifier settings
 fn minify_output(output: &mut String) {
-    // Remove comments
-    let comment_re = regex::Regex::new(r"(?m)//[^\n]*$").unwrap();
-    *output = comment_re.replace_all(output, "").to_string();
-    
-    // Collapse whitespace (but preserve strings)
-    let mut result = String::with_capacity(output.len());
-    let bytes = output.as_bytes();
-    let len = bytes.len();
-    let mut i = 0;
-    let mut last_was_space = false;
-
-    while i < len {
-        let ch = bytes[i];
-        
-        // Skip strings
-        if ch == b'"' || ch == b'\'' || ch == b'`' {
-            let quote = ch;
-            result.push(ch as char);
-            i += 1;
-            while i < len && bytes[i] != quote {
-                if bytes[i] == b'\\' && i + 1 < len {
-                    result.push(bytes[i] as char);
-                    i += 1;
-                }
-                result.push(bytes[i] as char);
-                i += 1;
-            }
-            if i < len {
-                result.push(bytes[i] as char);
-                i += 1;
-            }
-            last_was_space = false;
-            continue;
-        }
-
-        // Collapse whitespace
-        if ch == b' ' || ch == b'\t' || ch == b'\n' || ch == b'\r' {
-            if !last_was_space {
-                result.push(' ');
-                last_was_space = true;
-            }
-            i += 1;
-            continue;
-        }
-
-        result.push(ch as char);
-        last_was_space = false;
-        i += 1;
-    }
-
-    *output = result;
+    *output = crate::minifier::minify_internal(output, None, &crate::minifier::MinifyConfig::default()).code;
 }
 
 #[cfg(test)]
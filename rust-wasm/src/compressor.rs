@@ -0,0 +1,1433 @@
+//! Compress passes for the minifier
+//!
+//! Token-level rewrites, run before mangling:
+//! - `define` substitution (`process.env.NODE_ENV` -> `"production"`)
+//! - Constant folding of literal expressions
+//! - Dead branch removal for constant `if`, `?:`, `&&`, `||` and `??`
+//! - Inlining of single-use local constants
+//! - `drop_console` and `drop_debugger`
+//!
+//! Every removal is reported as a warning with its source position.
+
+use std::borrow::Cow;
+
+use crate::lexer::{number_value, string_value, tokenize, TokenKind};
+use crate::mangler::{analyze_scopes, ScopeInfo};
+use crate::minifier::{can_end_statement, can_start_statement, MinToken, MinifyConfig};
+use crate::utils::LineIndex;
+
+/// Precedence of an operator that can start at an operand's left or right edge
+const PREC_ASSIGN: u8 = 1;
+const PREC_CONDITIONAL: u8 = 2;
+const PREC_UNARY: u8 = 15;
+
+/// Deepest nesting of `!`, `(`, `void` and `typeof` a literal is folded through
+const MAX_DEPTH: usize = 150;
+
+/// Binary operator precedence (higher binds tighter)
+fn binary_precedence(op: &str) -> Option<u8> {
+    Some(match op {
+        "??" => 3,
+        "||" => 4,
+        "&&" => 5,
+        "|" => 6,
+        "^" => 7,
+        "&" => 8,
+        "==" | "!=" | "===" | "!==" => 9,
+        "<" | ">" | "<=" | ">=" | "in" | "instanceof" => 10,
+        "<<" | ">>" | ">>>" => 11,
+        "+" | "-" => 12,
+        "*" | "/" | "%" => 13,
+        "**" => 14,
+        _ => return None,
+    })
+}
+
+fn is_assignment(op: &str) -> bool {
+    matches!(
+        op,
+        "=" | "+="
+            | "-="
+            | "*="
+            | "/="
+            | "%="
+            | "**="
+            | "<<="
+            | ">>="
+            | ">>>="
+            | "&="
+            | "|="
+            | "^="
+            | "&&="
+            | "||="
+            | "??="
+    )
+}
+
+/// Keywords that are followed by an operand rather than ending one
+const OPERATOR_KEYWORDS: &[&str] = &[
+    "return",
+    "typeof",
+    "void",
+    "delete",
+    "in",
+    "instanceof",
+    "of",
+    "new",
+    "case",
+    "throw",
+    "else",
+    "do",
+    "await",
+    "yield",
+    "extends",
+];
+
+/// Statements whose extent isn't found by scanning for the next `;`
+const COMPOUND_STATEMENTS: &[&str] = &[
+    "for", "while", "do", "try", "switch", "function", "class", "with", "async", "import",
+    "export", "var", "let", "const",
+];
+
+/// A compile-time known value
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Literal {
+    Number(f64),
+    String(String),
+    Bool(bool),
+    Null,
+    Undefined,
+}
+
+impl Literal {
+    fn truthy(&self) -> bool {
+        match self {
+            Literal::Number(n) => *n != 0.0 && !n.is_nan(),
+            Literal::String(s) => !s.is_empty(),
+            Literal::Bool(b) => *b,
+            Literal::Null | Literal::Undefined => false,
+        }
+    }
+
+    fn is_nullish(&self) -> bool {
+        matches!(self, Literal::Null | Literal::Undefined)
+    }
+
+    fn type_of(&self) -> &'static str {
+        match self {
+            Literal::Number(_) => "number",
+            Literal::String(_) => "string",
+            Literal::Bool(_) => "boolean",
+            Literal::Null => "object",
+            Literal::Undefined => "undefined",
+        }
+    }
+
+    /// `ToString` for values whose string form we can produce exactly
+    fn to_js_string(&self) -> Option<String> {
+        match self {
+            Literal::Number(n) => format_number(*n),
+            Literal::String(s) => Some(s.clone()),
+            Literal::Bool(b) => Some(b.to_string()),
+            Literal::Null => Some("null".to_string()),
+            Literal::Undefined => Some("undefined".to_string()),
+        }
+    }
+}
+
+/// JavaScript `Number.prototype.toString` for the range where it prints plain decimals
+pub(crate) fn format_number(n: f64) -> Option<String> {
+    if n == 0.0 {
+        return Some("0".to_string());
+    }
+    let abs = n.abs();
+    if !abs.is_finite() || !(1e-6..1e21).contains(&abs) {
+        return None;
+    }
+    // Rust's shortest round-trip formatting matches JavaScript's in this range
+    Some(format!("{}", n))
+}
+
+/// ECMAScript `ToInt32`
+pub(crate) fn to_int32(n: f64) -> i32 {
+    if !n.is_finite() {
+        return 0;
+    }
+    n.trunc().rem_euclid(4294967296.0) as u32 as i32
+}
+
+/// Value of a string literal, unless it uses escapes we can't round-trip
+fn string_literal(raw: &str) -> Option<String> {
+    let bytes = raw.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'\\' {
+            match bytes.get(i + 1) {
+                Some(b'1'..=b'9') => return None,
+                Some(b'0') if bytes.get(i + 2).is_some_and(|b| b.is_ascii_digit()) => return None,
+                _ => {}
+            }
+            i += 1;
+        }
+        i += 1;
+    }
+    let value = string_value(raw);
+    (!value.contains('\u{FFFD}') || raw.contains('\u{FFFD}')).then_some(value)
+}
+
+/// Print a string literal, picking the quote that needs fewer escapes
+pub(crate) fn quote_string(value: &str) -> String {
+    let quote = if value.matches('"').count() > value.matches('\'').count() {
+        '\''
+    } else {
+        '"'
+    };
+    let mut out = String::with_capacity(value.len() + 2);
+    out.push(quote);
+    let mut chars = value.chars().peekable();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\u{2028}' => out.push_str("\\u2028"),
+            '\u{2029}' => out.push_str("\\u2029"),
+            '\0' if !chars.peek().is_some_and(|c| c.is_ascii_digit()) => out.push_str("\\0"),
+            c if c == quote => {
+                out.push('\\');
+                out.push(c);
+            }
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\x{:02x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push(quote);
+    out
+}
+
+/// Evaluate `left op right`, if both sides are known and the result can be printed exactly
+pub(crate) fn fold_binary(left: &Literal, op: &str, right: &Literal) -> Option<Literal> {
+    use Literal::*;
+    Some(match (left, op, right) {
+        (Number(a), _, Number(b)) => match op {
+            "+" => Number(a + b),
+            "-" => Number(a - b),
+            "*" => Number(a * b),
+            "/" => Number(a / b),
+            "%" => Number(a % b),
+            "|" => Number((to_int32(*a) | to_int32(*b)) as f64),
+            "&" => Number((to_int32(*a) & to_int32(*b)) as f64),
+            "^" => Number((to_int32(*a) ^ to_int32(*b)) as f64),
+            "<<" => Number(to_int32(*a).wrapping_shl(to_int32(*b) as u32 & 31) as f64),
+            ">>" => Number((to_int32(*a) >> (to_int32(*b) as u32 & 31)) as f64),
+            ">>>" => Number(((to_int32(*a) as u32) >> (to_int32(*b) as u32 & 31)) as f64),
+            "<" => Bool(a < b),
+            ">" => Bool(a > b),
+            "<=" => Bool(a <= b),
+            ">=" => Bool(a >= b),
+            "==" | "===" => Bool(a == b),
+            "!=" | "!==" => Bool(a != b),
+            _ => return None,
+        },
+        (String(a), _, String(b)) => match op {
+            "+" => String(format!("{}{}", a, b)),
+            "==" | "===" => Bool(a == b),
+            "!=" | "!==" => Bool(a != b),
+            // Relational comparison of strings is by UTF-16 code units
+            "<" => Bool(a.encode_utf16().lt(b.encode_utf16())),
+            ">" => Bool(a.encode_utf16().gt(b.encode_utf16())),
+            "<=" => Bool(a.encode_utf16().le(b.encode_utf16())),
+            ">=" => Bool(a.encode_utf16().ge(b.encode_utf16())),
+            _ => return None,
+        },
+        (String(a), "+", b) => String(format!("{}{}", a, b.to_js_string()?)),
+        (a, "+", String(b)) => String(format!("{}{}", a.to_js_string()?, b)),
+        (a, "===", b) => Bool(a == b),
+        (a, "!==", b) => Bool(a != b),
+        (a, "==", b) if std::mem::discriminant(a) == std::mem::discriminant(b) => Bool(a == b),
+        (a, "!=", b) if std::mem::discriminant(a) == std::mem::discriminant(b) => Bool(a != b),
+        (a, "==", b) if a.is_nullish() && b.is_nullish() => Bool(true),
+        (a, "!=", b) if a.is_nullish() && b.is_nullish() => Bool(false),
+        _ => return None,
+    })
+}
+
+fn new_token<'a>(
+    kind: TokenKind,
+    text: impl Into<Cow<'a, str>>,
+    pos: Option<usize>,
+) -> MinToken<'a> {
+    MinToken {
+        kind,
+        text: text.into(),
+        pos,
+        newline_before: false,
+    }
+}
+
+/// Shortest tokens for a literal; `None` if it can't be printed exactly
+fn literal_tokens<'a>(literal: &Literal, pos: Option<usize>) -> Option<Vec<MinToken<'a>>> {
+    Some(match literal {
+        Literal::Number(n) if n.is_sign_negative() && !n.is_nan() => vec![
+            new_token(TokenKind::Punct, "-", pos),
+            new_token(TokenKind::Number, format_number(-n)?, pos),
+        ],
+        Literal::Number(n) => vec![new_token(TokenKind::Number, format_number(*n)?, pos)],
+        Literal::String(s) => vec![new_token(TokenKind::String, quote_string(s), pos)],
+        Literal::Bool(b) => vec![
+            new_token(TokenKind::Punct, "!", pos),
+            new_token(TokenKind::Number, if *b { "0" } else { "1" }, pos),
+        ],
+        Literal::Null => vec![new_token(TokenKind::Name, "null", pos)],
+        Literal::Undefined => vec![
+            new_token(TokenKind::Name, "void", pos),
+            new_token(TokenKind::Number, "0", pos),
+        ],
+    })
+}
+
+/// `token` after a line break, if `newline` is set; clears `newline`
+fn with_newline<'a>(mut token: MinToken<'a>, newline: &mut bool) -> MinToken<'a> {
+    token.newline_before |= std::mem::take(newline);
+    token
+}
+
+fn text_len(tokens: &[MinToken]) -> usize {
+    tokens.iter().map(|t| t.text.len()).sum()
+}
+
+/// A pending rewrite of `tokens[start..end]`
+struct Edit<'a> {
+    start: usize,
+    end: usize,
+    replacement: Vec<MinToken<'a>>,
+}
+
+struct Compressor<'a, 'c> {
+    tokens: Vec<MinToken<'a>>,
+    source: &'c str,
+    line_index: &'c LineIndex,
+    warnings: &'c mut Vec<String>,
+    changed: bool,
+}
+
+impl<'a, 'c> Compressor<'a, 'c> {
+    fn text(&self, i: usize) -> &str {
+        self.tokens.get(i).map(|t| t.text.as_ref()).unwrap_or("")
+    }
+
+    fn kind(&self, i: usize) -> Option<TokenKind> {
+        self.tokens.get(i).map(|t| t.kind)
+    }
+
+    fn is_punct(&self, i: usize, punct: &str) -> bool {
+        self.tokens
+            .get(i)
+            .is_some_and(|t| t.kind == TokenKind::Punct && t.text == punct)
+    }
+
+    fn is_word(&self, i: usize, word: &str) -> bool {
+        self.tokens
+            .get(i)
+            .is_some_and(|t| t.kind == TokenKind::Name && t.text == word)
+    }
+
+    fn after_dot(&self, i: usize) -> bool {
+        i > 0 && (self.is_punct(i - 1, ".") || self.is_punct(i - 1, "?."))
+    }
+
+    /// 1-based `line:column` of a token, for warnings
+    fn location(&self, i: usize) -> String {
+        let pos = self.tokens[..=i.min(self.tokens.len().saturating_sub(1))]
+            .iter()
+            .rev()
+            .find_map(|t| t.pos)
+            .unwrap_or(0);
+        let (line, column) = self.line_index.position(self.source, pos);
+        format!("{}:{}", line + 1, column + 1)
+    }
+
+    /// Does the `)` at `close` end the head of an `if`, `for`, `while` or `with` statement?
+    fn closes_statement_head(&self, close: usize) -> bool {
+        let mut depth = 0usize;
+        for j in (0..=close).rev() {
+            match self.text(j) {
+                ")" | "]" | "}" => depth += 1,
+                "(" | "[" | "{" => {
+                    depth = match depth.checked_sub(1) {
+                        Some(depth) => depth,
+                        None => return false,
+                    };
+                    if depth == 0 {
+                        return j > 0 && matches!(self.text(j - 1), "if" | "for" | "while" | "with");
+                    }
+                }
+                _ => {}
+            }
+        }
+        false
+    }
+
+    fn warn(&mut self, message: &str, i: usize) {
+        let location = self.location(i);
+        self.warnings.push(format!("{} at {}", message, location));
+    }
+
+    /// Index of the bracket closing the one at `open`
+    fn matching(&self, open: usize) -> Option<usize> {
+        let mut depth = 0usize;
+        for j in open..self.tokens.len() {
+            match self.tokens[j].kind {
+                TokenKind::Punct => match self.text(j) {
+                    "(" | "[" | "{" => depth += 1,
+                    ")" | "]" | "}" => {
+                        depth = depth.checked_sub(1)?;
+                        if depth == 0 {
+                            return Some(j);
+                        }
+                    }
+                    _ => {}
+                },
+                TokenKind::TemplateHead => depth += 1,
+                TokenKind::TemplateTail => {
+                    depth = depth.checked_sub(1)?;
+                    if depth == 0 {
+                        return Some(j);
+                    }
+                }
+                _ => {}
+            }
+        }
+        None
+    }
+
+    /// Can the token at `i` be the last token of an operand?
+    fn ends_operand(&self, i: usize) -> bool {
+        let token = &self.tokens[i];
+        match token.kind {
+            TokenKind::Name => !OPERATOR_KEYWORDS.contains(&token.text.as_ref()),
+            TokenKind::Punct => matches!(token.text.as_ref(), ")" | "]" | "}" | "++" | "--"),
+            TokenKind::TemplateHead | TokenKind::TemplateMiddle => false,
+            _ => true,
+        }
+    }
+
+    /// Does an operand (rather than an operator) start at `i`?
+    fn operand_start(&self, i: usize) -> bool {
+        if i == 0 || !self.ends_operand(i - 1) {
+            return true;
+        }
+        // After a block, `!x` or a literal starts a new statement
+        self.is_punct(i - 1, "}")
+            && !matches!(self.text(i), "+" | "-" | "/" | "(" | "[")
+            && !matches!(
+                self.kind(i),
+                Some(TokenKind::Template | TokenKind::TemplateHead)
+            )
+    }
+
+    /// Precedence of whatever binds an operand starting at `i` from the left,
+    /// or `None` if it binds tighter than any operator (`.`, unary operators, `new`)
+    fn left_precedence(&self, i: usize) -> Option<u8> {
+        if i == 0 {
+            return Some(0);
+        }
+        let prev = &self.tokens[i - 1];
+        match prev.kind {
+            TokenKind::Punct => match prev.text.as_ref() {
+                "(" | "[" | "{" | "," | ";" | "}" | ")" => Some(0),
+                "=>" | "..." | "?" | ":" => Some(PREC_ASSIGN),
+                "+" | "-" if i < 2 || !self.ends_operand(i - 2) => None,
+                op if is_assignment(op) => Some(PREC_ASSIGN),
+                op => binary_precedence(op),
+            },
+            TokenKind::Name => match prev.text.as_ref() {
+                "return" | "case" | "throw" | "else" | "do" => Some(0),
+                "yield" | "of" => Some(PREC_ASSIGN),
+                "in" | "instanceof" => Some(10),
+                _ => None,
+            },
+            TokenKind::TemplateHead | TokenKind::TemplateMiddle => Some(0),
+            _ => None,
+        }
+    }
+
+    /// Can an operand ending right before `j` be replaced by an expression of
+    /// precedence `precedence` without changing what `tokens[j]` binds to?
+    fn right_allows(&self, j: usize, precedence: u8) -> bool {
+        let Some(token) = self.tokens.get(j) else {
+            return true;
+        };
+        match token.kind {
+            TokenKind::Punct => match token.text.as_ref() {
+                ")" | "]" | "}" | "," | ";" | ":" => true,
+                "?" => precedence > PREC_CONDITIONAL,
+                "**" => false,
+                op => binary_precedence(op).is_some_and(|p| p <= precedence),
+            },
+            TokenKind::Name if matches!(token.text.as_ref(), "in" | "instanceof") => {
+                precedence >= 10
+            }
+            TokenKind::TemplateMiddle | TokenKind::TemplateTail => true,
+            TokenKind::Template | TokenKind::TemplateHead => false,
+            _ => token.newline_before,
+        }
+    }
+
+    /// Parse a literal starting at operand position `i`: returns it and the index after it
+    fn parse_literal(&self, i: usize) -> Option<(Literal, usize)> {
+        self.parse_nested_literal(i, 0)
+    }
+
+    /// `parse_literal` within `depth` prefixes and parentheses; deeper
+    /// literals are left as they are
+    fn parse_nested_literal(&self, i: usize, depth: usize) -> Option<(Literal, usize)> {
+        if depth >= MAX_DEPTH {
+            return None;
+        }
+        let token = self.tokens.get(i)?;
+        match token.kind {
+            TokenKind::Number => Some((Literal::Number(number_value(&token.text)?), i + 1)),
+            TokenKind::String => Some((Literal::String(string_literal(&token.text)?), i + 1)),
+            TokenKind::Name => match token.text.as_ref() {
+                "true" => Some((Literal::Bool(true), i + 1)),
+                "false" => Some((Literal::Bool(false), i + 1)),
+                "null" => Some((Literal::Null, i + 1)),
+                "void" => self
+                    .parse_nested_literal(i + 1, depth + 1)
+                    .map(|(_, end)| (Literal::Undefined, end)),
+                "typeof" => self
+                    .parse_nested_literal(i + 1, depth + 1)
+                    .map(|(value, end)| (Literal::String(value.type_of().to_string()), end)),
+                _ => None,
+            },
+            TokenKind::Punct => match token.text.as_ref() {
+                "!" => self
+                    .parse_nested_literal(i + 1, depth + 1)
+                    .map(|(value, end)| (Literal::Bool(!value.truthy()), end)),
+                "-" if self.kind(i + 1) == Some(TokenKind::Number) => {
+                    Some((Literal::Number(-number_value(self.text(i + 1))?), i + 2))
+                }
+                "(" => {
+                    let (value, end) = self.parse_nested_literal(i + 1, depth + 1)?;
+                    self.is_punct(end, ")").then_some((value, end + 1))
+                }
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// End of the expression starting at `start` whose operators all bind
+    /// tighter than `precedence`; `None` if that can't be told from the tokens
+    fn expression_end(&self, start: usize, precedence: u8) -> Option<usize> {
+        let mut depth = 0usize;
+        let mut conditionals = 0usize;
+        let mut j = start;
+        while j < self.tokens.len() {
+            let token = &self.tokens[j];
+            if depth == 0
+                && j > start
+                && token.newline_before
+                && can_end_statement(&self.tokens[j - 1])
+                && can_start_statement(token)
+            {
+                // A line break may end the statement here
+                let is_name = token.kind == TokenKind::Name
+                    && !matches!(token.text.as_ref(), "in" | "instanceof");
+                return is_name.then_some(j);
+            }
+            match token.kind {
+                TokenKind::Punct => match token.text.as_ref() {
+                    "(" | "[" | "{" => depth += 1,
+                    ")" | "]" | "}" => {
+                        if depth == 0 {
+                            return Some(j);
+                        }
+                        depth -= 1;
+                    }
+                    "," | ";" if depth == 0 => return Some(j),
+                    "?" if depth == 0 => {
+                        if precedence >= PREC_CONDITIONAL {
+                            return Some(j);
+                        }
+                        conditionals += 1;
+                    }
+                    ":" if depth == 0 => {
+                        if conditionals == 0 {
+                            return Some(j);
+                        }
+                        conditionals -= 1;
+                    }
+                    op if depth == 0
+                        && (is_assignment(op) || op == "=>")
+                        && precedence > PREC_ASSIGN =>
+                    {
+                        return None;
+                    }
+                    // A leading `+`/`-` is unary
+                    op if depth == 0
+                        && (!matches!(op, "+" | "-") || self.ends_operand(j - 1))
+                        && binary_precedence(op).is_some_and(|p| p <= precedence) =>
+                    {
+                        return Some(j);
+                    }
+                    _ => {}
+                },
+                TokenKind::Name
+                    if depth == 0
+                        && matches!(token.text.as_ref(), "in" | "instanceof")
+                        && precedence >= 10 =>
+                {
+                    return Some(j);
+                }
+                TokenKind::TemplateHead => depth += 1,
+                TokenKind::TemplateMiddle if depth == 0 => return Some(j),
+                TokenKind::TemplateTail => {
+                    if depth == 0 {
+                        return Some(j);
+                    }
+                    depth -= 1;
+                }
+                _ => {}
+            }
+            j += 1;
+        }
+        Some(j)
+    }
+
+    /// End (exclusive) of the statement starting at `i`, for simple statements
+    fn statement_end(&self, i: usize) -> Option<usize> {
+        let token = self.tokens.get(i)?;
+        match token.kind {
+            TokenKind::Punct if token.text == "{" => return self.matching(i).map(|m| m + 1),
+            TokenKind::Punct if token.text == ";" => return Some(i + 1),
+            TokenKind::Name => match token.text.as_ref() {
+                "if" => {
+                    let close = self.matching(i + 1).filter(|_| self.is_punct(i + 1, "("))?;
+                    let body_end = self.statement_end(close + 1)?;
+                    if self.is_word(body_end, "else") {
+                        return self.statement_end(body_end + 1);
+                    }
+                    return Some(body_end);
+                }
+                "return" | "break" | "continue"
+                    if self.tokens.get(i + 1).map_or(true, |t| t.newline_before) =>
+                {
+                    return Some(i + 1 + usize::from(self.is_punct(i + 1, ";")));
+                }
+                word if COMPOUND_STATEMENTS.contains(&word) => return None,
+                // Labelled statement
+                _ if self.is_punct(i + 1, ":") => return None,
+                _ => {}
+            },
+            _ => {}
+        }
+
+        let mut depth = 0usize;
+        let mut j = i;
+        while j < self.tokens.len() {
+            let token = &self.tokens[j];
+            if depth == 0
+                && j > i
+                && token.newline_before
+                && can_end_statement(&self.tokens[j - 1])
+                && can_start_statement(token)
+            {
+                let is_name = token.kind == TokenKind::Name
+                    && !matches!(token.text.as_ref(), "in" | "instanceof" | "of");
+                return is_name.then_some(j);
+            }
+            match token.kind {
+                TokenKind::Punct => match token.text.as_ref() {
+                    "(" | "[" | "{" => depth += 1,
+                    ")" | "]" | "}" => {
+                        if depth == 0 {
+                            return Some(j);
+                        }
+                        depth -= 1;
+                    }
+                    ";" if depth == 0 => return Some(j + 1),
+                    _ => {}
+                },
+                TokenKind::TemplateHead => depth += 1,
+                TokenKind::TemplateTail => depth = depth.saturating_sub(1),
+                _ => {}
+            }
+            j += 1;
+        }
+        Some(j)
+    }
+
+    /// Does `tokens[start..end]` declare something visible outside it (`var`, function declarations)?
+    fn declares_hoisted(&self, start: usize, end: usize) -> bool {
+        (start..end).any(|j| {
+            (self.is_word(j, "var") && !self.after_dot(j))
+                || (self.is_word(j, "function")
+                    && (j == 0 || matches!(self.text(j - 1), ";" | "{" | "}")))
+        })
+    }
+
+    /// Is the token at `i` in a position where statements are listed
+    /// (as opposed to the single body of `if`, `else`, loops or a label)?
+    fn in_statement_list(&self, i: usize) -> bool {
+        i == 0
+            || matches!(self.text(i - 1), ";" | "{" | "}")
+                && self.kind(i - 1) == Some(TokenKind::Punct)
+    }
+
+    /// For each token, whether its innermost enclosing bracket is `(`, `[` or a template
+    /// (a `for` header or an expression) rather than a block
+    fn in_parentheses(&self) -> Vec<bool> {
+        let mut stack = Vec::new();
+        self.tokens
+            .iter()
+            .map(|token| {
+                let inside = stack.last().copied().unwrap_or(false);
+                match (token.kind, token.text.as_ref()) {
+                    (TokenKind::Punct, "(" | "[") | (TokenKind::TemplateHead, _) => {
+                        stack.push(true)
+                    }
+                    (TokenKind::Punct, "{") => stack.push(false),
+                    (TokenKind::Punct, ")" | "]" | "}") | (TokenKind::TemplateTail, _) => {
+                        stack.pop();
+                    }
+                    _ => {}
+                }
+                inside
+            })
+            .collect()
+    }
+
+    /// Edit that removes the statement `tokens[start..end]`, leaving `;` where a statement is required
+    fn remove_statement(&self, start: usize, end: usize) -> Edit<'a> {
+        let next_is_safe = match self.tokens.get(end) {
+            None => true,
+            Some(t) => {
+                (t.kind == TokenKind::Punct && t.text == "}")
+                    || (t.kind == TokenKind::Name
+                        && !matches!(t.text.as_ref(), "in" | "instanceof"))
+            }
+        };
+        let removable = start == 0
+            || self.is_punct(start - 1, ";")
+            || self.is_punct(start - 1, "{")
+            || (self.is_punct(start - 1, "}") && next_is_safe);
+        let replacement = if removable {
+            Vec::new()
+        } else {
+            let mut semicolon = new_token(TokenKind::Punct, ";", self.tokens[start].pos);
+            semicolon.newline_before = self.tokens[start].newline_before;
+            vec![semicolon]
+        };
+        Edit {
+            start,
+            end,
+            replacement,
+        }
+    }
+
+    /// Apply edits (in any order) in one pass that builds a new token vector;
+    /// of two overlapping edits, the one starting later wins
+    fn apply(&mut self, mut edits: Vec<Edit<'a>>) {
+        if edits.is_empty() {
+            return;
+        }
+        edits.sort_by_key(|e| std::cmp::Reverse(e.start));
+        let mut limit = usize::MAX;
+        edits.retain(|edit| {
+            let keep = edit.end <= limit;
+            if keep {
+                limit = edit.start;
+            }
+            keep
+        });
+        edits.reverse();
+
+        let mut old = std::mem::take(&mut self.tokens).into_iter();
+        let mut tokens = Vec::with_capacity(old.len());
+        // A line break of removed tokens goes to the next token kept
+        let mut newline = false;
+        let mut pos = 0;
+        for edit in edits {
+            for token in old.by_ref().take(edit.start - pos) {
+                tokens.push(with_newline(token, &mut newline));
+            }
+            let removed: Vec<_> = old.by_ref().take(edit.end - edit.start).collect();
+            pos = edit.end;
+            newline |= match edit.replacement.is_empty() {
+                true => removed.iter().any(|t| t.newline_before),
+                false => removed.first().is_some_and(|t| t.newline_before),
+            };
+            for token in edit.replacement {
+                tokens.push(with_newline(token, &mut newline));
+            }
+        }
+        for token in old {
+            tokens.push(with_newline(token, &mut newline));
+        }
+        self.tokens = tokens;
+        self.changed = true;
+    }
+
+    /// Replace `define`d globals (`process.env.NODE_ENV`) with their values
+    fn apply_defines(&mut self, config: &MinifyConfig, scopes: &ScopeInfo) {
+        let mut defines: Vec<(Vec<&str>, &str)> = config
+            .defines
+            .iter()
+            .map(|(k, v)| (k.split('.').collect(), v.as_str()))
+            .collect();
+        defines.sort_by_key(|(parts, _)| std::cmp::Reverse(parts.len()));
+
+        let mut edits = Vec::new();
+        let mut i = 0;
+        while i < self.tokens.len() {
+            let matched = defines.iter().find(|(parts, _)| {
+                scopes.is_global_reference(i)
+                    && self.is_word(i, parts[0])
+                    && parts[1..].iter().enumerate().all(|(n, part)| {
+                        self.is_punct(i + 1 + 2 * n, ".") && self.is_word(i + 2 + 2 * n, part)
+                    })
+            });
+            let Some((parts, value)) = matched else {
+                i += 1;
+                continue;
+            };
+            let end = i + 2 * parts.len() - 1;
+            let assigned = self.tokens.get(end).is_some_and(|t| {
+                t.kind == TokenKind::Punct
+                    && (is_assignment(&t.text) || matches!(t.text.as_ref(), "++" | "--" | "=>"))
+            });
+            if assigned || (i > 0 && matches!(self.text(i - 1), "++" | "--")) {
+                i = end;
+                continue;
+            }
+
+            let pos = self.tokens[i].pos;
+            let stream = tokenize(value);
+            let mut replacement: Vec<MinToken<'a>> = stream
+                .tokens
+                .iter()
+                .map(|t| new_token(t.kind, t.text(value).to_string(), pos))
+                .collect();
+            if replacement.len() > 1
+                && !(1..replacement.len()).all(|n| (n % 2 == 1) == (replacement[n].text == "."))
+            {
+                replacement.insert(0, new_token(TokenKind::Punct, "(", pos));
+                replacement.push(new_token(TokenKind::Punct, ")", pos));
+            }
+            if scopes.is_shorthand(i) {
+                replacement.splice(
+                    0..0,
+                    [
+                        new_token(TokenKind::Name, parts[0].to_string(), pos),
+                        new_token(TokenKind::Punct, ":", pos),
+                    ],
+                );
+            }
+            edits.push(Edit {
+                start: i,
+                end,
+                replacement,
+            });
+            i = end;
+        }
+        self.apply(edits);
+    }
+
+    /// Fold constant expressions until nothing changes, in rounds that each
+    /// make one pass over the tokens. Folds next to an earlier fold of the same
+    /// round wait for the next round, as they look at the tokens it replaces.
+    fn fold_constants(&mut self) {
+        loop {
+            let mut edits = Vec::new();
+            let mut i = 0;
+            while i < self.tokens.len() {
+                if self.operand_start(i) {
+                    if let Some(edit) = self.fold_at(i) {
+                        i = edit.end + 2;
+                        edits.push(edit);
+                        continue;
+                    }
+                }
+                i += 1;
+            }
+            if edits.is_empty() {
+                break;
+            }
+            self.apply(edits);
+        }
+    }
+
+    /// Try to fold the expression starting at operand position `i`
+    fn fold_at(&mut self, i: usize) -> Option<Edit<'a>> {
+        let (left, left_end) = self.parse_literal(i)?;
+        let left_prec = self.left_precedence(i)?;
+        let op = self.text(left_end).to_string();
+        let op_is_binary = self.kind(left_end) == Some(TokenKind::Punct)
+            || matches!(op.as_str(), "in" | "instanceof");
+        let pos = self.tokens[i].pos;
+
+        match op.as_str() {
+            "&&" | "||" | "??" if left_prec < binary_precedence(&op).unwrap_or(0) => {
+                let keep_left = match op.as_str() {
+                    "&&" => !left.truthy(),
+                    "||" => left.truthy(),
+                    _ => !left.is_nullish(),
+                };
+                let precedence = binary_precedence(&op).unwrap_or(0);
+                if keep_left {
+                    // `false && x` -> `false`
+                    let end = self.expression_end(left_end + 1, precedence)?;
+                    self.warn("Dropped unreachable code", left_end + 1);
+                    Some(Edit {
+                        start: left_end,
+                        end,
+                        replacement: Vec::new(),
+                    })
+                } else {
+                    // `true && x` -> `x`
+                    Some(Edit {
+                        start: i,
+                        end: left_end + 1,
+                        replacement: Vec::new(),
+                    })
+                }
+            }
+            "?" if left_prec <= PREC_ASSIGN => {
+                let colon = self
+                    .expression_end(left_end + 1, 0)
+                    .filter(|&c| self.is_punct(c, ":"))?;
+                let end = self.expression_end(colon + 1, PREC_ASSIGN)?;
+                let (kept, dropped) = if left.truthy() {
+                    (left_end + 1..colon, colon + 1..end)
+                } else {
+                    (colon + 1..end, left_end + 1..colon)
+                };
+                let mut replacement: Vec<MinToken<'a>> = self.tokens[kept].to_vec();
+                if replacement
+                    .first()
+                    .is_some_and(|t| matches!(t.text.as_ref(), "{" | "function" | "class"))
+                {
+                    replacement.insert(0, new_token(TokenKind::Punct, "(", pos));
+                    replacement.push(new_token(TokenKind::Punct, ")", pos));
+                }
+                self.warn("Dropped unreachable code", dropped.start);
+                Some(Edit {
+                    start: i,
+                    end,
+                    replacement,
+                })
+            }
+            _ if op_is_binary && op != "**" => {
+                let Some(precedence) = binary_precedence(&op).filter(|&p| left_prec < p) else {
+                    return self.canonicalize(i, &left, left_end);
+                };
+                let Some((right, right_end)) = self.parse_literal(left_end + 1) else {
+                    return self.canonicalize(i, &left, left_end);
+                };
+                if !self.right_allows(right_end, precedence) {
+                    return None;
+                }
+                let mut value = fold_binary(&left, &op, &right)?;
+                let mut end = right_end;
+                let mut folded = None;
+                loop {
+                    if let Some(replacement) = literal_tokens(&value, pos)
+                        .filter(|r| text_len(r) <= text_len(&self.tokens[i..end]))
+                    {
+                        folded = Some((replacement, end));
+                    }
+                    // The rest of a left-associative chain: `1 + 2 + 3`
+                    match self.fold_next(&value, end, left_prec) {
+                        Some((next, next_end)) => (value, end) = (next, next_end),
+                        None => break,
+                    }
+                }
+                let (replacement, end) = folded?;
+                Some(Edit {
+                    start: i,
+                    end,
+                    replacement,
+                })
+            }
+            _ => self.canonicalize(i, &left, left_end),
+        }
+    }
+
+    /// Fold `value` with the operator at `i` and the literal after it, when
+    /// nothing else binds them: the next link of a chain whose start has
+    /// `left_prec` to its left
+    fn fold_next(&self, value: &Literal, i: usize, left_prec: u8) -> Option<(Literal, usize)> {
+        let op = self.text(i);
+        if !(self.kind(i) == Some(TokenKind::Punct) || matches!(op, "in" | "instanceof"))
+            || matches!(op, "**" | "&&" | "||" | "??")
+        {
+            return None;
+        }
+        let precedence = binary_precedence(op).filter(|&p| left_prec < p)?;
+        let (right, right_end) = self.parse_literal(i + 1)?;
+        if !self.right_allows(right_end, precedence) {
+            return None;
+        }
+        Some((fold_binary(value, op, &right)?, right_end))
+    }
+
+    /// Replace a literal with its shortest form: `(1)` -> `1`, `!"a"` -> `!1`, `typeof 1` -> `"number"`
+    fn canonicalize(&self, i: usize, literal: &Literal, end: usize) -> Option<Edit<'a>> {
+        if i > 0 && self.is_word(i - 1, "new") || !self.right_allows(end, PREC_UNARY) {
+            return None;
+        }
+        let replacement = literal_tokens(literal, self.tokens[i].pos)?;
+        if text_len(&replacement) >= text_len(&self.tokens[i..end]) {
+            return None;
+        }
+        Some(Edit {
+            start: i,
+            end,
+            replacement,
+        })
+    }
+
+    /// Remove constant `if` branches and literal-only expression statements
+    fn remove_dead_code(&mut self) {
+        let in_parentheses = self.in_parentheses();
+        let mut edits = Vec::new();
+        let mut i = 0;
+        while i < self.tokens.len() {
+            if self.is_word(i, "if") && !self.after_dot(i) && self.is_punct(i + 1, "(") {
+                if let Some((edit, end)) = self.constant_if(i) {
+                    edits.push(edit);
+                    i = end;
+                    continue;
+                }
+            } else if self.in_statement_list(i) && !in_parentheses[i] {
+                if let Some((literal, end)) = self.parse_literal(i) {
+                    let terminated = self.is_punct(end, ";")
+                        || self.is_punct(end, "}")
+                        || end == self.tokens.len();
+                    // Strings may be directives
+                    if terminated && !matches!(literal, Literal::String(_)) {
+                        self.warn("Dropped side-effect-free expression", i);
+                        edits.push(
+                            self.remove_statement(i, end + usize::from(self.is_punct(end, ";"))),
+                        );
+                        i = end;
+                        continue;
+                    }
+                }
+            }
+            i += 1;
+        }
+        self.apply(edits);
+    }
+
+    /// Rewrite `if (literal) a else b` at `i`, returning the edit and the end of the statement
+    fn constant_if(&mut self, i: usize) -> Option<(Edit<'a>, usize)> {
+        let close = self.matching(i + 1)?;
+        let (condition, condition_end) = self.parse_literal(i + 2)?;
+        if condition_end != close {
+            return None;
+        }
+        let body_end = self.statement_end(close + 1)?;
+        let (else_start, end) = if self.is_word(body_end, "else") {
+            (Some(body_end + 1), self.statement_end(body_end + 1)?)
+        } else {
+            (None, body_end)
+        };
+
+        let (kept, dropped) = if condition.truthy() {
+            (Some(close + 1..body_end), else_start.map(|s| s..end))
+        } else {
+            (else_start.map(|s| s..end), Some(close + 1..body_end))
+        };
+        if let Some(dropped) = &dropped {
+            if self.declares_hoisted(dropped.start, dropped.end) {
+                return None;
+            }
+        }
+
+        let edit = match kept {
+            Some(kept) => {
+                let mut replacement: Vec<MinToken<'a>> = self.tokens[kept.clone()].to_vec();
+                let is_block = self.is_punct(kept.start, "{");
+                let has_declarations = (kept.start + 1..kept.end).any(|j| {
+                    matches!(self.text(j), "let" | "const" | "class" | "function")
+                        && self.kind(j) == Some(TokenKind::Name)
+                });
+                if is_block && self.in_statement_list(i) && !has_declarations {
+                    replacement.pop();
+                    replacement.remove(0);
+                }
+                if replacement
+                    .last()
+                    .is_some_and(|t| !matches!(t.text.as_ref(), ";" | "}"))
+                {
+                    replacement.push(new_token(TokenKind::Punct, ";", None));
+                }
+                if replacement.is_empty() {
+                    self.remove_statement(i, end)
+                } else {
+                    Edit {
+                        start: i,
+                        end,
+                        replacement,
+                    }
+                }
+            }
+            None => self.remove_statement(i, end),
+        };
+        if let Some(dropped) = dropped {
+            self.warn("Dropped unreachable code", dropped.start);
+        }
+        Some((edit, end))
+    }
+
+    /// Remove `console.*()` calls and `debugger` statements
+    fn drop_statements(&mut self, config: &MinifyConfig, scopes: &ScopeInfo) {
+        let in_parentheses = self.in_parentheses();
+        let mut edits = Vec::new();
+        let mut i = 0;
+        while i < self.tokens.len() {
+            if config.drop_debugger
+                && self.is_word(i, "debugger")
+                && !self.after_dot(i)
+                && !self.is_punct(i + 1, ":")
+                && !self.is_punct(i + 1, "(")
+            {
+                self.warn("Dropped debugger statement", i);
+                let end = i + 1 + usize::from(self.is_punct(i + 1, ";"));
+                edits.push(self.remove_statement(i, end));
+                i = end;
+                continue;
+            }
+
+            if config.drop_console
+                && self.is_word(i, "console")
+                && scopes.is_global_reference(i)
+                && self.is_punct(i + 1, ".")
+                && self.kind(i + 2) == Some(TokenKind::Name)
+                && self.is_punct(i + 3, "(")
+                && !(i > 0 && self.is_word(i - 1, "new"))
+            {
+                if let Some(close) = self.matching(i + 3) {
+                    let after = close + 1;
+                    // A call, member access or template continues the expression even across a
+                    // line break, so the dropped call keeps a parenthesized placeholder
+                    let chained = self.tokens.get(after).is_some_and(|t| {
+                        matches!(t.kind, TokenKind::Template | TokenKind::TemplateHead)
+                            || (t.kind == TokenKind::Punct
+                                && matches!(t.text.as_ref(), "." | "?." | "(" | "[" | "**"))
+                    });
+                    let message = format!("Dropped console.{} call", self.text(i + 2));
+                    self.warn(&message, i);
+                    let after_asi = i > 0
+                        && !matches!(self.text(i - 1), ";" | "{" | "}" | "else" | "do")
+                        && !(self.is_punct(i - 1, ")") && self.closes_statement_head(i - 1))
+                        && self.tokens[i].newline_before
+                        && can_end_statement(&self.tokens[i - 1]);
+                    let statement_start = after_asi
+                        || i == 0
+                        || matches!(self.text(i - 1), ";" | "{" | "}" | ")" | "else" | "do");
+                    let statement_end = self.tokens.get(after).map_or(true, |t| {
+                        t.newline_before || matches!(t.text.as_ref(), ";" | "}")
+                    });
+                    if !chained && statement_start && statement_end && !in_parentheses[i] {
+                        edits.push(
+                            self.remove_statement(i, after + usize::from(self.is_punct(after, ";"))),
+                        );
+                    } else {
+                        let pos = self.tokens[i].pos;
+                        let mut replacement =
+                            literal_tokens(&Literal::Undefined, pos).unwrap_or_default();
+                        if chained {
+                            replacement.insert(0, new_token(TokenKind::Punct, "(", pos));
+                            replacement.push(new_token(TokenKind::Punct, ")", pos));
+                            // `a()\n(void 0)` would call the result of `a()`
+                            if after_asi {
+                                replacement.insert(0, new_token(TokenKind::Punct, ";", pos));
+                            }
+                        }
+                        edits.push(Edit { start: i, end: after, replacement });
+                    }
+                    i = after;
+                    continue;
+                }
+            }
+            i += 1;
+        }
+        self.apply(edits);
+    }
+
+    /// Inline `const x = literal` bindings that are used exactly once
+    fn inline_constants(&mut self, scopes: &ScopeInfo) {
+        let in_parentheses = self.in_parentheses();
+        let mut edits = Vec::new();
+        for (declaration, reference) in scopes.single_use_locals() {
+            if declaration == 0
+                || in_parentheses[declaration]
+                || !self.is_word(declaration - 1, "const")
+                || !self.is_punct(declaration + 1, "=")
+            {
+                continue;
+            }
+            let Some((literal, end)) = self.parse_literal(declaration + 2) else {
+                continue;
+            };
+            if !(self.is_punct(end, ";") || self.is_punct(end, "}")) || reference < end {
+                continue;
+            }
+            let pos = self.tokens[reference].pos;
+            let Some(mut replacement) = literal_tokens(&literal, pos) else {
+                continue;
+            };
+            let next_binds_tighter = self.tokens.get(reference + 1).is_some_and(|t| {
+                !t.newline_before
+                    && (matches!(t.kind, TokenKind::Template | TokenKind::TemplateHead)
+                        || (t.kind == TokenKind::Punct && matches!(t.text.as_ref(), "(" | "**")))
+            });
+            if next_binds_tighter
+                || (replacement.len() > 1 && !self.right_allows(reference + 1, PREC_UNARY))
+            {
+                continue;
+            }
+            if reference > 0 && self.is_word(reference - 1, "new") {
+                continue;
+            }
+            if scopes.is_shorthand(reference) {
+                replacement.splice(
+                    0..0,
+                    [
+                        self.tokens[reference].clone(),
+                        new_token(TokenKind::Punct, ":", pos),
+                    ],
+                );
+            }
+
+            let message = format!("Inlined single-use constant `{}`", self.text(declaration));
+            self.warn(&message, declaration);
+            edits.push(
+                self.remove_statement(declaration - 1, end + usize::from(self.is_punct(end, ";"))),
+            );
+            edits.push(Edit {
+                start: reference,
+                end: reference + 1,
+                replacement,
+            });
+        }
+        self.apply(edits);
+    }
+}
+
+/// Run the compress passes over the token stream
+pub(crate) fn compress<'a>(
+    tokens: Vec<MinToken<'a>>,
+    config: &MinifyConfig,
+    source: &str,
+    line_index: &LineIndex,
+    warnings: &mut Vec<String>,
+) -> Vec<MinToken<'a>> {
+    let mut compressor = Compressor {
+        tokens,
+        source,
+        line_index,
+        warnings,
+        changed: false,
+    };
+    let Some(scopes) = analyze_scopes(&compressor.tokens) else {
+        compressor
+            .warnings
+            .push("Skipped compression: unbalanced brackets".to_string());
+        return compressor.tokens;
+    };
+    if !config.defines.is_empty() {
+        compressor.apply_defines(config, &scopes);
+    }
+
+    for _ in 0..config.passes.max(1) {
+        compressor.changed = false;
+        compressor.fold_constants();
+        compressor.remove_dead_code();
+        let Some(scopes) = analyze_scopes(&compressor.tokens) else {
+            break;
+        };
+        compressor.drop_statements(config, &scopes);
+        if let Some(scopes) = analyze_scopes(&compressor.tokens) {
+            compressor.inline_constants(&scopes);
+        }
+        if !compressor.changed {
+            break;
+        }
+    }
+    compressor.tokens
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::minifier::{minify_internal, MinifyConfig, MinifyResult};
+
+    fn compress_only() -> MinifyConfig {
+        MinifyConfig {
+            mangle: false,
+            ..MinifyConfig::default()
+        }
+    }
+
+    fn compressed(code: &str) -> String {
+        minify_internal(code, None, &compress_only()).code
+    }
+
+    fn compressed_with(code: &str, config: &MinifyConfig) -> MinifyResult {
+        minify_internal(code, None, config)
+    }
+
+    #[test]
+    fn test_folds_constants() {
+        assert_eq!(compressed("x = 1 + 2 * 3;"), "x=7;");
+        assert_eq!(compressed("x = 'a' + 'b' + 1;"), "x=\"ab1\";");
+        assert_eq!(compressed("x = -1 - 2, y = 5 | 3, z = typeof 1 === 'number';"), "x=-3,y=7,z=!0;");
+        // Folding must not change what the neighbouring operators bind to
+        assert_eq!(compressed("x = a * 1 + 2;"), "x=a*1+2;");
+        assert_eq!(compressed("x = 2 ** 10;"), "x=2**10;");
+        // Results longer than the source are left alone
+        assert_eq!(compressed("x = 1 / 3;"), "x=1/3;");
+    }
+
+    #[test]
+    fn test_deeply_nested_literals_are_left_alone() {
+        let code = format!("x = {}1;", "!".repeat(10000));
+        assert_eq!(compressed(&code), code.replace(' ', ""));
+        // Parentheses are removed from the inside out, a limited depth per round
+        let code = format!("x = {}1{} + 2;", "(".repeat(1000), ")".repeat(1000));
+        assert_eq!(compressed(&code), "x=3;");
+    }
+
+    #[test]
+    fn test_folding_keeps_line_breaks_for_asi() {
+        // `return` followed by a line break returns undefined
+        assert_eq!(compressed("function f() { return\n1 + 2 }"), "function f(){return\n3}");
+        assert_eq!(compressed("function f() { return\ntrue && x }"), "function f(){return\nx}");
+        assert_eq!(compressed("function f() { return\n1 ? a : b }"), "function f(){return\na}");
+        assert_eq!(compressed("function f() { return\n(1) }"), "function f(){return\n1}");
+    }
+
+    #[test]
+    fn test_removes_dead_branches_after_defines() {
+        let mut config = compress_only();
+        config.add_define("process.env.NODE_ENV".to_string(), "\"production\"".to_string());
+        let code = "if (process.env.NODE_ENV !== \"production\") {\n  check();\n}\nrun();";
+        let result = compressed_with(code, &config);
+        assert_eq!(result.code, "run();");
+        assert_eq!(result.warnings, vec!["Dropped unreachable code at 1:44"]);
+
+        // Without the define the check stays
+        assert_eq!(compressed(code), "if(process.env.NODE_ENV!==\"production\"){check();}\nrun();");
+        // Assignments to a defined expression are not replaced
+        assert_eq!(
+            compressed_with("process.env.NODE_ENV = 'test';", &config).code,
+            "process.env.NODE_ENV='test';"
+        );
+    }
+
+    #[test]
+    fn test_constant_if_else() {
+        assert_eq!(compressed("if (false) { a(); } else { b(); }"), "b();");
+        assert_eq!(compressed("if (1) a(); else b();"), "a();");
+        // Block-scoped declarations keep their block
+        assert_eq!(compressed("if (true) { let x = f(); g(x); }"), "{let x=f();g(x);}");
+        // A single-statement body still needs a statement
+        assert_eq!(compressed("while (x) if (0) a();"), "while(x);");
+        // `var` is hoisted out of the dead branch, so it stays
+        assert_eq!(compressed("if (false) { var x = 1; }"), "if(!1){var x=1;}");
+    }
+
+    #[test]
+    fn test_conditional_and_logical() {
+        assert_eq!(compressed("x = true ? a : b;"), "x=a;");
+        assert_eq!(compressed("x = 0 ? a : b + c;"), "x=b+c;");
+        assert_eq!(compressed("x = false && f();"), "x=!1;");
+        assert_eq!(compressed("x = 1 && f();"), "x=f();");
+        assert_eq!(compressed("x = null ?? y;"), "x=y;");
+        assert_eq!(compressed("x = y || (1 < 2);"), "x=y||!0;");
+    }
+
+    #[test]
+    fn test_drop_console() {
+        let mut config = compress_only();
+        config.drop_console = true;
+        let result = compressed_with("console.log('a');\nrun(console.error(b));", &config);
+        assert_eq!(result.code, "run(void 0);");
+        assert_eq!(
+            result.warnings,
+            vec!["Dropped console.log call at 1:1", "Dropped console.error call at 2:5"]
+        );
+        // Local bindings named `console` are not the global one
+        assert_eq!(
+            compressed_with("function f(console) { console.log(1); }", &config).code,
+            "function f(console){console.log(1);}"
+        );
+        // A following call, member access or template still applies to the dropped call
+        assert_eq!(
+            compressed_with("a()\nconsole.log(1)\n(b)", &config).code,
+            "a();(void 0)\n(b)"
+        );
+        assert_eq!(compressed_with("console.log(1).x;", &config).code, "(void 0).x;");
+        assert_eq!(
+            compressed_with("if (a)\nconsole.log(1)\n(b)", &config).code,
+            "if(a)\n(void 0)\n(b)"
+        );
+        // Off by default
+        assert_eq!(compressed("console.log('a');"), "console.log('a');");
+    }
+
+    #[test]
+    fn test_drop_debugger() {
+        let result = compressed_with("function f() { debugger; return 1; }", &compress_only());
+        assert_eq!(result.code, "function f(){return 1;}");
+        assert_eq!(result.warnings, vec!["Dropped debugger statement at 1:16"]);
+        assert_eq!(compressed("if (x) debugger; y();"), "if(x);y();");
+
+        let mut config = compress_only();
+        config.drop_debugger = false;
+        assert_eq!(compressed_with("debugger;", &config).code, "debugger;");
+    }
+
+    #[test]
+    fn test_inlines_single_use_constants() {
+        let result = compressed_with(
+            "function f(x) { const LIMIT = 10; return x > LIMIT; }",
+            &compress_only(),
+        );
+        assert_eq!(result.code, "function f(x){return x>10;}");
+        assert_eq!(result.warnings, vec!["Inlined single-use constant `LIMIT` at 1:23"]);
+        // Shorthand properties keep their key
+        assert_eq!(
+            compressed("function f() { const mode = 'a'; return { mode }; }"),
+            "function f(){return{mode:\"a\"};}"
+        );
+        // Top-level constants and constants used twice stay
+        assert_eq!(compressed("const A = 1; f(A);"), "const A=1;f(A);");
+        assert_eq!(
+            compressed("function f() { const a = 1; return a + a; }"),
+            "function f(){const a=1;return a+a;}"
+        );
+    }
+
+    #[test]
+    fn test_keeps_loop_headers() {
+        assert_eq!(compressed("for (let i = 0; true;) { f(i); }"), "for(let i=0;!0;){f(i);}");
+        assert_eq!(compressed("'use strict'; 1; f();"), "'use strict';f();");
+    }
+
+    #[test]
+    fn test_compress_disabled() {
+        let config = MinifyConfig {
+            compress: false,
+            mangle: false,
+            ..MinifyConfig::default()
+        };
+        let result = compressed_with("if (false) { debugger; } x = 1 + 2;", &config);
+        assert_eq!(result.code, "if(false){debugger;}x=1+2;");
+        assert!(result.warnings.is_empty());
+    }
+}
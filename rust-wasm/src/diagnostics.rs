@@ -0,0 +1,181 @@
+//! Diagnostics reported to JavaScript callers
+//!
+//! Positions are 1-based lines and columns, with columns counted in UTF-16
+//! code units so they line up with JavaScript string indices and editors.
+
+use serde::{Deserialize, Serialize};
+
+use crate::ast::ParseError;
+use crate::utils::LineIndex;
+
+/// Context lines shown above and below the marked lines of a code frame
+const FRAME_LINES_ABOVE: usize = 2;
+const FRAME_LINES_BELOW: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// A problem found in the source, ready to show to a user
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// Machine-readable code, e.g. `unexpected-token`
+    pub code: String,
+    pub message: String,
+    /// Byte offsets of the offending source
+    pub start: usize,
+    pub end: usize,
+    pub line: usize,
+    pub column: usize,
+    pub end_line: usize,
+    pub end_column: usize,
+    /// The surrounding source with the offending part underlined
+    pub code_frame: String,
+}
+
+impl Diagnostic {
+    pub fn new(
+        severity: Severity,
+        code: &str,
+        message: &str,
+        start: usize,
+        end: usize,
+        source: &str,
+        line_index: &LineIndex,
+    ) -> Self {
+        // Spans of single-byte lookahead may end inside a multi-byte character
+        let mut start = start.min(source.len());
+        while !source.is_char_boundary(start) {
+            start -= 1;
+        }
+        let mut end = end.clamp(start, source.len());
+        while !source.is_char_boundary(end) {
+            end += 1;
+        }
+        let (line, column) = line_index.position(source, start);
+        let (end_line, end_column) = line_index.position(source, end);
+        Self {
+            severity,
+            code: code.to_string(),
+            message: message.to_string(),
+            start,
+            end,
+            line: line + 1,
+            column: column + 1,
+            end_line: end_line + 1,
+            end_column: end_column + 1,
+            code_frame: code_frame(source, line_index, start, end),
+        }
+    }
+}
+
+/// Error diagnostics for the syntax errors of a parsed module
+pub fn from_parse_errors(source: &str, errors: &[ParseError]) -> Vec<Diagnostic> {
+    if errors.is_empty() {
+        return Vec::new();
+    }
+    let line_index = LineIndex::new(source);
+    errors
+        .iter()
+        .map(|e| {
+            let (start, end) = (e.span.start, e.span.end);
+            Diagnostic::new(Severity::Error, e.code.as_str(), &e.message, start, end, source, &line_index)
+        })
+        .collect()
+}
+
+/// Render the lines around `start..end`, Babel style:
+///
+/// ```text
+///   1 | import a from "a";
+/// > 2 | const = 1;
+///     |       ^
+/// ```
+pub fn code_frame(source: &str, line_index: &LineIndex, start: usize, end: usize) -> String {
+    let (first, _) = line_index.position(source, start);
+    let (last, _) = line_index.position(source, end.max(start));
+    let from = first.saturating_sub(FRAME_LINES_ABOVE);
+    let to = (last + FRAME_LINES_BELOW).min(line_index.line_count() - 1);
+    let gutter = (to + 1).to_string().len();
+
+    let mut frame = String::new();
+    for line in from..=to {
+        let range = line_index.line_range(source, line);
+        let text = &source[range.clone()];
+        let marked = first <= line && line <= last;
+        frame.push_str(&format!("{} {:>gutter$} |", if marked { '>' } else { ' ' }, line + 1));
+        if !text.is_empty() {
+            frame.push(' ');
+            frame.push_str(text);
+        }
+        frame.push('\n');
+        if !marked {
+            continue;
+        }
+
+        // Character range of the span on this line
+        let (line_start, line_end) = (range.start, range.end);
+        let mark_start = start.clamp(line_start, line_end);
+        let mark_end = end.clamp(mark_start, line_end);
+        let indent: String = source[line_start..mark_start]
+            .chars()
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect();
+        let width = source[mark_start..mark_end].chars().count().max(1);
+        frame.push_str(&format!("  {:>gutter$} | {}{}\n", "", indent, "^".repeat(width)));
+    }
+    frame.pop();
+    frame
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::js_parser::{parse_program, Syntax};
+
+    fn diagnostics(source: &str) -> Vec<Diagnostic> {
+        let program = parse_program(source, Syntax { typescript: false, jsx: false });
+        from_parse_errors(source, &program.errors)
+    }
+
+    #[test]
+    fn test_position_and_frame() {
+        let source = "import a from 'a';\nconst = 1;\nfoo();";
+        let diags = diagnostics(source);
+        assert_eq!(diags.len(), 1);
+        let d = &diags[0];
+        assert_eq!(d.severity, Severity::Error);
+        assert_eq!(d.code, "unexpected-token");
+        assert_eq!((d.line, d.column), (2, 7));
+        assert_eq!(
+            d.code_frame,
+            "  1 | import a from 'a';\n> 2 | const = 1;\n    |       ^\n  3 | foo();"
+        );
+    }
+
+    #[test]
+    fn test_utf16_columns() {
+        // The emoji is two UTF-16 code units but one character in the frame
+        let source = "let s = '😀'; const = 2;";
+        let d = &diagnostics(source)[0];
+        assert_eq!((d.line, d.column), (1, 21));
+        assert!(d.code_frame.ends_with("\n    |                    ^"));
+    }
+
+    #[test]
+    fn test_lexer_errors() {
+        let d = &diagnostics("const s = 'open\nnext();")[0];
+        assert_eq!(d.code, "unterminated");
+        assert_eq!((d.line, d.column), (1, 11));
+
+        // The span covers the whole multi-byte character
+        let all = diagnostics("a § b;");
+        let d = all.iter().find(|d| d.code == "invalid-character").unwrap();
+        assert_eq!((d.start, d.end), (2, 4));
+        assert!(d.code_frame.ends_with("|   ^"));
+    }
+}
@@ -0,0 +1,451 @@
+//! Incremental reparsing for the dev server
+//!
+//! A `ParseHandle` keeps the parse result split by top-level statement. An
+//! edit reparses only the statements it touches, plus one neighbour on each
+//! side so that ASI changes across the boundary are picked up, and moves the
+//! results of the others. Anything unusual (syntax errors, an edit that
+//! reaches past the reparsed range) falls back to a full parse.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use wasm_bindgen::prelude::*;
+
+use crate::ast::Program;
+use crate::js_parser;
+use crate::parser::{
+    self, AnnotationInfo, DependencyInfo, DependencyKind, DirectiveInfo, ExportInfo, ExportKind, ImportInfo,
+    ImportKind, ImportSpecifierInfo, Language, ParseResult,
+};
+use crate::utils::{byte_offset, LineIndex};
+
+/// What an edit changed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReparseResult {
+    /// Imports, exports, URL dependencies or top-level await differ from
+    /// before the edit, so the dependency graph needs updating
+    pub signature_changed: bool,
+    /// Top-level statements taken over from the previous parse
+    pub reused_statements: usize,
+    /// Top-level statements parsed again
+    pub reparsed_statements: usize,
+    /// The whole file was parsed again
+    pub full_reparse: bool,
+}
+
+/// A parsed file that edits can be applied to
+#[wasm_bindgen]
+pub struct ParseHandle {
+    source: String,
+    filename: Option<String>,
+    hint: Option<String>,
+    /// Language before JSX is taken into account
+    language: Language,
+    /// `None` when the last parse had errors; the next edit parses everything
+    statements: Option<Vec<Statement>>,
+    result: ParseResult,
+}
+
+#[wasm_bindgen]
+impl ParseHandle {
+    /// Current parse result
+    #[wasm_bindgen]
+    pub fn result(&self) -> Result<JsValue, JsError> {
+        parser::to_js(&self.result)
+    }
+
+    /// Current source text
+    #[wasm_bindgen]
+    pub fn source(&self) -> String {
+        self.source.clone()
+    }
+}
+
+impl ParseHandle {
+    pub fn new(source: &str, filename: Option<String>, hint: Option<String>) -> Self {
+        let (program, language) = parser::parse_classified(source, filename.as_deref(), hint.as_deref());
+        let statements = (program.errors.is_empty() && !program.body.is_empty()).then(|| split(&program, source));
+        Self {
+            result: parser::analyze_program(&program, source, language),
+            source: source.to_string(),
+            filename,
+            hint,
+            language,
+            statements,
+        }
+    }
+
+    pub fn parse_result(&self) -> &ParseResult {
+        &self.result
+    }
+}
+
+/// Analysis of one top-level statement. It owns the text from the end of the
+/// previous statement to its own end (the last one owns the rest of the file),
+/// so leading comments move with it.
+#[derive(Debug, Clone)]
+struct Statement {
+    start: usize,
+    end: usize,
+    /// Where the statement itself starts, after leading whitespace and comments
+    stmt_start: usize,
+    imports: Vec<ImportInfo>,
+    exports: Vec<ExportInfo>,
+    dependencies: Vec<DependencyInfo>,
+    annotations: Vec<AnnotationInfo>,
+    directive: Option<DirectiveInfo>,
+    has_jsx: bool,
+    has_top_level_await: bool,
+    is_type_only: bool,
+}
+
+/// Like `reparse_internal`, with the range in UTF-16 code units as editors
+/// and JavaScript string indices count them
+pub fn reparse_utf16_internal(
+    handle: &mut ParseHandle,
+    start: usize,
+    end: usize,
+    text: &str,
+) -> Result<ReparseResult, String> {
+    let (Some(byte_start), Some(byte_end)) = (byte_offset(&handle.source, start), byte_offset(&handle.source, end))
+    else {
+        let length: usize = handle.source.chars().map(char::len_utf16).sum();
+        return Err(format!("Invalid edit range {start}..{end} for a source of {length} UTF-16 code units"));
+    };
+    reparse_internal(handle, byte_start, byte_end, text)
+}
+
+/// Apply an edit to the handle's source and update its parse result
+pub fn reparse_internal(handle: &mut ParseHandle, start: usize, end: usize, text: &str) -> Result<ReparseResult, String> {
+    let old = &handle.source;
+    if start > end || end > old.len() || !old.is_char_boundary(start) || !old.is_char_boundary(end) {
+        return Err(format!("Invalid edit range {start}..{end} for a source of {} bytes", old.len()));
+    }
+    let source = format!("{}{}{}", &old[..start], text, &old[end..]);
+
+    let reparsed = handle
+        .statements
+        .take()
+        .and_then(|statements| reparse_statements(statements, &source, handle.language, start, end, text.len()));
+    let (next, result) = match reparsed {
+        Some((statements, reused, reparsed)) => {
+            let next = ParseHandle {
+                result: assemble(&statements, &source, handle.language),
+                source,
+                filename: handle.filename.take(),
+                hint: handle.hint.take(),
+                language: handle.language,
+                statements: Some(statements),
+            };
+            (next, (reused, reparsed, false))
+        }
+        None => {
+            let next = ParseHandle::new(&source, handle.filename.take(), handle.hint.take());
+            let reparsed = next.statements.as_ref().map_or(0, Vec::len);
+            (next, (0, reparsed, true))
+        }
+    };
+    let (reused_statements, reparsed_statements, full_reparse) = result;
+    let signature_changed = Signature::of(&next.result) != Signature::of(&handle.result);
+    *handle = next;
+    Ok(ReparseResult {
+        signature_changed,
+        reused_statements,
+        reparsed_statements,
+        full_reparse,
+    })
+}
+
+/// Reparse the statements around `start..end` (old offsets) of the new
+/// `source`. Returns the statements with the counts of reused and reparsed
+/// ones, or `None` when a full parse is needed.
+fn reparse_statements(
+    mut statements: Vec<Statement>,
+    source: &str,
+    language: Language,
+    start: usize,
+    end: usize,
+    inserted: usize,
+) -> Option<(Vec<Statement>, usize, usize)> {
+    let delta = inserted as isize - (end - start) as isize;
+    let count = statements.len();
+    let first = statements.partition_point(|s| s.end < start).min(count - 1);
+    let last = statements.partition_point(|s| s.start <= end).saturating_sub(1).max(first);
+    let (first, last) = (first.saturating_sub(1), (last + 1).min(count - 1));
+
+    let region_start = statements[first].start;
+    let region_end = shift(statements[last].end, delta);
+    let slice = &source[region_start..region_end];
+    let program = js_parser::parse_program(slice, language.syntax());
+    if !program.errors.is_empty() || program.body.is_empty() {
+        return None;
+    }
+    // The trailing neighbour must still be a statement of its own, or the
+    // boundary with the next, unparsed statement may have changed
+    if last + 1 < count {
+        let neighbour = shift(statements[last].stmt_start, delta) - region_start;
+        if program.body.last().map(|s| s.span().start) != Some(neighbour) {
+            return None;
+        }
+    }
+
+    let line_index = LineIndex::new(source);
+    let mut reparsed = split(&program, slice);
+    for statement in &mut reparsed {
+        statement.relocate(region_start as isize, source, &line_index);
+    }
+    let suffix = statements.split_off(last + 1);
+    statements.truncate(first);
+    let (reused, reparsed_count) = (statements.len() + suffix.len(), reparsed.len());
+    statements.extend(reparsed);
+    statements.extend(suffix.into_iter().map(|mut statement| {
+        statement.relocate(delta, source, &line_index);
+        statement
+    }));
+    Some((statements, reused, reparsed_count))
+}
+
+/// Per-statement results of a parse of `source`, with offsets into it
+fn split(program: &Program, source: &str) -> Vec<Statement> {
+    let body = &program.body;
+    let mut statements: Vec<_> = body
+        .iter()
+        .enumerate()
+        .map(|(i, stmt)| Statement {
+            start: if i == 0 { 0 } else { body[i - 1].span().end },
+            end: if i + 1 == body.len() { source.len() } else { stmt.span().end },
+            stmt_start: stmt.span().start,
+            imports: Vec::new(),
+            exports: Vec::new(),
+            dependencies: Vec::new(),
+            annotations: Vec::new(),
+            directive: parser::directive(stmt, source),
+            has_jsx: parser::stmt_has_jsx(stmt),
+            has_top_level_await: parser::stmt_has_top_level_await(stmt),
+            is_type_only: stmt.is_type_only(),
+        })
+        .collect();
+
+    let owner = |statements: &[Statement], offset: usize| {
+        statements.partition_point(|s| s.end <= offset).min(statements.len() - 1)
+    };
+    for import in parser::collect_imports(program, source) {
+        let i = owner(&statements, import.start);
+        statements[i].imports.push(import);
+    }
+    for export in parser::collect_exports(program, source) {
+        let i = owner(&statements, export.start);
+        statements[i].exports.push(export);
+    }
+    for dependency in parser::collect_url_dependencies(program, source) {
+        let i = owner(&statements, dependency.start);
+        statements[i].dependencies.push(dependency);
+    }
+    for annotation in parser::collect_annotations(program, source) {
+        let i = owner(&statements, annotation.start);
+        statements[i].annotations.push(annotation);
+    }
+    statements
+}
+
+/// The parse result the statements add up to
+fn assemble(statements: &[Statement], source: &str, language: Language) -> ParseResult {
+    let has_jsx = statements.iter().any(|s| s.has_jsx);
+    let language = language.with_jsx(has_jsx);
+    ParseResult {
+        imports: statements.iter().flat_map(|s| s.imports.iter().cloned()).collect(),
+        exports: statements.iter().flat_map(|s| s.exports.iter().cloned()).collect(),
+        dependencies: statements.iter().flat_map(|s| s.dependencies.iter().cloned()).collect(),
+        directives: statements.iter().map_while(|s| s.directive.clone()).collect(),
+        hashbang: source
+            .starts_with("#!")
+            .then(|| source[..source.find(['\n', '\r']).unwrap_or(source.len())].to_string()),
+        annotations: statements.iter().flat_map(|s| s.annotations.iter().cloned()).collect(),
+        has_jsx,
+        has_top_level_await: statements.iter().any(|s| s.has_top_level_await),
+        is_typescript: language.is_typescript(),
+        language,
+        is_type_only: language == Language::Dts || statements.iter().all(|s| s.is_type_only),
+        diagnostics: Vec::new(),
+        parse_time_us: 0,
+    }
+}
+
+fn shift(offset: usize, delta: isize) -> usize {
+    offset.saturating_add_signed(delta)
+}
+
+impl Statement {
+    /// Move every offset by `delta` and recompute line/column in `source`
+    fn relocate(&mut self, delta: isize, source: &str, line_index: &LineIndex) {
+        let position = |offset: usize| {
+            let (line, column) = line_index.position(source, offset);
+            (line + 1, column + 1)
+        };
+        self.start = shift(self.start, delta);
+        self.end = shift(self.end, delta);
+        self.stmt_start = shift(self.stmt_start, delta);
+        for import in &mut self.imports {
+            import.start = shift(import.start, delta);
+            import.end = shift(import.end, delta);
+            (import.line, import.column) = position(import.start);
+            for comment in &mut import.magic_comments {
+                comment.start = shift(comment.start, delta);
+                comment.end = shift(comment.end, delta);
+            }
+        }
+        for export in &mut self.exports {
+            export.start = shift(export.start, delta);
+            export.end = shift(export.end, delta);
+            (export.line, export.column) = position(export.start);
+        }
+        for dependency in &mut self.dependencies {
+            dependency.start = shift(dependency.start, delta);
+            dependency.end = shift(dependency.end, delta);
+            dependency.specifier_start = shift(dependency.specifier_start, delta);
+            dependency.specifier_end = shift(dependency.specifier_end, delta);
+            (dependency.line, dependency.column) = position(dependency.start);
+        }
+        for annotation in &mut self.annotations {
+            annotation.start = shift(annotation.start, delta);
+            annotation.end = shift(annotation.end, delta);
+            annotation.target_start = annotation.target_start.map(|offset| shift(offset, delta));
+            annotation.target_end = annotation.target_end.map(|offset| shift(offset, delta));
+        }
+        if let Some(directive) = &mut self.directive {
+            directive.start = shift(directive.start, delta);
+            directive.end = shift(directive.end, delta);
+        }
+    }
+}
+
+/// What other modules depend on, positions left out
+#[derive(PartialEq)]
+struct Signature<'a> {
+    imports: Vec<ImportSignature<'a>>,
+    exports: Vec<ExportSignature<'a>>,
+    dependencies: Vec<(DependencyKind, &'a str, bool)>,
+    has_top_level_await: bool,
+}
+
+/// Kind, exported name, local name, source and type-only flag
+type ExportSignature<'a> = (ExportKind, &'a str, Option<&'a str>, Option<&'a str>, bool);
+
+#[derive(PartialEq)]
+struct ImportSignature<'a> {
+    source: &'a str,
+    kind: ImportKind,
+    is_type_only: bool,
+    specifiers: &'a [ImportSpecifierInfo],
+    attributes: &'a BTreeMap<String, String>,
+    magic_comments: Vec<(&'a str, Option<&'a str>)>,
+}
+
+impl<'a> Signature<'a> {
+    fn of(result: &'a ParseResult) -> Self {
+        Self {
+            imports: result
+                .imports
+                .iter()
+                .map(|i| ImportSignature {
+                    source: &i.source,
+                    kind: i.kind,
+                    is_type_only: i.is_type_only,
+                    specifiers: &i.specifiers,
+                    attributes: &i.attributes,
+                    magic_comments: i.magic_comments.iter().map(|c| (c.key.as_str(), c.value.as_deref())).collect(),
+                })
+                .collect(),
+            exports: result
+                .exports
+                .iter()
+                .map(|e| (e.kind, e.exported.as_str(), e.local.as_deref(), e.source.as_deref(), e.is_type_only))
+                .collect(),
+            dependencies: result
+                .dependencies
+                .iter()
+                .map(|d| (d.kind, d.specifier.as_str(), d.is_module))
+                .collect(),
+            has_top_level_await: result.has_top_level_await,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Apply edits one by one and compare against a fresh parse each time
+    fn check(source: &str, edits: &[(&str, &str)]) -> Vec<ReparseResult> {
+        let mut handle = ParseHandle::new(source, Some("app.tsx".to_string()), None);
+        let mut results = Vec::new();
+        for (find, replace) in edits {
+            let start = handle.source.find(find).unwrap();
+            let result = reparse_internal(&mut handle, start, start + find.len(), replace).unwrap();
+            let fresh = parser::parse_file_internal(&handle.source, Some("app.tsx"), None);
+            assert_eq!(format!("{:?}", handle.result), format!("{:?}", fresh), "after {find:?} -> {replace:?}");
+            results.push(result);
+        }
+        results
+    }
+
+    #[test]
+    fn test_body_edit_reuses_statements() {
+        let source = "import a from './a';\nconst b = 1;\nexport function f() {\n  return a + b;\n}\nconst c = 2;\nexport const d = 3;\n";
+        let results = check(source, &[("a + b", "a * b * 2"), ("const c = 2", "const c = 2 + 1")]);
+        assert!(!results[0].signature_changed);
+        assert!(!results[0].full_reparse);
+        assert_eq!((results[0].reused_statements, results[0].reparsed_statements), (2, 3));
+        assert!(!results[1].signature_changed);
+    }
+
+    #[test]
+    fn test_signature_changes() {
+        let source = "import a from './a';\n\nexport const b = a;\n\nfunction g() {}\n";
+        let results = check(
+            source,
+            &[
+                ("'./a'", "'./other'"),
+                ("export const b", "export const renamed"),
+                ("function g() {}", "function g() {}\nexport { g };"),
+                ("function g() {}", "function g() { return <div/>; }"),
+            ],
+        );
+        let changed: Vec<_> = results.iter().map(|r| r.signature_changed).collect();
+        assert_eq!(changed, vec![true, true, true, false]);
+    }
+
+    #[test]
+    fn test_falls_back_to_full_parse() {
+        let source = "const a = 1;\nconst b = 2;\nconst c = 3;\nconst d = `x`;\nconst e = 5;\n";
+        // An unterminated template swallows the following statements
+        let results = check(source, &[("const b = 2;", "const b = `;"), ("const b = `;", "const b = 2;")]);
+        assert!(results[0].full_reparse);
+        assert!(results[1].full_reparse);
+
+        // ASI: the edited line now continues into the next one
+        let source = "let x = 1;\nlet y = f\n;(z)\nconst w = 2;\n";
+        check(source, &[("\n;(z)", "\n(z)"), ("let x = 1;", "let x = 1 +")]);
+    }
+
+    #[test]
+    fn test_invalid_range() {
+        let mut handle = ParseHandle::new("const a = 'é';", None, None);
+        assert!(reparse_internal(&mut handle, 12, 13, "x").is_err());
+        assert!(reparse_internal(&mut handle, 5, 100, "x").is_err());
+    }
+
+    #[test]
+    fn test_utf16_range() {
+        let source = "const a = '😀é';
+const b = 1;
+";
+        let mut handle = ParseHandle::new(source, None, None);
+        // `1` is at UTF-16 index 27 but byte offset 30
+        let index: usize = source[..source.find('1').unwrap()].chars().map(char::len_utf16).sum();
+        assert_eq!(index, 27);
+        reparse_utf16_internal(&mut handle, index, index + 1, "2").unwrap();
+        assert_eq!(handle.source, "const a = '😀é';\nconst b = 2;\n");
+        // The middle of the emoji is not a valid position
+        assert!(reparse_utf16_internal(&mut handle, 12, 12, "x").is_err());
+    }
+}
@@ -0,0 +1,3727 @@
+//! Recursive-descent parser for JavaScript, TypeScript and JSX
+//!
+//! Builds the `ast` tree on top of `lexer::Lexer`, reading one token at a
+//! time so the parser decides how ambiguous characters are read:
+//! - `/` at the start of an operand is re-read as a regex
+//! - `}` closing a template substitution is re-read as the template continuation
+//! - `>>` and `>=` closing type arguments are split
+//! - JSX tags and text are scanned by byte, the lexer only runs inside `{}`
+//!
+//! Syntax errors are recorded and parsing resumes at the next statement, so a
+//! typo doesn't hide the rest of the module.
+
+use std::collections::HashSet;
+
+use crate::ast::*;
+use crate::lexer::{self, is_id_continue, is_id_start, Lexer, Token, TokenKind};
+
+/// Nesting limit, keeping deeply nested input from overflowing the WASM stack
+const MAX_DEPTH: usize = 150;
+
+/// Words that can never be identifiers
+const RESERVED_WORDS: &[&str] = &[
+    "break", "case", "catch", "class", "const", "continue", "debugger", "default", "delete",
+    "do", "else", "enum", "export", "extends", "false", "finally", "for", "function", "if",
+    "import", "in", "instanceof", "new", "null", "return", "super", "switch", "this", "throw",
+    "true", "try", "typeof", "var", "void", "while", "with",
+];
+
+/// TypeScript keyword types
+const TYPE_KEYWORDS: &[&str] = &[
+    "any", "unknown", "number", "bigint", "boolean", "string", "symbol", "object", "never",
+    "void", "undefined", "null", "intrinsic",
+];
+
+/// Which extensions of JavaScript to accept
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Syntax {
+    pub typescript: bool,
+    pub jsx: bool,
+}
+
+impl Syntax {
+    /// Syntax implied by a file extension, `None` when it says nothing
+    pub fn from_filename(filename: &str) -> Option<Self> {
+        let (_, ext) = filename.rsplit_once('.')?;
+        let (typescript, jsx) = match ext.to_ascii_lowercase().as_str() {
+            "ts" | "mts" | "cts" => (true, false),
+            "tsx" => (true, true),
+            "js" | "mjs" | "cjs" | "jsx" => (false, true),
+            _ => return None,
+        };
+        Some(Self { typescript, jsx })
+    }
+}
+
+/// Parse `source` with the syntax its filename implies. Without one, parse as
+/// TSX and fall back to plain TypeScript (for `<T>x` assertions) when that
+/// gives fewer errors.
+pub fn parse(source: &str, filename: Option<&str>) -> (Program, Syntax) {
+    if let Some(syntax) = filename.and_then(Syntax::from_filename) {
+        return (parse_program(source, syntax), syntax);
+    }
+    let tsx = Syntax { typescript: true, jsx: true };
+    let program = parse_program(source, tsx);
+    if program.errors.is_empty() || !source.contains('<') {
+        return (program, tsx);
+    }
+    let ts = Syntax { typescript: true, jsx: false };
+    let retry = parse_program(source, ts);
+    if retry.errors.len() < program.errors.len() {
+        (retry, ts)
+    } else {
+        (program, tsx)
+    }
+}
+
+/// Parse a whole module
+pub fn parse_program(source: &str, syntax: Syntax) -> Program {
+    let mut parser = JsParser::new(source, syntax);
+    let hashbang = source
+        .starts_with("#!")
+        .then(|| Span::new(0, source.find(['\n', '\r']).unwrap_or(source.len())));
+    let body = parser.parse_statements(None);
+
+    let mut errors = parser.errors;
+    errors.extend(parser.lexer.errors.iter().map(|e| ParseError {
+        code: e.code,
+        message: e.message.clone(),
+        // One character, which may be several bytes
+        span: Span::new(e.pos, e.pos + source[e.pos..].chars().next().map_or(0, char::len_utf8)),
+    }));
+    errors.sort_by_key(|e| e.span.start);
+
+    Program {
+        span: Span::new(0, source.len()),
+        hashbang,
+        body,
+        comments: std::mem::take(&mut parser.lexer.comments),
+        errors,
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct Context {
+    in_function: bool,
+    in_async: bool,
+    in_generator: bool,
+    /// `in` is not an operator (`for (x in ...)` heads)
+    no_in: bool,
+    /// Parsing the middle of `a ? b : c`, where `(x): y => z` must not swallow the `:`
+    in_cond_cons: bool,
+}
+
+/// Parser state to rewind to after a failed speculative parse
+struct Snapshot {
+    pos: usize,
+    tok: Token,
+    prev_end: usize,
+    comments: usize,
+    lex_errors: usize,
+    errors: usize,
+    error_count: usize,
+    recovering: bool,
+    ctx: Context,
+    depth: usize,
+}
+
+/// Parsed arrow function head: type parameters, parameters, return type
+type ArrowHead = (Option<TsTypeParams>, Vec<Param>, Span, Option<TsTypeAnn>);
+
+struct JsParser<'a> {
+    source: &'a str,
+    bytes: &'a [u8],
+    lexer: Lexer<'a>,
+    tok: Token,
+    /// End of the last consumed token
+    prev_end: usize,
+    ts: bool,
+    jsx: bool,
+    ctx: Context,
+    errors: Vec<ParseError>,
+    /// Errors seen, including the ones not kept while recovering
+    error_count: usize,
+    /// An error was reported in the current statement
+    recovering: bool,
+    depth: usize,
+    /// Positions where an arrow function head failed to parse
+    arrow_failures: HashSet<usize>,
+    /// Decorators read ahead of `export` / `class`
+    pending_decorators: Vec<Decorator>,
+}
+
+impl<'a> JsParser<'a> {
+    fn new(source: &'a str, syntax: Syntax) -> Self {
+        let mut lexer = Lexer::new(source);
+        let tok = lexer.next_token(false);
+        Self {
+            source,
+            bytes: source.as_bytes(),
+            lexer,
+            tok,
+            prev_end: 0,
+            ts: syntax.typescript,
+            jsx: syntax.jsx,
+            ctx: Context::default(),
+            errors: Vec::new(),
+            error_count: 0,
+            recovering: false,
+            depth: 0,
+            arrow_failures: HashSet::new(),
+            pending_decorators: Vec::new(),
+        }
+    }
+
+    // -----------------------------------------------------------------------
+    // Token helpers
+    // -----------------------------------------------------------------------
+
+    #[inline]
+    fn text(&self) -> &'a str {
+        let source = self.source;
+        self.tok.text(source)
+    }
+
+    #[inline]
+    fn advance(&mut self) {
+        self.prev_end = self.tok.end;
+        self.tok = self.lexer.next_token(false);
+    }
+
+    #[inline]
+    fn is(&self, punct: &str) -> bool {
+        self.tok.is_punct(self.source, punct)
+    }
+
+    #[inline]
+    fn is_word(&self, word: &str) -> bool {
+        self.tok.is_word(self.source, word)
+    }
+
+    #[inline]
+    fn at_eof(&self) -> bool {
+        self.tok.kind == TokenKind::Eof
+    }
+
+    fn eat(&mut self, punct: &str) -> bool {
+        if self.is(punct) {
+            self.advance();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn eat_word(&mut self, word: &str) -> bool {
+        if self.is_word(word) {
+            self.advance();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, punct: &str) -> bool {
+        if self.eat(punct) {
+            return true;
+        }
+        self.unexpected(&format!("Expected `{}`", punct));
+        false
+    }
+
+    /// Record a syntax error. Only the first error of a statement is kept;
+    /// the rest are usually knock-on effects of the same mistake.
+    fn error(&mut self, code: ErrorCode, message: &str, span: Span) {
+        self.error_count += 1;
+        if self.recovering {
+            return;
+        }
+        self.recovering = true;
+        self.errors.push(ParseError { code, message: message.to_string(), span });
+    }
+
+    fn unexpected(&mut self, expected: &str) {
+        let span = Span::new(self.tok.start, self.tok.end);
+        let message = if self.at_eof() {
+            format!("{} but found end of file", expected)
+        } else {
+            format!("{} but found `{}`", expected, self.text())
+        };
+        self.error(ErrorCode::UnexpectedToken, &message, span);
+    }
+
+    /// The token after the current one
+    fn peek(&mut self) -> Token {
+        let pos = self.lexer.pos();
+        let comments = self.lexer.comments.len();
+        let errors = self.lexer.errors.len();
+        let tok = self.lexer.next_token(false);
+        self.lexer.reset(pos);
+        self.lexer.comments.truncate(comments);
+        self.lexer.errors.truncate(errors);
+        tok
+    }
+
+    fn peek_is(&mut self, punct: &str) -> bool {
+        let tok = self.peek();
+        tok.is_punct(self.source, punct)
+    }
+
+    fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            pos: self.lexer.pos(),
+            tok: self.tok,
+            prev_end: self.prev_end,
+            comments: self.lexer.comments.len(),
+            lex_errors: self.lexer.errors.len(),
+            errors: self.errors.len(),
+            error_count: self.error_count,
+            recovering: self.recovering,
+            ctx: self.ctx,
+            depth: self.depth,
+        }
+    }
+
+    fn restore(&mut self, snapshot: Snapshot) {
+        self.lexer.reset(snapshot.pos);
+        self.tok = snapshot.tok;
+        self.prev_end = snapshot.prev_end;
+        self.lexer.comments.truncate(snapshot.comments);
+        self.lexer.errors.truncate(snapshot.lex_errors);
+        self.errors.truncate(snapshot.errors);
+        self.error_count = snapshot.error_count;
+        self.recovering = snapshot.recovering;
+        self.ctx = snapshot.ctx;
+        self.depth = snapshot.depth;
+    }
+
+    /// Run `f`, rewinding when it returns `None` or reports an error
+    fn try_parse<T>(&mut self, f: impl FnOnce(&mut Self) -> Option<T>) -> Option<T> {
+        let snapshot = self.snapshot();
+        let errors = self.error_count;
+        match f(self) {
+            Some(value) if self.error_count == errors => Some(value),
+            _ => {
+                self.restore(snapshot);
+                None
+            }
+        }
+    }
+
+    /// Continue lexing at a byte offset, dropping anything lexed past it
+    fn resync(&mut self, pos: usize) {
+        let comments = self.lexer.comments.partition_point(|c| c.start < pos);
+        self.lexer.comments.truncate(comments);
+        let errors = self.lexer.errors.partition_point(|e| e.pos < pos);
+        self.lexer.errors.truncate(errors);
+        self.lexer.reset(pos);
+        self.prev_end = pos;
+        self.tok = self.lexer.next_token(false);
+    }
+
+    fn with_ctx<T>(&mut self, ctx: Context, f: impl FnOnce(&mut Self) -> T) -> T {
+        let saved = self.ctx;
+        self.ctx = ctx;
+        let result = f(self);
+        self.ctx = saved;
+        result
+    }
+
+    /// Run `f` inside brackets, where `in` is an operator again
+    fn nested<T>(&mut self, f: impl FnOnce(&mut Self) -> T) -> T {
+        let ctx = Context { no_in: false, in_cond_cons: false, ..self.ctx };
+        self.with_ctx(ctx, f)
+    }
+
+    /// Run `f` as the body of a function
+    fn function_body<T>(&mut self, is_async: bool, is_generator: bool, f: impl FnOnce(&mut Self) -> T) -> T {
+        let ctx = Context {
+            in_function: true,
+            in_async: is_async,
+            in_generator: is_generator,
+            ..Context::default()
+        };
+        self.with_ctx(ctx, f)
+    }
+
+    /// Track nesting; past the limit, report once and skip to the end
+    fn enter(&mut self) -> bool {
+        if self.depth >= MAX_DEPTH {
+            let span = Span::new(self.tok.start, self.tok.end);
+            self.error(ErrorCode::TooDeep, "Nesting is too deep", span);
+            self.lexer.reset(self.source.len());
+            self.advance();
+            return false;
+        }
+        self.depth += 1;
+        true
+    }
+
+    fn leave(&mut self) {
+        self.depth -= 1;
+    }
+
+    /// Automatic semicolon insertion
+    fn semicolon(&mut self) {
+        if self.eat(";") || self.is("}") || self.at_eof() || self.tok.newline_before {
+            return;
+        }
+        self.unexpected("Expected `;`");
+    }
+
+    fn span_from(&self, start: usize) -> Span {
+        Span::new(start, self.prev_end.max(start))
+    }
+
+    fn is_identifier(&self) -> bool {
+        self.tok.kind == TokenKind::Name && !RESERVED_WORDS.contains(&self.text())
+    }
+
+    fn ident_from_token(&self) -> Ident {
+        let raw = self.text();
+        let name = if raw.contains('\\') { lexer::decode_escapes(raw) } else { raw.to_string() };
+        Ident { span: Span::new(self.tok.start, self.tok.end), name }
+    }
+
+    /// Any word, keywords included (property names)
+    fn parse_ident_name(&mut self) -> Ident {
+        if self.tok.kind != TokenKind::Name {
+            self.unexpected("Expected an identifier");
+            return Ident { span: Span::new(self.tok.start, self.tok.start), name: String::new() };
+        }
+        let ident = self.ident_from_token();
+        self.advance();
+        ident
+    }
+
+    fn parse_binding_ident(&mut self) -> Ident {
+        if !(self.is_identifier() || self.ts && self.is_word("this")) {
+            self.unexpected("Expected an identifier");
+            return Ident { span: Span::new(self.tok.start, self.tok.start), name: String::new() };
+        }
+        let ident = self.ident_from_token();
+        self.advance();
+        ident
+    }
+
+    fn parse_string_lit(&mut self) -> Lit {
+        if self.tok.kind != TokenKind::String {
+            self.unexpected("Expected a string");
+            return Lit { span: Span::new(self.tok.start, self.tok.start), value: LitValue::Str(String::new()) };
+        }
+        let lit = Lit {
+            span: Span::new(self.tok.start, self.tok.end),
+            value: LitValue::Str(lexer::string_value(self.text())),
+        };
+        self.advance();
+        lit
+    }
+
+    /// Can the current token start an expression?
+    fn starts_expression(&self) -> bool {
+        match self.tok.kind {
+            TokenKind::Eof => false,
+            TokenKind::Name => !matches!(self.text(), "in" | "instanceof" | "as" | "satisfies" | "of"),
+            TokenKind::Punct => matches!(
+                self.text(),
+                "(" | "[" | "{" | "!" | "~" | "+" | "-" | "++" | "--" | "/" | "/=" | "<" | "@" | "#" | "..."
+            ),
+            _ => true,
+        }
+    }
+
+    // -----------------------------------------------------------------------
+    // Statements
+    // -----------------------------------------------------------------------
+
+    fn parse_statements(&mut self, terminator: Option<&str>) -> Vec<Stmt> {
+        let mut body = Vec::new();
+        while !self.at_eof() && !terminator.is_some_and(|t| self.is(t)) {
+            let start = self.tok.start;
+            self.recovering = false;
+            let errors = self.error_count;
+            body.push(self.parse_statement());
+            if self.error_count > errors {
+                self.recover(start);
+            }
+        }
+        body
+    }
+
+    /// Skip to the next statement after an error
+    fn recover(&mut self, start: usize) {
+        if self.tok.start == start && !self.at_eof() {
+            self.advance();
+        }
+        while !self.at_eof() && !self.is("}") && !self.tok.newline_before {
+            if self.eat(";") {
+                break;
+            }
+            self.advance();
+        }
+    }
+
+    fn parse_statement(&mut self) -> Stmt {
+        if !self.enter() {
+            return Stmt::Empty(Span::new(self.prev_end, self.prev_end));
+        }
+        let stmt = self.parse_statement_inner();
+        self.leave();
+        stmt
+    }
+
+    fn parse_statement_inner(&mut self) -> Stmt {
+        let start = self.tok.start;
+        match self.tok.kind {
+            TokenKind::Punct => match self.text() {
+                "{" => return Stmt::Block(self.parse_block()),
+                ";" => {
+                    self.advance();
+                    return Stmt::Empty(self.span_from(start));
+                }
+                "@" => {
+                    let decorators = self.parse_decorators();
+                    self.pending_decorators = decorators;
+                    if self.is_word("export") {
+                        return self.parse_export();
+                    }
+                    return self.parse_class_declaration(start, Vec::new());
+                }
+                _ => {}
+            },
+            TokenKind::Name => {
+                if let Some(stmt) = self.parse_keyword_statement(start) {
+                    return stmt;
+                }
+            }
+            _ => {}
+        }
+        self.parse_expression_statement(start)
+    }
+
+    fn parse_expression_statement(&mut self, start: usize) -> Stmt {
+        let expr = self.parse_expr();
+        self.semicolon();
+        Stmt::Expr(ExprStmt { span: self.span_from(start), expr })
+    }
+
+    /// Statements starting with a word; `None` means "parse an expression"
+    fn parse_keyword_statement(&mut self, start: usize) -> Option<Stmt> {
+        let stmt = match self.text() {
+            "var" => self.parse_var_statement(start, VarKind::Var),
+            "const" => {
+                if self.ts && self.peek().is_word(self.source, "enum") {
+                    self.advance();
+                    return Some(self.parse_enum(start, true, false));
+                }
+                self.parse_var_statement(start, VarKind::Const)
+            }
+            "let" => {
+                let next = self.peek();
+                let is_decl = next.kind == TokenKind::Name && !matches!(next.text(self.source), "in" | "instanceof" | "of")
+                    || next.is_punct(self.source, "[")
+                    || next.is_punct(self.source, "{");
+                if !is_decl {
+                    return None;
+                }
+                self.parse_var_statement(start, VarKind::Let)
+            }
+            "using" if self.starts_using_declaration() => self.parse_var_statement(start, VarKind::Using),
+            "await" if self.starts_await_using_declaration() => {
+                self.advance();
+                self.parse_var_statement(start, VarKind::AwaitUsing)
+            }
+            "function" => Stmt::Function(self.parse_function(start, false, true)),
+            "async" => {
+                let next = self.peek();
+                if !next.is_word(self.source, "function") || next.newline_before {
+                    return None;
+                }
+                self.advance();
+                Stmt::Function(self.parse_function(start, true, true))
+            }
+            "class" => self.parse_class_declaration(start, Vec::new()),
+            "if" => self.parse_if(start),
+            "for" => self.parse_for(start),
+            "while" => {
+                self.advance();
+                let test = self.parse_paren_condition();
+                let body = Box::new(self.parse_statement());
+                Stmt::While(WhileStmt { span: self.span_from(start), test, body })
+            }
+            "do" => {
+                self.advance();
+                let body = Box::new(self.parse_statement());
+                if !self.eat_word("while") {
+                    self.unexpected("Expected `while`");
+                }
+                let test = self.parse_paren_condition();
+                self.eat(";");
+                Stmt::DoWhile(DoWhileStmt { span: self.span_from(start), body, test })
+            }
+            "return" => {
+                self.advance();
+                let arg = if self.is(";") || self.is("}") || self.at_eof() || self.tok.newline_before {
+                    None
+                } else {
+                    Some(self.parse_expr())
+                };
+                self.semicolon();
+                Stmt::Return(ReturnStmt { span: self.span_from(start), arg })
+            }
+            "break" | "continue" => {
+                let is_break = self.is_word("break");
+                self.advance();
+                let label = (self.is_identifier() && !self.tok.newline_before).then(|| self.parse_ident_name());
+                self.semicolon();
+                let jump = JumpStmt { span: self.span_from(start), label };
+                if is_break {
+                    Stmt::Break(jump)
+                } else {
+                    Stmt::Continue(jump)
+                }
+            }
+            "throw" => {
+                self.advance();
+                if self.tok.newline_before {
+                    self.error(ErrorCode::InvalidSyntax, "Illegal newline after `throw`", Span::new(self.tok.start, self.tok.end));
+                }
+                let arg = self.parse_expr();
+                self.semicolon();
+                Stmt::Throw(ThrowStmt { span: self.span_from(start), arg })
+            }
+            "try" => self.parse_try(start),
+            "switch" => self.parse_switch(start),
+            "with" => {
+                self.advance();
+                let object = self.parse_paren_condition();
+                let body = Box::new(self.parse_statement());
+                Stmt::With(WithStmt { span: self.span_from(start), object, body })
+            }
+            "debugger" => {
+                self.advance();
+                self.semicolon();
+                Stmt::Debugger(self.span_from(start))
+            }
+            "import" => {
+                let next = self.peek();
+                if next.is_punct(self.source, "(") || next.is_punct(self.source, ".") {
+                    return None;
+                }
+                self.parse_import(start)
+            }
+            "export" => self.parse_export(),
+            "interface" if self.ts && self.next_is_name_on_same_line() => {
+                Stmt::TsInterface(self.parse_interface(start, false))
+            }
+            "type" if self.ts && self.next_is_name_on_same_line() => self.parse_type_alias(start, false),
+            "enum" if self.ts && self.peek().kind == TokenKind::Name => self.parse_enum(start, false, false),
+            "declare" if self.ts && self.next_is_name_on_same_line() => self.parse_declare(start),
+            "namespace" | "module" if self.ts && self.starts_module_declaration() => self.parse_module(start, false),
+            "abstract" if self.ts && self.peek().is_word(self.source, "class") && !self.peek().newline_before => {
+                self.parse_class_declaration(start, Vec::new())
+            }
+            "global" if self.ts && self.peek_is("{") => self.parse_module(start, false),
+            _ => {
+                if self.is_identifier() && self.peek_is(":") {
+                    let label = self.parse_ident_name();
+                    self.advance();
+                    let body = Box::new(self.parse_statement());
+                    return Some(Stmt::Labeled(LabeledStmt { span: self.span_from(start), label, body }));
+                }
+                return None;
+            }
+        };
+        Some(stmt)
+    }
+
+    fn next_is_name_on_same_line(&mut self) -> bool {
+        let next = self.peek();
+        next.kind == TokenKind::Name && !next.newline_before
+    }
+
+    fn starts_module_declaration(&mut self) -> bool {
+        let next = self.peek();
+        !next.newline_before && matches!(next.kind, TokenKind::Name | TokenKind::String)
+    }
+
+    fn starts_using_declaration(&mut self) -> bool {
+        let next = self.peek();
+        next.kind == TokenKind::Name && !next.newline_before && !matches!(next.text(self.source), "in" | "of" | "instanceof")
+    }
+
+    fn starts_await_using_declaration(&mut self) -> bool {
+        let snapshot = self.snapshot();
+        self.advance();
+        let result = self.is_word("using") && !self.tok.newline_before && self.starts_using_declaration();
+        self.restore(snapshot);
+        result
+    }
+
+    fn parse_block(&mut self) -> BlockStmt {
+        let start = self.tok.start;
+        self.expect("{");
+        let stmts = self.parse_statements(Some("}"));
+        self.expect("}");
+        BlockStmt { span: self.span_from(start), stmts }
+    }
+
+    fn parse_paren_condition(&mut self) -> Expr {
+        self.expect("(");
+        let expr = self.nested(|p| p.parse_expr());
+        self.expect(")");
+        expr
+    }
+
+    fn parse_var_statement(&mut self, start: usize, kind: VarKind) -> Stmt {
+        let decl = self.parse_var_decl(start, kind, false);
+        self.semicolon();
+        Stmt::Var(VarDecl { span: self.span_from(start), ..decl })
+    }
+
+    /// `var a = 1, b` without the semicolon; the keyword is the current token
+    fn parse_var_decl(&mut self, start: usize, kind: VarKind, declare: bool) -> VarDecl {
+        self.advance();
+        let mut decls = Vec::new();
+        loop {
+            let decl_start = self.tok.start;
+            let mut name = self.parse_binding_target();
+            if self.ts {
+                name = self.parse_pat_annotations(name, true);
+            }
+            let init = self.eat("=").then(|| self.parse_assign());
+            decls.push(VarDeclarator { span: self.span_from(decl_start), name, init });
+            if !self.eat(",") {
+                break;
+            }
+        }
+        VarDecl { span: self.span_from(start), kind, declare, decls }
+    }
+
+    /// `if` statement; `else if` chains are read in a loop so long chains
+    /// don't count against the nesting limit
+    fn parse_if(&mut self, start: usize) -> Stmt {
+        let mut chain = Vec::new();
+        let mut start = start;
+        let alt = loop {
+            self.advance();
+            let test = self.parse_paren_condition();
+            let cons = Box::new(self.parse_statement());
+            chain.push((start, test, cons));
+            if !self.eat_word("else") {
+                break None;
+            }
+            if !self.is_word("if") {
+                break Some(Box::new(self.parse_statement()));
+            }
+            start = self.tok.start;
+        };
+        let end = self.prev_end;
+        let mut alt = alt;
+        while let Some((start, test, cons)) = chain.pop() {
+            let stmt = Stmt::If(IfStmt { span: Span::new(start, end), test, cons, alt });
+            if chain.is_empty() {
+                return stmt;
+            }
+            alt = Some(Box::new(stmt));
+        }
+        unreachable!("an if chain has at least one link")
+    }
+
+    fn parse_for(&mut self, start: usize) -> Stmt {
+        self.advance();
+        let is_await = self.eat_word("await");
+        self.expect("(");
+        let no_in = Context { no_in: true, in_cond_cons: false, ..self.ctx };
+
+        let init_start = self.tok.start;
+        let init = if self.is(";") {
+            None
+        } else if let Some(kind) = self.for_declaration_kind() {
+            if kind == VarKind::AwaitUsing {
+                self.advance();
+            }
+            let decl = self.with_ctx(no_in, |p| p.parse_var_decl(init_start, kind, false));
+            Some(ForInit::Var(decl))
+        } else {
+            Some(ForInit::Expr(self.with_ctx(no_in, |p| p.parse_expr())))
+        };
+
+        let is_of = self.is_word("of");
+        if let Some(init) = init.as_ref().filter(|_| is_of || self.is_word("in")) {
+            self.advance();
+            let left = match init.clone() {
+                ForInit::Var(decl) => ForHead::Var(decl),
+                ForInit::Expr(expr) => ForHead::Pat(self.expr_to_pat(expr)),
+            };
+            let right = if is_of { self.nested(|p| p.parse_assign()) } else { self.nested(|p| p.parse_expr()) };
+            self.expect(")");
+            let body = Box::new(self.parse_statement());
+            let span = self.span_from(start);
+            return if is_of {
+                Stmt::ForOf(ForOfStmt { span, is_await, left, right, body })
+            } else {
+                Stmt::ForIn(ForInStmt { span, left, right, body })
+            };
+        }
+
+        self.expect(";");
+        let test = (!self.is(";")).then(|| self.nested(|p| p.parse_expr()));
+        self.expect(";");
+        let update = (!self.is(")")).then(|| self.nested(|p| p.parse_expr()));
+        self.expect(")");
+        let body = Box::new(self.parse_statement());
+        Stmt::For(ForStmt { span: self.span_from(start), init, test, update, body })
+    }
+
+    fn for_declaration_kind(&mut self) -> Option<VarKind> {
+        match self.text() {
+            "var" if self.tok.kind == TokenKind::Name => Some(VarKind::Var),
+            "const" if self.tok.kind == TokenKind::Name => Some(VarKind::Const),
+            "let" if self.tok.kind == TokenKind::Name => {
+                let next = self.peek();
+                let is_decl = next.kind == TokenKind::Name && !matches!(next.text(self.source), "in" | "of")
+                    || next.is_punct(self.source, "[")
+                    || next.is_punct(self.source, "{");
+                is_decl.then_some(VarKind::Let)
+            }
+            "using" if self.tok.kind == TokenKind::Name && self.starts_using_declaration() => Some(VarKind::Using),
+            "await" if self.tok.kind == TokenKind::Name && self.starts_await_using_declaration() => {
+                Some(VarKind::AwaitUsing)
+            }
+            _ => None,
+        }
+    }
+
+    fn parse_try(&mut self, start: usize) -> Stmt {
+        self.advance();
+        let block = self.parse_block();
+        let handler = if self.is_word("catch") {
+            let catch_start = self.tok.start;
+            self.advance();
+            let param = if self.eat("(") {
+                let mut pat = self.parse_binding_target();
+                if self.ts {
+                    pat = self.parse_pat_annotations(pat, false);
+                }
+                self.expect(")");
+                Some(pat)
+            } else {
+                None
+            };
+            let body = self.parse_block();
+            Some(CatchClause { span: self.span_from(catch_start), param, body })
+        } else {
+            None
+        };
+        let finalizer = self.eat_word("finally").then(|| self.parse_block());
+        if handler.is_none() && finalizer.is_none() {
+            self.unexpected("Expected `catch` or `finally`");
+        }
+        Stmt::Try(TryStmt { span: self.span_from(start), block, handler, finalizer })
+    }
+
+    fn parse_switch(&mut self, start: usize) -> Stmt {
+        self.advance();
+        let discriminant = self.parse_paren_condition();
+        self.expect("{");
+        let mut cases = Vec::new();
+        while !self.is("}") && !self.at_eof() {
+            let case_start = self.tok.start;
+            let test = if self.eat_word("case") {
+                Some(self.nested(|p| p.parse_expr()))
+            } else if self.eat_word("default") {
+                None
+            } else {
+                self.unexpected("Expected `case` or `default`");
+                self.advance();
+                continue;
+            };
+            self.expect(":");
+            let mut cons = Vec::new();
+            while !self.is("}") && !self.is_word("case") && !self.is_word("default") && !self.at_eof() {
+                let stmt_start = self.tok.start;
+                self.recovering = false;
+                let errors = self.error_count;
+                cons.push(self.parse_statement());
+                if self.error_count > errors {
+                    self.recover(stmt_start);
+                }
+            }
+            cases.push(SwitchCase { span: self.span_from(case_start), test, cons });
+        }
+        self.expect("}");
+        Stmt::Switch(SwitchStmt { span: self.span_from(start), discriminant, cases })
+    }
+
+    // -----------------------------------------------------------------------
+    // Modules
+    // -----------------------------------------------------------------------
+
+    fn parse_import(&mut self, start: usize) -> Stmt {
+        self.advance();
+
+        // `import type x from`, but not `import type from "x"`
+        let mut type_only = false;
+        if self.ts && self.is_word("type") {
+            let next = self.peek();
+            let is_modifier = (next.kind == TokenKind::Name && !next.is_word(self.source, "from"))
+                || next.is_punct(self.source, "{")
+                || next.is_punct(self.source, "*")
+                || (next.is_word(self.source, "from") && self.type_from_is_binding());
+            if is_modifier {
+                self.advance();
+                type_only = true;
+            }
+        }
+
+        let mut specifiers = Vec::new();
+        if self.tok.kind == TokenKind::String {
+            let source = self.parse_string_lit();
+            let attributes = self.parse_import_attributes();
+            self.semicolon();
+            return Stmt::Import(ImportDecl { span: self.span_from(start), type_only, specifiers, source, attributes });
+        }
+
+        if self.is_identifier() {
+            let local = self.parse_binding_ident();
+            if self.ts && self.is("=") {
+                return self.parse_import_equals(start, local, false, type_only);
+            }
+            specifiers.push(ImportSpecifier::Default(local));
+            if !self.eat(",") {
+                return self.finish_import(start, type_only, specifiers);
+            }
+        }
+
+        if self.is("*") {
+            let ns_start = self.tok.start;
+            self.advance();
+            if !self.eat_word("as") {
+                self.unexpected("Expected `as`");
+            }
+            let local = self.parse_binding_ident();
+            specifiers.push(ImportSpecifier::Namespace { span: self.span_from(ns_start), local });
+        } else if self.eat("{") {
+            while !self.is("}") && !self.at_eof() {
+                let spec_start = self.tok.start;
+                let spec_type_only = self.eat_specifier_type_modifier();
+                let imported = self.parse_module_export_name();
+                let local = if self.eat_word("as") {
+                    self.parse_binding_ident()
+                } else {
+                    match &imported {
+                        ModuleExportName::Ident(ident) => {
+                            if RESERVED_WORDS.contains(&ident.name.as_str()) {
+                                self.error(ErrorCode::UnexpectedToken, "Expected `as`", ident.span);
+                            }
+                            ident.clone()
+                        }
+                        ModuleExportName::Str(lit) => {
+                            self.error(ErrorCode::InvalidSyntax, "A string import must be renamed with `as`", lit.span);
+                            Ident { span: lit.span, name: String::new() }
+                        }
+                    }
+                };
+                specifiers.push(ImportSpecifier::Named {
+                    span: self.span_from(spec_start),
+                    imported,
+                    local,
+                    type_only: spec_type_only,
+                });
+                if !self.eat(",") {
+                    break;
+                }
+            }
+            self.expect("}");
+        } else {
+            self.unexpected("Expected an import specifier");
+        }
+        self.finish_import(start, type_only, specifiers)
+    }
+
+    /// `import type from from "x"`: the second `from` is the keyword
+    fn type_from_is_binding(&mut self) -> bool {
+        let snapshot = self.snapshot();
+        self.advance();
+        self.advance();
+        let result = self.is_word("from");
+        self.restore(snapshot);
+        result
+    }
+
+    fn finish_import(&mut self, start: usize, type_only: bool, specifiers: Vec<ImportSpecifier>) -> Stmt {
+        if !self.eat_word("from") {
+            self.unexpected("Expected `from`");
+        }
+        let source = self.parse_string_lit();
+        let attributes = self.parse_import_attributes();
+        self.semicolon();
+        Stmt::Import(ImportDecl { span: self.span_from(start), type_only, specifiers, source, attributes })
+    }
+
+    /// `type` before a specifier: `{ type A }`, but not `{ type }` or `{ type as B }`
+    fn eat_specifier_type_modifier(&mut self) -> bool {
+        if !self.ts || !self.is_word("type") {
+            return false;
+        }
+        let next = self.peek();
+        let is_modifier = match next.kind {
+            TokenKind::String => true,
+            TokenKind::Name if next.is_word(self.source, "as") => {
+                // `type as as x` / `type as x`
+                let snapshot = self.snapshot();
+                self.advance();
+                self.advance();
+                let result = self.is_word("as") || self.tok.kind == TokenKind::Name && !self.is_word("as") && {
+                    self.advance();
+                    !self.is_word("as") && (self.is(",") || self.is("}"))
+                };
+                self.restore(snapshot);
+                result
+            }
+            TokenKind::Name => true,
+            _ => false,
+        };
+        if is_modifier {
+            self.advance();
+        }
+        is_modifier
+    }
+
+    fn parse_module_export_name(&mut self) -> ModuleExportName {
+        if self.tok.kind == TokenKind::String {
+            ModuleExportName::Str(self.parse_string_lit())
+        } else {
+            ModuleExportName::Ident(self.parse_ident_name())
+        }
+    }
+
+    fn parse_import_attributes(&mut self) -> Option<ImportAttributes> {
+        let is_assert = self.is_word("assert") && !self.tok.newline_before;
+        if !self.is_word("with") && !is_assert {
+            return None;
+        }
+        let start = self.tok.start;
+        self.advance();
+        self.expect("{");
+        let mut entries = Vec::new();
+        while !self.is("}") && !self.at_eof() {
+            let entry_start = self.tok.start;
+            let key = self.parse_module_export_name();
+            self.expect(":");
+            let value = self.parse_string_lit();
+            entries.push(ImportAttribute { span: self.span_from(entry_start), key, value });
+            if !self.eat(",") {
+                break;
+            }
+        }
+        self.expect("}");
+        Some(ImportAttributes { span: self.span_from(start), is_assert, entries })
+    }
+
+    /// `import x = require("y")` / `import x = A.B`, after the `=`-preceding name
+    fn parse_import_equals(&mut self, start: usize, id: Ident, is_export: bool, type_only: bool) -> Stmt {
+        self.expect("=");
+        let module_ref = if self.is_word("require") && self.peek_is("(") {
+            self.advance();
+            self.advance();
+            let source = self.parse_string_lit();
+            self.expect(")");
+            TsModuleRef::Require(source)
+        } else {
+            TsModuleRef::Entity(self.parse_entity_name())
+        };
+        self.semicolon();
+        Stmt::TsImportEquals(TsImportEqualsDecl { span: self.span_from(start), is_export, type_only, id, module_ref })
+    }
+
+    fn parse_entity_name(&mut self) -> Vec<Ident> {
+        let mut path = vec![self.parse_ident_name()];
+        while self.is(".") && self.peek().kind == TokenKind::Name {
+            self.advance();
+            path.push(self.parse_ident_name());
+        }
+        path
+    }
+
+    fn parse_export(&mut self) -> Stmt {
+        let decorators = std::mem::take(&mut self.pending_decorators);
+        let start = decorators.first().map_or(self.tok.start, |d| d.span.start);
+        self.advance();
+
+        if self.is("@") {
+            let mut decorators = decorators;
+            decorators.extend(self.parse_decorators());
+            self.pending_decorators = decorators;
+        } else if !decorators.is_empty() {
+            self.pending_decorators = decorators;
+        }
+
+        if self.is_word("default") {
+            self.advance();
+            let decl = self.parse_export_default_decl();
+            return Stmt::ExportDefault(ExportDefault { span: self.span_from(start), decl });
+        }
+
+        if self.ts && self.is("=") {
+            self.advance();
+            let expr = self.parse_expr();
+            self.semicolon();
+            return Stmt::TsExportAssignment(TsExportAssignment { span: self.span_from(start), expr });
+        }
+
+        if self.ts && self.is_word("as") && self.peek().is_word(self.source, "namespace") {
+            self.advance();
+            self.advance();
+            let id = self.parse_ident_name();
+            self.semicolon();
+            return Stmt::TsNamespaceExport(TsNamespaceExport { span: self.span_from(start), id });
+        }
+
+        if self.ts && self.is_word("import") {
+            self.advance();
+            let type_only = self.is_word("type") && self.peek().kind == TokenKind::Name && {
+                self.advance();
+                true
+            };
+            let id = self.parse_binding_ident();
+            return self.parse_import_equals(start, id, true, type_only);
+        }
+
+        let type_only = self.ts
+            && self.is_word("type")
+            && (self.peek_is("{") || self.peek_is("*"))
+            && {
+                self.advance();
+                true
+            };
+
+        if self.is("*") {
+            self.advance();
+            let exported = self.eat_word("as").then(|| self.parse_module_export_name());
+            if !self.eat_word("from") {
+                self.unexpected("Expected `from`");
+            }
+            let source = self.parse_string_lit();
+            let attributes = self.parse_import_attributes();
+            self.semicolon();
+            return Stmt::ExportAll(ExportAll { span: self.span_from(start), type_only, exported, source, attributes });
+        }
+
+        if self.eat("{") {
+            let mut specifiers = Vec::new();
+            while !self.is("}") && !self.at_eof() {
+                let spec_start = self.tok.start;
+                let spec_type_only = self.eat_specifier_type_modifier();
+                let local = self.parse_module_export_name();
+                let exported = self.eat_word("as").then(|| self.parse_module_export_name());
+                specifiers.push(ExportSpecifier { span: self.span_from(spec_start), local, exported, type_only: spec_type_only });
+                if !self.eat(",") {
+                    break;
+                }
+            }
+            self.expect("}");
+            let (source, attributes) = if self.eat_word("from") {
+                let source = self.parse_string_lit();
+                (Some(source), self.parse_import_attributes())
+            } else {
+                (None, None)
+            };
+            self.semicolon();
+            return Stmt::ExportNamed(ExportNamed { span: self.span_from(start), type_only, specifiers, source, attributes });
+        }
+
+        let decl_start = self.tok.start;
+        let decl = match self.text() {
+            "var" | "let" | "const" | "function" | "async" | "class" | "using" | "abstract" | "interface" | "type"
+            | "enum" | "declare" | "namespace" | "module" | "@"
+                if matches!(self.tok.kind, TokenKind::Name | TokenKind::Punct) =>
+            {
+                self.parse_statement()
+            }
+            _ if !self.pending_decorators.is_empty() => self.parse_statement(),
+            _ => {
+                self.unexpected("Expected a declaration");
+                Stmt::Empty(Span::new(decl_start, decl_start))
+            }
+        };
+        Stmt::ExportDecl(ExportDecl { span: self.span_from(start), decl: Box::new(decl) })
+    }
+
+    fn parse_export_default_decl(&mut self) -> DefaultDecl {
+        let start = self.tok.start;
+        if self.is_word("function") {
+            return DefaultDecl::Function(self.parse_function(start, false, false));
+        }
+        if self.is_word("async") && self.peek().is_word(self.source, "function") && !self.peek().newline_before {
+            self.advance();
+            return DefaultDecl::Function(self.parse_function(start, true, false));
+        }
+        if self.is("@") {
+            let decorators = self.parse_decorators();
+            self.pending_decorators.extend(decorators);
+        }
+        let is_abstract_class =
+            self.ts && self.is_word("abstract") && self.peek().is_word(self.source, "class") && !self.peek().newline_before;
+        if self.is_word("class") || is_abstract_class {
+            let class_start = self.pending_decorators.first().map_or(start, |d| d.span.start);
+            let modifiers = self.parse_class_modifiers();
+            return DefaultDecl::Class(self.parse_class(class_start, modifiers, false));
+        }
+        if self.ts && self.is_word("interface") && self.next_is_name_on_same_line() {
+            return DefaultDecl::TsInterface(self.parse_interface(start, false));
+        }
+        let expr = self.parse_assign();
+        self.semicolon();
+        DefaultDecl::Expr(expr)
+    }
+
+    // -----------------------------------------------------------------------
+    // TypeScript declarations
+    // -----------------------------------------------------------------------
+
+    fn parse_declare(&mut self, start: usize) -> Stmt {
+        self.advance();
+        let inner_start = self.tok.start;
+        if self.is_word("global") {
+            return self.parse_module(start, true);
+        }
+        match self.text() {
+            "var" | "let" | "const" if !self.peek().is_word(self.source, "enum") => {
+                let kind = match self.text() {
+                    "var" => VarKind::Var,
+                    "let" => VarKind::Let,
+                    _ => VarKind::Const,
+                };
+                let decl = self.parse_var_decl(start, kind, true);
+                self.semicolon();
+                Stmt::Var(VarDecl { span: self.span_from(start), declare: true, ..decl })
+            }
+            "function" | "async" => {
+                let is_async = self.eat_word("async");
+                let mut function = self.parse_function(start, is_async, true);
+                function.declare = true;
+                Stmt::Function(function)
+            }
+            "class" | "abstract" => {
+                let mut modifiers = vec![Modifier { span: Span::new(start, start + "declare".len()), kind: ModifierKind::Declare }];
+                modifiers.extend(self.parse_class_modifiers());
+                let mut class = self.parse_class(start, modifiers, true);
+                class.declare = true;
+                Stmt::Class(class)
+            }
+            "const" => {
+                self.advance();
+                self.parse_enum(start, true, true)
+            }
+            "enum" => self.parse_enum(start, false, true),
+            "namespace" | "module" => self.parse_module(start, true),
+            "interface" => Stmt::TsInterface(self.parse_interface(start, true)),
+            "type" => self.parse_type_alias(start, true),
+            _ => {
+                self.unexpected("Expected a declaration");
+                Stmt::Empty(Span::new(inner_start, inner_start))
+            }
+        }
+    }
+
+    fn parse_interface(&mut self, start: usize, declare: bool) -> TsInterfaceDecl {
+        self.advance();
+        let id = self.parse_binding_ident();
+        if self.is("<") {
+            self.parse_type_params();
+        }
+        if self.eat_word("extends") {
+            loop {
+                self.parse_type();
+                if !self.eat(",") {
+                    break;
+                }
+            }
+        }
+        self.parse_type_members();
+        TsInterfaceDecl { span: self.span_from(start), id, declare }
+    }
+
+    fn parse_type_alias(&mut self, start: usize, declare: bool) -> Stmt {
+        self.advance();
+        let id = self.parse_binding_ident();
+        if self.is("<") {
+            self.parse_type_params();
+        }
+        self.expect("=");
+        self.parse_type();
+        self.semicolon();
+        Stmt::TsTypeAlias(TsTypeAliasDecl { span: self.span_from(start), id, declare })
+    }
+
+    /// `enum E { ... }`; the current token is `enum`
+    fn parse_enum(&mut self, start: usize, is_const: bool, declare: bool) -> Stmt {
+        self.advance();
+        let id = self.parse_binding_ident();
+        self.expect("{");
+        let mut members = Vec::new();
+        while !self.is("}") && !self.at_eof() {
+            let member_start = self.tok.start;
+            let id = match self.tok.kind {
+                TokenKind::String => PropKey::Str(self.parse_string_lit()),
+                TokenKind::Name => PropKey::Ident(self.parse_ident_name()),
+                _ => {
+                    self.unexpected("Expected an enum member name");
+                    break;
+                }
+            };
+            let init = self.eat("=").then(|| self.parse_assign());
+            members.push(TsEnumMember { span: self.span_from(member_start), id, init });
+            if !self.eat(",") {
+                break;
+            }
+        }
+        self.expect("}");
+        Stmt::TsEnum(TsEnumDecl { span: self.span_from(start), id, is_const, declare, members })
+    }
+
+    /// `namespace A.B {}`, `module "x" {}`, `global {}`; the keyword is the current token
+    fn parse_module(&mut self, start: usize, declare: bool) -> Stmt {
+        let name = if self.is_word("global") {
+            let span = Span::new(self.tok.start, self.tok.end);
+            self.advance();
+            TsModuleName::Global(span)
+        } else {
+            self.advance();
+            if self.tok.kind == TokenKind::String {
+                TsModuleName::Str(self.parse_string_lit())
+            } else {
+                TsModuleName::Path(self.parse_entity_name())
+            }
+        };
+        let body = if self.is("{") {
+            let body_start = self.tok.start;
+            self.advance();
+            let body = self.with_ctx(Context::default(), |p| p.parse_statements(Some("}")));
+            self.expect("}");
+            Some(TsModuleBlock { span: self.span_from(body_start), body })
+        } else {
+            self.semicolon();
+            None
+        };
+        Stmt::TsModule(TsModuleDecl { span: self.span_from(start), declare, name, body })
+    }
+
+    // -----------------------------------------------------------------------
+    // Functions and classes
+    // -----------------------------------------------------------------------
+
+    /// `function name() {}`; the current token is `function`, `start` may
+    /// point at a preceding `async`
+    fn parse_function(&mut self, start: usize, is_async: bool, is_declaration: bool) -> Function {
+        self.advance();
+        let is_generator = self.eat("*");
+        let id = if self.is_identifier() {
+            Some(self.parse_binding_ident())
+        } else {
+            if is_declaration {
+                self.unexpected("Expected a function name");
+            }
+            None
+        };
+        self.parse_function_rest(start, id, is_async, is_generator)
+    }
+
+    /// Type parameters, parameters, return type and body
+    fn parse_function_rest(&mut self, start: usize, id: Option<Ident>, is_async: bool, is_generator: bool) -> Function {
+        let type_params = (self.ts && self.is("<")).then(|| self.parse_type_params());
+        let (params, params_span) = self.function_body(is_async, is_generator, |p| p.parse_params());
+        let return_type = (self.ts && self.is(":")).then(|| self.parse_return_type_ann());
+        let body = if self.is("{") {
+            Some(self.function_body(is_async, is_generator, |p| p.parse_block()))
+        } else if self.ts {
+            self.semicolon();
+            None
+        } else {
+            self.unexpected("Expected `{`");
+            None
+        };
+        Function {
+            span: self.span_from(start),
+            id,
+            is_async,
+            is_generator,
+            declare: false,
+            type_params,
+            params_span,
+            params,
+            return_type,
+            body,
+        }
+    }
+
+    fn parse_params(&mut self) -> (Vec<Param>, Span) {
+        let start = self.tok.start;
+        let mut params = Vec::new();
+        if !self.expect("(") {
+            return (params, Span::new(start, start));
+        }
+        while !self.is(")") && !self.at_eof() {
+            let errors = self.error_count;
+            params.push(self.parse_param());
+            if self.error_count > errors || !self.eat(",") {
+                break;
+            }
+        }
+        self.expect(")");
+        (params, self.span_from(start))
+    }
+
+    fn parse_param(&mut self) -> Param {
+        let start = self.tok.start;
+        let decorators = if self.is("@") { self.parse_decorators() } else { Vec::new() };
+        let mut modifiers = Vec::new();
+        if self.ts {
+            while let Some(kind) = ModifierKind::from_keyword(self.text()).filter(|_| self.tok.kind == TokenKind::Name) {
+                let next = self.peek();
+                let follows = !next.newline_before
+                    && (next.kind == TokenKind::Name
+                        || next.is_punct(self.source, "{")
+                        || next.is_punct(self.source, "[")
+                        || next.is_punct(self.source, "..."));
+                if !follows {
+                    break;
+                }
+                modifiers.push(Modifier { span: Span::new(self.tok.start, self.tok.end), kind });
+                self.advance();
+            }
+        }
+        let pat = self.parse_binding_element(true);
+        Param { span: self.span_from(start), decorators, modifiers, pat }
+    }
+
+    /// Pattern with optional `?`, type annotation and default value
+    fn parse_binding_element(&mut self, allow_rest: bool) -> Pat {
+        let start = self.tok.start;
+        if allow_rest && self.is("...") {
+            self.advance();
+            let arg = Box::new(self.parse_binding_target());
+            let type_ann = (self.ts && self.is(":")).then(|| self.parse_type_ann());
+            if self.is("=") {
+                self.error(ErrorCode::InvalidSyntax, "A rest element can't have a default value", Span::new(self.tok.start, self.tok.end));
+            }
+            return Pat::Rest(RestPat { span: self.span_from(start), arg, type_ann });
+        }
+        let mut pat = self.parse_binding_target();
+        if self.ts {
+            pat = self.parse_pat_annotations(pat, false);
+        }
+        if self.eat("=") {
+            let right = self.nested(|p| p.parse_assign());
+            pat = Pat::Assign(AssignPat { span: self.span_from(start), left: Box::new(pat), right });
+        }
+        pat
+    }
+
+    /// Attach `?`, `!` and `: T` to a pattern
+    fn parse_pat_annotations(&mut self, mut pat: Pat, allow_definite: bool) -> Pat {
+        let optional = self.is("?").then(|| {
+            let span = Span::new(self.tok.start, self.tok.end);
+            self.advance();
+            span
+        });
+        let definite = (allow_definite && self.is("!")).then(|| {
+            let span = Span::new(self.tok.start, self.tok.end);
+            self.advance();
+            span
+        });
+        let type_ann = self.is(":").then(|| self.parse_type_ann());
+        if optional.is_none() && definite.is_none() && type_ann.is_none() {
+            return pat;
+        }
+        let end = self.prev_end;
+        match &mut pat {
+            Pat::Ident(p) => {
+                p.optional = optional;
+                p.definite = definite;
+                p.type_ann = type_ann;
+                p.span.end = end;
+            }
+            Pat::Array(p) => {
+                p.optional = optional;
+                p.type_ann = type_ann;
+                p.span.end = end;
+            }
+            Pat::Object(p) => {
+                p.optional = optional;
+                p.type_ann = type_ann;
+                p.span.end = end;
+            }
+            _ => {}
+        }
+        pat
+    }
+
+    /// Identifier, array pattern or object pattern
+    fn parse_binding_target(&mut self) -> Pat {
+        let start = self.tok.start;
+        if self.is("[") {
+            self.advance();
+            let mut elems = Vec::new();
+            while !self.is("]") && !self.at_eof() {
+                if self.is(",") {
+                    self.advance();
+                    elems.push(None);
+                    continue;
+                }
+                let errors = self.error_count;
+                elems.push(Some(self.nested(|p| p.parse_binding_element(true))));
+                if self.error_count > errors || !self.is("]") && !self.expect(",") {
+                    break;
+                }
+            }
+            self.expect("]");
+            return Pat::Array(ArrayPat { span: self.span_from(start), elems, optional: None, type_ann: None });
+        }
+        if self.is("{") {
+            self.advance();
+            let mut props = Vec::new();
+            while !self.is("}") && !self.at_eof() {
+                let prop_start = self.tok.start;
+                let errors = self.error_count;
+                if self.eat("...") {
+                    let arg = Box::new(self.parse_binding_target());
+                    props.push(ObjectPatProp::Rest(RestPat { span: self.span_from(prop_start), arg, type_ann: None }));
+                } else {
+                    let key = self.parse_prop_key();
+                    if self.eat(":") {
+                        let value = self.nested(|p| p.parse_binding_element(false));
+                        props.push(ObjectPatProp::KeyValue { span: self.span_from(prop_start), key, value });
+                    } else {
+                        let key = match key {
+                            PropKey::Ident(ident) if !RESERVED_WORDS.contains(&ident.name.as_str()) => ident,
+                            other => {
+                                self.error(ErrorCode::UnexpectedToken, "Expected `:`", other.span());
+                                Ident { span: other.span(), name: String::new() }
+                            }
+                        };
+                        let value = self.eat("=").then(|| self.nested(|p| p.parse_assign()));
+                        props.push(ObjectPatProp::Assign { span: self.span_from(prop_start), key, value });
+                    }
+                }
+                if self.error_count > errors || !self.is("}") && !self.expect(",") {
+                    break;
+                }
+            }
+            self.expect("}");
+            return Pat::Object(ObjectPat { span: self.span_from(start), props, optional: None, type_ann: None });
+        }
+        let id = self.parse_binding_ident();
+        Pat::Ident(BindingIdent { span: id.span, id, optional: None, definite: None, type_ann: None })
+    }
+
+    fn parse_decorators(&mut self) -> Vec<Decorator> {
+        let mut decorators = Vec::new();
+        while self.is("@") {
+            let start = self.tok.start;
+            self.advance();
+            let expr_start = self.tok.start;
+            let expr = if self.is("(") {
+                self.parse_primary()
+            } else {
+                let mut expr = Expr::Ident(self.parse_ident_name());
+                while self.is(".") {
+                    self.advance();
+                    let property = if self.tok.kind == TokenKind::PrivateName {
+                        let ident = self.ident_from_token();
+                        self.advance();
+                        MemberProp::PrivateName(ident)
+                    } else {
+                        MemberProp::Ident(self.parse_ident_name())
+                    };
+                    expr = Expr::Member(Box::new(MemberExpr {
+                        span: self.span_from(expr_start),
+                        object: expr,
+                        property,
+                        optional: false,
+                    }));
+                }
+                if self.ts && self.is("<") {
+                    if let Some(type_args) = self.try_parse(|p| {
+                        let args = p.parse_type_args();
+                        p.is("(").then_some(args)
+                    }) {
+                        return_call(self, &mut expr, expr_start, Some(type_args));
+                    }
+                }
+                if self.is("(") {
+                    return_call(self, &mut expr, expr_start, None);
+                }
+                expr
+            };
+            decorators.push(Decorator { span: self.span_from(start), expr });
+        }
+        return decorators;
+
+        fn return_call(p: &mut JsParser, expr: &mut Expr, start: usize, type_args: Option<TsTypeArgs>) {
+            let callee = std::mem::replace(expr, Expr::Invalid(Span::default()));
+            let (args, args_span) = p.parse_args();
+            *expr = Expr::Call(Box::new(CallExpr {
+                span: p.span_from(start),
+                callee,
+                type_args,
+                args_span,
+                args,
+                optional: false,
+            }));
+        }
+    }
+
+    /// `abstract` / `declare` in front of `class`
+    fn parse_class_modifiers(&mut self) -> Vec<Modifier> {
+        let mut modifiers = Vec::new();
+        while self.ts && (self.is_word("abstract") || self.is_word("declare")) {
+            let kind = if self.is_word("abstract") { ModifierKind::Abstract } else { ModifierKind::Declare };
+            modifiers.push(Modifier { span: Span::new(self.tok.start, self.tok.end), kind });
+            self.advance();
+        }
+        modifiers
+    }
+
+    fn parse_class_declaration(&mut self, start: usize, mut modifiers: Vec<Modifier>) -> Stmt {
+        modifiers.extend(self.parse_class_modifiers());
+        if !self.is_word("class") {
+            self.unexpected("Expected `class`");
+            self.pending_decorators.clear();
+            return Stmt::Empty(Span::new(start, start));
+        }
+        Stmt::Class(self.parse_class(start, modifiers, true))
+    }
+
+    /// `class Name extends Base {}`; the current token is `class`
+    fn parse_class(&mut self, start: usize, modifiers: Vec<Modifier>, is_declaration: bool) -> Class {
+        let decorators = std::mem::take(&mut self.pending_decorators);
+        let start = decorators.first().map_or(start, |d| d.span.start.min(start));
+        self.advance();
+        let id = if self.is_identifier() && !self.is_word("implements") {
+            Some(self.parse_binding_ident())
+        } else {
+            if is_declaration {
+                self.unexpected("Expected a class name");
+            }
+            None
+        };
+        let type_params = (self.ts && self.is("<")).then(|| self.parse_type_params());
+        let mut super_class = None;
+        let mut super_type_args = None;
+        if self.eat_word("extends") {
+            let expr_start = self.tok.start;
+            let base = self.nested(|p| {
+                let expr = p.parse_primary_or_new();
+                p.parse_call_tail(expr_start, expr, true)
+            });
+            super_class = Some(Box::new(base));
+            if self.ts && self.is("<") {
+                super_type_args = Some(self.parse_type_args());
+            }
+        }
+        let implements = if self.ts && self.is_word("implements") {
+            let impl_start = self.tok.start;
+            self.advance();
+            loop {
+                self.parse_type();
+                if !self.eat(",") {
+                    break;
+                }
+            }
+            Some(self.span_from(impl_start))
+        } else {
+            None
+        };
+
+        let body_start = self.tok.start;
+        let mut body = Vec::new();
+        if self.expect("{") {
+            let ctx = Context { in_function: true, ..Context::default() };
+            self.with_ctx(ctx, |p| {
+                while !p.is("}") && !p.at_eof() {
+                    let member_start = p.tok.start;
+                    p.recovering = false;
+                    let errors = p.error_count;
+                    body.push(p.parse_class_member());
+                    if p.error_count > errors {
+                        p.recover(member_start);
+                    }
+                }
+            });
+            self.expect("}");
+        }
+        let body_span = self.span_from(body_start);
+
+        let declare = modifiers.iter().any(|m| m.kind == ModifierKind::Declare);
+        let is_abstract = modifiers.iter().any(|m| m.kind == ModifierKind::Abstract);
+        Class {
+            span: self.span_from(start),
+            decorators,
+            modifiers,
+            id,
+            type_params,
+            super_class,
+            super_type_args,
+            implements,
+            body_span,
+            body,
+            declare,
+            is_abstract,
+        }
+    }
+
+    /// Can the token after a modifier keyword continue a class member?
+    fn modifier_applies(&mut self, kind: ModifierKind) -> bool {
+        let next = self.peek();
+        if next.newline_before && kind != ModifierKind::Static {
+            return false;
+        }
+        match next.kind {
+            TokenKind::Eof => false,
+            TokenKind::Punct => matches!(next.text(self.source), "[" | "{" | "*" | "#" | "@"),
+            _ => true,
+        }
+    }
+
+    fn parse_class_member(&mut self) -> ClassMember {
+        let start = self.tok.start;
+        if self.eat(";") {
+            return ClassMember::Empty(self.span_from(start));
+        }
+        let decorators = if self.is("@") { self.parse_decorators() } else { Vec::new() };
+
+        if self.is_word("static") && self.peek_is("{") {
+            self.advance();
+            let body = self.function_body(false, false, |p| p.parse_block());
+            return ClassMember::StaticBlock(StaticBlock { span: self.span_from(start), body });
+        }
+
+        let mut modifiers = Vec::new();
+        while self.tok.kind == TokenKind::Name {
+            let Some(kind) = ModifierKind::from_keyword(self.text()) else { break };
+            if (kind.is_typescript() && !self.ts) || !self.modifier_applies(kind) {
+                break;
+            }
+            modifiers.push(Modifier { span: Span::new(self.tok.start, self.tok.end), kind });
+            self.advance();
+        }
+        let is_static = modifiers.iter().any(|m| m.kind == ModifierKind::Static);
+
+        // Index signature: [key: string]: T
+        if self.ts && self.is("[") && self.looks_like_index_signature() {
+            self.parse_index_signature();
+            self.eat_member_separator();
+            return ClassMember::IndexSignature(self.span_from(start));
+        }
+
+        let method_start = self.tok.start;
+        let mut is_async = false;
+        let mut is_generator = false;
+        let mut kind = MethodKind::Method;
+        if self.is_word("async") && self.starts_method_after_modifier(false) {
+            self.advance();
+            is_async = true;
+        }
+        if self.eat("*") {
+            is_generator = true;
+        }
+        if !is_async && !is_generator && (self.is_word("get") || self.is_word("set")) && self.starts_method_after_modifier(true) {
+            kind = if self.is_word("get") { MethodKind::Getter } else { MethodKind::Setter };
+            self.advance();
+        }
+
+        let key = self.parse_prop_key();
+        let optional = (self.ts && self.is("?")).then(|| {
+            let span = Span::new(self.tok.start, self.tok.end);
+            self.advance();
+            span
+        });
+
+        if self.is("(") || self.is("<") {
+            if kind == MethodKind::Method && !is_static && key.static_name().as_deref() == Some("constructor") {
+                kind = MethodKind::Constructor;
+            }
+            let function_start = self.tok.start;
+            let function = self.parse_function_rest(function_start, None, is_async, is_generator);
+            let _ = method_start;
+            return ClassMember::Method(ClassMethod {
+                span: self.span_from(start),
+                decorators,
+                modifiers,
+                is_static,
+                kind,
+                key,
+                optional,
+                function,
+            });
+        }
+
+        let definite = (self.ts && self.is("!")).then(|| {
+            let span = Span::new(self.tok.start, self.tok.end);
+            self.advance();
+            span
+        });
+        let type_ann = (self.ts && self.is(":")).then(|| self.parse_type_ann());
+        let value = self.eat("=").then(|| self.function_body(false, false, |p| p.parse_assign()));
+        self.eat_member_separator();
+        ClassMember::Property(ClassProperty {
+            span: self.span_from(start),
+            decorators,
+            modifiers,
+            is_static,
+            key,
+            optional,
+            definite,
+            type_ann,
+            value,
+        })
+    }
+
+    /// `async` / `get` / `set` followed by a property name (rather than being one)
+    fn starts_method_after_modifier(&mut self, allow_newline: bool) -> bool {
+        let next = self.peek();
+        if next.newline_before && !allow_newline {
+            return false;
+        }
+        match next.kind {
+            TokenKind::Name | TokenKind::String | TokenKind::Number | TokenKind::BigInt | TokenKind::PrivateName => true,
+            TokenKind::Punct => next.is_punct(self.source, "[") || next.is_punct(self.source, "*") && !allow_newline,
+            _ => false,
+        }
+    }
+
+    fn eat_member_separator(&mut self) {
+        if self.eat(";") || self.eat(",") || self.is("}") || self.tok.newline_before || self.at_eof() {
+            return;
+        }
+        self.unexpected("Expected `;`");
+    }
+
+    fn looks_like_index_signature(&mut self) -> bool {
+        let snapshot = self.snapshot();
+        self.advance();
+        let result = self.tok.kind == TokenKind::Name && {
+            self.advance();
+            self.is(":") || self.is(",")
+        };
+        self.restore(snapshot);
+        result
+    }
+
+    /// `[key: K]: T`, without the separator
+    fn parse_index_signature(&mut self) {
+        self.expect("[");
+        while !self.is("]") && !self.at_eof() {
+            self.parse_ident_name();
+            if self.is(":") {
+                self.parse_type_ann();
+            }
+            if !self.eat(",") {
+                break;
+            }
+        }
+        self.expect("]");
+        if self.is(":") {
+            self.parse_type_ann();
+        }
+    }
+
+    fn parse_prop_key(&mut self) -> PropKey {
+        let span = Span::new(self.tok.start, self.tok.end);
+        match self.tok.kind {
+            TokenKind::Name => PropKey::Ident(self.parse_ident_name()),
+            TokenKind::String => PropKey::Str(self.parse_string_lit()),
+            TokenKind::Number => {
+                let value = LitValue::Num(lexer::number_value(self.text()).unwrap_or(f64::NAN));
+                self.advance();
+                PropKey::Num(Lit { span, value })
+            }
+            TokenKind::BigInt => {
+                let value = LitValue::BigInt(self.text().to_string());
+                self.advance();
+                PropKey::BigInt(Lit { span, value })
+            }
+            TokenKind::PrivateName => {
+                let ident = self.ident_from_token();
+                self.advance();
+                PropKey::Private(ident)
+            }
+            TokenKind::Punct if self.is("[") => {
+                self.advance();
+                let expr = Box::new(self.nested(|p| p.parse_assign()));
+                self.expect("]");
+                PropKey::Computed(ComputedKey { span: self.span_from(span.start), expr })
+            }
+            _ => {
+                self.unexpected("Expected a property name");
+                PropKey::Ident(Ident { span: Span::new(span.start, span.start), name: String::new() })
+            }
+        }
+    }
+
+    // -----------------------------------------------------------------------
+    // Expressions
+    // -----------------------------------------------------------------------
+
+    fn parse_expr(&mut self) -> Expr {
+        let start = self.tok.start;
+        let first = self.parse_assign();
+        if !self.is(",") {
+            return first;
+        }
+        let mut exprs = vec![first];
+        while self.eat(",") {
+            exprs.push(self.parse_assign());
+        }
+        Expr::Seq(SeqExpr { span: self.span_from(start), exprs })
+    }
+
+    fn parse_assign(&mut self) -> Expr {
+        if !self.enter() {
+            return Expr::Invalid(Span::new(self.prev_end, self.prev_end));
+        }
+        let expr = self.parse_assign_inner();
+        self.leave();
+        expr
+    }
+
+    fn parse_assign_inner(&mut self) -> Expr {
+        let start = self.tok.start;
+        if self.ctx.in_generator && self.is_word("yield") {
+            return self.parse_yield();
+        }
+        if let Some(arrow) = self.try_parse_arrow() {
+            return arrow;
+        }
+
+        let left = self.parse_conditional();
+        if self.tok.kind != TokenKind::Punct {
+            return left;
+        }
+        let Some(op) = AssignOp::from_op(self.text()) else { return left };
+        let op_span = Span::new(self.tok.start, self.tok.end);
+        let left = if op == AssignOp::Assign {
+            self.expr_to_pat(left)
+        } else {
+            self.simple_target(left)
+        };
+        self.advance();
+        let right = self.parse_assign();
+        Expr::Assign(Box::new(AssignExpr { span: self.span_from(start), op, op_span, left, right }))
+    }
+
+    fn parse_yield(&mut self) -> Expr {
+        let start = self.tok.start;
+        self.advance();
+        let delegate = !self.tok.newline_before && self.eat("*");
+        let has_arg = delegate
+            || (!self.tok.newline_before
+                && self.starts_expression()
+                && !self.is(")")
+                && !self.is("]")
+                && !self.is("}"));
+        let arg = has_arg.then(|| self.parse_assign());
+        Expr::Yield(Box::new(YieldExpr { span: self.span_from(start), delegate, arg }))
+    }
+
+    /// Arrow function at the current position, if there is one
+    fn try_parse_arrow(&mut self) -> Option<Expr> {
+        let start = self.tok.start;
+        match self.tok.kind {
+            TokenKind::Name => {
+                let next = self.peek();
+                if self.is_word("async") && !next.newline_before {
+                    if next.kind == TokenKind::Name && !next.is_word(self.source, "function") {
+                        // async x => ...
+                        let snapshot = self.snapshot();
+                        self.advance();
+                        if self.is_identifier() && self.peek_is("=>") && !self.peek().newline_before {
+                            let param = self.parse_binding_ident();
+                            return Some(self.parse_single_param_arrow(start, param, true));
+                        }
+                        self.restore(snapshot);
+                        return None;
+                    }
+                    if next.is_punct(self.source, "(") || (self.ts && next.is_punct(self.source, "<")) {
+                        if self.arrow_failures.contains(&start) {
+                            return None;
+                        }
+                        let snapshot = self.snapshot();
+                        self.advance();
+                        if let Some(arrow) = self.parse_arrow_after_head(start, true) {
+                            return Some(arrow);
+                        }
+                        self.restore(snapshot);
+                        self.arrow_failures.insert(start);
+                        return None;
+                    }
+                }
+                if self.is_identifier() && next.is_punct(self.source, "=>") && !next.newline_before {
+                    let param = self.parse_binding_ident();
+                    return Some(self.parse_single_param_arrow(start, param, false));
+                }
+                None
+            }
+            TokenKind::Punct if self.is("(") || (self.ts && self.is("<")) => {
+                if self.is("<") && !self.may_start_generic_arrow() || self.arrow_failures.contains(&start) {
+                    return None;
+                }
+                let arrow = self.parse_arrow_after_head(start, false);
+                if arrow.is_none() {
+                    self.arrow_failures.insert(start);
+                }
+                arrow
+            }
+            _ => None,
+        }
+    }
+
+    /// In TSX, `<T,>` and `<T extends U>` start generic arrows, anything else is JSX
+    fn may_start_generic_arrow(&mut self) -> bool {
+        if !self.jsx {
+            return true;
+        }
+        let snapshot = self.snapshot();
+        self.advance();
+        let result = self.tok.kind == TokenKind::Name && {
+            self.advance();
+            self.is(",") || (self.is_word("extends") && !self.peek_is("=") && !self.peek_is(">"))
+        };
+        self.restore(snapshot);
+        result
+    }
+
+    fn parse_single_param_arrow(&mut self, start: usize, param: Ident, is_async: bool) -> Expr {
+        let params_span = param.span;
+        let pat = Pat::Ident(BindingIdent { span: param.span, id: param, optional: None, definite: None, type_ann: None });
+        let params = vec![Param { span: params_span, decorators: Vec::new(), modifiers: Vec::new(), pat }];
+        self.expect("=>");
+        let body = self.parse_arrow_body(is_async);
+        Expr::Arrow(Box::new(ArrowFunction {
+            span: self.span_from(start),
+            is_async,
+            type_params: None,
+            params_span,
+            params,
+            return_type: None,
+            body,
+        }))
+    }
+
+    /// `(params): T => body` / `<T>(params) => body`, rewinding when there's no arrow
+    fn parse_arrow_after_head(&mut self, start: usize, is_async: bool) -> Option<Expr> {
+        let in_cond_cons = self.ctx.in_cond_cons;
+        let snapshot = self.snapshot();
+        let head: Option<ArrowHead> = self.try_parse(|p| {
+            let type_params = if p.is("<") { Some(p.parse_type_params()) } else { None };
+            if !p.is("(") {
+                return None;
+            }
+            let (params, params_span) = p.function_body(is_async, false, |p| p.parse_params());
+            let return_type = if p.ts && p.is(":") { Some(p.parse_return_type_ann()) } else { None };
+            (p.is("=>") && !p.tok.newline_before).then_some((type_params, params, params_span, return_type))
+        });
+        let (type_params, params, params_span, return_type) = head?;
+        self.advance();
+        let has_return_type = return_type.is_some();
+        let body = self.parse_arrow_body(is_async);
+        if in_cond_cons && has_return_type && !self.is(":") {
+            // `a ? (b) : c => d`: the `:` belongs to the conditional
+            self.restore(snapshot);
+            return None;
+        }
+        Some(Expr::Arrow(Box::new(ArrowFunction {
+            span: self.span_from(start),
+            is_async,
+            type_params,
+            params_span,
+            params,
+            return_type,
+            body,
+        })))
+    }
+
+    fn parse_arrow_body(&mut self, is_async: bool) -> ArrowBody {
+        if self.is("{") {
+            return ArrowBody::Block(self.function_body(is_async, false, |p| p.parse_block()));
+        }
+        let no_in = self.ctx.no_in;
+        let ctx = Context { in_function: true, in_async: is_async, in_generator: false, no_in, in_cond_cons: false };
+        ArrowBody::Expr(Box::new(self.with_ctx(ctx, |p| p.parse_assign())))
+    }
+
+    fn parse_conditional(&mut self) -> Expr {
+        let start = self.tok.start;
+        let test = self.parse_binary(0);
+        if !self.is("?") {
+            return test;
+        }
+        self.advance();
+        let cons_ctx = Context { no_in: false, in_cond_cons: true, ..self.ctx };
+        let cons = self.with_ctx(cons_ctx, |p| p.parse_assign());
+        self.expect(":");
+        let alt_ctx = Context { in_cond_cons: false, ..self.ctx };
+        let alt = self.with_ctx(alt_ctx, |p| p.parse_assign());
+        Expr::Cond(Box::new(CondExpr { span: self.span_from(start), test, cons, alt }))
+    }
+
+    fn binary_op(&self) -> Option<BinaryOp> {
+        match self.tok.kind {
+            TokenKind::Punct => BinaryOp::from_op(self.text()),
+            TokenKind::Name => match self.text() {
+                "instanceof" => Some(BinaryOp::InstanceOf),
+                "in" if !self.ctx.no_in => Some(BinaryOp::In),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Precedence climbing over binary operators (and `as` / `satisfies`)
+    fn parse_binary(&mut self, min_prec: u8) -> Expr {
+        let start = self.tok.start;
+        let mut left = self.parse_unary();
+        loop {
+            if self.ts && !self.tok.newline_before && (self.is_word("as") || self.is_word("satisfies")) {
+                // Same precedence as relational operators
+                if min_prec >= 8 {
+                    break;
+                }
+                left = self.parse_as_expr(start, left);
+                continue;
+            }
+            let Some(op) = self.binary_op() else { break };
+            let prec = op.precedence();
+            if prec <= min_prec {
+                break;
+            }
+            let op_span = Span::new(self.tok.start, self.tok.end);
+            self.advance();
+            // `**` is right-associative
+            let right = self.parse_binary(if op == BinaryOp::Exp { prec - 1 } else { prec });
+            left = Expr::Binary(Box::new(BinaryExpr { span: self.span_from(start), op, op_span, left, right }));
+        }
+        left
+    }
+
+    fn parse_as_expr(&mut self, start: usize, expr: Expr) -> Expr {
+        let is_as = self.is_word("as");
+        self.advance();
+        let type_ann = if is_as && self.is_word("const") {
+            let span = Span::new(self.tok.start, self.tok.end);
+            self.advance();
+            TsType { span, kind: TsTypeKind::Const }
+        } else {
+            self.parse_type()
+        };
+        let node = Box::new(TsAsExpr { span: self.span_from(start), expr, type_ann });
+        if is_as {
+            Expr::TsAs(node)
+        } else {
+            Expr::TsSatisfies(node)
+        }
+    }
+
+    fn unary_op(&self) -> Option<UnaryOp> {
+        match (self.tok.kind, self.text()) {
+            (TokenKind::Punct, "!") => Some(UnaryOp::Not),
+            (TokenKind::Punct, "~") => Some(UnaryOp::BitNot),
+            (TokenKind::Punct, "+") => Some(UnaryOp::Plus),
+            (TokenKind::Punct, "-") => Some(UnaryOp::Minus),
+            (TokenKind::Name, "typeof") => Some(UnaryOp::TypeOf),
+            (TokenKind::Name, "void") => Some(UnaryOp::Void),
+            (TokenKind::Name, "delete") => Some(UnaryOp::Delete),
+            _ => None,
+        }
+    }
+
+    fn parse_unary(&mut self) -> Expr {
+        let is_prefix = self.unary_op().is_some()
+            || self.is("++")
+            || self.is("--")
+            || (self.ts && !self.jsx && self.is("<"))
+            || (self.is_word("await") && self.await_is_keyword());
+        if is_prefix {
+            if !self.enter() {
+                return Expr::Invalid(Span::new(self.prev_end, self.prev_end));
+            }
+            let expr = self.parse_prefix_expr();
+            self.leave();
+            return expr;
+        }
+        let start = self.tok.start;
+        let expr = self.parse_lhs();
+        if (self.is("++") || self.is("--")) && !self.tok.newline_before {
+            return self.parse_update(Some((start, expr)));
+        }
+        expr
+    }
+
+    /// `-x`, `typeof x`, `++x`, `await x` or `<T>x`
+    fn parse_prefix_expr(&mut self) -> Expr {
+        if let Some(op) = self.unary_op() {
+            self.parse_unary_op(op)
+        } else if self.is("++") || self.is("--") {
+            self.parse_update(None)
+        } else if self.is("<") {
+            self.parse_type_assertion()
+        } else {
+            self.parse_await()
+        }
+    }
+
+    fn parse_unary_op(&mut self, op: UnaryOp) -> Expr {
+        let start = self.tok.start;
+        self.advance();
+        let arg = self.parse_unary();
+        Expr::Unary(Box::new(UnaryExpr { span: self.span_from(start), op, arg }))
+    }
+
+    fn parse_await(&mut self) -> Expr {
+        let start = self.tok.start;
+        self.advance();
+        let arg = self.parse_unary();
+        Expr::Await(Box::new(AwaitExpr { span: self.span_from(start), arg }))
+    }
+
+    /// `++x`, or `x++` when the operand has already been parsed
+    fn parse_update(&mut self, postfix: Option<(usize, Expr)>) -> Expr {
+        let increment = self.is("++");
+        let prefix = postfix.is_none();
+        let (start, arg) = match postfix {
+            Some((start, arg)) => {
+                self.advance();
+                (start, arg)
+            }
+            None => {
+                let start = self.tok.start;
+                self.advance();
+                (start, self.parse_unary())
+            }
+        };
+        let arg = match self.simple_target(arg) {
+            Pat::Expr(expr) => *expr,
+            Pat::Ident(b) => Expr::Ident(b.id),
+            other => Expr::Invalid(other.span()),
+        };
+        Expr::Update(Box::new(UpdateExpr { span: self.span_from(start), increment, prefix, arg }))
+    }
+
+    /// `await` is an operator in async functions and at the top level of a
+    /// module; in plain functions only when an operand follows on the same line
+    fn await_is_keyword(&mut self) -> bool {
+        if self.ctx.in_async {
+            return true;
+        }
+        let next = self.peek();
+        if next.newline_before && self.ctx.in_function {
+            return false;
+        }
+        match next.kind {
+            TokenKind::Eof => false,
+            TokenKind::Name => !matches!(next.text(self.source), "in" | "instanceof" | "of" | "as" | "satisfies"),
+            TokenKind::Punct => matches!(
+                next.text(self.source),
+                "(" | "[" | "{" | "!" | "~" | "+" | "-" | "++" | "--" | "/" | "/=" | "<" | "#"
+            ) && !(next.text(self.source) == "(" && self.ctx.in_function),
+            _ => true,
+        }
+    }
+
+    /// `<T>expr` (TypeScript without JSX)
+    fn parse_type_assertion(&mut self) -> Expr {
+        let start = self.tok.start;
+        self.advance();
+        if self.is_word("const") {
+            self.advance();
+        } else {
+            self.parse_type();
+        }
+        self.expect_gt();
+        let type_span = self.span_from(start);
+        let expr = self.parse_unary();
+        Expr::TsTypeAssertion(Box::new(TsTypeAssertion { span: self.span_from(start), type_span, expr }))
+    }
+
+    fn parse_lhs(&mut self) -> Expr {
+        let start = self.tok.start;
+        let expr = self.parse_primary_or_new();
+        self.parse_call_tail(start, expr, true)
+    }
+
+    fn parse_primary_or_new(&mut self) -> Expr {
+        let start = self.tok.start;
+        if self.tok.kind == TokenKind::Name {
+            match self.text() {
+                "new" => return self.parse_new(),
+                "super" => {
+                    self.advance();
+                    return Expr::Super(self.span_from(start));
+                }
+                "import" => {
+                    self.advance();
+                    if self.eat(".") {
+                        let property = self.parse_ident_name();
+                        if property.name != "meta" {
+                            self.error(ErrorCode::UnexpectedToken, "Expected `import.meta`", property.span);
+                        }
+                        return Expr::MetaProp(MetaProp { span: self.span_from(start), kind: MetaPropKind::ImportMeta });
+                    }
+                    return self.parse_import_call(start);
+                }
+                _ => {}
+            }
+        }
+        self.parse_primary()
+    }
+
+    fn parse_import_call(&mut self, start: usize) -> Expr {
+        if !self.expect("(") {
+            return Expr::Invalid(self.span_from(start));
+        }
+        let (source, options) = self.nested(|p| {
+            let source = p.parse_assign();
+            let options = (p.eat(",") && !p.is(")")).then(|| p.parse_assign());
+            p.eat(",");
+            (source, options)
+        });
+        self.expect(")");
+        Expr::Import(Box::new(ImportCall { span: self.span_from(start), source, options }))
+    }
+
+    fn parse_new(&mut self) -> Expr {
+        let start = self.tok.start;
+        self.advance();
+        if self.eat(".") {
+            let property = self.parse_ident_name();
+            if property.name != "target" {
+                self.error(ErrorCode::UnexpectedToken, "Expected `new.target`", property.span);
+            }
+            return Expr::MetaProp(MetaProp { span: self.span_from(start), kind: MetaPropKind::NewTarget });
+        }
+        let callee_start = self.tok.start;
+        let callee = if self.is_word("new") {
+            self.parse_new()
+        } else {
+            let expr = self.parse_primary_or_new();
+            self.parse_call_tail(callee_start, expr, false)
+        };
+        let type_args = if self.ts && self.is("<") {
+            self.try_parse(|p| Some(p.parse_type_args()))
+        } else {
+            None
+        };
+        let args = self.is("(").then(|| self.parse_args().0);
+        Expr::New(Box::new(NewExpr { span: self.span_from(start), callee, type_args, args }))
+    }
+
+    fn parse_args(&mut self) -> (Vec<ExprOrSpread>, Span) {
+        let start = self.tok.start;
+        self.expect("(");
+        let args = self.nested(|p| {
+            let mut args = Vec::new();
+            while !p.is(")") && !p.at_eof() {
+                let spread = p.is("...").then(|| {
+                    let span = Span::new(p.tok.start, p.tok.end);
+                    p.advance();
+                    span
+                });
+                let expr = p.parse_assign();
+                args.push(ExprOrSpread { spread, expr });
+                if !p.is(")") && !p.expect(",") {
+                    break;
+                }
+            }
+            args
+        });
+        self.expect(")");
+        (args, self.span_from(start))
+    }
+
+    /// Can type arguments end here, as in `f<T>(x)` or `f<T>;`, rather than
+    /// being comparisons like `a < b > c`?
+    fn can_follow_type_args(&self) -> bool {
+        match self.tok.kind {
+            TokenKind::Template | TokenKind::TemplateHead => true,
+            TokenKind::Punct if self.is("(") => true,
+            TokenKind::Punct if matches!(self.text(), "<" | ">" | "+" | "-") => false,
+            _ => self.tok.newline_before || self.binary_op().is_some() || !self.starts_expression(),
+        }
+    }
+
+    fn parse_call_tail(&mut self, start: usize, mut expr: Expr, allow_call: bool) -> Expr {
+        let mut in_chain = false;
+        loop {
+            // Each suffix is parsed in its own function to keep this frame,
+            // which is live across every level of nesting, small
+            let step = match self.tok.kind {
+                TokenKind::Punct => match self.text() {
+                    "." => {
+                        self.advance();
+                        Ok(self.finish_member(start, expr, false))
+                    }
+                    "?." if allow_call => {
+                        in_chain = true;
+                        self.advance();
+                        Ok(self.parse_optional_suffix(start, expr))
+                    }
+                    "[" => Ok(self.finish_computed_member(start, expr, false)),
+                    "(" if allow_call => Ok(self.finish_call(start, expr, None, false)),
+                    "!" if self.ts && !self.tok.newline_before => {
+                        self.advance();
+                        Ok(Expr::TsNonNull(Box::new(TsNonNullExpr { span: self.span_from(start), expr })))
+                    }
+                    "<" if self.ts => self.parse_type_args_suffix(start, expr, allow_call),
+                    _ => Err(expr),
+                },
+                TokenKind::Template | TokenKind::TemplateHead => {
+                    if in_chain {
+                        self.error(ErrorCode::InvalidSyntax, "Tagged template in optional chain", Span::new(self.tok.start, self.tok.end));
+                    }
+                    Ok(self.finish_tagged_template(start, expr, None))
+                }
+                _ => Err(expr),
+            };
+            match step {
+                Ok(next) => expr = next,
+                Err(last) => {
+                    expr = last;
+                    break;
+                }
+            }
+        }
+        if in_chain {
+            expr = Expr::OptChain(Box::new(OptChainExpr { span: self.span_from(start), expr }));
+        }
+        expr
+    }
+
+    /// `.name` / `?.name` / `.#name`, after the dot
+    fn finish_member(&mut self, start: usize, object: Expr, optional: bool) -> Expr {
+        let property = if self.tok.kind == TokenKind::PrivateName {
+            let ident = self.ident_from_token();
+            self.advance();
+            MemberProp::PrivateName(ident)
+        } else {
+            MemberProp::Ident(self.parse_ident_name())
+        };
+        Expr::Member(Box::new(MemberExpr { span: self.span_from(start), object, property, optional }))
+    }
+
+    fn finish_computed_member(&mut self, start: usize, object: Expr, optional: bool) -> Expr {
+        let property = MemberProp::Computed(self.parse_computed_member());
+        Expr::Member(Box::new(MemberExpr { span: self.span_from(start), object, property, optional }))
+    }
+
+    fn finish_call(&mut self, start: usize, callee: Expr, type_args: Option<TsTypeArgs>, optional: bool) -> Expr {
+        let (args, args_span) = self.parse_args();
+        Expr::Call(Box::new(CallExpr { span: self.span_from(start), callee, type_args, args_span, args, optional }))
+    }
+
+    fn finish_tagged_template(&mut self, start: usize, tag: Expr, type_args: Option<TsTypeArgs>) -> Expr {
+        let quasi = self.parse_template_lit();
+        Expr::TaggedTemplate(Box::new(TaggedTemplate { span: self.span_from(start), tag, type_args, quasi }))
+    }
+
+    /// What follows `?.`
+    fn parse_optional_suffix(&mut self, start: usize, expr: Expr) -> Expr {
+        if self.is("(") {
+            self.finish_call(start, expr, None, true)
+        } else if self.is("[") {
+            self.finish_computed_member(start, expr, true)
+        } else if self.ts && self.is("<") {
+            let type_args = Some(self.parse_type_args());
+            self.finish_call(start, expr, type_args, true)
+        } else {
+            self.finish_member(start, expr, true)
+        }
+    }
+
+    /// `f<T>(x)`, `` f<T>`x` `` or `f<T>`; `Err` when the `<` is a comparison
+    fn parse_type_args_suffix(&mut self, start: usize, expr: Expr, allow_call: bool) -> Result<Expr, Expr> {
+        let Some(type_args) = self.try_parse(|p| {
+            let args = p.parse_type_args();
+            p.can_follow_type_args().then_some(args)
+        }) else {
+            return Err(expr);
+        };
+        Ok(if self.is("(") && allow_call {
+            self.finish_call(start, expr, Some(type_args), false)
+        } else if matches!(self.tok.kind, TokenKind::Template | TokenKind::TemplateHead) {
+            self.finish_tagged_template(start, expr, Some(type_args))
+        } else {
+            Expr::TsInstantiation(Box::new(TsInstantiation { span: self.span_from(start), expr, type_args }))
+        })
+    }
+
+    fn parse_computed_member(&mut self) -> ComputedKey {
+        let start = self.tok.start;
+        self.advance();
+        let expr = Box::new(self.nested(|p| p.parse_expr()));
+        self.expect("]");
+        ComputedKey { span: self.span_from(start), expr }
+    }
+
+    fn parse_primary(&mut self) -> Expr {
+        match self.tok.kind {
+            TokenKind::Name => self.parse_primary_word(),
+            TokenKind::Punct => match self.text() {
+                "(" => self.parse_paren_expr(),
+                "[" => self.parse_array_lit(),
+                "{" => self.parse_object_lit(),
+                "/" | "/=" => {
+                    // Re-read as a regex now that we know an operand is expected
+                    let newline_before = self.tok.newline_before;
+                    self.lexer.reset(self.tok.start);
+                    self.tok = self.lexer.next_token(true);
+                    self.tok.newline_before = newline_before;
+                    self.parse_regex()
+                }
+                "<" if self.jsx => self.parse_jsx(),
+                "<" if self.ts => self.parse_type_assertion(),
+                "@" => self.parse_decorated_class_expr(),
+                _ => self.expected_expression(),
+            },
+            TokenKind::Template | TokenKind::TemplateHead => Expr::Template(self.parse_template_lit()),
+            TokenKind::Regex => self.parse_regex(),
+            TokenKind::Eof => self.expected_expression(),
+            _ => self.parse_literal(),
+        }
+    }
+
+    fn expected_expression(&mut self) -> Expr {
+        let span = Span::new(self.tok.start, self.tok.end);
+        self.unexpected("Expected an expression");
+        if !self.at_eof() && !matches!(self.text(), ")" | "]" | "}" | ";" | ",") {
+            self.advance();
+        }
+        Expr::Invalid(span)
+    }
+
+    /// Numbers, bigints, strings and `#name` (in `#name in obj`)
+    fn parse_literal(&mut self) -> Expr {
+        let span = Span::new(self.tok.start, self.tok.end);
+        let value = match self.tok.kind {
+            TokenKind::Number => LitValue::Num(lexer::number_value(self.text()).unwrap_or(f64::NAN)),
+            TokenKind::BigInt => LitValue::BigInt(self.text().to_string()),
+            TokenKind::String => return Expr::Lit(self.parse_string_lit()),
+            TokenKind::PrivateName => {
+                let ident = self.ident_from_token();
+                self.advance();
+                return Expr::PrivateName(ident);
+            }
+            _ => return self.expected_expression(),
+        };
+        self.advance();
+        Expr::Lit(Lit { span, value })
+    }
+
+    fn parse_primary_word(&mut self) -> Expr {
+        let start = self.tok.start;
+        let span = Span::new(self.tok.start, self.tok.end);
+        match self.text() {
+            "this" => {
+                self.advance();
+                Expr::This(span)
+            }
+            "null" => {
+                self.advance();
+                Expr::Lit(Lit { span, value: LitValue::Null })
+            }
+            "true" | "false" => {
+                let value = LitValue::Bool(self.is_word("true"));
+                self.advance();
+                Expr::Lit(Lit { span, value })
+            }
+            "function" => Expr::Function(Box::new(self.parse_function(start, false, false))),
+            "async" if self.peek().is_word(self.source, "function") && !self.peek().newline_before => {
+                self.advance();
+                Expr::Function(Box::new(self.parse_function(start, true, false)))
+            }
+            "class" => Expr::Class(Box::new(self.parse_class(start, Vec::new(), false))),
+            "new" | "super" | "import" => self.parse_primary_or_new(),
+            _ if self.is_identifier() => Expr::Ident(self.parse_binding_ident()),
+            _ => self.expected_expression(),
+        }
+    }
+
+    fn parse_paren_expr(&mut self) -> Expr {
+        let start = self.tok.start;
+        self.advance();
+        let expr = self.nested(|p| p.parse_expr());
+        self.expect(")");
+        Expr::Paren(Box::new(ParenExpr { span: self.span_from(start), expr }))
+    }
+
+    fn parse_decorated_class_expr(&mut self) -> Expr {
+        let start = self.tok.start;
+        let decorators = self.parse_decorators();
+        self.pending_decorators = decorators;
+        if !self.is_word("class") {
+            self.unexpected("Expected `class`");
+            self.pending_decorators.clear();
+            return Expr::Invalid(self.span_from(start));
+        }
+        Expr::Class(Box::new(self.parse_class(start, Vec::new(), false)))
+    }
+
+    fn parse_regex(&mut self) -> Expr {
+        let span = Span::new(self.tok.start, self.tok.end);
+        let raw = self.text();
+        let value = match raw.rfind('/') {
+            Some(slash) if slash > 0 => LitValue::Regex { pattern: raw[1..slash].to_string(), flags: raw[slash + 1..].to_string() },
+            _ => LitValue::Regex { pattern: raw.get(1..).unwrap_or("").to_string(), flags: String::new() },
+        };
+        self.advance();
+        Expr::Lit(Lit { span, value })
+    }
+
+    /// Raw text span of a template part, delimiters excluded
+    fn quasi_span(&self, tok: Token) -> Span {
+        let text = tok.text(self.source);
+        let start = tok.start + 1;
+        let end = match tok.kind {
+            TokenKind::TemplateHead | TokenKind::TemplateMiddle if text.ends_with("${") => tok.end - 2,
+            _ if text.len() >= 2 && text.ends_with('`') => tok.end - 1,
+            _ => tok.end,
+        };
+        Span::new(start.min(end.max(start)), end.max(start))
+    }
+
+    fn parse_template_lit(&mut self) -> TemplateLit {
+        let start = self.tok.start;
+        let mut quasis = vec![self.quasi_span(self.tok)];
+        let mut exprs = Vec::new();
+        let is_head = self.tok.kind == TokenKind::TemplateHead;
+        self.advance();
+        if is_head {
+            loop {
+                exprs.push(self.nested(|p| p.parse_expr()));
+                if !self.is("}") {
+                    self.unexpected("Expected `}`");
+                    break;
+                }
+                self.tok = self.lexer.rescan_template_continuation(self.tok);
+                quasis.push(self.quasi_span(self.tok));
+                let is_tail = self.tok.kind == TokenKind::TemplateTail;
+                self.advance();
+                if is_tail {
+                    break;
+                }
+            }
+        }
+        TemplateLit { span: self.span_from(start), quasis, exprs }
+    }
+
+    fn parse_array_lit(&mut self) -> Expr {
+        let start = self.tok.start;
+        self.advance();
+        let elems = self.nested(|p| {
+            let mut elems = Vec::new();
+            while !p.is("]") && !p.at_eof() {
+                if p.eat(",") {
+                    elems.push(None);
+                    continue;
+                }
+                let spread = p.is("...").then(|| {
+                    let span = Span::new(p.tok.start, p.tok.end);
+                    p.advance();
+                    span
+                });
+                let expr = p.parse_assign();
+                elems.push(Some(ExprOrSpread { spread, expr }));
+                if !p.is("]") && !p.expect(",") {
+                    break;
+                }
+            }
+            elems
+        });
+        self.expect("]");
+        Expr::Array(ArrayLit { span: self.span_from(start), elems })
+    }
+
+    fn parse_object_lit(&mut self) -> Expr {
+        let start = self.tok.start;
+        self.advance();
+        let props = self.nested(|p| {
+            let mut props = Vec::new();
+            while !p.is("}") && !p.at_eof() {
+                let errors = p.error_count;
+                props.push(p.parse_object_prop());
+                if p.error_count > errors || !p.is("}") && !p.expect(",") {
+                    break;
+                }
+            }
+            props
+        });
+        self.expect("}");
+        Expr::Object(ObjectLit { span: self.span_from(start), props })
+    }
+
+    fn parse_object_prop(&mut self) -> Prop {
+        let start = self.tok.start;
+        if self.eat("...") {
+            let expr = self.parse_assign();
+            return Prop::Spread(SpreadElement { span: self.span_from(start), expr });
+        }
+        let mut is_async = false;
+        let mut is_generator = false;
+        let mut kind = MethodKind::Method;
+        if self.is_word("async") && self.starts_method_after_modifier(false) {
+            self.advance();
+            is_async = true;
+        }
+        if self.eat("*") {
+            is_generator = true;
+        }
+        if !is_async && !is_generator && (self.is_word("get") || self.is_word("set")) && self.starts_method_after_modifier(true) {
+            kind = if self.is_word("get") { MethodKind::Getter } else { MethodKind::Setter };
+            self.advance();
+        }
+        let key = self.parse_prop_key();
+
+        if self.is("(") || self.is("<") {
+            let function_start = self.tok.start;
+            let function = self.parse_function_rest(function_start, None, is_async, is_generator);
+            return Prop::Method(MethodProp { span: self.span_from(start), kind, key, function });
+        }
+        if is_async || is_generator || kind != MethodKind::Method {
+            self.unexpected("Expected `(`");
+        }
+        if self.eat(":") {
+            let value = self.parse_assign();
+            return Prop::KeyValue(KeyValueProp { span: self.span_from(start), key, value });
+        }
+        match key {
+            PropKey::Ident(ident) if self.is("=") => {
+                self.advance();
+                let value = self.parse_assign();
+                Prop::Assign(AssignProp { span: self.span_from(start), key: ident, value })
+            }
+            PropKey::Ident(ident) => {
+                if RESERVED_WORDS.contains(&ident.name.as_str()) {
+                    self.error(ErrorCode::UnexpectedToken, "Expected `:`", ident.span);
+                }
+                Prop::Shorthand(ident)
+            }
+            other => {
+                self.unexpected("Expected `:`");
+                Prop::KeyValue(KeyValueProp { span: self.span_from(start), value: Expr::Invalid(other.span()), key: other })
+            }
+        }
+    }
+
+    /// Reinterpret an expression as an assignment target (`[a, b] = c`)
+    fn expr_to_pat(&mut self, expr: Expr) -> Pat {
+        match expr {
+            Expr::Ident(id) => Pat::Ident(BindingIdent { span: id.span, id, optional: None, definite: None, type_ann: None }),
+            Expr::Array(array) => {
+                let elems = array
+                    .elems
+                    .into_iter()
+                    .map(|elem| {
+                        elem.map(|e| match e.spread {
+                            Some(spread) => {
+                                let span = Span::new(spread.start, e.expr.span().end);
+                                Pat::Rest(RestPat { span, arg: Box::new(self.expr_to_pat(e.expr)), type_ann: None })
+                            }
+                            None => self.expr_to_pat(e.expr),
+                        })
+                    })
+                    .collect();
+                Pat::Array(ArrayPat { span: array.span, elems, optional: None, type_ann: None })
+            }
+            Expr::Object(object) => {
+                let props = object
+                    .props
+                    .into_iter()
+                    .map(|prop| match prop {
+                        Prop::Shorthand(key) => ObjectPatProp::Assign { span: key.span, key, value: None },
+                        Prop::Assign(p) => ObjectPatProp::Assign { span: p.span, key: p.key, value: Some(p.value) },
+                        Prop::KeyValue(p) => ObjectPatProp::KeyValue { span: p.span, key: p.key, value: self.expr_to_pat(p.value) },
+                        Prop::Spread(s) => ObjectPatProp::Rest(RestPat { span: s.span, arg: Box::new(self.expr_to_pat(s.expr)), type_ann: None }),
+                        Prop::Method(m) => {
+                            self.error(ErrorCode::InvalidSyntax, "Invalid destructuring target", m.span);
+                            ObjectPatProp::Rest(RestPat { span: m.span, arg: Box::new(Pat::Invalid(m.span)), type_ann: None })
+                        }
+                    })
+                    .collect();
+                Pat::Object(ObjectPat { span: object.span, props, optional: None, type_ann: None })
+            }
+            Expr::Assign(assign) if assign.op == AssignOp::Assign => {
+                Pat::Assign(AssignPat { span: assign.span, left: Box::new(assign.left), right: assign.right })
+            }
+            other => self.simple_target(other),
+        }
+    }
+
+    /// Target of `+=` and `++`: an identifier or a member expression
+    fn simple_target(&mut self, expr: Expr) -> Pat {
+        match expr.unwrapped() {
+            Expr::Ident(_) | Expr::Member(_) => {}
+            Expr::Invalid(_) => return Pat::Expr(Box::new(expr)),
+            _ => self.error(ErrorCode::InvalidSyntax, "Invalid assignment target", expr.span()),
+        }
+        match expr {
+            Expr::Ident(id) => Pat::Ident(BindingIdent { span: id.span, id, optional: None, definite: None, type_ann: None }),
+            other => Pat::Expr(Box::new(other)),
+        }
+    }
+
+    // -----------------------------------------------------------------------
+    // TypeScript types
+    // -----------------------------------------------------------------------
+
+    /// Close type arguments, splitting `>>`, `>=` and friends
+    fn expect_gt(&mut self) {
+        if self.tok.kind == TokenKind::Punct && self.text().starts_with('>') {
+            if self.text().len() == 1 {
+                self.advance();
+            } else {
+                let split = self.tok.start + 1;
+                self.prev_end = split;
+                self.lexer.reset(split);
+                self.tok = self.lexer.next_token(false);
+                self.tok.newline_before = false;
+            }
+            return;
+        }
+        self.unexpected("Expected `>`");
+    }
+
+    fn parse_type_ann(&mut self) -> TsTypeAnn {
+        let start = self.tok.start;
+        self.advance();
+        let ty = self.parse_type();
+        TsTypeAnn { span: self.span_from(start), ty }
+    }
+
+    /// Return type annotation, allowing `x is T` and `asserts x`
+    fn parse_return_type_ann(&mut self) -> TsTypeAnn {
+        let start = self.tok.start;
+        self.advance();
+        let ty = self.parse_return_type();
+        TsTypeAnn { span: self.span_from(start), ty }
+    }
+
+    fn parse_return_type(&mut self) -> TsType {
+        let start = self.tok.start;
+        if self.is_word("asserts") {
+            let next = self.peek();
+            if next.kind == TokenKind::Name && !next.newline_before {
+                self.advance();
+                self.advance();
+                if self.eat_word("is") {
+                    self.parse_type();
+                }
+                return TsType { span: self.span_from(start), kind: TsTypeKind::Other };
+            }
+        }
+        if self.tok.kind == TokenKind::Name {
+            let next = self.peek();
+            if next.is_word(self.source, "is") && !next.newline_before {
+                self.advance();
+                self.advance();
+                self.parse_type();
+                return TsType { span: self.span_from(start), kind: TsTypeKind::Other };
+            }
+        }
+        self.parse_type()
+    }
+
+    fn parse_type_params(&mut self) -> TsTypeParams {
+        let start = self.tok.start;
+        self.advance();
+        let mut params = Vec::new();
+        while !(self.at_eof() || self.tok.kind == TokenKind::Punct && self.text().starts_with('>')) {
+            while (self.is_word("const") || self.is_word("in") || self.is_word("out")) && self.peek().kind == TokenKind::Name {
+                self.advance();
+            }
+            params.push(self.parse_binding_ident());
+            if self.eat_word("extends") {
+                self.parse_type();
+            }
+            if self.eat("=") {
+                self.parse_type();
+            }
+            if !self.eat(",") {
+                break;
+            }
+        }
+        self.expect_gt();
+        TsTypeParams { span: self.span_from(start), params }
+    }
+
+    fn parse_type_args(&mut self) -> TsTypeArgs {
+        let start = self.tok.start;
+        self.advance();
+        let mut args = Vec::new();
+        while !(self.at_eof() || self.tok.kind == TokenKind::Punct && self.text().starts_with('>')) {
+            args.push(self.parse_type());
+            if !self.eat(",") {
+                break;
+            }
+        }
+        self.expect_gt();
+        TsTypeArgs { span: self.span_from(start), args }
+    }
+
+    fn parse_type(&mut self) -> TsType {
+        if !self.enter() {
+            return TsType { span: Span::new(self.prev_end, self.prev_end), kind: TsTypeKind::Other };
+        }
+        let ty = self.parse_type_inner();
+        self.leave();
+        ty
+    }
+
+    fn parse_type_inner(&mut self) -> TsType {
+        let start = self.tok.start;
+        if self.is("<") {
+            return self.parse_function_type(start, TsTypeKind::Function);
+        }
+        if self.is_word("new") {
+            self.advance();
+            return self.parse_function_type(start, TsTypeKind::Constructor);
+        }
+        if self.is_word("abstract") && self.peek().is_word(self.source, "new") {
+            self.advance();
+            self.advance();
+            return self.parse_function_type(start, TsTypeKind::Constructor);
+        }
+        if self.is("(") {
+            if let Some(ty) = self.try_parse(|p| {
+                let ty = p.parse_function_type(start, TsTypeKind::Function);
+                Some(ty)
+            }) {
+                return ty;
+            }
+        }
+        let check = self.parse_union_type();
+        if self.is_word("extends") && !self.tok.newline_before {
+            self.advance();
+            self.parse_union_type();
+            self.expect("?");
+            self.parse_type();
+            self.expect(":");
+            self.parse_type();
+            return TsType { span: self.span_from(start), kind: TsTypeKind::Other };
+        }
+        check
+    }
+
+    /// `<T>(a: A) => R`, after `new` if any
+    fn parse_function_type(&mut self, start: usize, kind: TsTypeKind) -> TsType {
+        if self.is("<") {
+            self.parse_type_params();
+        }
+        self.parse_params();
+        self.expect("=>");
+        self.parse_return_type();
+        TsType { span: self.span_from(start), kind }
+    }
+
+    fn parse_union_type(&mut self) -> TsType {
+        let start = self.tok.start;
+        let leading = self.eat("|");
+        let first = self.parse_intersection_type();
+        if !self.is("|") {
+            if leading {
+                return TsType { span: self.span_from(start), kind: TsTypeKind::Union(vec![first]) };
+            }
+            return first;
+        }
+        let mut types = vec![first];
+        while self.eat("|") {
+            types.push(self.parse_intersection_type());
+        }
+        TsType { span: self.span_from(start), kind: TsTypeKind::Union(types) }
+    }
+
+    fn parse_intersection_type(&mut self) -> TsType {
+        let start = self.tok.start;
+        let leading = self.eat("&");
+        let first = self.parse_type_operator();
+        if !self.is("&") {
+            if leading {
+                return TsType { span: self.span_from(start), kind: TsTypeKind::Intersection(vec![first]) };
+            }
+            return first;
+        }
+        let mut types = vec![first];
+        while self.eat("&") {
+            types.push(self.parse_type_operator());
+        }
+        TsType { span: self.span_from(start), kind: TsTypeKind::Intersection(types) }
+    }
+
+    fn parse_type_operator(&mut self) -> TsType {
+        let start = self.tok.start;
+        if matches!(self.text(), "keyof" | "unique" | "readonly") && self.tok.kind == TokenKind::Name {
+            let next = self.peek();
+            let applies = match next.kind {
+                TokenKind::Name | TokenKind::String | TokenKind::Number | TokenKind::Template | TokenKind::TemplateHead => true,
+                TokenKind::Punct => matches!(next.text(self.source), "(" | "[" | "{" | "<" | "-"),
+                _ => false,
+            };
+            if applies {
+                self.advance();
+                self.parse_type_operator();
+                return TsType { span: self.span_from(start), kind: TsTypeKind::Other };
+            }
+        }
+        if self.is_word("infer") && self.peek().kind == TokenKind::Name {
+            self.advance();
+            self.parse_ident_name();
+            if self.is_word("extends") {
+                // `infer U extends X ? ...` is a conditional, not a constraint
+                let _ = self.try_parse(|p| {
+                    p.advance();
+                    p.parse_union_type();
+                    (!p.is("?")).then_some(())
+                });
+            }
+            return TsType { span: self.span_from(start), kind: TsTypeKind::Other };
+        }
+        self.parse_postfix_type()
+    }
+
+    fn parse_postfix_type(&mut self) -> TsType {
+        let start = self.tok.start;
+        let mut ty = self.parse_primary_type();
+        while self.is("[") && !self.tok.newline_before {
+            self.advance();
+            if self.eat("]") {
+                ty = TsType { span: self.span_from(start), kind: TsTypeKind::Array(Box::new(ty)) };
+            } else {
+                self.parse_type();
+                self.expect("]");
+                ty = TsType { span: self.span_from(start), kind: TsTypeKind::Other };
+            }
+        }
+        ty
+    }
+
+    fn parse_primary_type(&mut self) -> TsType {
+        let start = self.tok.start;
+        match self.tok.kind {
+            TokenKind::Name => {
+                let word = self.text();
+                if word == "this" {
+                    self.advance();
+                    return TsType { span: self.span_from(start), kind: TsTypeKind::This };
+                }
+                if word == "typeof" {
+                    self.advance();
+                    if self.is_word("import") {
+                        self.parse_import_type();
+                    } else {
+                        self.parse_entity_name();
+                    }
+                    if self.is("<") && !self.tok.newline_before {
+                        self.parse_type_args();
+                    }
+                    return TsType { span: self.span_from(start), kind: TsTypeKind::Query };
+                }
+                if word == "import" {
+                    self.parse_import_type();
+                    if self.is("<") {
+                        self.parse_type_args();
+                    }
+                    return TsType { span: self.span_from(start), kind: TsTypeKind::Other };
+                }
+                if word == "true" || word == "false" {
+                    self.advance();
+                    return TsType { span: self.span_from(start), kind: TsTypeKind::Literal(LitKind::Bool) };
+                }
+                if TYPE_KEYWORDS.contains(&word) && !self.peek_is(".") {
+                    self.advance();
+                    return TsType { span: self.span_from(start), kind: TsTypeKind::Keyword(word.to_string()) };
+                }
+                if word == "asserts" || word == "infer" || word == "keyof" {
+                    // Used as plain type names
+                }
+                let path = self.parse_entity_name();
+                if self.is("<") && !self.tok.newline_before {
+                    self.parse_type_args();
+                }
+                TsType { span: self.span_from(start), kind: TsTypeKind::Reference(path) }
+            }
+            TokenKind::String => {
+                self.advance();
+                TsType { span: self.span_from(start), kind: TsTypeKind::Literal(LitKind::Str) }
+            }
+            TokenKind::Number => {
+                self.advance();
+                TsType { span: self.span_from(start), kind: TsTypeKind::Literal(LitKind::Num) }
+            }
+            TokenKind::BigInt => {
+                self.advance();
+                TsType { span: self.span_from(start), kind: TsTypeKind::Literal(LitKind::BigInt) }
+            }
+            TokenKind::Template => {
+                self.advance();
+                TsType { span: self.span_from(start), kind: TsTypeKind::Literal(LitKind::Template) }
+            }
+            TokenKind::TemplateHead => {
+                self.advance();
+                loop {
+                    self.parse_type();
+                    if !self.is("}") {
+                        self.unexpected("Expected `}`");
+                        break;
+                    }
+                    self.tok = self.lexer.rescan_template_continuation(self.tok);
+                    let is_tail = self.tok.kind == TokenKind::TemplateTail;
+                    self.advance();
+                    if is_tail {
+                        break;
+                    }
+                }
+                TsType { span: self.span_from(start), kind: TsTypeKind::Literal(LitKind::Template) }
+            }
+            TokenKind::Punct => match self.text() {
+                "-" if self.peek().kind == TokenKind::Number || self.peek().kind == TokenKind::BigInt => {
+                    self.advance();
+                    self.advance();
+                    TsType { span: self.span_from(start), kind: TsTypeKind::Literal(LitKind::Num) }
+                }
+                "(" => {
+                    self.advance();
+                    let inner = self.parse_type();
+                    self.expect(")");
+                    TsType { span: self.span_from(start), kind: TsTypeKind::Paren(Box::new(inner)) }
+                }
+                "[" => {
+                    self.advance();
+                    while !self.is("]") && !self.at_eof() {
+                        self.eat("...");
+                        // Named member: `name?: T`
+                        if self.tok.kind == TokenKind::Name && {
+                            let next = self.peek();
+                            next.is_punct(self.source, ":") || next.is_punct(self.source, "?") && self.is_named_tuple_member()
+                        } {
+                            self.advance();
+                            self.eat("?");
+                            self.expect(":");
+                            self.eat("...");
+                        }
+                        self.parse_type();
+                        self.eat("?");
+                        if !self.eat(",") {
+                            break;
+                        }
+                    }
+                    self.expect("]");
+                    TsType { span: self.span_from(start), kind: TsTypeKind::Tuple }
+                }
+                "{" => {
+                    if self.looks_like_mapped_type() {
+                        self.parse_mapped_type();
+                    } else {
+                        self.parse_type_members();
+                    }
+                    TsType { span: self.span_from(start), kind: TsTypeKind::TypeLiteral }
+                }
+                _ => {
+                    self.unexpected("Expected a type");
+                    TsType { span: Span::new(start, start), kind: TsTypeKind::Other }
+                }
+            },
+            _ => {
+                self.unexpected("Expected a type");
+                TsType { span: Span::new(start, start), kind: TsTypeKind::Other }
+            }
+        }
+    }
+
+    /// `[name?: T]` (as opposed to `[T?]`)
+    fn is_named_tuple_member(&mut self) -> bool {
+        let snapshot = self.snapshot();
+        self.advance();
+        self.advance();
+        let result = self.is(":");
+        self.restore(snapshot);
+        result
+    }
+
+    /// `import("x").A.B`
+    fn parse_import_type(&mut self) {
+        self.advance();
+        self.expect("(");
+        self.parse_string_lit();
+        if self.eat(",") && !self.is(")") {
+            self.nested(|p| p.parse_assign());
+            self.eat(",");
+        }
+        self.expect(")");
+        while self.eat(".") {
+            self.parse_ident_name();
+        }
+    }
+
+    fn looks_like_mapped_type(&mut self) -> bool {
+        let snapshot = self.snapshot();
+        self.advance();
+        if self.is("+") || self.is("-") {
+            self.advance();
+        }
+        if self.is_word("readonly") {
+            self.advance();
+        }
+        let result = self.is("[") && {
+            self.advance();
+            self.tok.kind == TokenKind::Name && {
+                self.advance();
+                self.is_word("in")
+            }
+        };
+        self.restore(snapshot);
+        result
+    }
+
+    /// `{ readonly [K in T as U]-?: V }`
+    fn parse_mapped_type(&mut self) {
+        self.expect("{");
+        if self.is("+") || self.is("-") {
+            self.advance();
+        }
+        self.eat_word("readonly");
+        self.expect("[");
+        self.parse_ident_name();
+        self.eat_word("in");
+        self.parse_type();
+        if self.eat_word("as") {
+            self.parse_type();
+        }
+        self.expect("]");
+        if self.is("+") || self.is("-") {
+            self.advance();
+        }
+        self.eat("?");
+        if self.is(":") {
+            self.parse_type_ann();
+        }
+        self.eat(";");
+        self.eat(",");
+        self.expect("}");
+    }
+
+    /// `{ a: T; b(): U; [k: string]: V; new (): W }`, braces included
+    fn parse_type_members(&mut self) {
+        if !self.expect("{") {
+            return;
+        }
+        while !self.is("}") && !self.at_eof() {
+            let errors = self.error_count;
+            self.parse_type_member();
+            if self.error_count > errors {
+                break;
+            }
+            self.eat_member_separator();
+        }
+        self.expect("}");
+    }
+
+    fn parse_type_member(&mut self) {
+        if self.is("(") || self.is("<") {
+            self.parse_signature_rest();
+            return;
+        }
+        if self.is_word("new") && (self.peek_is("(") || self.peek_is("<")) {
+            self.advance();
+            self.parse_signature_rest();
+            return;
+        }
+        while self.is_word("readonly") && self.starts_method_after_modifier(false) {
+            self.advance();
+        }
+        if (self.is_word("get") || self.is_word("set")) && self.starts_method_after_modifier(false) {
+            self.advance();
+        }
+        if self.is("[") && self.looks_like_index_signature() {
+            self.parse_index_signature();
+            return;
+        }
+        self.parse_prop_key();
+        self.eat("?");
+        if self.is("(") || self.is("<") {
+            self.parse_signature_rest();
+        } else if self.is(":") {
+            self.parse_type_ann();
+        }
+    }
+
+    /// Type parameters, parameters and return type of a call signature
+    fn parse_signature_rest(&mut self) {
+        if self.is("<") {
+            self.parse_type_params();
+        }
+        self.parse_params();
+        if self.is(":") {
+            self.parse_return_type_ann();
+        }
+    }
+
+    // -----------------------------------------------------------------------
+    // JSX
+    // -----------------------------------------------------------------------
+
+    #[inline]
+    fn byte(&self, pos: usize) -> u8 {
+        self.bytes.get(pos).copied().unwrap_or(0)
+    }
+
+    fn parse_jsx(&mut self) -> Expr {
+        let start = self.tok.start;
+        let (expr, end) = self.parse_jsx_at(start);
+        self.resync(end);
+        expr
+    }
+
+    /// Skip whitespace and comments inside a JSX tag
+    fn jsx_skip_trivia(&mut self, mut pos: usize) -> usize {
+        loop {
+            match self.byte(pos) {
+                b' ' | b'\t' | b'\n' | b'\r' => pos += 1,
+                b'/' if self.byte(pos + 1) == b'/' => {
+                    let end = self.source[pos..].find(['\n', '\r']).map_or(self.source.len(), |i| pos + i);
+                    self.lexer.comments.push(lexer::Comment { start: pos, end, is_block: false });
+                    pos = end;
+                }
+                b'/' if self.byte(pos + 1) == b'*' => {
+                    let end = self.source[pos + 2..].find("*/").map_or(self.source.len(), |i| pos + 2 + i + 2);
+                    self.lexer.comments.push(lexer::Comment { start: pos, end, is_block: true });
+                    pos = end;
+                }
+                0 => return pos,
+                b if b >= 0x80 => match self.source[pos..].chars().next() {
+                    Some(ch) if ch.is_whitespace() => pos += ch.len_utf8(),
+                    _ => return pos,
+                },
+                _ => return pos,
+            }
+        }
+    }
+
+    /// Identifier in a tag or attribute name, dashes allowed
+    fn jsx_ident(&self, pos: usize) -> Option<(Ident, usize)> {
+        let mut chars = self.source.get(pos..)?.char_indices();
+        let (_, first) = chars.next()?;
+        if !is_id_start(first) {
+            return None;
+        }
+        let mut end = pos + first.len_utf8();
+        for (i, ch) in chars {
+            if is_id_continue(ch) || ch == '-' {
+                end = pos + i + ch.len_utf8();
+            } else {
+                break;
+            }
+        }
+        Some((Ident { span: Span::new(pos, end), name: self.source[pos..end].to_string() }, end))
+    }
+
+    /// `a`, `a:b` or `a.b.c`
+    fn jsx_name(&mut self, pos: usize, allow_member: bool) -> Option<(JsxName, usize)> {
+        let (ident, mut end) = self.jsx_ident(pos)?;
+        let after = self.jsx_skip_trivia(end);
+        if self.byte(after) == b':' {
+            let name_start = self.jsx_skip_trivia(after + 1);
+            let (name, name_end) = self.jsx_ident(name_start)?;
+            let span = Span::new(pos, name_end);
+            return Some((JsxName::Namespaced { span, ns: ident, name }, name_end));
+        }
+        let mut name = JsxName::Ident(ident);
+        let mut dot = self.jsx_skip_trivia(end);
+        while allow_member && self.byte(dot) == b'.' {
+            let property_start = self.jsx_skip_trivia(dot + 1);
+            let Some((property, property_end)) = self.jsx_ident(property_start) else { break };
+            end = property_end;
+            name = JsxName::Member { span: Span::new(pos, end), object: Box::new(name), property };
+            dot = self.jsx_skip_trivia(end);
+        }
+        Some((name, end))
+    }
+
+    /// Element or fragment starting at the `<` at `pos`; returns it with its end
+    fn parse_jsx_at(&mut self, pos: usize) -> (Expr, usize) {
+        if self.depth >= MAX_DEPTH {
+            self.error(ErrorCode::TooDeep, "Nesting is too deep", Span::new(pos, pos + 1));
+            return (Expr::Invalid(Span::new(pos, self.source.len())), self.source.len());
+        }
+        self.depth += 1;
+        let result = self.parse_jsx_inner(pos);
+        self.depth -= 1;
+        result
+    }
+
+    fn parse_jsx_inner(&mut self, start: usize) -> (Expr, usize) {
+        let mut pos = self.jsx_skip_trivia(start + 1);
+
+        if self.byte(pos) == b'>' {
+            let opening = Span::new(start, pos + 1);
+            let (children, closing_start) = self.parse_jsx_children(pos + 1);
+            let mut end = closing_start;
+            if closing_start < self.source.len() {
+                end = self.jsx_skip_trivia(closing_start + 2);
+                if self.byte(end) == b'>' {
+                    end += 1;
+                } else {
+                    self.error(ErrorCode::UnexpectedToken, "Expected `</>`", Span::new(closing_start, end));
+                }
+            }
+            let closing = Span::new(closing_start, end);
+            let fragment = JsxFragment { span: Span::new(start, end), opening, children, closing };
+            return (Expr::JsxFragment(Box::new(fragment)), end);
+        }
+
+        let Some((name, name_end)) = self.jsx_name(pos, true) else {
+            self.error(ErrorCode::UnexpectedToken, "Expected a JSX tag name", Span::new(pos, pos + 1));
+            return (Expr::Invalid(Span::new(start, pos)), pos.max(start + 1));
+        };
+        pos = name_end;
+
+        let mut type_args = None;
+        let after_name = self.jsx_skip_trivia(pos);
+        if self.ts && self.byte(after_name) == b'<' {
+            self.resync(after_name);
+            type_args = Some(self.parse_type_args());
+            pos = self.prev_end;
+        }
+
+        let mut attrs = Vec::new();
+        let self_closing;
+        loop {
+            pos = self.jsx_skip_trivia(pos);
+            match self.byte(pos) {
+                b'/' => {
+                    let gt = self.jsx_skip_trivia(pos + 1);
+                    if self.byte(gt) != b'>' {
+                        self.error(ErrorCode::UnexpectedToken, "Expected `>`", Span::new(gt, gt + 1));
+                        pos = gt;
+                    } else {
+                        pos = gt + 1;
+                    }
+                    self_closing = true;
+                    break;
+                }
+                b'>' => {
+                    pos += 1;
+                    self_closing = false;
+                    break;
+                }
+                b'{' => {
+                    let attr_start = pos;
+                    self.resync(pos + 1);
+                    if !self.expect("...") {
+                        pos = self.skip_jsx_container();
+                        continue;
+                    }
+                    let expr = self.nested(|p| p.parse_assign());
+                    pos = self.skip_jsx_container();
+                    attrs.push(JsxAttrOrSpread::Spread(SpreadElement { span: Span::new(attr_start, pos), expr }));
+                }
+                0 => {
+                    self.error(ErrorCode::Unterminated, "Unterminated JSX element", Span::new(start, pos));
+                    let element = JsxElement {
+                        span: Span::new(start, pos),
+                        opening: JsxOpening { span: Span::new(start, pos), name, type_args, attrs, self_closing: true },
+                        children: Vec::new(),
+                        closing: None,
+                    };
+                    return (Expr::JsxElement(Box::new(element)), pos);
+                }
+                _ => {
+                    let attr_start = pos;
+                    let Some((attr_name, name_end)) = self.jsx_name(pos, false) else {
+                        self.error(ErrorCode::UnexpectedToken, "Expected a JSX attribute", Span::new(pos, pos + 1));
+                        pos += self.source[pos..].chars().next().map_or(1, |c| c.len_utf8());
+                        continue;
+                    };
+                    pos = self.jsx_skip_trivia(name_end);
+                    let value = if self.byte(pos) == b'=' {
+                        pos = self.jsx_skip_trivia(pos + 1);
+                        let (value, end) = self.parse_jsx_attr_value(pos);
+                        pos = end;
+                        value
+                    } else {
+                        pos = name_end;
+                        None
+                    };
+                    attrs.push(JsxAttrOrSpread::Attr(JsxAttr { span: Span::new(attr_start, pos), name: attr_name, value }));
+                }
+            }
+        }
+
+        let opening = JsxOpening { span: Span::new(start, pos), name, type_args, attrs, self_closing };
+        if self_closing {
+            let element = JsxElement { span: Span::new(start, pos), opening, children: Vec::new(), closing: None };
+            return (Expr::JsxElement(Box::new(element)), pos);
+        }
+
+        let (children, closing_start) = self.parse_jsx_children(pos);
+        let mut end = closing_start;
+        let mut closing = None;
+        if closing_start < self.source.len() {
+            let name_start = self.jsx_skip_trivia(closing_start + 2);
+            let expected = opening.name.name();
+            match self.jsx_name(name_start, true) {
+                Some((closing_name, name_end)) if closing_name.name() == expected => {
+                    end = self.jsx_skip_trivia(name_end);
+                }
+                other => {
+                    let span_end = other.map_or(name_start, |(_, e)| e);
+                    self.error(
+                        ErrorCode::UnexpectedToken,
+                        &format!("Expected corresponding JSX closing tag for `{}`", expected),
+                        Span::new(closing_start, span_end),
+                    );
+                    end = self.jsx_skip_trivia(span_end);
+                }
+            }
+            if self.byte(end) == b'>' {
+                end += 1;
+            } else {
+                self.error(ErrorCode::UnexpectedToken, "Expected `>`", Span::new(end, end + 1));
+            }
+            closing = Some(Span::new(closing_start, end));
+        }
+        let element = JsxElement { span: Span::new(start, end), opening, children, closing };
+        (Expr::JsxElement(Box::new(element)), end)
+    }
+
+    /// After an expression inside `{}`: the position past the `}`
+    fn skip_jsx_container(&mut self) -> usize {
+        if self.is("}") {
+            return self.tok.end;
+        }
+        self.unexpected("Expected `}`");
+        // Resume after the next `}` so one bad expression doesn't eat the element
+        let from = self.tok.start;
+        self.source[from..].find('}').map_or(self.source.len(), |i| from + i + 1)
+    }
+
+    fn parse_jsx_attr_value(&mut self, pos: usize) -> (Option<JsxAttrValue>, usize) {
+        match self.byte(pos) {
+            quote @ (b'"' | b'\'') => {
+                let end = match self.source[pos + 1..].find(quote as char) {
+                    Some(i) => pos + 1 + i + 1,
+                    None => {
+                        self.error(ErrorCode::Unterminated, "Unterminated string literal", Span::new(pos, pos + 1));
+                        self.source.len()
+                    }
+                };
+                let inner = &self.source[pos + 1..end.saturating_sub(1).max(pos + 1)];
+                let lit = Lit { span: Span::new(pos, end), value: LitValue::Str(inner.to_string()) };
+                (Some(JsxAttrValue::Str(lit)), end)
+            }
+            b'{' => {
+                let container = self.parse_jsx_container(pos);
+                if container.expr.is_none() {
+                    self.error(ErrorCode::InvalidSyntax, "JSX attributes must only be assigned a non-empty expression", container.span);
+                }
+                let end = container.span.end;
+                (Some(JsxAttrValue::Expr(container)), end)
+            }
+            b'<' => {
+                let (expr, end) = self.parse_jsx_at(pos);
+                let value = match expr {
+                    Expr::JsxElement(element) => Some(JsxAttrValue::Element(element)),
+                    Expr::JsxFragment(fragment) => Some(JsxAttrValue::Fragment(fragment)),
+                    _ => None,
+                };
+                (value, end)
+            }
+            _ => {
+                self.error(ErrorCode::UnexpectedToken, "Expected a JSX attribute value", Span::new(pos, pos + 1));
+                (None, pos)
+            }
+        }
+    }
+
+    /// `{expr}` or `{}` starting at `pos`
+    fn parse_jsx_container(&mut self, pos: usize) -> JsxExprContainer {
+        self.resync(pos + 1);
+        let expr = (!self.is("}")).then(|| self.nested(|p| p.parse_expr()));
+        let end = self.skip_jsx_container();
+        JsxExprContainer { span: Span::new(pos, end), expr }
+    }
+
+    /// Children up to the closing tag; returns them with the position of `</`
+    fn parse_jsx_children(&mut self, mut pos: usize) -> (Vec<JsxChild>, usize) {
+        let mut children = Vec::new();
+        loop {
+            match self.byte(pos) {
+                0 if pos >= self.source.len() => {
+                    self.error(ErrorCode::Unterminated, "Unterminated JSX contents", Span::new(pos, pos));
+                    return (children, self.source.len());
+                }
+                b'<' => {
+                    let next = self.jsx_skip_trivia(pos + 1);
+                    if self.byte(next) == b'/' {
+                        return (children, pos);
+                    }
+                    let (expr, end) = self.parse_jsx_at(pos);
+                    match expr {
+                        Expr::JsxElement(element) => children.push(JsxChild::Element(element)),
+                        Expr::JsxFragment(fragment) => children.push(JsxChild::Fragment(fragment)),
+                        _ => {}
+                    }
+                    pos = end.max(pos + 1);
+                }
+                b'{' => {
+                    self.resync(pos + 1);
+                    if self.is("...") {
+                        self.advance();
+                        let expr = self.nested(|p| p.parse_expr());
+                        let end = self.skip_jsx_container();
+                        children.push(JsxChild::Spread(SpreadElement { span: Span::new(pos, end), expr }));
+                        pos = end;
+                    } else {
+                        let container = self.parse_jsx_container(pos);
+                        pos = container.span.end;
+                        children.push(JsxChild::Expr(container));
+                    }
+                }
+                _ => {
+                    let end = self.source[pos..].find(['<', '{']).map_or(self.source.len(), |i| pos + i);
+                    children.push(JsxChild::Text(Span::new(pos, end)));
+                    pos = end;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ts(source: &str) -> Program {
+        parse_program(source, Syntax { typescript: true, jsx: false })
+    }
+
+    fn tsx(source: &str) -> Program {
+        parse_program(source, Syntax { typescript: true, jsx: true })
+    }
+
+    fn js(source: &str) -> Program {
+        parse_program(source, Syntax { typescript: false, jsx: true })
+    }
+
+    fn assert_ok(program: &Program) {
+        assert!(program.errors.is_empty(), "unexpected errors: {:?}", program.errors);
+    }
+
+    #[test]
+    fn test_parse_statements() {
+        let program = js(r#"
+            import a, { b as c } from "mod";
+            export const x = 1, [y, ...z] = arr;
+            label: for (const k in obj) { if (k) continue label; else break; }
+            for await (const v of gen()) {}
+            try { throw new Error("x") } catch ({ message }) {} finally {}
+            switch (x) { case 1: default: x++ }
+            do x--; while (x)
+            export default class extends Base { static #count = 0; get v() { return this.#count } static { init() } }
+        "#);
+        assert_ok(&program);
+        assert_eq!(program.body.len(), 8);
+        assert!(matches!(program.body[1], Stmt::ExportDecl(_)));
+    }
+
+    #[test]
+    fn test_regex_and_templates() {
+        let source = "const re = /import 'x'/g; const t = `a ${b / 2} ${`nested ${c}`}`; x = a / b / c;";
+        let program = js(source);
+        assert_ok(&program);
+        let Stmt::Var(decl) = &program.body[0] else { panic!() };
+        let Some(Expr::Lit(Lit { value: LitValue::Regex { pattern, flags }, .. })) = &decl.decls[0].init else { panic!() };
+        assert_eq!(pattern, "import 'x'");
+        assert_eq!(flags, "g");
+        let Stmt::Var(decl) = &program.body[1] else { panic!() };
+        let Some(Expr::Template(t)) = &decl.decls[0].init else { panic!() };
+        assert_eq!(t.exprs.len(), 2);
+        assert_eq!(t.quasis[0].text(source), "a ");
+    }
+
+    #[test]
+    fn test_arrows_and_unicode() {
+        let program = js("const café = async (a, { b = 1 }, ...rest) => a; const f = x => y => x + y; const ü = async x => await x;");
+        assert_ok(&program);
+        let Stmt::Var(decl) = &program.body[0] else { panic!() };
+        assert_eq!(decl.decls[0].name.bound_names()[0].name, "café");
+        assert!(matches!(decl.decls[0].init, Some(Expr::Arrow(_))));
+    }
+
+    #[test]
+    fn test_typescript() {
+        let program = ts(r#"
+            interface A<T> extends B { a?: T; (x: number): string; [k: string]: any }
+            type Fn = <T>(x: T) => x is T extends string ? 1 : 2;
+            declare module "x" { export const y: number; }
+            namespace N.M { export enum E { A = 1, B } }
+            abstract class C<T> extends D<T> implements I {
+                private readonly x!: Map<string, Array<number>>;
+                constructor(public a: string, protected b?: number) { super(); }
+                abstract m(): void;
+                static s<T>(this: C<T>, ...args: T[]): asserts args {}
+            }
+            function over(x: string): void;
+            function over(x: any) { return x as const satisfies unknown; }
+            const v = <T,>(x: T): T => x!;
+            let n = f<string>(1) >> 2, m = a < b, o = g<A<B>>;
+            import fs = require("fs");
+            export = over;
+        "#);
+        assert_ok(&program);
+        assert_eq!(program.body.len(), 11);
+    }
+
+    #[test]
+    fn test_jsx() {
+        let source = r##"const el = <div className="a" {...rest} data-x={1} xlink:href="#"><Foo.Bar>text {x} </Foo.Bar><>frag</>{/* comment */}</div>;"##;
+        let program = tsx(source);
+        assert_ok(&program);
+        let Stmt::Var(decl) = &program.body[0] else { panic!() };
+        let Some(Expr::JsxElement(el)) = &decl.decls[0].init else { panic!() };
+        assert_eq!(el.opening.attrs.len(), 4);
+        assert_eq!(el.children.len(), 3);
+        assert!(program.comments.iter().any(|c| c.body(source) == " comment "));
+    }
+
+    #[test]
+    fn test_jsx_vs_comparison() {
+        assert_ok(&tsx("if (a < b && c > d) {}"));
+        assert_ok(&tsx("const f = <T,>(x: T) => x;"));
+        let (program, syntax) = parse("const x = <any>y;", None);
+        assert_ok(&program);
+        assert!(!syntax.jsx);
+    }
+
+    #[test]
+    fn test_error_recovery() {
+        let program = js("import a from 'a';\nconst = ;\nimport b from 'b';");
+        assert_eq!(program.errors.len(), 1);
+        assert!(program.body.iter().filter(|s| matches!(s, Stmt::Import(_))).count() == 2);
+    }
+
+    #[test]
+    fn test_deep_nesting() {
+        let source = format!("x = {}1{};", "(".repeat(5000), ")".repeat(5000));
+        let program = js(&source);
+        assert!(!program.errors.is_empty());
+        for (open, close) in [("[", "]"), ("{a:", "}"), ("function f(){", "}"), ("if (a) {", "}"), ("a ? b : ", ""), ("<a>", "</a>"), ("() => ", ""), ("f(", ")"), ("`${", "}`"), ("-", ""), ("if (a) b; else ", ""), ("if (a) b; else if (c) ", ""), ("class A { m() {", "}}"), ("x => ({a: ", "})")] {
+            for n in [140, 5000] {
+                let source = format!("x = {}1{};", open.repeat(n), close.repeat(n));
+                let _ = tsx(&source);
+                let source = format!("let x: {}1{};", "Array<".repeat(n), ">".repeat(n));
+                let _ = ts(&source);
+            }
+        }
+    }
+}
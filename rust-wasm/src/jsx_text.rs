@@ -0,0 +1,435 @@
+//! JSX text semantics: HTML entities, whitespace and string printing
+//!
+//! Follows Babel: entities are decoded as the parser does, text children are
+//! collapsed line by line, and the resulting strings are printed with double
+//! quotes and minimal escapes.
+
+use std::borrow::Cow;
+
+/// Decode `&name;`, `&#123;` and `&#x7B;`; unknown or malformed entities
+/// stay as written
+pub(crate) fn decode_entities(text: &str) -> Cow<'_, str> {
+    if !text.contains('&') {
+        return Cow::Borrowed(text);
+    }
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(i) = rest.find('&') {
+        out.push_str(&rest[..i]);
+        rest = &rest[i + 1..];
+        match entity(rest) {
+            Some((ch, len)) => {
+                out.push(ch);
+                rest = &rest[len..];
+            }
+            None => out.push('&'),
+        }
+    }
+    out.push_str(rest);
+    Cow::Owned(out)
+}
+
+/// The character of the entity at the start of `text` (after `&`) and the
+/// length of the entity up to and including `;`
+fn entity(text: &str) -> Option<(char, usize)> {
+    if let Some(number) = text.strip_prefix('#') {
+        let (digits, radix, prefix) = match number.strip_prefix('x') {
+            Some(hex) => (hex, 16, 2),
+            None => (number, 10, 1),
+        };
+        let len = digits.find(|c: char| !c.is_digit(radix)).unwrap_or(digits.len());
+        if len == 0 || !digits[len..].starts_with(';') {
+            return None;
+        }
+        let code = u32::from_str_radix(&digits[..len], radix).ok()?;
+        return char::from_u32(code).map(|ch| (ch, prefix + len + 1));
+    }
+    let semi = text.char_indices().take(10).find(|&(_, c)| c == ';')?.0;
+    let ch = XHTML_ENTITIES.iter().find(|(name, _)| *name == &text[..semi])?.1;
+    Some((ch, semi + 1))
+}
+
+/// The string a text child contributes, or `None` when it is only
+/// formatting. Lines are trimmed except at the outer edges of the text,
+/// blank lines dropped and the rest joined by single spaces, so
+/// `a\n    b` is `"a b"` while ` a ` keeps its spaces.
+pub(crate) fn clean_text(text: &str) -> Option<String> {
+    let lines = split_lines(text);
+    let last_non_empty = lines.iter().rposition(|line| line.contains(|c| c != ' ' && c != '\t')).unwrap_or(0);
+    let mut out = String::new();
+    for (i, line) in lines.iter().enumerate() {
+        let line = line.replace('\t', " ");
+        let mut line = line.as_str();
+        if i > 0 {
+            line = line.trim_start_matches(' ');
+        }
+        if i < lines.len() - 1 {
+            line = line.trim_end_matches(' ');
+        }
+        if !line.is_empty() {
+            out.push_str(line);
+            if i != last_non_empty {
+                out.push(' ');
+            }
+        }
+    }
+    (!out.is_empty()).then_some(out)
+}
+
+fn split_lines(text: &str) -> Vec<&str> {
+    let mut lines = Vec::new();
+    let mut start = 0;
+    let bytes = text.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\r' | b'\n' => {
+                lines.push(&text[start..i]);
+                if bytes[i] == b'\r' && bytes.get(i + 1) == Some(&b'\n') {
+                    i += 1;
+                }
+                start = i + 1;
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    lines.push(&text[start..]);
+    lines
+}
+
+/// The value of a quoted attribute: entities decoded and each line break
+/// with the indentation after it folded into a space
+pub(crate) fn attr_value(raw: &str) -> String {
+    let value = decode_entities(raw);
+    let mut out = String::with_capacity(value.len());
+    let mut chars = value.chars().peekable();
+    while let Some(ch) = chars.next() {
+        if ch == '\n' && chars.peek().is_some_and(|c| is_js_whitespace(*c)) {
+            while chars.peek().is_some_and(|c| is_js_whitespace(*c)) {
+                chars.next();
+            }
+            out.push(' ');
+        } else {
+            out.push(ch);
+        }
+    }
+    out
+}
+
+/// `\s` in a JavaScript regular expression
+fn is_js_whitespace(c: char) -> bool {
+    matches!(c, '\u{FEFF}' | '\u{1680}' | '\u{2000}'..='\u{200A}' | '\u{2028}' | '\u{2029}' | '\u{202F}' | '\u{205F}' | '\u{3000}')
+        || matches!(c, '\t' | '\n' | '\u{B}' | '\u{C}' | '\r' | ' ' | '\u{A0}')
+}
+
+/// A double-quoted string literal escaping only what has to be escaped
+pub(crate) fn string_literal(value: &str) -> String {
+    let mut out = String::with_capacity(value.len() + 2);
+    out.push('"');
+    let mut chars = value.chars().peekable();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\u{8}' => out.push_str("\\b"),
+            '\u{C}' => out.push_str("\\f"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            '\u{2028}' => out.push_str("\\u2028"),
+            '\u{2029}' => out.push_str("\\u2029"),
+            '\0' if !chars.peek().is_some_and(|c| c.is_ascii_digit()) => out.push_str("\\0"),
+            '\0' => out.push_str("\\x00"),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// The XHTML 1.0 character entities JSX supports
+const XHTML_ENTITIES: &[(&str, char)] = &[
+    ("quot", '\u{22}'),
+    ("amp", '&'),
+    ("apos", '\''),
+    ("lt", '<'),
+    ("gt", '>'),
+    ("nbsp", '\u{A0}'),
+    ("iexcl", '\u{A1}'),
+    ("cent", '\u{A2}'),
+    ("pound", '\u{A3}'),
+    ("curren", '\u{A4}'),
+    ("yen", '\u{A5}'),
+    ("brvbar", '\u{A6}'),
+    ("sect", '\u{A7}'),
+    ("uml", '\u{A8}'),
+    ("copy", '\u{A9}'),
+    ("ordf", '\u{AA}'),
+    ("laquo", '\u{AB}'),
+    ("not", '\u{AC}'),
+    ("shy", '\u{AD}'),
+    ("reg", '\u{AE}'),
+    ("macr", '\u{AF}'),
+    ("deg", '\u{B0}'),
+    ("plusmn", '\u{B1}'),
+    ("sup2", '\u{B2}'),
+    ("sup3", '\u{B3}'),
+    ("acute", '\u{B4}'),
+    ("micro", '\u{B5}'),
+    ("para", '\u{B6}'),
+    ("middot", '\u{B7}'),
+    ("cedil", '\u{B8}'),
+    ("sup1", '\u{B9}'),
+    ("ordm", '\u{BA}'),
+    ("raquo", '\u{BB}'),
+    ("frac14", '\u{BC}'),
+    ("frac12", '\u{BD}'),
+    ("frac34", '\u{BE}'),
+    ("iquest", '\u{BF}'),
+    ("Agrave", '\u{C0}'),
+    ("Aacute", '\u{C1}'),
+    ("Acirc", '\u{C2}'),
+    ("Atilde", '\u{C3}'),
+    ("Auml", '\u{C4}'),
+    ("Aring", '\u{C5}'),
+    ("AElig", '\u{C6}'),
+    ("Ccedil", '\u{C7}'),
+    ("Egrave", '\u{C8}'),
+    ("Eacute", '\u{C9}'),
+    ("Ecirc", '\u{CA}'),
+    ("Euml", '\u{CB}'),
+    ("Igrave", '\u{CC}'),
+    ("Iacute", '\u{CD}'),
+    ("Icirc", '\u{CE}'),
+    ("Iuml", '\u{CF}'),
+    ("ETH", '\u{D0}'),
+    ("Ntilde", '\u{D1}'),
+    ("Ograve", '\u{D2}'),
+    ("Oacute", '\u{D3}'),
+    ("Ocirc", '\u{D4}'),
+    ("Otilde", '\u{D5}'),
+    ("Ouml", '\u{D6}'),
+    ("times", '\u{D7}'),
+    ("Oslash", '\u{D8}'),
+    ("Ugrave", '\u{D9}'),
+    ("Uacute", '\u{DA}'),
+    ("Ucirc", '\u{DB}'),
+    ("Uuml", '\u{DC}'),
+    ("Yacute", '\u{DD}'),
+    ("THORN", '\u{DE}'),
+    ("szlig", '\u{DF}'),
+    ("agrave", '\u{E0}'),
+    ("aacute", '\u{E1}'),
+    ("acirc", '\u{E2}'),
+    ("atilde", '\u{E3}'),
+    ("auml", '\u{E4}'),
+    ("aring", '\u{E5}'),
+    ("aelig", '\u{E6}'),
+    ("ccedil", '\u{E7}'),
+    ("egrave", '\u{E8}'),
+    ("eacute", '\u{E9}'),
+    ("ecirc", '\u{EA}'),
+    ("euml", '\u{EB}'),
+    ("igrave", '\u{EC}'),
+    ("iacute", '\u{ED}'),
+    ("icirc", '\u{EE}'),
+    ("iuml", '\u{EF}'),
+    ("eth", '\u{F0}'),
+    ("ntilde", '\u{F1}'),
+    ("ograve", '\u{F2}'),
+    ("oacute", '\u{F3}'),
+    ("ocirc", '\u{F4}'),
+    ("otilde", '\u{F5}'),
+    ("ouml", '\u{F6}'),
+    ("divide", '\u{F7}'),
+    ("oslash", '\u{F8}'),
+    ("ugrave", '\u{F9}'),
+    ("uacute", '\u{FA}'),
+    ("ucirc", '\u{FB}'),
+    ("uuml", '\u{FC}'),
+    ("yacute", '\u{FD}'),
+    ("thorn", '\u{FE}'),
+    ("yuml", '\u{FF}'),
+    ("OElig", '\u{152}'),
+    ("oelig", '\u{153}'),
+    ("Scaron", '\u{160}'),
+    ("scaron", '\u{161}'),
+    ("Yuml", '\u{178}'),
+    ("fnof", '\u{192}'),
+    ("circ", '\u{2C6}'),
+    ("tilde", '\u{2DC}'),
+    ("Alpha", '\u{391}'),
+    ("Beta", '\u{392}'),
+    ("Gamma", '\u{393}'),
+    ("Delta", '\u{394}'),
+    ("Epsilon", '\u{395}'),
+    ("Zeta", '\u{396}'),
+    ("Eta", '\u{397}'),
+    ("Theta", '\u{398}'),
+    ("Iota", '\u{399}'),
+    ("Kappa", '\u{39A}'),
+    ("Lambda", '\u{39B}'),
+    ("Mu", '\u{39C}'),
+    ("Nu", '\u{39D}'),
+    ("Xi", '\u{39E}'),
+    ("Omicron", '\u{39F}'),
+    ("Pi", '\u{3A0}'),
+    ("Rho", '\u{3A1}'),
+    ("Sigma", '\u{3A3}'),
+    ("Tau", '\u{3A4}'),
+    ("Upsilon", '\u{3A5}'),
+    ("Phi", '\u{3A6}'),
+    ("Chi", '\u{3A7}'),
+    ("Psi", '\u{3A8}'),
+    ("Omega", '\u{3A9}'),
+    ("alpha", '\u{3B1}'),
+    ("beta", '\u{3B2}'),
+    ("gamma", '\u{3B3}'),
+    ("delta", '\u{3B4}'),
+    ("epsilon", '\u{3B5}'),
+    ("zeta", '\u{3B6}'),
+    ("eta", '\u{3B7}'),
+    ("theta", '\u{3B8}'),
+    ("iota", '\u{3B9}'),
+    ("kappa", '\u{3BA}'),
+    ("lambda", '\u{3BB}'),
+    ("mu", '\u{3BC}'),
+    ("nu", '\u{3BD}'),
+    ("xi", '\u{3BE}'),
+    ("omicron", '\u{3BF}'),
+    ("pi", '\u{3C0}'),
+    ("rho", '\u{3C1}'),
+    ("sigmaf", '\u{3C2}'),
+    ("sigma", '\u{3C3}'),
+    ("tau", '\u{3C4}'),
+    ("upsilon", '\u{3C5}'),
+    ("phi", '\u{3C6}'),
+    ("chi", '\u{3C7}'),
+    ("psi", '\u{3C8}'),
+    ("omega", '\u{3C9}'),
+    ("thetasym", '\u{3D1}'),
+    ("upsih", '\u{3D2}'),
+    ("piv", '\u{3D6}'),
+    ("ensp", '\u{2002}'),
+    ("emsp", '\u{2003}'),
+    ("thinsp", '\u{2009}'),
+    ("zwnj", '\u{200C}'),
+    ("zwj", '\u{200D}'),
+    ("lrm", '\u{200E}'),
+    ("rlm", '\u{200F}'),
+    ("ndash", '\u{2013}'),
+    ("mdash", '\u{2014}'),
+    ("lsquo", '\u{2018}'),
+    ("rsquo", '\u{2019}'),
+    ("sbquo", '\u{201A}'),
+    ("ldquo", '\u{201C}'),
+    ("rdquo", '\u{201D}'),
+    ("bdquo", '\u{201E}'),
+    ("dagger", '\u{2020}'),
+    ("Dagger", '\u{2021}'),
+    ("bull", '\u{2022}'),
+    ("hellip", '\u{2026}'),
+    ("permil", '\u{2030}'),
+    ("prime", '\u{2032}'),
+    ("Prime", '\u{2033}'),
+    ("lsaquo", '\u{2039}'),
+    ("rsaquo", '\u{203A}'),
+    ("oline", '\u{203E}'),
+    ("frasl", '\u{2044}'),
+    ("euro", '\u{20AC}'),
+    ("image", '\u{2111}'),
+    ("weierp", '\u{2118}'),
+    ("real", '\u{211C}'),
+    ("trade", '\u{2122}'),
+    ("alefsym", '\u{2135}'),
+    ("larr", '\u{2190}'),
+    ("uarr", '\u{2191}'),
+    ("rarr", '\u{2192}'),
+    ("darr", '\u{2193}'),
+    ("harr", '\u{2194}'),
+    ("crarr", '\u{21B5}'),
+    ("lArr", '\u{21D0}'),
+    ("uArr", '\u{21D1}'),
+    ("rArr", '\u{21D2}'),
+    ("dArr", '\u{21D3}'),
+    ("hArr", '\u{21D4}'),
+    ("forall", '\u{2200}'),
+    ("part", '\u{2202}'),
+    ("exist", '\u{2203}'),
+    ("empty", '\u{2205}'),
+    ("nabla", '\u{2207}'),
+    ("isin", '\u{2208}'),
+    ("notin", '\u{2209}'),
+    ("ni", '\u{220B}'),
+    ("prod", '\u{220F}'),
+    ("sum", '\u{2211}'),
+    ("minus", '\u{2212}'),
+    ("lowast", '\u{2217}'),
+    ("radic", '\u{221A}'),
+    ("prop", '\u{221D}'),
+    ("infin", '\u{221E}'),
+    ("ang", '\u{2220}'),
+    ("and", '\u{2227}'),
+    ("or", '\u{2228}'),
+    ("cap", '\u{2229}'),
+    ("cup", '\u{222A}'),
+    ("int", '\u{222B}'),
+    ("there4", '\u{2234}'),
+    ("sim", '\u{223C}'),
+    ("cong", '\u{2245}'),
+    ("asymp", '\u{2248}'),
+    ("ne", '\u{2260}'),
+    ("equiv", '\u{2261}'),
+    ("le", '\u{2264}'),
+    ("ge", '\u{2265}'),
+    ("sub", '\u{2282}'),
+    ("sup", '\u{2283}'),
+    ("nsub", '\u{2284}'),
+    ("sube", '\u{2286}'),
+    ("supe", '\u{2287}'),
+    ("oplus", '\u{2295}'),
+    ("otimes", '\u{2297}'),
+    ("perp", '\u{22A5}'),
+    ("sdot", '\u{22C5}'),
+    ("lceil", '\u{2308}'),
+    ("rceil", '\u{2309}'),
+    ("lfloor", '\u{230A}'),
+    ("rfloor", '\u{230B}'),
+    ("lang", '\u{2329}'),
+    ("rang", '\u{232A}'),
+    ("loz", '\u{25CA}'),
+    ("spades", '\u{2660}'),
+    ("clubs", '\u{2663}'),
+    ("hearts", '\u{2665}'),
+    ("diams", '\u{2666}'),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_entities() {
+        assert_eq!(decode_entities("a &amp; b"), "a & b");
+        assert_eq!(decode_entities("&nbsp;&#123;&#x7D;&hellip;"), "\u{A0}{}\u{2026}");
+        assert_eq!(decode_entities("&copy &unknown; & &#xZ; &#;"), "&copy &unknown; & &#xZ; &#;");
+        assert_eq!(decode_entities("&abcdefghijk;"), "&abcdefghijk;");
+    }
+
+    #[test]
+    fn test_clean_text() {
+        assert_eq!(clean_text("  a  ").as_deref(), Some("  a  "));
+        assert_eq!(clean_text("\n    a\n    b  c\n  "), Some("a b  c".to_string()));
+        assert_eq!(clean_text("a \n\n  \n b"), Some("a b".to_string()));
+        assert_eq!(clean_text("\r\n\t\t"), None);
+        assert_eq!(clean_text("\ta\t"), Some(" a ".to_string()));
+        assert_eq!(clean_text("\n  \u{A0}\n"), Some("\u{A0}".to_string()));
+    }
+
+    #[test]
+    fn test_attr_value_and_string_literal() {
+        assert_eq!(attr_value("a\n    b &quot;c&quot;"), "a b \"c\"");
+        assert_eq!(string_literal("it's \"q\" \\ \u{A0}\n"), "\"it's \\\"q\\\" \\\\ \u{A0}\\n\"");
+    }
+}
@@ -0,0 +1,774 @@
+//! JavaScript/TypeScript tokenizer
+//!
+//! Shared by the minifier and the parser. Handles:
+//! - Regex literals vs division (driven by the caller, or by `tokenize`'s heuristic)
+//! - Template literals with nested `${}` substitutions
+//! - Unicode identifiers and escapes
+//! - Comments (collected, not discarded)
+
+use crate::ast::ErrorCode;
+
+/// Token kinds
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenKind {
+    /// Identifiers and keywords
+    Name,
+    /// `#name` inside classes
+    PrivateName,
+    Number,
+    BigInt,
+    String,
+    /// Template without substitutions: `abc`
+    Template,
+    /// Template start up to the first substitution: `abc${
+    TemplateHead,
+    /// Template part between two substitutions: }abc${
+    TemplateMiddle,
+    /// Template end after the last substitution: }abc`
+    TemplateTail,
+    Regex,
+    /// Operators and punctuation
+    Punct,
+    Eof,
+}
+
+/// A token, referencing its source text by byte offsets
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub start: usize,
+    pub end: usize,
+    /// A line terminator appears between the previous token and this one
+    pub newline_before: bool,
+}
+
+impl Token {
+    #[inline]
+    pub fn text<'a>(&self, source: &'a str) -> &'a str {
+        &source[self.start..self.end]
+    }
+
+    #[inline]
+    pub fn is_punct(&self, source: &str, punct: &str) -> bool {
+        self.kind == TokenKind::Punct && self.text(source) == punct
+    }
+
+    #[inline]
+    pub fn is_word(&self, source: &str, word: &str) -> bool {
+        self.kind == TokenKind::Name && self.text(source) == word
+    }
+}
+
+/// A comment found between tokens
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Comment {
+    pub start: usize,
+    pub end: usize,
+    /// `/* */` comment (as opposed to `//`)
+    pub is_block: bool,
+}
+
+impl Comment {
+    /// Comment text without the delimiters
+    pub fn body<'a>(&self, source: &'a str) -> &'a str {
+        if self.is_block {
+            &source[self.start + 2..self.end.saturating_sub(2).max(self.start + 2)]
+        } else {
+            &source[self.start + 2..self.end]
+        }
+    }
+}
+
+/// A recoverable tokenizer error
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LexError {
+    pub code: ErrorCode,
+    pub message: String,
+    pub pos: usize,
+}
+
+/// Punctuators, longest first so the first match wins
+const PUNCTUATORS: &[&str] = &[
+    ">>>=", "...", "===", "!==", "**=", "<<=", ">>=", ">>>", "&&=", "||=", "??=",
+    "=>", "==", "!=", "<=", ">=", "&&", "||", "??", "?.", "++", "--", "+=", "-=", "*=",
+    "/=", "%=", "&=", "|=", "^=", "<<", ">>", "**",
+    "{", "}", "(", ")", "[", "]", ";", ",", "<", ">", "+", "-", "*", "/", "%", "&", "|",
+    "^", "!", "~", "?", ":", "=", ".", "@", "#",
+];
+
+/// Keywords after which a `/` starts a regex rather than a division
+const REGEX_PRECEDING_KEYWORDS: &[&str] = &[
+    "return", "typeof", "instanceof", "in", "of", "new", "delete", "void", "throw",
+    "case", "do", "else", "yield", "await", "extends",
+];
+
+#[inline]
+pub fn is_id_start(ch: char) -> bool {
+    ch.is_ascii_alphabetic() || ch == '$' || ch == '_' || (!ch.is_ascii() && ch.is_alphabetic())
+}
+
+#[inline]
+pub fn is_id_continue(ch: char) -> bool {
+    ch.is_ascii_alphanumeric()
+        || ch == '$'
+        || ch == '_'
+        || ch == '\u{200C}'
+        || ch == '\u{200D}'
+        || (!ch.is_ascii() && ch.is_alphanumeric())
+}
+
+#[inline]
+fn is_line_terminator(ch: char) -> bool {
+    matches!(ch, '\n' | '\r' | '\u{2028}' | '\u{2029}')
+}
+
+#[inline]
+fn is_whitespace(ch: char) -> bool {
+    matches!(
+        ch,
+        ' ' | '\t' | '\u{000B}' | '\u{000C}' | '\u{00A0}' | '\u{FEFF}' | '\u{1680}'
+            | '\u{2000}'..='\u{200A}' | '\u{202F}' | '\u{205F}' | '\u{3000}'
+    )
+}
+
+/// Tokenizer over a source string
+pub struct Lexer<'a> {
+    source: &'a str,
+    bytes: &'a [u8],
+    pos: usize,
+    /// Comments seen so far, in source order
+    pub comments: Vec<Comment>,
+    /// Errors seen so far, in source order
+    pub errors: Vec<LexError>,
+}
+
+impl<'a> Lexer<'a> {
+    pub fn new(source: &'a str) -> Self {
+        Self {
+            source,
+            bytes: source.as_bytes(),
+            pos: 0,
+            comments: Vec::new(),
+            errors: Vec::new(),
+        }
+    }
+
+    #[inline]
+    pub fn pos(&self) -> usize {
+        self.pos
+    }
+
+    /// Move the lexer to an arbitrary byte offset (used for re-scanning)
+    #[inline]
+    pub fn reset(&mut self, pos: usize) {
+        self.pos = pos;
+    }
+
+    #[inline]
+    fn peek(&self) -> u8 {
+        self.bytes.get(self.pos).copied().unwrap_or(0)
+    }
+
+    #[inline]
+    fn peek_n(&self, n: usize) -> u8 {
+        self.bytes.get(self.pos + n).copied().unwrap_or(0)
+    }
+
+    #[inline]
+    fn peek_char(&self) -> Option<char> {
+        self.source[self.pos..].chars().next()
+    }
+
+    fn error(&mut self, code: ErrorCode, message: &str, pos: usize) {
+        self.errors.push(LexError {
+            code,
+            message: message.to_string(),
+            pos,
+        });
+    }
+
+    /// Skip whitespace and comments, returning whether a line terminator was crossed
+    fn skip_trivia(&mut self) -> bool {
+        let mut newline = false;
+        while self.pos < self.bytes.len() {
+            match self.peek() {
+                b' ' | b'\t' => self.pos += 1,
+                b'\n' | b'\r' => {
+                    newline = true;
+                    self.pos += 1;
+                }
+                b'/' if self.peek_n(1) == b'/' => {
+                    let start = self.pos;
+                    while let Some(ch) = self.peek_char() {
+                        if is_line_terminator(ch) {
+                            break;
+                        }
+                        self.pos += ch.len_utf8();
+                    }
+                    self.comments.push(Comment { start, end: self.pos, is_block: false });
+                }
+                b'/' if self.peek_n(1) == b'*' => {
+                    let start = self.pos;
+                    match self.source[self.pos + 2..].find("*/") {
+                        Some(offset) => {
+                            let end = self.pos + 2 + offset + 2;
+                            if self.source[start..end].contains(is_line_terminator) {
+                                newline = true;
+                            }
+                            self.pos = end;
+                        }
+                        None => {
+                            self.error(ErrorCode::Unterminated, "Unterminated comment", start);
+                            self.pos = self.bytes.len();
+                        }
+                    }
+                    self.comments.push(Comment { start, end: self.pos, is_block: true });
+                }
+                b'#' if self.pos == 0 && self.peek_n(1) == b'!' => {
+                    // Hashbang line
+                    while self.pos < self.bytes.len() && self.peek() != b'\n' {
+                        self.pos += 1;
+                    }
+                }
+                b if b < 0x80 => {
+                    if b == 0x0B || b == 0x0C {
+                        self.pos += 1;
+                    } else {
+                        break;
+                    }
+                }
+                _ => {
+                    let ch = self.peek_char().unwrap_or(' ');
+                    if is_line_terminator(ch) {
+                        newline = true;
+                    } else if !is_whitespace(ch) {
+                        break;
+                    }
+                    self.pos += ch.len_utf8();
+                }
+            }
+        }
+        newline
+    }
+
+    /// Read the next token. `regex_allowed` decides how a `/` is read.
+    pub fn next_token(&mut self, regex_allowed: bool) -> Token {
+        let newline_before = self.skip_trivia();
+        let start = self.pos;
+
+        if self.pos >= self.bytes.len() {
+            return Token { kind: TokenKind::Eof, start, end: start, newline_before };
+        }
+
+        let ch = self.peek();
+        let kind = match ch {
+            b'"' | b'\'' => {
+                self.read_string(ch);
+                TokenKind::String
+            }
+            b'`' => {
+                self.pos += 1;
+                self.read_template_chars(TokenKind::Template, TokenKind::TemplateHead, start)
+            }
+            b'0'..=b'9' => self.read_number(),
+            b'.' if self.peek_n(1).is_ascii_digit() => self.read_number(),
+            b'/' if regex_allowed => {
+                self.read_regex();
+                TokenKind::Regex
+            }
+            b'#' if self.peek_n(1) != 0 && self.starts_identifier(self.pos + 1) => {
+                self.pos += 1;
+                self.read_identifier();
+                TokenKind::PrivateName
+            }
+            _ if self.starts_identifier(self.pos) => {
+                self.read_identifier();
+                TokenKind::Name
+            }
+            _ => {
+                let rest = &self.source[self.pos..];
+                match PUNCTUATORS.iter().find(|p| rest.starts_with(**p)) {
+                    // `?.` followed by a digit is a conditional: a?.5:b
+                    Some(&"?.") if self.peek_n(2).is_ascii_digit() => self.pos += 1,
+                    Some(p) => self.pos += p.len(),
+                    None => {
+                        let len = self.peek_char().map(|c| c.len_utf8()).unwrap_or(1);
+                        self.error(ErrorCode::InvalidCharacter, "Unexpected character", start);
+                        self.pos += len;
+                    }
+                }
+                TokenKind::Punct
+            }
+        };
+
+        Token { kind, start, end: self.pos, newline_before }
+    }
+
+    /// Re-read a `}` token as the continuation of a template literal
+    pub fn rescan_template_continuation(&mut self, close_brace: Token) -> Token {
+        self.pos = close_brace.start + 1;
+        let kind = self.read_template_chars(
+            TokenKind::TemplateTail,
+            TokenKind::TemplateMiddle,
+            close_brace.start,
+        );
+        Token {
+            kind,
+            start: close_brace.start,
+            end: self.pos,
+            newline_before: close_brace.newline_before,
+        }
+    }
+
+    fn starts_identifier(&self, pos: usize) -> bool {
+        match self.source[pos..].chars().next() {
+            Some('\\') => self.bytes.get(pos + 1) == Some(&b'u'),
+            Some(ch) => is_id_start(ch),
+            None => false,
+        }
+    }
+
+    fn read_identifier(&mut self) {
+        while let Some(ch) = self.peek_char() {
+            if ch == '\\' && self.peek_n(1) == b'u' {
+                self.pos += 2;
+                if self.peek() == b'{' {
+                    while self.pos < self.bytes.len() && self.peek() != b'}' {
+                        self.pos += 1;
+                    }
+                    self.pos += 1;
+                } else {
+                    self.pos += 4;
+                }
+                self.pos = self.pos.min(self.bytes.len());
+            } else if is_id_continue(ch) {
+                self.pos += ch.len_utf8();
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn read_string(&mut self, quote: u8) {
+        let start = self.pos;
+        self.pos += 1;
+        while self.pos < self.bytes.len() {
+            match self.peek() {
+                b'\\' => {
+                    self.pos += 1;
+                    // Skip the escaped character (may be multi-byte or CRLF)
+                    if self.peek() == b'\r' && self.peek_n(1) == b'\n' {
+                        self.pos += 2;
+                    } else if let Some(ch) = self.peek_char() {
+                        self.pos += ch.len_utf8();
+                    }
+                }
+                b'\n' | b'\r' => break,
+                b if b == quote => {
+                    self.pos += 1;
+                    return;
+                }
+                b if b < 0x80 => self.pos += 1,
+                _ => self.pos += self.peek_char().map(|c| c.len_utf8()).unwrap_or(1),
+            }
+        }
+        self.error(ErrorCode::Unterminated, "Unterminated string literal", start);
+    }
+
+    /// Read template characters after a backtick or closing `}` of a substitution
+    fn read_template_chars(&mut self, end_kind: TokenKind, subst_kind: TokenKind, start: usize) -> TokenKind {
+        while self.pos < self.bytes.len() {
+            match self.peek() {
+                b'`' => {
+                    self.pos += 1;
+                    return end_kind;
+                }
+                b'\\' => {
+                    self.pos += 1;
+                    if let Some(ch) = self.peek_char() {
+                        self.pos += ch.len_utf8();
+                    }
+                }
+                b'$' if self.peek_n(1) == b'{' => {
+                    self.pos += 2;
+                    return subst_kind;
+                }
+                b if b < 0x80 => self.pos += 1,
+                _ => self.pos += self.peek_char().map(|c| c.len_utf8()).unwrap_or(1),
+            }
+        }
+        self.error(ErrorCode::Unterminated, "Unterminated template literal", start);
+        end_kind
+    }
+
+    fn read_regex(&mut self) {
+        let start = self.pos;
+        self.pos += 1;
+        let mut in_class = false;
+        loop {
+            match self.peek_char() {
+                None => {
+                    self.error(ErrorCode::Unterminated, "Unterminated regular expression", start);
+                    return;
+                }
+                Some(ch) if is_line_terminator(ch) => {
+                    self.error(ErrorCode::Unterminated, "Unterminated regular expression", start);
+                    return;
+                }
+                Some('\\') => {
+                    self.pos += 1;
+                    if let Some(ch) = self.peek_char() {
+                        if !is_line_terminator(ch) {
+                            self.pos += ch.len_utf8();
+                        }
+                    }
+                }
+                Some('[') => {
+                    in_class = true;
+                    self.pos += 1;
+                }
+                Some(']') => {
+                    in_class = false;
+                    self.pos += 1;
+                }
+                Some('/') if !in_class => {
+                    self.pos += 1;
+                    break;
+                }
+                Some(ch) => self.pos += ch.len_utf8(),
+            }
+        }
+        // Flags
+        while let Some(ch) = self.peek_char() {
+            if is_id_continue(ch) {
+                self.pos += ch.len_utf8();
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn read_number(&mut self) -> TokenKind {
+        let radix_prefix = self.peek() == b'0' && matches!(self.peek_n(1) | 0x20, b'x' | b'o' | b'b');
+        if radix_prefix {
+            self.pos += 2;
+            while self.peek().is_ascii_hexdigit() || self.peek() == b'_' {
+                self.pos += 1;
+            }
+        } else {
+            while self.peek().is_ascii_digit() || self.peek() == b'_' {
+                self.pos += 1;
+            }
+            if self.peek() == b'.' {
+                self.pos += 1;
+                while self.peek().is_ascii_digit() || self.peek() == b'_' {
+                    self.pos += 1;
+                }
+            }
+            if self.peek() | 0x20 == b'e'
+                && (self.peek_n(1).is_ascii_digit()
+                    || (matches!(self.peek_n(1), b'+' | b'-') && self.peek_n(2).is_ascii_digit()))
+            {
+                self.pos += 2;
+                while self.peek().is_ascii_digit() || self.peek() == b'_' {
+                    self.pos += 1;
+                }
+            }
+        }
+        if self.peek() == b'n' {
+            self.pos += 1;
+            return TokenKind::BigInt;
+        }
+        TokenKind::Number
+    }
+}
+
+/// Whether a `/` after `prev` starts a regex, given what the matching
+/// bracket of a closing `)` or `}` opened
+fn regex_allowed_after(source: &str, prev: Option<&Token>, closed: Option<Context>) -> bool {
+    let prev = match prev {
+        Some(t) => t,
+        None => return true,
+    };
+    match prev.kind {
+        TokenKind::Name => REGEX_PRECEDING_KEYWORDS.contains(&prev.text(source)),
+        TokenKind::Punct => match prev.text(source) {
+            ")" => closed == Some(Context::ParenCondition),
+            "}" => closed == Some(Context::BraceBlock),
+            "]" | "++" | "--" => false,
+            _ => true,
+        },
+        TokenKind::TemplateHead | TokenKind::TemplateMiddle => true,
+        _ => false,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Context {
+    BraceBlock,
+    BraceExpression,
+    TemplateSubstitution,
+    ParenCondition,
+    Paren,
+    Bracket,
+}
+
+/// Tokenized source with its comments and errors
+#[derive(Debug, Clone, Default)]
+pub struct TokenStream {
+    pub tokens: Vec<Token>,
+    pub comments: Vec<Comment>,
+    pub errors: Vec<LexError>,
+}
+
+/// Tokenize a whole source without a parser, deciding regex vs division
+/// and block vs object braces from the surrounding tokens.
+pub fn tokenize(source: &str) -> TokenStream {
+    let mut lexer = Lexer::new(source);
+    let mut tokens: Vec<Token> = Vec::new();
+    let mut stack: Vec<Context> = Vec::new();
+    let mut last_closed: Option<Context> = None;
+
+    loop {
+        let regex_allowed = regex_allowed_after(source, tokens.last(), last_closed);
+        let mut token = lexer.next_token(regex_allowed);
+        if token.kind == TokenKind::Eof {
+            break;
+        }
+
+        last_closed = None;
+        match token.kind {
+            TokenKind::TemplateHead => stack.push(Context::TemplateSubstitution),
+            TokenKind::Punct => match token.text(source) {
+                "{" => {
+                    let context = if brace_starts_block(source, tokens.last(), stack.last()) {
+                        Context::BraceBlock
+                    } else {
+                        Context::BraceExpression
+                    };
+                    stack.push(context);
+                }
+                "(" => {
+                    let is_condition = tokens.last().is_some_and(|t| {
+                        t.kind == TokenKind::Name
+                            && matches!(t.text(source), "if" | "while" | "for" | "with")
+                    });
+                    stack.push(if is_condition { Context::ParenCondition } else { Context::Paren });
+                }
+                "[" => stack.push(Context::Bracket),
+                "}" => {
+                    if stack.last() == Some(&Context::TemplateSubstitution) {
+                        stack.pop();
+                        token = lexer.rescan_template_continuation(token);
+                        if token.kind == TokenKind::TemplateMiddle {
+                            stack.push(Context::TemplateSubstitution);
+                        }
+                    } else {
+                        last_closed = stack.pop();
+                    }
+                }
+                ")" | "]" => last_closed = stack.pop(),
+                _ => {}
+            },
+            _ => {}
+        }
+
+        tokens.push(token);
+    }
+
+    TokenStream {
+        tokens,
+        comments: lexer.comments,
+        errors: lexer.errors,
+    }
+}
+
+/// Heuristic: does a `{` after `prev` open a block (as opposed to an object literal)?
+fn brace_starts_block(source: &str, prev: Option<&Token>, enclosing: Option<&Context>) -> bool {
+    let prev = match prev {
+        Some(t) => t,
+        None => return true,
+    };
+    match prev.kind {
+        TokenKind::Punct => match prev.text(source) {
+            ";" | "{" | "}" | ")" | "=>" => true,
+            ":" => enclosing == Some(&Context::BraceBlock) || enclosing.is_none(),
+            _ => false,
+        },
+        TokenKind::Name => !matches!(
+            prev.text(source),
+            "return" | "typeof" | "yield" | "await" | "case" | "in" | "of" | "new" | "delete"
+                | "void" | "throw" | "instanceof"
+        ),
+        TokenKind::TemplateHead | TokenKind::TemplateMiddle => false,
+        _ => true,
+    }
+}
+
+/// Decode the value of a string literal (including its quotes)
+pub fn string_value(raw: &str) -> String {
+    let inner = if raw.len() >= 2 { &raw[1..raw.len() - 1] } else { "" };
+    decode_escapes(inner)
+}
+
+/// Decode JavaScript escape sequences
+pub fn decode_escapes(raw: &str) -> String {
+    if !raw.contains('\\') {
+        return raw.to_string();
+    }
+    let mut out = String::with_capacity(raw.len());
+    let mut chars = raw.chars().peekable();
+    while let Some(ch) = chars.next() {
+        if ch != '\\' {
+            out.push(ch);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => out.push('\n'),
+            Some('t') => out.push('\t'),
+            Some('r') => out.push('\r'),
+            Some('b') => out.push('\u{0008}'),
+            Some('f') => out.push('\u{000C}'),
+            Some('v') => out.push('\u{000B}'),
+            Some('0') if !chars.peek().is_some_and(|c| c.is_ascii_digit()) => out.push('\0'),
+            Some('x') => {
+                let hex: String = chars.by_ref().take(2).collect();
+                if let Some(c) = u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32) {
+                    out.push(c);
+                }
+            }
+            Some('u') => {
+                let code = if chars.peek() == Some(&'{') {
+                    chars.next();
+                    let hex: String = chars.by_ref().take_while(|c| *c != '}').collect();
+                    u32::from_str_radix(&hex, 16).ok()
+                } else {
+                    let hex: String = chars.by_ref().take(4).collect();
+                    u32::from_str_radix(&hex, 16).ok()
+                };
+                match code {
+                    // Surrogate pair: 😀
+                    Some(high @ 0xD800..=0xDBFF) => {
+                        let mut lookahead = chars.clone();
+                        let low = if lookahead.next() == Some('\\') && lookahead.next() == Some('u') {
+                            let hex: String = lookahead.by_ref().take(4).collect();
+                            u32::from_str_radix(&hex, 16).ok().filter(|l| (0xDC00..=0xDFFF).contains(l))
+                        } else {
+                            None
+                        };
+                        match low {
+                            Some(low) => {
+                                chars = lookahead;
+                                let c = 0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00);
+                                out.push(char::from_u32(c).unwrap_or('\u{FFFD}'));
+                            }
+                            None => out.push('\u{FFFD}'),
+                        }
+                    }
+                    Some(c) => out.push(char::from_u32(c).unwrap_or('\u{FFFD}')),
+                    None => {}
+                }
+            }
+            // Line continuation
+            Some('\r') if chars.peek() == Some(&'\n') => {
+                chars.next();
+            }
+            Some('\r') => {}
+            Some('\n') | Some('\u{2028}') | Some('\u{2029}') => {}
+            Some(other) => out.push(other),
+            None => {}
+        }
+    }
+    out
+}
+
+/// Numeric value of a number literal
+pub fn number_value(raw: &str) -> Option<f64> {
+    let clean = raw.replace('_', "");
+    let lower = clean.to_ascii_lowercase();
+    let radix = match lower.get(..2) {
+        Some("0x") => 16,
+        Some("0o") => 8,
+        Some("0b") => 2,
+        _ => 10,
+    };
+    if radix != 10 {
+        return u64::from_str_radix(&lower[2..], radix).ok().map(|v| v as f64);
+    }
+    // Legacy octal: 0123
+    if lower.len() > 1 && lower.starts_with('0') && lower.bytes().all(|b| b.is_ascii_digit()) {
+        if lower.bytes().all(|b| b < b'8') {
+            return u64::from_str_radix(&lower[1..], 8).ok().map(|v| v as f64);
+        }
+        return lower.parse().ok();
+    }
+    lower.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts(source: &str) -> Vec<String> {
+        tokenize(source)
+            .tokens
+            .iter()
+            .map(|t| t.text(source).to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_regex_vs_division() {
+        assert_eq!(texts("a = b / c / d"), vec!["a", "=", "b", "/", "c", "/", "d"]);
+        assert_eq!(texts("x = /ab+c/gi.test(s)"), vec!["x", "=", "/ab+c/gi", ".", "test", "(", "s", ")"]);
+        assert_eq!(texts("if (x) /re/.exec(y)")[4], "/re/");
+        assert_eq!(texts("(a) / 2")[3], "/");
+        assert_eq!(texts("const re = /import 'x'/")[3], "/import 'x'/");
+        assert_eq!(texts("x = /[/]/")[2], "/[/]/");
+    }
+
+    #[test]
+    fn test_template_substitutions() {
+        let source = "`a${ {b: 1}.b }c${d}e`";
+        let stream = tokenize(source);
+        let kinds: Vec<TokenKind> = stream.tokens.iter().map(|t| t.kind).collect();
+        assert_eq!(kinds[0], TokenKind::TemplateHead);
+        assert_eq!(stream.tokens[8].text(source), "}c${");
+        assert_eq!(kinds[8], TokenKind::TemplateMiddle);
+        assert_eq!(*kinds.last().unwrap(), TokenKind::TemplateTail);
+        assert!(stream.errors.is_empty());
+    }
+
+    #[test]
+    fn test_unicode_identifiers_and_comments() {
+        let source = "const café = 1; // note\n/* block */ ñ";
+        let stream = tokenize(source);
+        assert_eq!(stream.tokens[1].text(source), "café");
+        assert_eq!(stream.tokens[5].text(source), "ñ");
+        assert!(stream.tokens[5].newline_before);
+        assert_eq!(stream.comments.len(), 2);
+    }
+
+    #[test]
+    fn test_numbers() {
+        assert_eq!(texts("1_000.5e+3 0xFFn .5"), vec!["1_000.5e+3", "0xFFn", ".5"]);
+        assert_eq!(number_value("1_000"), Some(1000.0));
+        assert_eq!(number_value("0x1F"), Some(31.0));
+        assert_eq!(number_value("017"), Some(15.0));
+    }
+
+    #[test]
+    fn test_string_value() {
+        assert_eq!(string_value(r#""a\nb""#), "a\nb");
+        assert_eq!(string_value(r#"'\u{1F600}'"#), "😀");
+        assert_eq!(string_value(r#"'😀'"#), "😀");
+        assert_eq!(string_value(r#""\x41""#), "A");
+    }
+
+    #[test]
+    fn test_unterminated_string() {
+        let stream = tokenize("'abc\nx");
+        assert_eq!(stream.errors.len(), 1);
+        assert_eq!(stream.errors[0].pos, 0);
+    }
+}
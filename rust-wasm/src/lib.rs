@@ -3,10 +3,18 @@
 //! This module provides Rust-powered optimizations for the Kona bundler,
 //! exposed via WebAssembly for seamless JavaScript integration.
 
+pub mod lexer;
+pub mod ast;
+pub mod diagnostics;
+pub mod js_parser;
+mod compressor;
+mod mangler;
 pub mod minifier;
 pub mod tree_shaker;
 mod utils;
+mod jsx_text;
 pub mod parser;
+pub mod incremental;
 pub mod transformer;
 pub mod bundler;
 pub mod parallel;
@@ -14,11 +22,11 @@ pub mod parallel;
 use wasm_bindgen::prelude::*;
 
 pub use minifier::*;
-pub use tree_shaker::*;
+pub use tree_shaker::{DependencyGraph, ModuleAnalysis, TreeShakeConfig, TreeShakeResult, TreeShakeStats, TreeShaker};
 pub use parser::*;
 pub use transformer::*;
 pub use bundler::*;
-pub use parallel::*;
+pub use parallel::{ModuleOutput, ParallelProcessor};
 
 /// Initialize the WASM module with panic hook for better error messages
 #[wasm_bindgen(start)]
@@ -0,0 +1,1307 @@
+//! Identifier mangling for the minifier
+//!
+//! Works on the minifier's token stream:
+//! - Token-level scope analysis (function, block, class and catch scopes, hoisting)
+//! - References resolved through the scope chain; unresolved names are globals and kept
+//! - Scopes containing a direct `eval` or a `with` statement keep their names
+//! - Frequency-based allocation: the most used bindings get the shortest names
+
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+
+use crate::lexer::TokenKind;
+use crate::minifier::{can_end_statement, MinToken, MinifyConfig};
+
+/// Words that are never identifiers
+const KEYWORDS: &[&str] = &[
+    "break", "case", "catch", "class", "const", "continue", "debugger", "default", "delete",
+    "do", "else", "enum", "export", "extends", "false", "finally", "for", "function", "if",
+    "import", "in", "instanceof", "new", "null", "return", "super", "switch", "this", "throw",
+    "true", "try", "typeof", "var", "void", "while", "with",
+];
+
+/// Names a mangled binding must never get, on top of `KEYWORDS`
+const RESERVED_NAMES: &[&str] = &[
+    "let", "static", "yield", "await", "async", "of", "get", "set", "implements", "interface",
+    "package", "private", "protected", "public", "arguments", "eval", "undefined", "NaN",
+    "Infinity",
+];
+
+/// Default character order for generated names
+const NAME_ALPHABET: &str = "etnrisouaflchpdvmgybwESxTNCkLAOM_DPHBjFIqRUzWXV$JKQGYZ0516372984";
+
+type ScopeId = usize;
+type BindingId = usize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ScopeKind {
+    Top,
+    Function,
+    Block,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BindingKind {
+    Var,
+    Lexical,
+    Function,
+    Class,
+}
+
+#[derive(Debug)]
+struct Scope {
+    kind: ScopeKind,
+    parent: Option<ScopeId>,
+    children: Vec<ScopeId>,
+    bindings: HashMap<String, BindingId>,
+    /// Contains a direct `eval` or a `with` statement
+    tainted: bool,
+}
+
+#[derive(Debug)]
+struct Binding {
+    kind: BindingKind,
+    scope: ScopeId,
+    occurrences: usize,
+}
+
+/// An identifier token that declares or references a binding
+#[derive(Debug, Clone, Copy)]
+struct Occurrence {
+    token: usize,
+    scope: ScopeId,
+    declares: Option<BindingId>,
+    /// `{ a }` shorthand that must be printed as `{ a: x }` once renamed
+    shorthand: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FrameKind {
+    Top,
+    Block,
+    FunctionBody,
+    ClassBody,
+    Object,
+    ObjectPattern,
+    ArrayPattern,
+    Params,
+    /// Header of `if`, `for`, `while`, `switch` and `with`
+    Condition,
+    Paren,
+    Bracket,
+    Template,
+}
+
+/// Position inside an object literal, object pattern, class body or parameter list
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Member {
+    Key,
+    AfterKey,
+    Value,
+    Default,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DeclState {
+    Pattern,
+    AfterPattern,
+    Init,
+}
+
+/// Scope waiting for the body that follows a parameter list or loop header
+#[derive(Debug, Clone, Copy)]
+enum PendingBody {
+    Function(ScopeId),
+    Block(ScopeId),
+}
+
+#[derive(Debug, Clone, Copy)]
+struct PendingDefinition {
+    scope: ScopeId,
+    is_declaration: bool,
+}
+
+/// A bracket level being analyzed
+#[derive(Debug)]
+struct Frame {
+    kind: FrameKind,
+    /// Scope pushed when this frame opened
+    scope: Option<ScopeId>,
+    /// Scopes opened at this level without brackets: arrow expression bodies
+    /// (`true`, closed by `,`) and brace-less loop bodies (closed at statement end)
+    open_scopes: Vec<bool>,
+    /// Where bindings of a pattern frame are declared
+    binding: Option<(ScopeId, BindingKind)>,
+    decl: Option<(BindingKind, DeclState)>,
+    member: Member,
+    /// After `case`, `default` or a label name: the next `:` ends a statement
+    colon_pending: bool,
+    /// Index of the `:` that ended a `case` clause or label
+    statement_colon: Option<usize>,
+    /// This bracket is a computed property key
+    computed_key: bool,
+    pending_body: Option<PendingBody>,
+    pending_function: Option<PendingDefinition>,
+    pending_class: Option<PendingDefinition>,
+}
+
+impl Frame {
+    fn new(kind: FrameKind) -> Self {
+        Self {
+            kind,
+            scope: None,
+            open_scopes: Vec::new(),
+            binding: None,
+            decl: None,
+            member: match kind {
+                FrameKind::Object | FrameKind::ObjectPattern | FrameKind::ClassBody => Member::Key,
+                _ => Member::Value,
+            },
+            colon_pending: false,
+            statement_colon: None,
+            computed_key: false,
+            pending_body: None,
+            pending_function: None,
+            pending_class: None,
+        }
+    }
+
+    fn is_statement_list(&self) -> bool {
+        matches!(self.kind, FrameKind::Top | FrameKind::Block | FrameKind::FunctionBody)
+    }
+
+    fn is_pattern(&self) -> bool {
+        matches!(self.kind, FrameKind::ObjectPattern | FrameKind::ArrayPattern | FrameKind::Params)
+    }
+
+    fn has_keys(&self) -> bool {
+        matches!(self.kind, FrameKind::Object | FrameKind::ObjectPattern | FrameKind::ClassBody)
+    }
+
+    fn closing_bracket(&self) -> &'static str {
+        match self.kind {
+            FrameKind::Params | FrameKind::Condition | FrameKind::Paren => ")",
+            FrameKind::ArrayPattern | FrameKind::Bracket => "]",
+            FrameKind::Template | FrameKind::Top => "",
+            _ => "}",
+        }
+    }
+}
+
+/// Token-level scope analysis: finds bindings and the identifiers referring to them
+struct ScopeAnalyzer<'t, 'a> {
+    tokens: &'t [MinToken<'a>],
+    /// Index of the matching close bracket for each open bracket
+    matching: Vec<usize>,
+    scopes: Vec<Scope>,
+    bindings: Vec<Binding>,
+    occurrences: Vec<Occurrence>,
+    frames: Vec<Frame>,
+    scope_stack: Vec<ScopeId>,
+    last_closed: Option<FrameKind>,
+    skip_until: usize,
+    failed: bool,
+}
+
+impl<'t, 'a> ScopeAnalyzer<'t, 'a> {
+    fn new(tokens: &'t [MinToken<'a>]) -> Self {
+        let mut matching = vec![usize::MAX; tokens.len()];
+        let mut stack = Vec::new();
+        for (i, token) in tokens.iter().enumerate() {
+            match token.kind {
+                TokenKind::Punct => match token.text.as_ref() {
+                    "(" | "[" | "{" => stack.push(i),
+                    ")" | "]" | "}" => {
+                        if let Some(open) = stack.pop() {
+                            matching[open] = i;
+                        }
+                    }
+                    _ => {}
+                },
+                TokenKind::TemplateHead => stack.push(i),
+                TokenKind::TemplateMiddle => {
+                    if let Some(open) = stack.pop() {
+                        matching[open] = i;
+                    }
+                    stack.push(i);
+                }
+                TokenKind::TemplateTail => {
+                    if let Some(open) = stack.pop() {
+                        matching[open] = i;
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Self {
+            tokens,
+            matching,
+            scopes: vec![Scope {
+                kind: ScopeKind::Top,
+                parent: None,
+                children: Vec::new(),
+                bindings: HashMap::new(),
+                tainted: false,
+            }],
+            bindings: Vec::new(),
+            occurrences: Vec::new(),
+            frames: vec![Frame::new(FrameKind::Top)],
+            scope_stack: vec![0],
+            last_closed: None,
+            skip_until: 0,
+            failed: false,
+        }
+    }
+
+    fn text(&self, i: usize) -> &str {
+        self.tokens.get(i).map(|t| t.text.as_ref()).unwrap_or("")
+    }
+
+    fn is_punct(&self, i: usize, punct: &str) -> bool {
+        self.tokens.get(i).is_some_and(|t| t.kind == TokenKind::Punct && t.text == punct)
+    }
+
+    fn is_word(&self, i: usize, word: &str) -> bool {
+        self.tokens.get(i).is_some_and(|t| t.kind == TokenKind::Name && t.text == word)
+    }
+
+    /// Is the `(` at `i` the parameter list of an arrow function?
+    fn is_arrow_params(&self, i: usize) -> bool {
+        self.matching.get(i).is_some_and(|&close| self.is_punct(close.wrapping_add(1), "=>"))
+    }
+
+    fn frame(&mut self) -> &mut Frame {
+        self.frames.last_mut().expect("top frame is never popped")
+    }
+
+    fn current_frame(&self) -> &Frame {
+        self.frames.last().expect("top frame is never popped")
+    }
+
+    fn current_scope(&self) -> ScopeId {
+        *self.scope_stack.last().unwrap_or(&0)
+    }
+
+    /// Nearest function (or top-level) scope, where `var` declarations live
+    fn hoist_scope(&self) -> ScopeId {
+        self.scope_stack
+            .iter()
+            .rev()
+            .copied()
+            .find(|s| self.scopes[*s].kind != ScopeKind::Block)
+            .unwrap_or(0)
+    }
+
+    fn new_scope(&mut self, kind: ScopeKind) -> ScopeId {
+        let parent = self.current_scope();
+        let id = self.scopes.len();
+        self.scopes.push(Scope {
+            kind,
+            parent: Some(parent),
+            children: Vec::new(),
+            bindings: HashMap::new(),
+            tainted: false,
+        });
+        self.scopes[parent].children.push(id);
+        id
+    }
+
+    fn push_frame(&mut self, mut frame: Frame, scope: Option<ScopeId>) {
+        if let Some(scope) = scope {
+            self.scope_stack.push(scope);
+            frame.scope = Some(scope);
+        }
+        self.frames.push(frame);
+    }
+
+    fn pop_frame(&mut self, bracket: &str) -> Option<Frame> {
+        if self.frames.len() == 1 || self.current_frame().closing_bracket() != bracket {
+            self.failed = true;
+            return None;
+        }
+        let frame = self.frames.pop()?;
+        let pushed = usize::from(frame.scope.is_some()) + frame.open_scopes.len();
+        self.scope_stack.truncate(self.scope_stack.len() - pushed);
+        self.last_closed = Some(frame.kind);
+        Some(frame)
+    }
+
+    /// Close the bracket-less scopes at the end of the current frame's stack
+    fn close_open_scopes(&mut self, arrow_bodies_only: bool) {
+        let frame = self.frames.last_mut().expect("top frame is never popped");
+        let mut closed = 0;
+        while frame.open_scopes.last().is_some_and(|&arrow| arrow || !arrow_bodies_only) {
+            frame.open_scopes.pop();
+            closed += 1;
+        }
+        self.scope_stack.truncate(self.scope_stack.len() - closed);
+    }
+
+    fn declare(&mut self, scope: ScopeId, token: usize, kind: BindingKind, shorthand: bool) {
+        let name = self.text(token).to_string();
+        let binding = match self.scopes[scope].bindings.get(&name) {
+            Some(&b) => b,
+            None => {
+                let b = self.bindings.len();
+                self.bindings.push(Binding {
+                    kind,
+                    scope,
+                    occurrences: 0,
+                });
+                self.scopes[scope].bindings.insert(name, b);
+                b
+            }
+        };
+        self.occurrences.push(Occurrence {
+            token,
+            scope,
+            declares: Some(binding),
+            shorthand,
+        });
+    }
+
+    fn reference(&mut self, token: usize, shorthand: bool) {
+        let scope = self.current_scope();
+        self.occurrences.push(Occurrence {
+            token,
+            scope,
+            declares: None,
+            shorthand,
+        });
+    }
+
+    /// Mark the current scope and all its ancestors as tainted
+    fn taint(&mut self) {
+        let mut scope = Some(self.current_scope());
+        while let Some(s) = scope {
+            self.scopes[s].tainted = true;
+            scope = self.scopes[s].parent;
+        }
+    }
+
+    /// Reset per-statement state of the current frame
+    fn end_statement(&mut self) {
+        self.close_open_scopes(false);
+        let frame = self.frame();
+        frame.decl = None;
+        if frame.kind == FrameKind::ClassBody {
+            frame.member = Member::Key;
+        }
+    }
+
+    fn at_statement_start(&self, i: usize, after_condition: bool) -> bool {
+        if i == 0 {
+            return true;
+        }
+        let prev = &self.tokens[i - 1];
+        if self.tokens[i].newline_before && can_end_statement(prev) {
+            return true;
+        }
+        match prev.kind {
+            TokenKind::Punct => match prev.text.as_ref() {
+                ";" | "{" | "}" => true,
+                ")" => after_condition,
+                ":" => self.current_frame().statement_colon == Some(i - 1),
+                _ => false,
+            },
+            TokenKind::Name => matches!(prev.text.as_ref(), "else" | "do"),
+            _ => false,
+        }
+    }
+
+    fn analyze(&mut self) {
+        for i in 0..self.tokens.len() {
+            if self.failed {
+                return;
+            }
+            if i < self.skip_until {
+                continue;
+            }
+            let closed = self.last_closed.take();
+            let token = &self.tokens[i];
+
+            // A line break before a name or literal ends the statement (ASI)
+            if i > 0
+                && token.newline_before
+                && matches!(token.kind, TokenKind::Name | TokenKind::PrivateName | TokenKind::String | TokenKind::Number)
+                && !matches!(token.text.as_ref(), "in" | "instanceof" | "of")
+                && can_end_statement(&self.tokens[i - 1])
+                && (self.current_frame().is_statement_list() || self.current_frame().kind == FrameKind::ClassBody)
+            {
+                self.end_statement();
+            }
+
+            // A body that isn't a `{` block: arrow expression body or brace-less loop body
+            if !(token.kind == TokenKind::Punct && (token.text == "{" || token.text == "=>")) {
+                if let Some(pending) = self.frame().pending_body.take() {
+                    let (scope, arrow) = match pending {
+                        PendingBody::Function(s) => (s, true),
+                        PendingBody::Block(s) => (s, false),
+                    };
+                    self.frame().open_scopes.push(arrow);
+                    self.scope_stack.push(scope);
+                }
+            }
+
+            match token.kind {
+                TokenKind::Punct => self.punct(i),
+                TokenKind::Name => self.name(i, closed),
+                TokenKind::TemplateHead => self.push_frame(Frame::new(FrameKind::Template), None),
+                TokenKind::TemplateMiddle => {
+                    self.frames.pop();
+                    self.push_frame(Frame::new(FrameKind::Template), None);
+                }
+                TokenKind::TemplateTail => {
+                    if self.current_frame().kind != FrameKind::Template {
+                        self.failed = true;
+                    }
+                    self.frames.pop();
+                }
+                TokenKind::String | TokenKind::Number | TokenKind::BigInt | TokenKind::PrivateName => {
+                    let frame = self.frame();
+                    if frame.member == Member::Key && frame.has_keys() {
+                        frame.member = Member::AfterKey;
+                    }
+                }
+                _ => {}
+            }
+        }
+        if self.frames.len() != 1 {
+            self.failed = true;
+        }
+    }
+
+    fn punct(&mut self, i: usize) {
+        match self.text(i) {
+            "{" => self.open_brace(i),
+            "(" => self.open_paren(i),
+            "[" => self.open_bracket(),
+            bracket @ ("}" | ")" | "]") => {
+                let bracket = bracket.to_string();
+                if let Some(frame) = self.pop_frame(&bracket) {
+                    self.after_close(i, frame);
+                }
+            }
+            "," => {
+                self.close_open_scopes(true);
+                let frame = self.frame();
+                match frame.kind {
+                    FrameKind::Object | FrameKind::ObjectPattern => frame.member = Member::Key,
+                    FrameKind::ArrayPattern | FrameKind::Params => frame.member = Member::Value,
+                    _ => {}
+                }
+                if let Some((kind, DeclState::AfterPattern | DeclState::Init)) = frame.decl {
+                    frame.decl = Some((kind, DeclState::Pattern));
+                }
+            }
+            ";" => self.end_statement(),
+            "=" => {
+                let frame = self.frame();
+                if frame.is_pattern() || frame.has_keys() {
+                    frame.member = Member::Default;
+                }
+                if let Some((kind, DeclState::AfterPattern)) = frame.decl {
+                    frame.decl = Some((kind, DeclState::Init));
+                }
+            }
+            ":" => {
+                let frame = self.frame();
+                if frame.member == Member::AfterKey {
+                    frame.member = Member::Value;
+                } else if frame.colon_pending {
+                    frame.colon_pending = false;
+                    frame.statement_colon = Some(i);
+                }
+            }
+            "..." => {
+                let frame = self.frame();
+                if frame.member == Member::Key && frame.kind != FrameKind::ClassBody {
+                    frame.member = Member::Value;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn decl_binding(&self, kind: BindingKind) -> (ScopeId, BindingKind) {
+        match kind {
+            BindingKind::Var | BindingKind::Function => (self.hoist_scope(), kind),
+            _ => (self.current_scope(), kind),
+        }
+    }
+
+    fn open_brace(&mut self, i: usize) {
+        let prev_is_static = i > 0 && self.is_word(i - 1, "static");
+        let frame = self.frames.last_mut().expect("top frame is never popped");
+
+        if let Some(body) = frame.pending_body.take() {
+            match body {
+                PendingBody::Function(scope) => self.push_frame(Frame::new(FrameKind::FunctionBody), Some(scope)),
+                PendingBody::Block(scope) => self.push_frame(Frame::new(FrameKind::Block), Some(scope)),
+            }
+            return;
+        }
+        if let Some(class) = frame.pending_class.take() {
+            self.push_frame(Frame::new(FrameKind::ClassBody), Some(class.scope));
+            return;
+        }
+        if frame.kind == FrameKind::ClassBody && frame.member == Member::Key && prev_is_static {
+            // `static { ... }` initialization block
+            let scope = self.new_scope(ScopeKind::Function);
+            self.push_frame(Frame::new(FrameKind::FunctionBody), Some(scope));
+            return;
+        }
+
+        let pattern_binding = match frame.decl {
+            Some((kind, DeclState::Pattern)) => Some(self.decl_binding(kind)),
+            _ if frame.is_pattern() && frame.member != Member::Default => frame.binding,
+            _ => None,
+        };
+        if pattern_binding.is_some() {
+            let mut pattern = Frame::new(FrameKind::ObjectPattern);
+            pattern.binding = pattern_binding;
+            self.push_frame(pattern, None);
+            return;
+        }
+
+        if self.current_frame().is_statement_list() && self.brace_starts_block(i) {
+            let scope = self.new_scope(ScopeKind::Block);
+            self.push_frame(Frame::new(FrameKind::Block), Some(scope));
+        } else {
+            self.push_frame(Frame::new(FrameKind::Object), None);
+        }
+    }
+
+    /// In a statement list, does the `{` at `i` open a block?
+    fn brace_starts_block(&self, i: usize) -> bool {
+        if i == 0 {
+            return true;
+        }
+        let prev = &self.tokens[i - 1];
+        match prev.kind {
+            TokenKind::Punct => match prev.text.as_ref() {
+                ";" | "{" | "}" | ")" => true,
+                ":" => self.current_frame().statement_colon == Some(i - 1),
+                _ => false,
+            },
+            TokenKind::Name => {
+                if prev.text == "default" && i >= 2 && self.is_word(i - 2, "export") {
+                    return false;
+                }
+                !matches!(
+                    prev.text.as_ref(),
+                    "return" | "typeof" | "yield" | "await" | "case" | "in" | "of" | "new" | "delete"
+                        | "void" | "throw" | "instanceof" | "extends"
+                )
+            }
+            _ => true,
+        }
+    }
+
+    fn open_paren(&mut self, i: usize) {
+        let after_property = i > 1 && (self.is_punct(i - 2, ".") || self.is_punct(i - 2, "?."));
+        let prev_word = if i > 0 && self.tokens[i - 1].kind == TokenKind::Name && !after_property {
+            self.text(i - 1).to_string()
+        } else {
+            String::new()
+        };
+        let is_arrow = self.is_arrow_params(i);
+        let frame = self.frames.last_mut().expect("top frame is never popped");
+
+        if let Some(function) = frame.pending_function.take() {
+            let mut params = Frame::new(FrameKind::Params);
+            params.binding = Some((function.scope, BindingKind::Var));
+            self.push_frame(params, Some(function.scope));
+            return;
+        }
+        let is_method = matches!(frame.kind, FrameKind::Object | FrameKind::ClassBody) && frame.member == Member::AfterKey;
+        if is_method || is_arrow {
+            if is_method {
+                frame.member = Member::Value;
+            }
+            let scope = self.new_scope(ScopeKind::Function);
+            let mut params = Frame::new(FrameKind::Params);
+            params.binding = Some((scope, BindingKind::Var));
+            self.push_frame(params, Some(scope));
+            return;
+        }
+
+        match prev_word.as_str() {
+            "catch" => {
+                let scope = self.new_scope(ScopeKind::Block);
+                let mut params = Frame::new(FrameKind::Params);
+                params.binding = Some((scope, BindingKind::Lexical));
+                self.push_frame(params, Some(scope));
+            }
+            "for" => {
+                let scope = self.new_scope(ScopeKind::Block);
+                self.push_frame(Frame::new(FrameKind::Condition), Some(scope));
+            }
+            "if" | "while" | "switch" | "with" => {
+                if prev_word == "with" {
+                    self.taint();
+                }
+                self.push_frame(Frame::new(FrameKind::Condition), None);
+            }
+            _ => self.push_frame(Frame::new(FrameKind::Paren), None),
+        }
+    }
+
+    fn open_bracket(&mut self) {
+        let frame = self.current_frame();
+        if frame.member == Member::Key && frame.has_keys() {
+            let mut bracket = Frame::new(FrameKind::Bracket);
+            bracket.computed_key = true;
+            self.push_frame(bracket, None);
+            return;
+        }
+        let pattern_binding = match frame.decl {
+            Some((kind, DeclState::Pattern)) => Some(self.decl_binding(kind)),
+            _ if frame.is_pattern() && frame.member != Member::Default => frame.binding,
+            _ => None,
+        };
+        if pattern_binding.is_some() {
+            let mut pattern = Frame::new(FrameKind::ArrayPattern);
+            pattern.binding = pattern_binding;
+            self.push_frame(pattern, None);
+        } else {
+            self.push_frame(Frame::new(FrameKind::Bracket), None);
+        }
+    }
+
+    /// Update the enclosing frame after the bracket at `i` closed `closing`
+    fn after_close(&mut self, i: usize, closing: Frame) {
+        let next_continues = ["else", "catch", "finally"].iter().any(|w| self.is_word(i + 1, w));
+        let parent = self.frame();
+        match closing.kind {
+            FrameKind::Params => {
+                parent.pending_body = closing.scope.map(|scope| match closing.binding {
+                    Some((_, BindingKind::Lexical)) => PendingBody::Block(scope),
+                    _ => PendingBody::Function(scope),
+                });
+            }
+            // The `for (...)` scope continues over the loop body
+            FrameKind::Condition => parent.pending_body = closing.scope.map(PendingBody::Block),
+            FrameKind::FunctionBody => match parent.kind {
+                FrameKind::ClassBody => parent.member = Member::Key,
+                FrameKind::Object => parent.member = Member::Value,
+                _ => {}
+            },
+            // `for (...) { ... }` as the body of a brace-less loop ends the loop
+            FrameKind::Block if parent.is_statement_list() && !next_continues => {
+                self.close_open_scopes(false);
+            }
+            FrameKind::ObjectPattern | FrameKind::ArrayPattern => {
+                if let Some((kind, DeclState::Pattern)) = parent.decl {
+                    parent.decl = Some((kind, DeclState::AfterPattern));
+                }
+                if parent.kind == FrameKind::ObjectPattern && parent.member == Member::Value {
+                    parent.member = Member::AfterKey;
+                }
+            }
+            FrameKind::Bracket if closing.computed_key => parent.member = Member::AfterKey,
+            _ => {}
+        }
+    }
+
+    fn name(&mut self, i: usize, closed: Option<FrameKind>) {
+        if i > 0 && (self.is_punct(i - 1, ".") || self.is_punct(i - 1, "?.")) {
+            return;
+        }
+
+        let text = self.text(i).to_string();
+        let after_condition = closed == Some(FrameKind::Condition);
+        let next_is_key = self.tokens.get(i + 1).is_some_and(|t| {
+            !t.newline_before
+                && match t.kind {
+                    TokenKind::Name | TokenKind::String | TokenKind::Number | TokenKind::BigInt | TokenKind::PrivateName => true,
+                    TokenKind::Punct => matches!(t.text.as_ref(), "[" | "*" | "{"),
+                    _ => false,
+                }
+        });
+        let next_colon = self.is_punct(i + 1, ":");
+        let next_paren = self.is_punct(i + 1, "(");
+        let next_brace = self.is_punct(i + 1, "{");
+        let next_ends_shorthand = self.is_punct(i + 1, ",") || self.is_punct(i + 1, "}") || self.is_punct(i + 1, "=");
+
+        // Property keys
+        let frame = self.frames.last_mut().expect("top frame is never popped");
+        if frame.member == Member::Key {
+            match frame.kind {
+                FrameKind::Object => {
+                    if matches!(text.as_str(), "get" | "set" | "async") && next_is_key && !next_brace {
+                        return;
+                    }
+                    frame.member = Member::AfterKey;
+                    if !next_colon && !next_paren && next_ends_shorthand {
+                        self.reference(i, true);
+                    }
+                    return;
+                }
+                FrameKind::ObjectPattern => {
+                    frame.member = Member::AfterKey;
+                    if !next_colon {
+                        let (scope, kind) = frame.binding.unwrap_or((0, BindingKind::Var));
+                        self.declare(scope, i, kind, true);
+                    }
+                    return;
+                }
+                FrameKind::ClassBody => {
+                    if matches!(text.as_str(), "static" | "get" | "set" | "async" | "accessor") && next_is_key {
+                        return;
+                    }
+                    frame.member = Member::AfterKey;
+                    return;
+                }
+                _ => {}
+            }
+        }
+
+        match text.as_str() {
+            "var" | "const" => {
+                let kind = if text == "var" { BindingKind::Var } else { BindingKind::Lexical };
+                self.frame().decl = Some((kind, DeclState::Pattern));
+                return;
+            }
+            "let" => {
+                let next_starts_binding = self.tokens.get(i + 1).is_some_and(|t| {
+                    t.kind == TokenKind::Name || (t.kind == TokenKind::Punct && (t.text == "[" || t.text == "{"))
+                });
+                if next_starts_binding
+                    && (self.at_statement_start(i, after_condition) || self.current_frame().kind == FrameKind::Condition)
+                {
+                    self.frame().decl = Some((BindingKind::Lexical, DeclState::Pattern));
+                    return;
+                }
+            }
+            "function" => {
+                let statement_start = self.at_statement_start(i, after_condition)
+                    || (i > 0 && (self.is_word(i - 1, "default") || self.is_word(i - 1, "export")))
+                    || (i > 0 && self.is_word(i - 1, "async") && self.at_statement_start(i - 1, after_condition));
+                let is_declaration = statement_start && self.current_frame().is_statement_list();
+                let scope = self.new_scope(ScopeKind::Function);
+                self.frame().pending_function = Some(PendingDefinition { scope, is_declaration });
+                return;
+            }
+            "class" => {
+                let statement_start = self.at_statement_start(i, after_condition)
+                    || (i > 0 && (self.is_word(i - 1, "default") || self.is_word(i - 1, "export")));
+                let is_declaration = statement_start && self.current_frame().is_statement_list();
+                let scope = self.new_scope(ScopeKind::Block);
+                self.frame().pending_class = Some(PendingDefinition { scope, is_declaration });
+                return;
+            }
+            "case" | "default" => {
+                self.frame().colon_pending = true;
+                return;
+            }
+            "import" => {
+                if !next_paren && !self.is_punct(i + 1, ".") && self.current_frame().kind == FrameKind::Top {
+                    self.skip_until = self.module_clause_end(i);
+                }
+                return;
+            }
+            "export" => {
+                if self.is_punct(i + 1, "{") || self.is_punct(i + 1, "*") {
+                    self.skip_until = self.module_clause_end(i);
+                }
+                return;
+            }
+            // `async function`, `async () =>` and `async x =>` use `async` as a keyword
+            "async"
+                if !self.tokens.get(i + 1).is_some_and(|t| t.newline_before)
+                    && (self.is_word(i + 1, "function")
+                        || (self.is_punct(i + 1, "(") && self.is_arrow_params(i + 1))
+                        || self.is_punct(i + 2, "=>")) =>
+            {
+                return;
+            }
+            // `for (x of xs)`
+            "of" if self.current_frame().kind == FrameKind::Condition
+                && i > 0
+                && (self.tokens[i - 1].kind == TokenKind::Name || self.is_punct(i - 1, "]") || self.is_punct(i - 1, "}")) =>
+            {
+                return;
+            }
+            _ => {}
+        }
+        if KEYWORDS.contains(&text.as_str()) {
+            return;
+        }
+
+        // Labels
+        if i > 0 && (self.is_word(i - 1, "break") || self.is_word(i - 1, "continue")) && !self.tokens[i].newline_before {
+            return;
+        }
+        if next_colon && self.current_frame().is_statement_list() && self.at_statement_start(i, after_condition) {
+            self.frame().colon_pending = true;
+            return;
+        }
+
+        // Function and class names
+        let after_function_keyword = i > 0 && (self.is_word(i - 1, "function") || self.is_punct(i - 1, "*"));
+        if let Some(function) = self.current_frame().pending_function.filter(|_| after_function_keyword) {
+            let scope = if function.is_declaration { self.hoist_scope() } else { function.scope };
+            self.declare(scope, i, BindingKind::Function, false);
+            return;
+        }
+        if let Some(class) = self.current_frame().pending_class.filter(|_| i > 0 && self.is_word(i - 1, "class")) {
+            let scope = if class.is_declaration { self.current_scope() } else { class.scope };
+            self.declare(scope, i, BindingKind::Class, false);
+            return;
+        }
+
+        // Single-parameter arrow: `x => ...`
+        if self.is_punct(i + 1, "=>") {
+            let scope = self.new_scope(ScopeKind::Function);
+            self.declare(scope, i, BindingKind::Var, false);
+            self.frame().pending_body = Some(PendingBody::Function(scope));
+            return;
+        }
+
+        // Bindings in patterns and declarations
+        let frame = self.current_frame();
+        let binding = match frame.kind {
+            FrameKind::ArrayPattern | FrameKind::Params if frame.member != Member::Default => frame.binding,
+            FrameKind::ObjectPattern if frame.member == Member::Value => frame.binding,
+            _ => match frame.decl {
+                Some((kind, DeclState::Pattern)) => Some(self.decl_binding(kind)),
+                _ => None,
+            },
+        };
+        if let Some((scope, kind)) = binding {
+            self.declare(scope, i, kind, false);
+            let frame = self.frame();
+            if let Some((kind, DeclState::Pattern)) = frame.decl {
+                frame.decl = Some((kind, DeclState::AfterPattern));
+            }
+            if frame.kind == FrameKind::ObjectPattern {
+                frame.member = Member::AfterKey;
+            }
+            return;
+        }
+
+        if text == "eval" && next_paren {
+            self.taint();
+        }
+        self.reference(i, false);
+    }
+
+    /// Index just past the module specifier (or `}` clause) of an import/export statement
+    fn module_clause_end(&self, start: usize) -> usize {
+        let mut j = start + 1;
+        while j < self.tokens.len() {
+            let token = &self.tokens[j];
+            match token.kind {
+                TokenKind::Punct if token.text == "{" => {
+                    let close = self.matching[j];
+                    if close == usize::MAX {
+                        return self.tokens.len();
+                    }
+                    j = close;
+                    if !self.is_word(j + 1, "from") {
+                        return j + 1;
+                    }
+                }
+                TokenKind::Punct if token.text == ";" => return j,
+                TokenKind::String => return j + 1,
+                _ => {}
+            }
+            j += 1;
+        }
+        j
+    }
+}
+
+/// Generates the n-th shortest identifier from an alphabet
+struct NameGenerator {
+    first: Vec<char>,
+    rest: Vec<char>,
+}
+
+impl NameGenerator {
+    /// Order the alphabet by how often each character appears in the kept output,
+    /// so generated names repeat characters the compressor already sees
+    fn new(frequencies: &HashMap<char, usize>) -> Self {
+        let mut rest: Vec<char> = NAME_ALPHABET.chars().collect();
+        // Stable sort keeps the default order for ties
+        rest.sort_by_key(|c| std::cmp::Reverse(frequencies.get(c).copied().unwrap_or(0)));
+        let first = rest.iter().copied().filter(|c| !c.is_ascii_digit()).collect();
+        Self { first, rest }
+    }
+
+    fn name(&self, mut n: usize) -> String {
+        let mut name = String::new();
+        name.push(self.first[n % self.first.len()]);
+        n /= self.first.len();
+        while n > 0 {
+            n -= 1;
+            name.push(self.rest[n % self.rest.len()]);
+            n /= self.rest.len();
+        }
+        name
+    }
+}
+
+/// Bindings and resolved identifiers of a token stream
+pub(crate) struct ScopeInfo {
+    scopes: Vec<Scope>,
+    bindings: Vec<Binding>,
+    /// Every declaring or referencing identifier with the binding it resolves to
+    occurrences: Vec<(Occurrence, Option<BindingId>)>,
+    /// Token index -> index into `occurrences`
+    by_token: HashMap<usize, usize>,
+}
+
+impl ScopeInfo {
+    /// Is the token at `i` a reference to a global (a name no enclosing scope declares)?
+    pub(crate) fn is_global_reference(&self, i: usize) -> bool {
+        self.by_token
+            .get(&i)
+            .is_some_and(|&o| self.occurrences[o].1.is_none())
+    }
+
+    /// Is the token at `i` an object shorthand (`{ a }`)?
+    pub(crate) fn is_shorthand(&self, i: usize) -> bool {
+        self.by_token.get(&i).is_some_and(|&o| self.occurrences[o].0.shorthand)
+    }
+
+    /// Local bindings declared once and referenced once, as (declaration, reference) token pairs
+    pub(crate) fn single_use_locals(&self) -> Vec<(usize, usize)> {
+        let mut uses: Vec<(Option<usize>, Option<usize>, usize)> = vec![(None, None, 0); self.bindings.len()];
+        for (occurrence, binding) in &self.occurrences {
+            let Some(b) = *binding else {
+                continue;
+            };
+            let entry = &mut uses[b];
+            entry.2 += 1;
+            if occurrence.declares.is_some() {
+                entry.0 = Some(occurrence.token);
+            } else {
+                entry.1 = Some(occurrence.token);
+            }
+        }
+        uses.iter()
+            .enumerate()
+            .filter(|(b, use_)| {
+                let scope = &self.scopes[self.bindings[*b].scope];
+                use_.2 == 2 && scope.kind != ScopeKind::Top && !scope.tainted
+            })
+            .filter_map(|(_, use_)| Some((use_.0?, use_.1?)))
+            .collect()
+    }
+}
+
+/// Find bindings and resolve references. `None` if the brackets don't balance.
+pub(crate) fn analyze_scopes(tokens: &[MinToken]) -> Option<ScopeInfo> {
+    let mut analyzer = ScopeAnalyzer::new(tokens);
+    analyzer.analyze();
+    if analyzer.failed {
+        return None;
+    }
+    let ScopeAnalyzer {
+        scopes,
+        mut bindings,
+        occurrences,
+        ..
+    } = analyzer;
+
+    // Resolve references through the scope chain
+    let mut resolved: Vec<(Occurrence, Option<BindingId>)> = Vec::with_capacity(occurrences.len());
+    let mut by_token = HashMap::with_capacity(occurrences.len());
+    for occurrence in occurrences {
+        let name = tokens[occurrence.token].text.as_ref();
+        let binding = occurrence.declares.or_else(|| {
+            let mut scope = Some(occurrence.scope);
+            while let Some(s) = scope {
+                if let Some(&b) = scopes[s].bindings.get(name) {
+                    return Some(b);
+                }
+                scope = scopes[s].parent;
+            }
+            None
+        });
+        if let Some(b) = binding {
+            bindings[b].occurrences += 1;
+        }
+        by_token.insert(occurrence.token, resolved.len());
+        resolved.push((occurrence, binding));
+    }
+
+    Some(ScopeInfo {
+        scopes,
+        bindings,
+        occurrences: resolved,
+        by_token,
+    })
+}
+
+/// `Function` or `Class` if the binding declared at `i` is initialized with an
+/// anonymous function, arrow or class expression, which takes its name
+fn named_initializer(tokens: &[MinToken], i: usize) -> Option<BindingKind> {
+    let text = |j: usize| tokens.get(j).map_or("", |t| t.text.as_ref());
+    let is_punct = |j: usize, p: &str| tokens.get(j).is_some_and(|t| t.kind == TokenKind::Punct && t.text == p);
+    let is_name = |j: usize| tokens.get(j).is_some_and(|t| t.kind == TokenKind::Name);
+    // `(...) =>`: the parentheses are balanced, since scope analysis succeeded
+    let arrow_params = |open: usize| {
+        let mut depth = 0usize;
+        for j in open..tokens.len() {
+            match text(j) {
+                "(" if is_punct(j, "(") => depth += 1,
+                ")" if is_punct(j, ")") => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return is_punct(j + 1, "=>");
+                    }
+                }
+                _ => {}
+            }
+        }
+        false
+    };
+    if !is_punct(i + 1, "=") {
+        return None;
+    }
+    let mut j = i + 2;
+    if text(j) == "async" && is_name(j) && !tokens.get(j + 1).is_some_and(|t| t.newline_before) {
+        j += 1;
+    }
+    let anonymous_function = text(j) == "function"
+        && (is_punct(j + 1, "(") || is_punct(j + 1, "*") && is_punct(j + 2, "("));
+    let arrow = is_punct(j, "(") && arrow_params(j) || is_name(j) && is_punct(j + 1, "=>");
+    if anonymous_function || arrow {
+        return Some(BindingKind::Function);
+    }
+    let anonymous_class = text(j) == "class" && (is_punct(j + 1, "{") || text(j + 1) == "extends");
+    anonymous_class.then_some(BindingKind::Class)
+}
+
+/// Rename local bindings in place.
+pub(crate) fn mangle(tokens: &mut [MinToken], config: &MinifyConfig, warnings: &mut Vec<String>) {
+    let Some(ScopeInfo {
+        scopes,
+        bindings,
+        occurrences: resolved,
+        ..
+    }) = analyze_scopes(tokens)
+    else {
+        warnings.push("Skipped mangling: unbalanced brackets".to_string());
+        return;
+    };
+
+    let mut reserved: HashSet<String> = KEYWORDS.iter().chain(RESERVED_NAMES).map(|s| s.to_string()).collect();
+    for (occurrence, binding) in &resolved {
+        if binding.is_none() {
+            reserved.insert(tokens[occurrence.token].text.to_string());
+        }
+    }
+
+    // `const f = () => {}` and `const E = class {}` name the function or class
+    let named: HashSet<BindingId> = resolved
+        .iter()
+        .filter_map(|(occurrence, _)| {
+            let kept = match named_initializer(tokens, occurrence.token)? {
+                BindingKind::Class => config.keep_class_names,
+                _ => config.keep_fn_names,
+            };
+            occurrence.declares.filter(|_| kept)
+        })
+        .collect();
+    let mangleable = |b: BindingId| {
+        let binding = &bindings[b];
+        let kept_name = match binding.kind {
+            BindingKind::Function => config.keep_fn_names,
+            BindingKind::Class => config.keep_class_names,
+            _ => named.contains(&b),
+        };
+        scopes[binding.scope].kind != ScopeKind::Top && !scopes[binding.scope].tainted && !kept_name
+    };
+    for (name, &b) in scopes.iter().flat_map(|s| s.bindings.iter()) {
+        if !mangleable(b) {
+            reserved.insert(name.clone());
+        }
+    }
+
+    // Assign slots: a scope's bindings follow its ancestors' slots, siblings reuse them
+    let mut slots: Vec<Option<usize>> = vec![None; bindings.len()];
+    let mut slot_counts: Vec<usize> = Vec::new();
+    let mut stack = vec![(0, 0)];
+    while let Some((scope, start)) = stack.pop() {
+        let mut own: Vec<BindingId> = scopes[scope].bindings.values().copied().filter(|&b| mangleable(b)).collect();
+        own.sort_by_key(|&b| (std::cmp::Reverse(bindings[b].occurrences), b));
+        for (offset, &b) in own.iter().enumerate() {
+            let slot = start + offset;
+            if slot_counts.len() <= slot {
+                slot_counts.resize(slot + 1, 0);
+            }
+            slot_counts[slot] += bindings[b].occurrences;
+            slots[b] = Some(slot);
+        }
+        for &child in &scopes[scope].children {
+            stack.push((child, start + own.len()));
+        }
+    }
+    if slot_counts.is_empty() {
+        return;
+    }
+
+    let mut frequencies: HashMap<char, usize> = HashMap::new();
+    let renamed: HashSet<usize> = resolved
+        .iter()
+        .filter(|(_, b)| b.is_some_and(|b| slots[b].is_some()))
+        .map(|(o, _)| o.token)
+        .collect();
+    for (i, token) in tokens.iter().enumerate() {
+        if !renamed.contains(&i) && matches!(token.kind, TokenKind::Name | TokenKind::Punct) {
+            for ch in token.text.chars() {
+                *frequencies.entry(ch).or_insert(0) += 1;
+            }
+        }
+    }
+
+    // Most frequent slots get the shortest names
+    let generator = NameGenerator::new(&frequencies);
+    let mut order: Vec<usize> = (0..slot_counts.len()).collect();
+    order.sort_by_key(|&slot| (std::cmp::Reverse(slot_counts[slot]), slot));
+    let mut names = vec![String::new(); slot_counts.len()];
+    let mut n = 0;
+    for slot in order {
+        let name = loop {
+            let candidate = generator.name(n);
+            n += 1;
+            if !reserved.contains(&candidate) {
+                break candidate;
+            }
+        };
+        names[slot] = name;
+    }
+
+    for (occurrence, binding) in resolved {
+        let Some(slot) = binding.and_then(|b| slots[b]) else {
+            continue;
+        };
+        let token = &mut tokens[occurrence.token];
+        token.text = if occurrence.shorthand {
+            Cow::Owned(format!("{}:{}", token.text, names[slot]))
+        } else {
+            Cow::Owned(names[slot].clone())
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::minifier::{minify_internal, MinifyConfig};
+
+    fn mangle_only() -> MinifyConfig {
+        MinifyConfig {
+            compress: false,
+            ..MinifyConfig::default()
+        }
+    }
+
+    fn mangled(code: &str) -> String {
+        minify_internal(code, None, &mangle_only()).code
+    }
+
+    #[test]
+    fn test_renames_locals_keeps_globals() {
+        assert_eq!(
+            mangled("function add(first, second) { var total = first + second; return total + offset; }"),
+            "function add(t,n){var r=t+n;return r+offset;}"
+        );
+        // Top-level bindings are visible to other scripts
+        assert_eq!(mangled("var counter = 1; let other = counter;"), "var counter=1;let other=counter;");
+        // `.for(` is a method call, not a loop header
+        assert_eq!(mangled("const key = Symbol.for('k')\nconst other = key"), "const key=Symbol.for('k')\nconst other=key");
+    }
+
+    #[test]
+    fn test_frequency_order() {
+        let code = "function f(rare, often) { return often + often + often + rare; }";
+        assert_eq!(mangled(code), "function f(t,n){return n+n+n+t;}");
+    }
+
+    #[test]
+    fn test_nested_scopes_do_not_capture() {
+        let code = "function f(outer) { function g(inner) { return outer + inner; } return g; }";
+        assert_eq!(mangled(code), "function f(n){function t(r){return n+r;}return t;}");
+    }
+
+    #[test]
+    fn test_properties_keys_and_labels_untouched() {
+        let code = "function f(value) { outer: for (;;) { break outer; } return { value: value, key: value.key }; }";
+        assert_eq!(
+            mangled(code),
+            "function f(e){outer:for(;;){break outer;}return{value:e,key:e.key};}"
+        );
+    }
+
+    #[test]
+    fn test_shorthand_properties() {
+        let code = "function f(name) { const { size } = name; return { name, size }; }";
+        assert_eq!(mangled(code), "function f(n){const{size:t}=n;return{name:n,size:t};}");
+    }
+
+    #[test]
+    fn test_eval_and_with_taint() {
+        let code = "function f(secret) { return eval('secret'); }";
+        assert_eq!(mangled(code), "function f(secret){return eval('secret');}");
+        let code = "function g(obj, value) { with (obj) { return value; } }";
+        assert_eq!(mangled(code), "function g(obj,value){with(obj){return value;}}");
+    }
+
+    #[test]
+    fn test_keep_fn_and_class_names() {
+        let code = "function f() { function helper() {} class Widget {} return [helper, Widget]; }";
+        assert_eq!(mangled(code), "function f(){function n(){}class t{}return[n,t];}");
+        let config = MinifyConfig {
+            keep_fn_names: true,
+            keep_class_names: true,
+            ..mangle_only()
+        };
+        assert_eq!(
+            minify_internal(code, None, &config).code,
+            "function f(){function helper(){}class Widget{}return[helper,Widget];}"
+        );
+        // Anonymous functions, arrows and classes take the name of the binding they initialize
+        let code = "function g() { const handler = () => 1, run = async function () {}, Model = class extends Base {}; \
+                    let total = sum(1); return [handler, run, Model, total]; }";
+        assert_eq!(
+            minify_internal(code, None, &config).code,
+            "function g(){const handler=()=>1,run=async function(){},Model=class extends Base{};\
+             let n=sum(1);return[handler,run,Model,n];}"
+        );
+        let config = MinifyConfig {
+            keep_fn_names: true,
+            ..mangle_only()
+        };
+        assert_eq!(
+            minify_internal("function h() { const E = class {}, f = (x) => x; return [E, f]; }", None, &config).code,
+            "function h(){const n=class{},f=(t)=>t;return[n,f];}"
+        );
+    }
+
+    #[test]
+    fn test_class_members_after_asi() {
+        let code = "class A {\n  #handler = (level) => level\n  #write (level, data) { return level + data }\n}";
+        assert_eq!(mangled(code), "class A{#handler=(r)=>r\n#write(r,s){return r+s}}");
+    }
+
+    #[test]
+    fn test_arrows_catch_and_block_scopes() {
+        let code = "function f(list) { try { list.map(item => item * 2); } catch (error) { let copy = error; return copy; } }";
+        assert_eq!(
+            mangled(code),
+            "function f(n){try{n.map(t=>t*2);}catch(t){let r=t;return r;}}"
+        );
+    }
+
+    #[test]
+    fn test_generated_names_avoid_globals() {
+        // `e` is an unresolved global here, so the local can't take it
+        let code = "function f(local) { return e(local); }";
+        assert_eq!(mangled(code), "function f(n){return e(n);}");
+    }
+}
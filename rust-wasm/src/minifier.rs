@@ -0,0 +1,554 @@
+//! JavaScript minifier
+//!
+//! Token-based minification:
+//! - Whitespace and comment removal (license comments preserved)
+//! - Line breaks kept only where automatic semicolon insertion needs them
+//! - Constant folding, dead code and `console`/`debugger` removal (see `compressor`)
+//! - Scope-aware identifier mangling (see `mangler`)
+//! - Source map generation
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::compressor::compress;
+use crate::lexer::{tokenize, Comment, TokenKind};
+use crate::mangler::mangle;
+use crate::utils::{now_ms, LineIndex};
+
+/// Configuration for minification
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[wasm_bindgen]
+pub struct MinifyConfig {
+    /// Apply compress passes
+    #[wasm_bindgen(skip)]
+    pub compress: bool,
+    /// Rename local bindings
+    #[wasm_bindgen(skip)]
+    pub mangle: bool,
+    /// Generate a source map
+    #[wasm_bindgen(skip)]
+    pub source_map: bool,
+    /// Keep function names when mangling
+    #[wasm_bindgen(skip)]
+    pub keep_fn_names: bool,
+    /// Keep class names when mangling
+    #[wasm_bindgen(skip)]
+    pub keep_class_names: bool,
+    /// Output language level ("es2020", "esnext", ...)
+    #[wasm_bindgen(skip)]
+    pub target: String,
+    /// Remove `console.*` calls
+    #[wasm_bindgen(skip)]
+    pub drop_console: bool,
+    /// Remove `debugger` statements
+    #[wasm_bindgen(skip)]
+    pub drop_debugger: bool,
+    /// Number of compress passes
+    #[wasm_bindgen(skip)]
+    pub passes: u32,
+    /// Global expressions replaced before compressing (`process.env.NODE_ENV` -> `"production"`)
+    #[wasm_bindgen(skip)]
+    pub defines: HashMap<String, String>,
+}
+
+#[wasm_bindgen]
+impl MinifyConfig {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set_compress(&mut self, value: bool) {
+        self.compress = value;
+    }
+
+    pub fn set_mangle(&mut self, value: bool) {
+        self.mangle = value;
+    }
+
+    pub fn set_source_map(&mut self, value: bool) {
+        self.source_map = value;
+    }
+
+    pub fn set_keep_fn_names(&mut self, value: bool) {
+        self.keep_fn_names = value;
+    }
+
+    pub fn set_keep_class_names(&mut self, value: bool) {
+        self.keep_class_names = value;
+    }
+
+    pub fn set_target(&mut self, value: String) {
+        self.target = value.to_lowercase();
+    }
+
+    pub fn set_drop_console(&mut self, value: bool) {
+        self.drop_console = value;
+    }
+
+    pub fn set_drop_debugger(&mut self, value: bool) {
+        self.drop_debugger = value;
+    }
+
+    pub fn set_passes(&mut self, value: u32) {
+        self.passes = value.max(1);
+    }
+
+    pub fn add_define(&mut self, key: String, value: String) {
+        self.defines.insert(key, value);
+    }
+}
+
+impl Default for MinifyConfig {
+    fn default() -> Self {
+        Self {
+            compress: true,
+            mangle: true,
+            source_map: false,
+            keep_fn_names: false,
+            keep_class_names: false,
+            target: "es2020".to_string(),
+            drop_console: false,
+            drop_debugger: true,
+            passes: 2,
+            defines: HashMap::new(),
+        }
+    }
+}
+
+/// Result of minification
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MinifyResult {
+    /// Minified code
+    pub code: String,
+    /// Source map (if requested)
+    pub source_map: Option<String>,
+    /// Statistics about the minification
+    pub stats: MinifyStats,
+    /// Non-fatal problems found while minifying
+    pub warnings: Vec<String>,
+}
+
+/// Statistics from minification
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct MinifyStats {
+    /// Original code size in bytes
+    pub original_size: usize,
+    /// Minified code size in bytes
+    pub minified_size: usize,
+    /// minified_size / original_size
+    pub compression_ratio: f64,
+    /// Time spent minifying
+    pub time_ms: f64,
+}
+
+/// File to minify in a batch
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileInput {
+    pub filename: String,
+    pub code: String,
+}
+
+/// Main minifier struct
+#[wasm_bindgen]
+pub struct Minifier {
+    config: MinifyConfig,
+}
+
+#[wasm_bindgen]
+impl Minifier {
+    #[wasm_bindgen(constructor)]
+    pub fn new(config: Option<MinifyConfig>) -> Self {
+        Self {
+            config: config.unwrap_or_default(),
+        }
+    }
+
+    /// Minify JavaScript code
+    #[wasm_bindgen]
+    pub fn minify(&self, code: &str, filename: Option<String>) -> JsValue {
+        let result = minify_internal(code, filename.as_deref(), &self.config);
+        serde_wasm_bindgen::to_value(&result).unwrap_or(JsValue::NULL)
+    }
+
+    /// Minify multiple files
+    #[wasm_bindgen]
+    pub fn minify_batch(&self, files_js: JsValue) -> JsValue {
+        let files: Vec<FileInput> = serde_wasm_bindgen::from_value(files_js).unwrap_or_default();
+
+        let results: Vec<MinifyResult> = files
+            .iter()
+            .map(|f| minify_internal(&f.code, Some(&f.filename), &self.config))
+            .collect();
+        serde_wasm_bindgen::to_value(&results).unwrap_or(JsValue::NULL)
+    }
+
+    /// Whitespace and comment removal only (faster but less optimal)
+    #[wasm_bindgen]
+    pub fn quick_minify(&self, code: &str) -> String {
+        quick_minify_internal(code)
+    }
+}
+
+/// A token being minified. Text is borrowed from the source until a pass rewrites it.
+#[derive(Debug, Clone)]
+pub(crate) struct MinToken<'a> {
+    pub(crate) kind: TokenKind,
+    pub(crate) text: Cow<'a, str>,
+    /// Source offset this token maps back to
+    pub(crate) pos: Option<usize>,
+    pub(crate) newline_before: bool,
+}
+
+/// Full minification
+pub fn minify_internal(code: &str, filename: Option<&str>, config: &MinifyConfig) -> MinifyResult {
+    let start_time = now_ms();
+    let stream = tokenize(code);
+    let mut warnings = Vec::new();
+
+    let line_index = LineIndex::new(code);
+    for error in &stream.errors {
+        let (line, column) = line_index.position(code, error.pos);
+        warnings.push(format!("{} at {}:{}", error.message, line + 1, column + 1));
+    }
+
+    let mut tokens: Vec<MinToken> = stream
+        .tokens
+        .iter()
+        .map(|t| MinToken {
+            kind: t.kind,
+            text: Cow::Borrowed(t.text(code)),
+            pos: Some(t.start),
+            newline_before: t.newline_before,
+        })
+        .collect();
+
+    if config.compress && stream.errors.is_empty() {
+        tokens = compress(tokens, config, code, &line_index, &mut warnings);
+    }
+
+    if config.mangle {
+        if stream.errors.is_empty() {
+            mangle(&mut tokens, config, &mut warnings);
+        } else {
+            warnings.push("Skipped mangling: source has syntax errors".to_string());
+        }
+    }
+
+    let mut source_map = config.source_map.then(|| SourceMapBuilder::new(code, &line_index));
+    let output = print(code, &tokens, &stream.comments, source_map.as_mut());
+
+    let original_size = code.len();
+    let minified_size = output.len();
+    MinifyResult {
+        code: output,
+        source_map: source_map.map(|m| m.finish(filename.unwrap_or("input.js"), code)),
+        stats: MinifyStats {
+            original_size,
+            minified_size,
+            compression_ratio: if original_size > 0 {
+                minified_size as f64 / original_size as f64
+            } else {
+                1.0
+            },
+            time_ms: now_ms() - start_time,
+        },
+        warnings,
+    }
+}
+
+/// Whitespace and comment removal only
+pub fn quick_minify_internal(code: &str) -> String {
+    let stream = tokenize(code);
+    let tokens: Vec<MinToken> = stream
+        .tokens
+        .iter()
+        .map(|t| MinToken {
+            kind: t.kind,
+            text: Cow::Borrowed(t.text(code)),
+            pos: Some(t.start),
+            newline_before: t.newline_before,
+        })
+        .collect();
+    print(code, &tokens, &stream.comments, None)
+}
+
+/// Keywords that can never end a statement, so a line break after them is insignificant
+const CONTINUATION_KEYWORDS: &[&str] = &[
+    "in", "instanceof", "typeof", "new", "delete", "void", "else", "do", "case", "extends",
+    "var", "const", "function", "class", "if", "for", "while", "with", "switch", "try",
+    "catch", "finally", "export",
+];
+
+/// Can this token be the last token of a statement?
+pub(crate) fn can_end_statement(token: &MinToken) -> bool {
+    match token.kind {
+        TokenKind::Name => !CONTINUATION_KEYWORDS.contains(&token.text.as_ref()),
+        TokenKind::Punct => matches!(token.text.as_ref(), ")" | "]" | "}" | "++" | "--"),
+        TokenKind::TemplateHead | TokenKind::TemplateMiddle => false,
+        _ => true,
+    }
+}
+
+/// Can this token be the first token of a statement?
+pub(crate) fn can_start_statement(token: &MinToken) -> bool {
+    match token.kind {
+        TokenKind::Punct => matches!(
+            token.text.as_ref(),
+            "(" | "[" | "{" | "+" | "-" | "!" | "~" | "++" | "--" | "..." | "@" | "#" | "<"
+        ),
+        TokenKind::TemplateMiddle | TokenKind::TemplateTail => false,
+        _ => true,
+    }
+}
+
+#[inline]
+fn is_word_char(ch: char) -> bool {
+    ch.is_ascii_alphanumeric() || ch == '$' || ch == '_' || ch == '\\' || !ch.is_ascii()
+}
+
+/// Would printing `next` right after `prev` change how the code tokenizes?
+fn needs_space(prev: &MinToken, next: &MinToken) -> bool {
+    let (last, first) = match (prev.text.chars().last(), next.text.chars().next()) {
+        (Some(l), Some(f)) => (l, f),
+        _ => return false,
+    };
+    if is_word_char(first) && (is_word_char(last) || prev.kind == TokenKind::Regex) {
+        return true;
+    }
+    match (last, first) {
+        ('+', '+') | ('-', '-') => true,
+        // `a / /re/` must not become a comment
+        ('/', '/') | ('/', '*') => true,
+        // `1 .toString()`
+        (_, '.') if prev.kind == TokenKind::Number => {
+            !prev.text.contains(['.', 'e', 'E', 'x', 'X', 'o', 'O', 'b', 'B'])
+        }
+        // `<!--` and `-->` are HTML comments in scripts
+        ('<', '!') => next.text.starts_with("!--"),
+        ('-', '>') => prev.text.ends_with("--"),
+        _ => false,
+    }
+}
+
+/// Is this comment kept in the output (`/*!`, `@license`, `@preserve`)?
+fn is_preserved_comment(source: &str, comment: &Comment) -> bool {
+    let body = comment.body(source);
+    body.starts_with('!') || body.contains("@license") || body.contains("@preserve")
+}
+
+/// Generated code tracking its own line/column (UTF-16) for source maps
+struct Output {
+    code: String,
+    line: usize,
+    column: usize,
+}
+
+impl Output {
+    fn push(&mut self, text: &str) {
+        self.code.push_str(text);
+        match text.rfind('\n') {
+            Some(idx) => {
+                self.line += text.matches('\n').count();
+                self.column = text[idx + 1..].encode_utf16().count();
+            }
+            None => self.column += text.encode_utf16().count(),
+        }
+    }
+}
+
+/// Print tokens with minimal whitespace
+fn print(source: &str, tokens: &[MinToken], comments: &[Comment], mut source_map: Option<&mut SourceMapBuilder>) -> String {
+    let mut out = Output {
+        code: String::with_capacity(source.len() / 2),
+        line: 0,
+        column: 0,
+    };
+    let mut preserved = comments.iter().filter(|c| is_preserved_comment(source, c)).peekable();
+    let mut prev: Option<&MinToken> = None;
+
+    for token in tokens {
+        if let Some(pos) = token.pos {
+            while let Some(comment) = preserved.next_if(|c| c.end <= pos) {
+                if !out.code.is_empty() && !out.code.ends_with('\n') {
+                    out.push("\n");
+                }
+                out.push(&source[comment.start..comment.end]);
+                out.push("\n");
+                prev = None;
+            }
+        }
+
+        if let Some(p) = prev {
+            if token.newline_before && can_end_statement(p) && can_start_statement(token) {
+                out.push("\n");
+            } else if needs_space(p, token) {
+                out.push(" ");
+            }
+        }
+
+        if let (Some(map), Some(pos)) = (source_map.as_deref_mut(), token.pos) {
+            map.add_mapping(out.line, out.column, pos);
+        }
+        out.push(&token.text);
+        prev = Some(token);
+    }
+
+    for comment in preserved {
+        if !out.code.is_empty() {
+            out.push("\n");
+        }
+        out.push(&source[comment.start..comment.end]);
+    }
+
+    out.code
+}
+
+const BASE64_CHARS: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Append a base64 VLQ encoded value
+fn encode_vlq(out: &mut String, value: i64) {
+    let mut vlq = if value < 0 { ((-value) << 1) | 1 } else { value << 1 };
+    loop {
+        let mut digit = (vlq & 0b11111) as usize;
+        vlq >>= 5;
+        if vlq > 0 {
+            digit |= 0b100000;
+        }
+        out.push(BASE64_CHARS[digit] as char);
+        if vlq == 0 {
+            break;
+        }
+    }
+}
+
+/// Builds a version 3 source map mapping each output token to its source token
+struct SourceMapBuilder<'a> {
+    source: &'a str,
+    line_index: &'a LineIndex,
+    mappings: String,
+    generated_line: usize,
+    prev_generated_column: i64,
+    prev_source_line: i64,
+    prev_source_column: i64,
+    first_in_line: bool,
+}
+
+impl<'a> SourceMapBuilder<'a> {
+    fn new(source: &'a str, line_index: &'a LineIndex) -> Self {
+        Self {
+            source,
+            line_index,
+            mappings: String::new(),
+            generated_line: 0,
+            prev_generated_column: 0,
+            prev_source_line: 0,
+            prev_source_column: 0,
+            first_in_line: true,
+        }
+    }
+
+    fn add_mapping(&mut self, generated_line: usize, generated_column: usize, source_pos: usize) {
+        while self.generated_line < generated_line {
+            self.mappings.push(';');
+            self.generated_line += 1;
+            self.prev_generated_column = 0;
+            self.first_in_line = true;
+        }
+        if !self.first_in_line {
+            self.mappings.push(',');
+        }
+        self.first_in_line = false;
+
+        let (line, column) = self.line_index.position(self.source, source_pos);
+        let (line, column) = (line as i64, column as i64);
+        encode_vlq(&mut self.mappings, generated_column as i64 - self.prev_generated_column);
+        encode_vlq(&mut self.mappings, 0);
+        encode_vlq(&mut self.mappings, line - self.prev_source_line);
+        encode_vlq(&mut self.mappings, column - self.prev_source_column);
+        self.prev_generated_column = generated_column as i64;
+        self.prev_source_line = line;
+        self.prev_source_column = column;
+    }
+
+    fn finish(self, filename: &str, source: &str) -> String {
+        serde_json::json!({
+            "version": 3,
+            "sources": [filename],
+            "sourcesContent": [source],
+            "names": [],
+            "mappings": self.mappings,
+        })
+        .to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn whitespace_only() -> MinifyConfig {
+        MinifyConfig {
+            compress: false,
+            mangle: false,
+            ..MinifyConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_removes_whitespace_and_comments() {
+        let code = "// comment\nfunction add(a, b) {\n  /* sum */\n  return a + b;\n}\n";
+        let result = minify_internal(code, None, &whitespace_only());
+        assert_eq!(result.code, "function add(a,b){return a+b;}");
+        assert!(result.stats.compression_ratio < 1.0);
+        assert!(result.warnings.is_empty());
+    }
+
+    #[test]
+    fn test_preserves_strings_regex_and_templates() {
+        let code = "const s = 'a  b', r = / +/g, t = `x ${ y + 1 } z`;";
+        assert_eq!(quick_minify_internal(code), "const s='a  b',r=/ +/g,t=`x ${y+1} z`;");
+    }
+
+    #[test]
+    fn test_keeps_asi_line_breaks() {
+        let code = "let a = 1\nlet b = a\n++b\nreturn\nx";
+        assert_eq!(quick_minify_internal(code), "let a=1\nlet b=a\n++b\nreturn\nx");
+        assert_eq!(quick_minify_internal("a = b +\n c"), "a=b+c");
+    }
+
+    #[test]
+    fn test_token_separation() {
+        assert_eq!(quick_minify_internal("a + +b; c - -d; e / /re/"), "a+ +b;c- -d;e/ /re/");
+        assert_eq!(quick_minify_internal("1 .toString(); 1.5 .toFixed()"), "1 .toString();1.5.toFixed()");
+        assert_eq!(quick_minify_internal("x = /re/g in y"), "x=/re/g in y");
+    }
+
+    #[test]
+    fn test_license_comments_kept() {
+        let code = "/*! lib v1 | MIT */\nvar a = 1; /* drop */";
+        assert_eq!(quick_minify_internal(code), "/*! lib v1 | MIT */\nvar a=1;");
+    }
+
+    #[test]
+    fn test_source_map() {
+        let config = MinifyConfig {
+            source_map: true,
+            ..whitespace_only()
+        };
+        let result = minify_internal("var a = 1;\nvar b = 2;", Some("input.js"), &config);
+        let map: serde_json::Value = serde_json::from_str(result.source_map.as_deref().unwrap()).unwrap();
+        assert_eq!(map["sources"][0], "input.js");
+        // Both statements end up on generated line 0
+        assert_eq!(map["mappings"], "AAAA,IAAI,CAAE,CAAE,CAAC,CACT,IAAI,CAAE,CAAE,CAAC");
+    }
+
+    #[test]
+    fn test_unterminated_string_warning() {
+        let result = minify_internal("var a = 'oops\n", None, &whitespace_only());
+        assert_eq!(result.warnings, vec!["Unterminated string literal at 1:9".to_string()]);
+    }
+}
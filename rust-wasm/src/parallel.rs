@@ -31,6 +31,12 @@ pub struct ModuleOutput {
 #[wasm_bindgen]
 pub struct ParallelProcessor;
 
+impl Default for ParallelProcessor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 #[wasm_bindgen]
 impl ParallelProcessor {
     #[wasm_bindgen(constructor)]
@@ -48,8 +54,13 @@ impl ParallelProcessor {
         };
 
         let options = TransformOptions::default();
-        let results: Vec<ModuleOutput> = modules
-            .iter()
+
+        #[cfg(not(target_arch = "wasm32"))]
+        let iter = modules.par_iter();
+        #[cfg(target_arch = "wasm32")]
+        let iter = modules.iter();
+
+        let results: Vec<ModuleOutput> = iter
             .map(|m| {
                 let result = transform_internal(&m.code, &m.filename, &options);
                 let size = result.code.len();
@@ -1,290 +1,346 @@
-//! Fast JavaScript/TypeScript parser for import extraction
+//! JavaScript/TypeScript import extraction
 //!
-//! Minimal parser focused on:
+//! Works on the syntax tree from `js_parser`, so strings, comments, regular
+//! expressions and template literals never produce false matches. Reports:
 //! - Static imports: import x from 'y'
 //! - Dynamic imports: import('y')
-//! - Exports: export { x } from 'y'
+//! - Re-exports: export { x } from 'y'
 //! - require(): require('y')
+//! - Exports of every form, including TypeScript `export =`
+//! - CommonJS exports, matching cjs-module-lexer
+//! - Asset URLs and workers: new URL('./x', import.meta.url)
+//! - Directives ("use client"), hashbang, #__PURE__ annotations
 //! - JSX detection
 //! - Top-level await detection
 
 use wasm_bindgen::prelude::*;
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+use crate::ast::{
+    self, walk_expr, walk_program, walk_prop_key, walk_stmt, ArrowFunction, CallExpr, ExportNamed, Expr,
+    Function, JsxElement, JsxFragment, Program, Span, Stmt, TsModuleRef, Visit,
+};
+use crate::diagnostics::{self, Diagnostic};
+use crate::incremental::{reparse_utf16_internal, ParseHandle};
+use crate::js_parser;
+use crate::lexer::Comment;
+use crate::utils::LineIndex;
 
 /// Import information
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ImportInfo {
-    /// The import source/path
+    /// The import source/path; empty for `import()` of a computed source
     pub source: String,
+    /// Which syntax brought the module in
+    pub kind: ImportKind,
+    /// Bindings created or names re-exported, in source order
+    pub specifiers: Vec<ImportSpecifierInfo>,
     /// Is this a dynamic import?
     pub is_dynamic: bool,
     /// Is this a type-only import?
     pub is_type_only: bool,
+    /// Import attributes: `with { type: "json" }`, the older
+    /// `assert { ... }`, or the `with` option of `import()`
+    pub attributes: BTreeMap<String, String>,
+    /// Block comments before the source inside `import(...)`, such as
+    /// `webpackChunkName: "admin"` or `@vite-ignore`
+    pub magic_comments: Vec<MagicComment>,
     /// Start position in source
     pub start: usize,
     /// End position in source
     pub end: usize,
+    /// 1-based line of `start`
+    pub line: usize,
+    /// 1-based column of `start`, in UTF-16 code units
+    pub column: usize,
 }
 
-/// Parse result
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ParseResult {
-    /// All imports found
-    pub imports: Vec<ImportInfo>,
-    /// Has JSX syntax
-    pub has_jsx: bool,
-    /// Has top-level await
-    pub has_top_level_await: bool,
-    /// Is TypeScript
-    pub is_typescript: bool,
-    /// Parse time in microseconds
-    pub parse_time_us: u64,
+/// One `key: value` entry (or bare marker) of a magic comment
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct MagicComment {
+    /// `webpackChunkName`, `@vite-ignore`, ...
+    pub key: String,
+    /// Value with string quotes removed; other values (`true`, `/regex/`,
+    /// `["a", "b"]`) are kept as written. `None` for markers.
+    pub value: Option<String>,
+    /// Start of the comment (0-indexed)
+    pub start: usize,
+    /// End of the comment (0-indexed)
+    pub end: usize,
 }
 
-/// Token types for lexer
-#[derive(Debug, Clone, Copy, PartialEq)]
-enum Token {
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportKind {
+    /// `import ... from "x"` or `import "x"`
     Import,
-    Export,
-    From,
+    /// `export { a as b } from "x"`
+    ReExport,
+    /// `export * from "x"` or `export * as ns from "x"`
+    ExportAll,
+    /// `import("x")`
+    Dynamic,
+    /// `require("x")`
     Require,
-    Await,
-    Type,
-    StringLiteral,
-    OpenParen,
-    CloseParen,
-    OpenBrace,
-    CloseBrace,
-    Semicolon,
-    Comma,
-    Star,
-    As,
-    Identifier,
-    JSXOpen,
-    EOF,
-    Other,
-}
-
-/// Fast lexer for JavaScript/TypeScript
-struct Lexer<'a> {
-    source: &'a [u8],
-    pos: usize,
-    len: usize,
-}
-
-impl<'a> Lexer<'a> {
-    fn new(source: &'a str) -> Self {
-        let bytes = source.as_bytes();
-        Self {
-            source: bytes,
-            pos: 0,
-            len: bytes.len(),
-        }
-    }
-
-    #[inline]
-    fn peek(&self) -> u8 {
-        if self.pos < self.len {
-            self.source[self.pos]
-        } else {
-            0
-        }
-    }
-
-    #[inline]
-    fn peek_n(&self, n: usize) -> u8 {
-        let pos = self.pos + n;
-        if pos < self.len {
-            self.source[pos]
-        } else {
-            0
-        }
-    }
-
-    #[inline]
-    fn advance(&mut self) {
-        self.pos += 1;
-    }
-
-    #[inline]
-    fn advance_n(&mut self, n: usize) {
-        self.pos += n;
-    }
-
-    fn skip_whitespace(&mut self) {
-        while self.pos < self.len {
-            match self.peek() {
-                b' ' | b'\t' | b'\n' | b'\r' => self.advance(),
-                b'/' => {
-                    if self.peek_n(1) == b'/' {
-                        // Single-line comment
-                        self.advance_n(2);
-                        while self.pos < self.len && self.peek() != b'\n' {
-                            self.advance();
-                        }
-                    } else if self.peek_n(1) == b'*' {
-                        // Multi-line comment
-                        self.advance_n(2);
-                        while self.pos < self.len {
-                            if self.peek() == b'*' && self.peek_n(1) == b'/' {
-                                self.advance_n(2);
-                                break;
-                            }
-                            self.advance();
-                        }
-                    } else {
-                        break;
-                    }
-                }
-                _ => break,
-            }
-        }
-    }
+    /// TypeScript `import x = require("x")`
+    ImportEquals,
+}
 
-    fn read_string(&mut self) -> Option<String> {
-        let quote = self.peek();
-        if quote != b'"' && quote != b'\'' && quote != b'`' {
-            return None;
-        }
-        
-        self.advance(); // skip opening quote
-        let start = self.pos;
-        
-        while self.pos < self.len {
-            let ch = self.peek();
-            if ch == quote {
-                let s = String::from_utf8_lossy(&self.source[start..self.pos]).to_string();
-                self.advance(); // skip closing quote
-                return Some(s);
-            }
-            if ch == b'\\' {
-                self.advance(); // skip escape
-            }
-            self.advance();
-        }
-        
-        None
-    }
+/// One name taken from the imported module
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ImportSpecifierInfo {
+    pub kind: SpecifierKind,
+    /// Name exported by the imported module: `"default"` for default
+    /// imports, `"*"` for namespaces
+    pub imported: String,
+    /// Local binding; for re-exports, the name this module exports it as
+    pub local: String,
+    /// Has its own `type` modifier, or belongs to an `import type`
+    pub is_type_only: bool,
+}
 
-    fn read_identifier(&mut self) -> String {
-        let start = self.pos;
-        while self.pos < self.len {
-            let ch = self.peek();
-            if ch.is_ascii_alphanumeric() || ch == b'_' || ch == b'$' {
-                self.advance();
-            } else {
-                break;
-            }
-        }
-        String::from_utf8_lossy(&self.source[start..self.pos]).to_string()
-    }
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SpecifierKind {
+    /// `import a from`
+    Default,
+    /// `import * as ns from`, `export * as ns from`
+    Namespace,
+    /// `import { a as b } from`, `export { a as b } from`
+    Named,
+}
 
-    fn check_keyword(&self, keyword: &[u8]) -> bool {
-        let len = keyword.len();
-        if self.pos + len > self.len {
-            return false;
-        }
-        
-        // Check if matches keyword
-        if &self.source[self.pos..self.pos + len] != keyword {
-            return false;
-        }
-        
-        // Check it's not part of a larger identifier
-        if self.pos + len < self.len {
-            let next = self.source[self.pos + len];
-            if next.is_ascii_alphanumeric() || next == b'_' || next == b'$' {
-                return false;
-            }
-        }
-        
-        true
-    }
+/// Export information
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportInfo {
+    pub kind: ExportKind,
+    /// Name other modules import; `"default"` for `export default` and `export =`
+    pub exported: String,
+    /// Local binding behind the export; for re-exports, the name in the
+    /// source module. `None` for anonymous defaults and expressions.
+    pub local: Option<String>,
+    /// Source module of a re-export
+    pub source: Option<String>,
+    /// Type-only or ambient export, erased at runtime
+    pub is_type_only: bool,
+    /// Start position of the exported binding (0-indexed)
+    pub start: usize,
+    /// End position of the exported binding (0-indexed)
+    pub end: usize,
+    /// Line number (1-indexed)
+    pub line: usize,
+    /// Column number (1-indexed, UTF-16)
+    pub column: usize,
+}
 
-    fn next_token(&mut self) -> (Token, usize, Option<String>) {
-        self.skip_whitespace();
-        
-        if self.pos >= self.len {
-            return (Token::EOF, self.pos, None);
-        }
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportKind {
+    /// `export const/function/class/...`, one entry per bound name
+    Declaration,
+    /// `export default ...`
+    Default,
+    /// `export { a as b }`
+    Named,
+    /// `export { a as b } from "x"`
+    ReExport,
+    /// `export * as ns from "x"`
+    Namespace,
+    /// TypeScript `export = x`
+    Assignment,
+}
 
-        let start = self.pos;
-        let ch = self.peek();
+/// A file referenced through a URL rather than an import
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DependencyInfo {
+    pub kind: DependencyKind,
+    /// The URL as written, e.g. `./worker.ts`
+    pub specifier: String,
+    /// Start position of the whole expression (0-indexed)
+    pub start: usize,
+    /// End position of the whole expression (0-indexed)
+    pub end: usize,
+    /// Start of the string literal to rewrite, quotes included
+    pub specifier_start: usize,
+    /// End of the string literal to rewrite, quotes included
+    pub specifier_end: usize,
+    /// Line number (1-indexed)
+    pub line: usize,
+    /// Column number (1-indexed, UTF-16)
+    pub column: usize,
+    /// Worker created with `{ type: "module" }`
+    pub is_module: bool,
+}
 
-        // Keywords
-        if self.check_keyword(b"import") {
-            self.advance_n(6);
-            return (Token::Import, start, None);
-        }
-        if self.check_keyword(b"export") {
-            self.advance_n(6);
-            return (Token::Export, start, None);
-        }
-        if self.check_keyword(b"from") {
-            self.advance_n(4);
-            return (Token::From, start, None);
-        }
-        if self.check_keyword(b"require") {
-            self.advance_n(7);
-            return (Token::Require, start, None);
-        }
-        if self.check_keyword(b"await") {
-            self.advance_n(5);
-            return (Token::Await, start, None);
-        }
-        if self.check_keyword(b"type") {
-            self.advance_n(4);
-            return (Token::Type, start, None);
-        }
-        if self.check_keyword(b"as") {
-            self.advance_n(2);
-            return (Token::As, start, None);
-        }
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DependencyKind {
+    /// `new URL("./x", import.meta.url)`
+    AssetUrl,
+    /// `new Worker(new URL("./x", import.meta.url))`
+    Worker,
+    /// `new SharedWorker(new URL("./x", import.meta.url))`
+    SharedWorker,
+    /// `navigator.serviceWorker.register(new URL("./x", import.meta.url))`
+    ServiceWorker,
+}
 
-        // String literals
-        if ch == b'"' || ch == b'\'' || ch == b'`' {
-            if let Some(s) = self.read_string() {
-                return (Token::StringLiteral, start, Some(s));
-            }
-        }
+/// A prologue directive such as `"use client"`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DirectiveInfo {
+    /// Text between the quotes, as written
+    pub value: String,
+    /// Start position, quotes included (0-indexed)
+    pub start: usize,
+    /// End position, quotes included (0-indexed)
+    pub end: usize,
+}
 
-        // Single character tokens
-        match ch {
-            b'(' => { self.advance(); return (Token::OpenParen, start, None); }
-            b')' => { self.advance(); return (Token::CloseParen, start, None); }
-            b'{' => { self.advance(); return (Token::OpenBrace, start, None); }
-            b'}' => { self.advance(); return (Token::CloseBrace, start, None); }
-            b';' => { self.advance(); return (Token::Semicolon, start, None); }
-            b',' => { self.advance(); return (Token::Comma, start, None); }
-            b'*' => { self.advance(); return (Token::Star, start, None); }
-            b'<' => {
-                // Check for JSX
-                if self.peek_n(1).is_ascii_alphabetic() || self.peek_n(1) == b'/' {
-                    self.advance();
-                    return (Token::JSXOpen, start, None);
-                }
-            }
-            _ => {}
+/// A `/*#__PURE__*/` or `/*@__NO_SIDE_EFFECTS__*/` comment
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnnotationInfo {
+    pub kind: AnnotationKind,
+    /// Start of the comment (0-indexed)
+    pub start: usize,
+    /// End of the comment (0-indexed)
+    pub end: usize,
+    /// Call or function the annotation applies to; `None` when it is
+    /// misplaced and has no effect
+    pub target_start: Option<usize>,
+    pub target_end: Option<usize>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AnnotationKind {
+    /// `#__PURE__`: the following call or `new` can be dropped if unused
+    Pure,
+    /// `#__NO_SIDE_EFFECTS__`: calls to the following function are pure
+    NoSideEffects,
+}
+
+/// Named exports of a CommonJS module, found the way Node's
+/// cjs-module-lexer finds them
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CjsExports {
+    /// Export names in first-seen order, `__esModule` included
+    pub exports: Vec<String>,
+    /// Modules re-exported whole (`module.exports = require("x")`)
+    pub reexports: Vec<String>,
+    /// Has an `__esModule` marker
+    pub is_es_module: bool,
+}
+
+/// Source language of a file
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Language {
+    Js,
+    Jsx,
+    Ts,
+    Tsx,
+    /// Declaration file (`.d.ts`)
+    Dts,
+}
+
+impl Language {
+    /// Language named by a hint such as `"tsx"`
+    pub fn from_hint(hint: &str) -> Option<Self> {
+        match hint.to_ascii_lowercase().as_str() {
+            "js" | "javascript" => Some(Language::Js),
+            "jsx" => Some(Language::Jsx),
+            "ts" | "typescript" => Some(Language::Ts),
+            "tsx" => Some(Language::Tsx),
+            "dts" | "d.ts" => Some(Language::Dts),
+            _ => None,
         }
+    }
+
+    /// Language implied by a file extension, `None` when it says nothing
+    pub fn from_filename(filename: &str) -> Option<Self> {
+        let name = filename.rsplit(['/', '\\']).next().unwrap_or(filename);
+        let (stem, ext) = name.rsplit_once('.')?;
+        let language = match ext.to_ascii_lowercase().as_str() {
+            // `x.d.ts`, and `x.d.css.ts` for arbitrary-extension declarations
+            "ts" | "mts" | "cts" if stem.ends_with(".d") || stem.contains(".d.") => Language::Dts,
+            "ts" | "mts" | "cts" => Language::Ts,
+            "tsx" => Language::Tsx,
+            "jsx" => Language::Jsx,
+            "js" | "mjs" | "cjs" => Language::Js,
+            _ => return None,
+        };
+        Some(language)
+    }
+
+    pub fn is_typescript(self) -> bool {
+        matches!(self, Language::Ts | Language::Tsx | Language::Dts)
+    }
 
-        // Identifier
-        if ch.is_ascii_alphabetic() || ch == b'_' || ch == b'$' {
-            let ident = self.read_identifier();
-            return (Token::Identifier, start, Some(ident));
+    /// `Js`/`Ts` become `Jsx`/`Tsx` once JSX has been seen
+    pub(crate) fn with_jsx(self, has_jsx: bool) -> Self {
+        match self {
+            Language::Js if has_jsx => Language::Jsx,
+            Language::Ts if has_jsx => Language::Tsx,
+            language => language,
         }
+    }
 
-        // Skip unknown character
-        self.advance();
-        (Token::Other, start, None)
+    /// Syntax to parse with; JSX is accepted in `.js` files too
+    pub(crate) fn syntax(self) -> js_parser::Syntax {
+        let (typescript, jsx) = match self {
+            Language::Js | Language::Jsx => (false, true),
+            Language::Ts | Language::Dts => (true, false),
+            Language::Tsx => (true, true),
+        };
+        js_parser::Syntax { typescript, jsx }
     }
 }
 
+/// Parse result
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ParseResult {
+    /// All imports found
+    pub imports: Vec<ImportInfo>,
+    /// All exports found; `export * from` is only listed in `imports`
+    pub exports: Vec<ExportInfo>,
+    /// Assets and workers referenced with `new URL(..., import.meta.url)`
+    pub dependencies: Vec<DependencyInfo>,
+    /// Module-level directives, in order
+    pub directives: Vec<DirectiveInfo>,
+    /// `#!` line, if any
+    pub hashbang: Option<String>,
+    /// Pure and no-side-effects annotations, in source order
+    pub annotations: Vec<AnnotationInfo>,
+    /// Has JSX syntax
+    pub has_jsx: bool,
+    /// Has top-level await
+    pub has_top_level_await: bool,
+    /// Is TypeScript (`ts`, `tsx` or `dts`)
+    pub is_typescript: bool,
+    /// Language from the filename, the hint, or the syntax seen
+    pub language: Language,
+    /// Every statement is erased when compiled (types, `declare`,
+    /// `import type`, ...), so the module can be skipped
+    pub is_type_only: bool,
+    /// Syntax errors, each with its position and a code frame
+    pub diagnostics: Vec<Diagnostic>,
+    /// Parse time in microseconds
+    pub parse_time_us: u64,
+}
+
 /// Main parser
 #[wasm_bindgen]
 pub struct Parser {
     // No state needed
 }
 
+impl Default for Parser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 #[wasm_bindgen]
 impl Parser {
     #[wasm_bindgen(constructor)]
@@ -294,9 +350,9 @@ impl Parser {
 
     /// Extract imports from source code
     #[wasm_bindgen]
-    pub fn extract_imports(&self, source: &str) -> JsValue {
+    pub fn extract_imports(&self, source: &str) -> Result<JsValue, JsError> {
         let result = extract_imports_internal(source);
-        serde_wasm_bindgen::to_value(&result).unwrap_or(JsValue::NULL)
+        to_js(&result)
     }
 
     /// Extract import sources only (faster, returns array of strings)
@@ -304,6 +360,7 @@ impl Parser {
     pub fn extract_import_sources(&self, source: &str) -> Vec<JsValue> {
         extract_imports_internal(source)
             .into_iter()
+            .filter(|i| !i.source.is_empty())
             .map(|i| JsValue::from_str(&i.source))
             .collect()
     }
@@ -313,6 +370,7 @@ impl Parser {
     pub fn extract_imports_fast(&self, source: &str) -> String {
         extract_imports_internal(source)
             .into_iter()
+            .filter(|i| !i.source.is_empty())
             .map(|i| i.source)
             .collect::<Vec<_>>()
             .join("\n")
@@ -330,309 +388,1053 @@ impl Parser {
         has_top_level_await_internal(source)
     }
 
-    /// Full parse
+    /// Named exports and re-exports of a CommonJS module
+    #[wasm_bindgen]
+    pub fn detect_cjs_exports(&self, source: &str) -> Result<JsValue, JsError> {
+        let result = detect_cjs_exports_internal(source);
+        Ok(serde_wasm_bindgen::to_value(&result)?)
+    }
+
+    /// Full parse; syntax errors are reported in `diagnostics`
     #[wasm_bindgen]
-    pub fn parse(&self, source: &str) -> JsValue {
+    pub fn parse(&self, source: &str) -> Result<JsValue, JsError> {
         let result = parse_internal(source);
-        serde_wasm_bindgen::to_value(&result).unwrap_or(JsValue::NULL)
+        to_js(&result)
+    }
+
+    /// Parse and keep per-statement results, so that edits can be applied
+    /// with `reparse`
+    #[wasm_bindgen]
+    pub fn parse_incremental(&self, source: &str, filename: Option<String>, language: Option<String>) -> ParseHandle {
+        ParseHandle::new(source, filename, language)
+    }
+
+    /// Apply an edit (a range of the previous source in UTF-16 code units, as
+    /// JavaScript string indices, and its replacement), reparsing only the
+    /// statements it touches
+    #[wasm_bindgen]
+    pub fn reparse(&self, handle: &mut ParseHandle, start: usize, end: usize, text: &str) -> Result<JsValue, JsError> {
+        let result = reparse_utf16_internal(handle, start, end, text).map_err(|e| JsError::new(&e))?;
+        to_js(&result)
     }
+
+    /// Full parse using the filename and an optional language hint
+    /// (`"js"`, `"jsx"`, `"ts"`, `"tsx"` or `"dts"`) to pick the syntax
+    #[wasm_bindgen]
+    pub fn parse_file(&self, source: &str, filename: &str, language: Option<String>) -> Result<JsValue, JsError> {
+        let result = parse_file_internal(source, Some(filename), language.as_deref());
+        to_js(&result)
+    }
+}
+
+/// Serialize maps (import attributes) as plain objects rather than `Map`s
+pub(crate) fn to_js<T: Serialize>(value: &T) -> Result<JsValue, JsError> {
+    Ok(value.serialize(&serde_wasm_bindgen::Serializer::json_compatible())?)
 }
 
 /// Extract all imports from source
 pub fn extract_imports_internal(source: &str) -> Vec<ImportInfo> {
-    let mut imports = Vec::new();
-    let mut lexer = Lexer::new(source);
-    
-    loop {
-        let (token, start, value) = lexer.next_token();
-        
-        match token {
-            Token::EOF => break,
-            
-            Token::Import => {
-                // import ... from 'source'
-                // import('source')
-                // import type ... from 'source'
-                let mut is_type_only = false;
-                let mut is_dynamic = false;
-                
-                let (next_token, _, next_value) = lexer.next_token();
-                
-                match next_token {
-                    Token::OpenParen => {
-                        // Dynamic import: import('source')
-                        is_dynamic = true;
-                        let (str_token, _, str_value) = lexer.next_token();
-                        if str_token == Token::StringLiteral {
-                            if let Some(source) = str_value {
-                                imports.push(ImportInfo {
-                                    source,
-                                    is_dynamic: true,
-                                    is_type_only: false,
-                                    start,
-                                    end: lexer.pos,
-                                });
-                            }
-                        }
+    let (program, _) = js_parser::parse(source, None);
+    collect_imports(&program, source)
+}
+
+/// Check if source contains JSX
+pub fn has_jsx_internal(source: &str) -> bool {
+    let (program, _) = js_parser::parse(source, None);
+    program_has_jsx(&program)
+}
+
+/// Check if source has top-level await
+pub fn has_top_level_await_internal(source: &str) -> bool {
+    let (program, _) = js_parser::parse(source, None);
+    program_has_top_level_await(&program)
+}
+
+/// Detect CommonJS exports
+pub fn detect_cjs_exports_internal(source: &str) -> CjsExports {
+    let program = js_parser::parse_program(source, js_parser::Syntax { typescript: false, jsx: false });
+    let mut finder = CjsExportFinder {
+        source,
+        requires: HashMap::new(),
+        result: CjsExports::default(),
+    };
+    walk_program(&mut finder, &program);
+    finder.result
+}
+
+/// Full parse
+pub fn parse_internal(source: &str) -> ParseResult {
+    parse_file_internal(source, None, None)
+}
+
+/// Full parse of a file; the language comes from `language` (a hint such as
+/// `"tsx"`), then the filename, then the syntax seen
+pub fn parse_file_internal(source: &str, filename: Option<&str>, language: Option<&str>) -> ParseResult {
+    let (program, language) = parse_classified(source, filename, language);
+    analyze_program(&program, source, language)
+}
+
+/// Everything `ParseResult` reports about a parsed program
+pub(crate) fn analyze_program(program: &Program, source: &str, language: Language) -> ParseResult {
+    let has_jsx = program_has_jsx(program);
+    let language = language.with_jsx(has_jsx);
+
+    ParseResult {
+        imports: collect_imports(program, source),
+        exports: collect_exports(program, source),
+        dependencies: collect_url_dependencies(program, source),
+        directives: collect_directives(program, source),
+        hashbang: program.hashbang.map(|span| source[span.start..span.end].to_string()),
+        annotations: collect_annotations(program, source),
+        has_jsx,
+        has_top_level_await: program_has_top_level_await(program),
+        is_typescript: language.is_typescript(),
+        language,
+        is_type_only: language == Language::Dts
+            || (!program.body.is_empty() && program.body.iter().all(Stmt::is_type_only)),
+        diagnostics: diagnostics::from_parse_errors(source, &program.errors),
+        parse_time_us: 0, // Time measurement not available in WASM
+    }
+}
+
+/// Parse with the language the hint or filename names. Otherwise parse as
+/// JavaScript, and take TypeScript only when that gives fewer errors.
+pub(crate) fn parse_classified(source: &str, filename: Option<&str>, hint: Option<&str>) -> (Program, Language) {
+    let named = hint.and_then(Language::from_hint).or_else(|| filename.and_then(Language::from_filename));
+    if let Some(language) = named {
+        return (js_parser::parse_program(source, language.syntax()), language);
+    }
+    let program = js_parser::parse_program(source, Language::Js.syntax());
+    if program.errors.is_empty() {
+        return (program, Language::Js);
+    }
+    let (retry, _) = js_parser::parse(source, None);
+    if retry.errors.len() < program.errors.len() {
+        (retry, Language::Ts)
+    } else {
+        (program, Language::Js)
+    }
+}
+
+/// Imports, re-exports, `import()` and `require()` calls, in source order
+pub(crate) fn collect_imports(program: &Program, source: &str) -> Vec<ImportInfo> {
+    let mut collector = ImportCollector {
+        source,
+        line_index: LineIndex::new(source),
+        comments: &program.comments,
+        imports: Vec::new(),
+    };
+    walk_program(&mut collector, program);
+    collector.imports
+}
+
+struct ImportCollector<'a> {
+    source: &'a str,
+    line_index: LineIndex,
+    comments: &'a [Comment],
+    imports: Vec<ImportInfo>,
+}
+
+impl ImportCollector<'_> {
+    fn push(
+        &mut self,
+        source: &ast::Lit,
+        kind: ImportKind,
+        is_type_only: bool,
+        specifiers: Vec<ImportSpecifierInfo>,
+        span: Span,
+    ) -> &mut ImportInfo {
+        let source = source.value.as_str().unwrap_or_default().to_string();
+        self.push_source(source, kind, is_type_only, specifiers, span)
+    }
+
+    fn push_source(
+        &mut self,
+        source: String,
+        kind: ImportKind,
+        is_type_only: bool,
+        specifiers: Vec<ImportSpecifierInfo>,
+        span: Span,
+    ) -> &mut ImportInfo {
+        let (line, column) = self.line_index.position(self.source, span.start);
+        self.imports.push(ImportInfo {
+            source,
+            kind,
+            specifiers,
+            is_dynamic: kind == ImportKind::Dynamic,
+            is_type_only,
+            attributes: BTreeMap::new(),
+            magic_comments: Vec::new(),
+            start: span.start,
+            end: span.end,
+            line: line + 1,
+            column: column + 1,
+        });
+        self.imports.last_mut().unwrap()
+    }
+
+    /// Entries of the block comments between `start` and `end`
+    fn magic_comments(&self, start: usize, end: usize) -> Vec<MagicComment> {
+        let first = self.comments.partition_point(|c| c.start < start);
+        self.comments[first..]
+            .iter()
+            .take_while(|c| c.end <= end)
+            .filter(|c| c.is_block)
+            .flat_map(|comment| {
+                split_top_level(comment.body(self.source)).into_iter().map(|entry| {
+                    let (key, value) = match entry.split_once(':') {
+                        Some((key, value)) => (key.trim(), Some(unquote(value.trim()))),
+                        None => (entry, None),
+                    };
+                    MagicComment {
+                        key: key.to_string(),
+                        value,
+                        start: comment.start,
+                        end: comment.end,
                     }
-                    Token::Type => {
-                        // import type { ... } from 'source'
-                        is_type_only = true;
-                        // Continue to find 'from'
-                        loop {
-                            let (t, _, v) = lexer.next_token();
-                            match t {
-                                Token::From => {
-                                    let (str_token, _, str_value) = lexer.next_token();
-                                    if str_token == Token::StringLiteral {
-                                        if let Some(source) = str_value {
-                                            imports.push(ImportInfo {
-                                                source,
-                                                is_dynamic: false,
-                                                is_type_only: true,
-                                                start,
-                                                end: lexer.pos,
-                                            });
-                                        }
-                                    }
-                                    break;
-                                }
-                                Token::EOF | Token::Semicolon => break,
-                                _ => continue,
+                })
+            })
+            .collect()
+    }
+}
+
+/// Split on commas outside of quotes and brackets, dropping empty entries
+fn split_top_level(text: &str) -> Vec<&str> {
+    let mut entries = Vec::new();
+    let mut depth = 0usize;
+    let mut quote = None;
+    let mut entry_start = 0;
+    let mut escaped = false;
+    for (i, c) in text.char_indices() {
+        match quote {
+            Some(_) if escaped => escaped = false,
+            Some(_) if c == '\\' => escaped = true,
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None => match c {
+                '"' | '\'' | '`' => quote = Some(c),
+                '[' | '{' | '(' => depth += 1,
+                ']' | '}' | ')' => depth = depth.saturating_sub(1),
+                ',' if depth == 0 => {
+                    entries.push(&text[entry_start..i]);
+                    entry_start = i + 1;
+                }
+                _ => {}
+            },
+        }
+    }
+    entries.push(&text[entry_start..]);
+    entries.into_iter().map(str::trim).filter(|e| !e.is_empty()).collect()
+}
+
+fn unquote(value: &str) -> String {
+    let quoted = value.len() >= 2
+        && matches!(value.as_bytes()[0], b'"' | b'\'' | b'`')
+        && value.as_bytes()[value.len() - 1] == value.as_bytes()[0];
+    if quoted {
+        crate::lexer::decode_escapes(&value[1..value.len() - 1])
+    } else {
+        value.to_string()
+    }
+}
+
+fn attribute_map(attributes: Option<&ast::ImportAttributes>) -> BTreeMap<String, String> {
+    attributes
+        .into_iter()
+        .flat_map(|attrs| &attrs.entries)
+        .map(|entry| (entry.key.name(), entry.value.value.as_str().unwrap_or_default().to_string()))
+        .collect()
+}
+
+/// `{ with: { type: "json" } }` (or `assert`) passed to `import()`;
+/// entries that are not string literals are skipped
+fn dynamic_import_attributes(options: &Expr) -> BTreeMap<String, String> {
+    let mut attributes = BTreeMap::new();
+    let Expr::Object(options) = unparen(options) else { return attributes };
+    for prop in &options.props {
+        let ast::Prop::KeyValue(option) = prop else { continue };
+        let Expr::Object(entries) = unparen(&option.value) else { continue };
+        if !(prop_key_is(&option.key, "with") || prop_key_is(&option.key, "assert")) {
+            continue;
+        }
+        for entry in &entries.props {
+            let ast::Prop::KeyValue(entry) = entry else { continue };
+            if let (Some(key), Expr::Lit(ast::Lit { value: ast::LitValue::Str(value), .. })) =
+                (prop_key_name(&entry.key), &entry.value)
+            {
+                attributes.insert(key.to_string(), value.clone());
+            }
+        }
+    }
+    attributes
+}
+
+fn specifier(kind: SpecifierKind, imported: String, local: String, is_type_only: bool) -> ImportSpecifierInfo {
+    ImportSpecifierInfo {
+        kind,
+        imported,
+        local,
+        is_type_only,
+    }
+}
+
+impl Visit for ImportCollector<'_> {
+    fn visit_stmt(&mut self, stmt: &Stmt) {
+        match stmt {
+            Stmt::Import(decl) => {
+                let specifiers = decl
+                    .specifiers
+                    .iter()
+                    .map(|spec| {
+                        let local = spec.local().name.clone();
+                        match spec {
+                            ast::ImportSpecifier::Default(_) => {
+                                specifier(SpecifierKind::Default, "default".to_string(), local, decl.type_only)
                             }
-                        }
-                    }
-                    Token::StringLiteral => {
-                        // import 'source' (side-effect import)
-                        if let Some(source) = next_value {
-                            imports.push(ImportInfo {
-                                source,
-                                is_dynamic: false,
-                                is_type_only: false,
-                                start,
-                                end: lexer.pos,
-                            });
-                        }
-                    }
-                    _ => {
-                        // import x from 'source'
-                        // import { x } from 'source'
-                        // import * as x from 'source'
-                        loop {
-                            let (t, _, v) = lexer.next_token();
-                            match t {
-                                Token::From => {
-                                    let (str_token, _, str_value) = lexer.next_token();
-                                    if str_token == Token::StringLiteral {
-                                        if let Some(source) = str_value {
-                                            imports.push(ImportInfo {
-                                                source,
-                                                is_dynamic: false,
-                                                is_type_only: false,
-                                                start,
-                                                end: lexer.pos,
-                                            });
-                                        }
-                                    }
-                                    break;
-                                }
-                                Token::EOF | Token::Semicolon => break,
-                                _ => continue,
+                            ast::ImportSpecifier::Namespace { .. } => {
+                                specifier(SpecifierKind::Namespace, "*".to_string(), local, decl.type_only)
+                            }
+                            ast::ImportSpecifier::Named { imported, type_only, .. } => {
+                                specifier(SpecifierKind::Named, imported.name(), local, decl.type_only || *type_only)
                             }
                         }
-                    }
+                    })
+                    .collect();
+                self.push(&decl.source, ImportKind::Import, decl.type_only, specifiers, decl.span)
+                    .attributes = attribute_map(decl.attributes.as_ref());
+            }
+            Stmt::ExportNamed(ExportNamed { span, type_only, specifiers, source: Some(source), attributes }) => {
+                let specifiers = specifiers
+                    .iter()
+                    .map(|spec| {
+                        let is_type_only = *type_only || spec.type_only;
+                        specifier(SpecifierKind::Named, spec.local.name(), spec.exported_name(), is_type_only)
+                    })
+                    .collect();
+                self.push(source, ImportKind::ReExport, *type_only, specifiers, *span).attributes =
+                    attribute_map(attributes.as_ref());
+            }
+            Stmt::ExportAll(decl) => {
+                let specifiers = decl
+                    .exported
+                    .iter()
+                    .map(|ns| specifier(SpecifierKind::Namespace, "*".to_string(), ns.name(), decl.type_only))
+                    .collect();
+                self.push(&decl.source, ImportKind::ExportAll, decl.type_only, specifiers, decl.span)
+                    .attributes = attribute_map(decl.attributes.as_ref());
+            }
+            Stmt::TsImportEquals(decl) => {
+                if let TsModuleRef::Require(source) = &decl.module_ref {
+                    let local = decl.id.name.clone();
+                    let specifiers = vec![specifier(SpecifierKind::Namespace, "*".to_string(), local, decl.type_only)];
+                    self.push(source, ImportKind::ImportEquals, decl.type_only, specifiers, decl.span);
                 }
             }
-            
-            Token::Export => {
-                // export { x } from 'source'
-                // export * from 'source'
-                // export default ...
-                loop {
-                    let (t, _, _v) = lexer.next_token();
-                    match t {
-                        Token::From => {
-                            let (str_token, _, str_value) = lexer.next_token();
-                            if str_token == Token::StringLiteral {
-                                if let Some(source) = str_value {
-                                    imports.push(ImportInfo {
-                                        source,
-                                        is_dynamic: false,
-                                        is_type_only: false,
-                                        start,
-                                        end: lexer.pos,
-                                    });
-                                }
-                            }
-                            break;
-                        }
-                        Token::EOF | Token::Semicolon => break,
-                        // Don't break on Identifier - continue looking for 'from'
-                        _ => continue,
-                    }
+            _ => walk_stmt(self, stmt),
+        }
+    }
+
+    fn visit_expr(&mut self, expr: &Expr) {
+        match expr {
+            Expr::Import(call) => {
+                // Computed sources are kept for their magic comments, like `@vite-ignore`
+                let source = call.source.as_static_string(self.source).unwrap_or_default();
+                let attributes = call.options.as_ref().map(dynamic_import_attributes).unwrap_or_default();
+                let magic_comments = self.magic_comments(call.span.start, call.source.span().start);
+                let import = self.push_source(source, ImportKind::Dynamic, false, Vec::new(), call.span);
+                import.attributes = attributes;
+                import.magic_comments = magic_comments;
+            }
+            Expr::Call(call) if is_require_call(call) => {
+                if let Some(source) = call.args[0].expr.as_static_string(self.source) {
+                    self.push_source(source, ImportKind::Require, false, Vec::new(), call.span);
                 }
             }
-            
-            Token::Require => {
-                // require('source')
-                let (paren_token, _, _) = lexer.next_token();
-                if paren_token == Token::OpenParen {
-                    let (str_token, _, str_value) = lexer.next_token();
-                    if str_token == Token::StringLiteral {
-                        if let Some(source) = str_value {
-                            imports.push(ImportInfo {
-                                source,
-                                is_dynamic: false,
-                                is_type_only: false,
-                                start,
-                                end: lexer.pos,
-                            });
-                        }
-                    }
+            _ => {}
+        }
+        walk_expr(self, expr);
+    }
+}
+
+/// Exported bindings of the top-level statements, in source order
+pub(crate) fn collect_exports(program: &Program, source: &str) -> Vec<ExportInfo> {
+    let line_index = LineIndex::new(source);
+    let mut exports = Vec::new();
+    let mut push = |kind, exported: String, local: Option<String>, module: Option<&ast::Lit>, is_type_only, span: Span| {
+        let (line, column) = line_index.position(source, span.start);
+        exports.push(ExportInfo {
+            kind,
+            exported,
+            local,
+            source: module.map(|lit| lit.value.as_str().unwrap_or_default().to_string()),
+            is_type_only,
+            start: span.start,
+            end: span.end,
+            line: line + 1,
+            column: column + 1,
+        });
+    };
+
+    for stmt in &program.body {
+        match stmt {
+            Stmt::ExportDecl(decl) => {
+                let is_type_only = decl.decl.is_type_only();
+                for id in declared_idents(&decl.decl) {
+                    push(ExportKind::Declaration, id.name.clone(), Some(id.name.clone()), None, is_type_only, id.span);
                 }
             }
-            
-            _ => continue,
+            Stmt::ExportDefault(decl) => {
+                let (local, is_type_only) = match &decl.decl {
+                    ast::DefaultDecl::Function(f) => (f.id.as_ref(), f.body.is_none()),
+                    ast::DefaultDecl::Class(c) => (c.id.as_ref(), false),
+                    ast::DefaultDecl::TsInterface(i) => (Some(&i.id), true),
+                    ast::DefaultDecl::Expr(Expr::Ident(id)) => (Some(id), false),
+                    ast::DefaultDecl::Expr(_) => (None, false),
+                };
+                let local = local.map(|id| id.name.clone());
+                push(ExportKind::Default, "default".to_string(), local, None, is_type_only, decl.span);
+            }
+            Stmt::ExportNamed(decl) => {
+                let kind = if decl.source.is_some() { ExportKind::ReExport } else { ExportKind::Named };
+                for spec in &decl.specifiers {
+                    let is_type_only = decl.type_only || spec.type_only;
+                    let local = Some(spec.local.name());
+                    push(kind, spec.exported_name(), local, decl.source.as_ref(), is_type_only, spec.span);
+                }
+            }
+            Stmt::ExportAll(decl) => {
+                if let Some(exported) = &decl.exported {
+                    let span = exported.span();
+                    push(ExportKind::Namespace, exported.name(), None, Some(&decl.source), decl.type_only, span);
+                }
+            }
+            Stmt::TsImportEquals(decl) if decl.is_export => {
+                let id = &decl.id;
+                push(ExportKind::Declaration, id.name.clone(), Some(id.name.clone()), None, decl.type_only, id.span);
+            }
+            Stmt::TsExportAssignment(decl) => {
+                let local = match &decl.expr {
+                    Expr::Ident(id) => Some(id.name.clone()),
+                    _ => None,
+                };
+                push(ExportKind::Assignment, "default".to_string(), local, None, false, decl.span);
+            }
+            _ => {}
         }
     }
-    
-    imports
+    exports
 }
 
-/// Check if source contains JSX
-pub fn has_jsx_internal(source: &str) -> bool {
-    let bytes = source.as_bytes();
-    let len = bytes.len();
-    let mut i = 0;
-    
-    while i < len {
-        // Skip strings
-        if bytes[i] == b'"' || bytes[i] == b'\'' || bytes[i] == b'`' {
-            let quote = bytes[i];
-            i += 1;
-            while i < len && bytes[i] != quote {
-                if bytes[i] == b'\\' { i += 1; }
-                i += 1;
+/// Bindings a declaration introduces, including type-level ones
+fn declared_idents(decl: &Stmt) -> Vec<&ast::Ident> {
+    match decl {
+        Stmt::Var(d) => d.decls.iter().flat_map(|d| d.name.bound_names()).collect(),
+        Stmt::Function(Function { id: Some(id), .. }) => vec![id],
+        Stmt::Class(ast::Class { id: Some(id), .. }) => vec![id],
+        Stmt::TsEnum(e) => vec![&e.id],
+        Stmt::TsInterface(i) => vec![&i.id],
+        Stmt::TsTypeAlias(t) => vec![&t.id],
+        Stmt::TsModule(ast::TsModuleDecl { name: ast::TsModuleName::Path(path), .. }) => path.iter().take(1).collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Like cjs-module-lexer this matches patterns anywhere in the file, so
+/// UMD wrappers and transpiler helpers are covered, and ignores shadowing.
+struct CjsExportFinder<'a> {
+    source: &'a str,
+    /// `var _x = require("./x")` bindings, for Babel's `export *` loop
+    requires: HashMap<String, String>,
+    result: CjsExports,
+}
+
+impl CjsExportFinder<'_> {
+    fn add_export(&mut self, name: &str) {
+        if name == "__esModule" {
+            self.result.is_es_module = true;
+        }
+        if !self.result.exports.iter().any(|e| e == name) {
+            self.result.exports.push(name.to_string());
+        }
+    }
+
+    fn add_reexport(&mut self, expr: &Expr) -> bool {
+        let Expr::Call(call) = unparen(expr) else { return false };
+        if !is_require_call(call) {
+            return false;
+        }
+        match call.args[0].expr.as_static_string(self.source) {
+            Some(source) => {
+                if !self.result.reexports.contains(&source) {
+                    self.result.reexports.push(source);
+                }
+                true
             }
-            i += 1;
-            continue;
+            None => false,
         }
-        
-        // Skip comments
-        if bytes[i] == b'/' {
-            if i + 1 < len && bytes[i + 1] == b'/' {
-                while i < len && bytes[i] != b'\n' { i += 1; }
-                continue;
+    }
+
+    /// `module.exports = { a, b: c, ...require("x") }`. Stops at the first
+    /// property whose value is not a plain reference, as the lexer does.
+    fn object_exports(&mut self, object: &ast::ObjectLit) {
+        for prop in &object.props {
+            match prop {
+                ast::Prop::Shorthand(id) => self.add_export(&id.name),
+                ast::Prop::KeyValue(kv) if is_reference(&kv.value) => match &kv.key {
+                    ast::PropKey::Ident(id) => self.add_export(&id.name),
+                    ast::PropKey::Str(ast::Lit { value: ast::LitValue::Str(name), .. }) => self.add_export(name),
+                    _ => break,
+                },
+                ast::Prop::Spread(spread) if self.add_reexport(&spread.expr) => {}
+                _ => break,
             }
-            if i + 1 < len && bytes[i + 1] == b'*' {
-                i += 2;
-                while i + 1 < len && !(bytes[i] == b'*' && bytes[i + 1] == b'/') { i += 1; }
-                i += 2;
-                continue;
+        }
+    }
+
+    /// `Object.defineProperty(exports, "x", { value })` or a getter that
+    /// returns a plain reference
+    fn define_property(&mut self, call: &CallExpr) {
+        let [target, name, descriptor] = call.args.as_slice() else { return };
+        let name = name.expr.as_static_string(self.source);
+        let (Some(name), Expr::Object(descriptor)) = (name, unparen(&descriptor.expr)) else {
+            return;
+        };
+        if !is_exports_object(&target.expr) {
+            return;
+        }
+        let exported = descriptor.props.iter().any(|prop| match prop {
+            ast::Prop::KeyValue(kv) if prop_key_is(&kv.key, "value") => true,
+            ast::Prop::KeyValue(kv) if prop_key_is(&kv.key, "get") => match unparen(&kv.value) {
+                Expr::Function(f) => f.body.as_ref().is_some_and(returns_reference),
+                Expr::Arrow(a) => match &a.body {
+                    ast::ArrowBody::Block(body) => returns_reference(body),
+                    ast::ArrowBody::Expr(expr) => is_reference(expr),
+                },
+                _ => false,
+            },
+            ast::Prop::Method(m) if prop_key_is(&m.key, "get") => {
+                m.function.body.as_ref().is_some_and(returns_reference)
             }
+            _ => false,
+        });
+        if exported {
+            self.add_export(&name);
         }
-        
-        // Check for JSX: <Component or </Component or <>
-        if bytes[i] == b'<' {
-            if i + 1 < len {
-                let next = bytes[i + 1];
-                // <Component (uppercase)
-                if next.is_ascii_uppercase() {
-                    return true;
+    }
+
+    /// Babel's `export * from "./x"`: `Object.keys(_x).forEach(function (key) { ... })`
+    /// copying each key to `exports[key]` or defining it on `exports`
+    fn for_each_reexport(&mut self, call: &CallExpr) {
+        let Expr::Member(member) = unparen(&call.callee) else { return };
+        let Expr::Call(keys) = unparen(&member.object) else { return };
+        let [callback] = call.args.as_slice() else { return };
+        if callee_name(&keys.callee) != Some("keys") || !is_member_of(&keys.callee, "Object") {
+            return;
+        }
+        let object = keys.args.first().and_then(|a| a.expr.as_ident());
+        let Some(source) = object.and_then(|id| self.requires.get(&id.name)) else {
+            return;
+        };
+        let params = match unparen(&callback.expr) {
+            Expr::Function(f) => &f.params,
+            Expr::Arrow(a) => &a.params,
+            _ => return,
+        };
+        let Some(ast::Pat::Ident(key)) = params.first().map(|p| &p.pat) else { return };
+        let mut copies = KeyCopyFinder { key: &key.id.name, found: false };
+        walk_expr(&mut copies, &callback.expr);
+        if copies.found && !self.result.reexports.contains(source) {
+            self.result.reexports.push(source.clone());
+        }
+    }
+}
+
+/// Whether a callback copies its `key` parameter onto `exports`
+struct KeyCopyFinder<'a> {
+    key: &'a str,
+    found: bool,
+}
+
+impl KeyCopyFinder<'_> {
+    fn is_key(&self, expr: &Expr) -> bool {
+        unparen(expr).as_ident().is_some_and(|id| id.name == self.key)
+    }
+}
+
+impl Visit for KeyCopyFinder<'_> {
+    fn visit_expr(&mut self, expr: &Expr) {
+        match expr {
+            // `exports[key] = _x[key]`
+            Expr::Assign(assign) if assign.op == ast::AssignOp::Assign => {
+                if let ast::Pat::Expr(target) = &assign.left {
+                    if let Expr::Member(m) = unparen(target) {
+                        if let ast::MemberProp::Computed(key) = &m.property {
+                            self.found |= is_exports_object(&m.object) && self.is_key(&key.expr);
+                        }
+                    }
                 }
-                // </
-                if next == b'/' {
-                    return true;
+            }
+            // `Object.defineProperty(exports, key, { get })`
+            Expr::Call(call)
+                if callee_name(&call.callee) == Some("defineProperty") && is_member_of(&call.callee, "Object") =>
+            {
+                if let [target, key, ..] = call.args.as_slice() {
+                    self.found |= is_exports_object(&target.expr) && self.is_key(&key.expr);
+                }
+            }
+            _ => {}
+        }
+        walk_expr(self, expr);
+    }
+}
+
+impl Visit for CjsExportFinder<'_> {
+    fn visit_stmt(&mut self, stmt: &Stmt) {
+        if let Stmt::Var(var) = stmt {
+            for decl in &var.decls {
+                let (ast::Pat::Ident(name), Some(Expr::Call(call))) = (&decl.name, decl.init.as_ref().map(unparen))
+                else {
+                    continue;
+                };
+                if !is_require_call(call) {
+                    continue;
                 }
-                // <> (fragment)
-                if next == b'>' {
-                    return true;
+                if let Some(source) = call.args[0].expr.as_static_string(self.source) {
+                    self.requires.insert(name.id.name.clone(), source);
                 }
-                // Check for lowercase tags that aren't comparison
-                if next.is_ascii_lowercase() {
-                    // Look ahead for > or attributes
-                    let mut j = i + 2;
-                    while j < len && (bytes[j].is_ascii_alphanumeric() || bytes[j] == b'-') {
-                        j += 1;
-                    }
-                    // Skip whitespace
-                    while j < len && (bytes[j] == b' ' || bytes[j] == b'\t') {
-                        j += 1;
+            }
+        }
+        walk_stmt(self, stmt);
+    }
+
+    fn visit_expr(&mut self, expr: &Expr) {
+        match expr {
+            Expr::Assign(assign) if assign.op == ast::AssignOp::Assign => {
+                if let ast::Pat::Expr(target) = &assign.left {
+                    if let Some(name) = exports_member(target) {
+                        self.add_export(name);
+                    } else if is_module_exports(target) {
+                        match unparen(&assign.right) {
+                            Expr::Object(object) => self.object_exports(object),
+                            right => {
+                                self.add_reexport(right);
+                            }
+                        }
                     }
-                    // If followed by > or attribute, it's JSX
-                    if j < len && (bytes[j] == b'>' || bytes[j] == b'/' || bytes[j].is_ascii_alphabetic()) {
-                        return true;
+                }
+            }
+            Expr::Call(call) => match callee_name(&call.callee) {
+                Some("defineProperty") if is_member_of(&call.callee, "Object") => self.define_property(call),
+                // TypeScript's `__exportStar(require("x"), exports)` and the older `__export(require("x"))`
+                Some("__exportStar" | "__export") if !call.args.is_empty() => {
+                    self.add_reexport(&call.args[0].expr);
+                }
+                Some("forEach") => self.for_each_reexport(call),
+                _ => {}
+            },
+            _ => {}
+        }
+        walk_expr(self, expr);
+    }
+}
+
+fn unparen(mut expr: &Expr) -> &Expr {
+    while let Expr::Paren(paren) = expr {
+        expr = &paren.expr;
+    }
+    expr
+}
+
+/// `exports` or `module.exports`
+fn is_exports_object(expr: &Expr) -> bool {
+    match unparen(expr) {
+        Expr::Ident(id) => id.name == "exports",
+        expr => is_module_exports(expr),
+    }
+}
+
+fn is_module_exports(expr: &Expr) -> bool {
+    matches!(unparen(expr), Expr::Member(m)
+        if m.property.static_name() == Some("exports")
+            && m.object.as_ident().is_some_and(|id| id.name == "module"))
+}
+
+/// `"foo"` in `exports.foo` / `module.exports["foo"]`
+fn exports_member(expr: &Expr) -> Option<&str> {
+    match unparen(expr) {
+        Expr::Member(m) if is_exports_object(&m.object) => m.property.static_name(),
+        _ => None,
+    }
+}
+
+/// Called function name: `f` in `f()` and `a.f()`
+fn callee_name(callee: &Expr) -> Option<&str> {
+    match unparen(callee) {
+        Expr::Ident(id) => Some(&id.name),
+        Expr::Member(m) => m.property.static_name(),
+        _ => None,
+    }
+}
+
+fn is_member_of(callee: &Expr, object: &str) -> bool {
+    matches!(unparen(callee), Expr::Member(m) if m.object.as_ident().is_some_and(|id| id.name == object))
+}
+
+/// Name of an identifier or string-literal property key
+fn prop_key_name(key: &ast::PropKey) -> Option<&str> {
+    match key {
+        ast::PropKey::Ident(id) => Some(&id.name),
+        ast::PropKey::Str(lit) => lit.value.as_str(),
+        _ => None,
+    }
+}
+
+fn prop_key_is(key: &ast::PropKey, name: &str) -> bool {
+    prop_key_name(key) == Some(name)
+}
+
+/// An identifier or a member chain off one
+fn is_reference(expr: &Expr) -> bool {
+    match unparen(expr) {
+        Expr::Ident(_) => true,
+        Expr::Member(m) => !matches!(m.property, ast::MemberProp::PrivateName(_)) && is_reference(&m.object),
+        _ => false,
+    }
+}
+
+fn returns_reference(body: &ast::BlockStmt) -> bool {
+    matches!(body.stmts.as_slice(), [Stmt::Return(ast::ReturnStmt { arg: Some(arg), .. })] if is_reference(arg))
+}
+
+/// `require("x")` with a single argument
+fn is_require_call(call: &CallExpr) -> bool {
+    call.args.len() == 1
+        && call.args[0].spread.is_none()
+        && call.callee.as_ident().is_some_and(|id| id.name == "require")
+}
+
+/// `new URL("./x", import.meta.url)` references, and workers created from them
+pub(crate) fn collect_url_dependencies(program: &Program, source: &str) -> Vec<DependencyInfo> {
+    let mut collector = UrlDependencyCollector {
+        source,
+        line_index: LineIndex::new(source),
+        dependencies: Vec::new(),
+    };
+    walk_program(&mut collector, program);
+    collector.dependencies
+}
+
+struct UrlDependencyCollector<'a> {
+    source: &'a str,
+    line_index: LineIndex,
+    dependencies: Vec<DependencyInfo>,
+}
+
+impl UrlDependencyCollector<'_> {
+    /// Records a worker or service worker whose first argument is a module
+    /// URL; the rest of the arguments are still visited
+    fn worker(&mut self, kind: DependencyKind, span: Span, args: &[ast::ExprOrSpread]) -> bool {
+        let Some((url, rest)) = args.split_first() else { return false };
+        let Some(specifier) = url.spread.is_none().then(|| module_url(&url.expr)).flatten() else { return false };
+        let is_module = rest.first().is_some_and(|options| is_module_worker_options(&options.expr));
+        self.push(kind, span, specifier, is_module);
+        for arg in rest {
+            self.visit_expr(&arg.expr);
+        }
+        true
+    }
+
+    fn push(&mut self, kind: DependencyKind, span: Span, specifier: &ast::Lit, is_module: bool) {
+        let (line, column) = self.line_index.position(self.source, span.start);
+        self.dependencies.push(DependencyInfo {
+            kind,
+            specifier: specifier.value.as_str().unwrap_or_default().to_string(),
+            start: span.start,
+            end: span.end,
+            specifier_start: specifier.span.start,
+            specifier_end: specifier.span.end,
+            line: line + 1,
+            column: column + 1,
+            is_module,
+        });
+    }
+}
+
+impl Visit for UrlDependencyCollector<'_> {
+    fn visit_expr(&mut self, expr: &Expr) {
+        match expr {
+            Expr::New(new) => {
+                let args = new.args.as_deref().unwrap_or_default();
+                let kind = match new.callee.as_ident().map(|id| id.name.as_str()) {
+                    Some("Worker") => Some(DependencyKind::Worker),
+                    Some("SharedWorker") => Some(DependencyKind::SharedWorker),
+                    _ => None,
+                };
+                if let Some(kind) = kind {
+                    if self.worker(kind, new.span, args) {
+                        return;
                     }
                 }
+                if let Some(specifier) = module_url(expr) {
+                    self.push(DependencyKind::AssetUrl, new.span, specifier, false);
+                    return;
+                }
+            }
+            Expr::Call(call)
+                if is_service_worker_register(&call.callee)
+                    && self.worker(DependencyKind::ServiceWorker, call.span, &call.args) =>
+            {
+                return;
             }
+            _ => {}
         }
-        
-        i += 1;
+        walk_expr(self, expr);
     }
-    
-    false
 }
 
-/// Check if source has top-level await
-pub fn has_top_level_await_internal(source: &str) -> bool {
-    let bytes = source.as_bytes();
-    let len = bytes.len();
-    let mut i = 0;
-    let mut brace_depth: i32 = 0;
-    let mut paren_depth: i32 = 0;
-    
-    while i < len {
-        // Skip strings
-        if bytes[i] == b'"' || bytes[i] == b'\'' || bytes[i] == b'`' {
-            let quote = bytes[i];
-            i += 1;
-            while i < len && bytes[i] != quote {
-                if bytes[i] == b'\\' { i += 1; }
-                i += 1;
+/// The string literal in `new URL("./x", import.meta.url)`
+fn module_url(expr: &Expr) -> Option<&ast::Lit> {
+    let Expr::New(new) = unparen(expr) else { return None };
+    if new.callee.as_ident().map_or(true, |id| id.name != "URL") {
+        return None;
+    }
+    let [specifier, base] = new.args.as_deref()? else { return None };
+    let is_import_meta_url = matches!(unparen(&base.expr), Expr::Member(m)
+        if m.property.static_name() == Some("url")
+            && matches!(m.object, Expr::MetaProp(ast::MetaProp { kind: ast::MetaPropKind::ImportMeta, .. })));
+    match unparen(&specifier.expr) {
+        Expr::Lit(lit @ ast::Lit { value: ast::LitValue::Str(_), .. })
+            if is_import_meta_url && specifier.spread.is_none() =>
+        {
+            Some(lit)
+        }
+        _ => None,
+    }
+}
+
+/// `navigator.serviceWorker.register` or `serviceWorker.register`
+fn is_service_worker_register(callee: &Expr) -> bool {
+    let Expr::Member(register) = unparen(callee) else { return false };
+    if register.property.static_name() != Some("register") {
+        return false;
+    }
+    match unparen(&register.object) {
+        Expr::Ident(id) => id.name == "serviceWorker",
+        Expr::Member(m) => m.property.static_name() == Some("serviceWorker"),
+        _ => false,
+    }
+}
+
+/// `{ type: "module" }`
+fn is_module_worker_options(options: &Expr) -> bool {
+    let Expr::Object(options) = unparen(options) else { return false };
+    options.props.iter().any(|prop| {
+        matches!(prop, ast::Prop::KeyValue(kv)
+            if prop_key_is(&kv.key, "type") && matches!(&kv.value, Expr::Lit(lit) if lit.value.as_str() == Some("module")))
+    })
+}
+
+/// String-literal statements at the start of the module
+pub(crate) fn collect_directives(program: &Program, source: &str) -> Vec<DirectiveInfo> {
+    program.body.iter().map_while(|stmt| directive(stmt, source)).collect()
+}
+
+/// The statement as a directive, if it is a lone string literal
+pub(crate) fn directive(stmt: &Stmt, source: &str) -> Option<DirectiveInfo> {
+    match stmt {
+        Stmt::Expr(ast::ExprStmt { expr: Expr::Lit(lit @ ast::Lit { value: ast::LitValue::Str(_), .. }), .. }) => {
+            let raw = &source[lit.span.start..lit.span.end];
+            // An unterminated string has no closing quote
+            let value = raw[1..].strip_suffix(&raw[..1]).unwrap_or(&raw[1..]);
+            Some(DirectiveInfo {
+                value: value.to_string(),
+                start: lit.span.start,
+                end: lit.span.end,
+            })
+        }
+        _ => None,
+    }
+}
+
+/// Annotation comments, each matched with the node starting right after it
+pub(crate) fn collect_annotations(program: &Program, source: &str) -> Vec<AnnotationInfo> {
+    let annotations: Vec<_> = program
+        .comments
+        .iter()
+        .filter(|c| c.is_block)
+        .filter_map(|comment| {
+            let body = comment.body(source).trim();
+            let kind = match body.strip_prefix(['#', '@'])? {
+                "__PURE__" => AnnotationKind::Pure,
+                "__NO_SIDE_EFFECTS__" => AnnotationKind::NoSideEffects,
+                _ => return None,
+            };
+            Some((kind, comment))
+        })
+        .collect();
+    if annotations.is_empty() {
+        return Vec::new();
+    }
+
+    let mut targets = AnnotationTargets::default();
+    walk_program(&mut targets, program);
+    annotations
+        .into_iter()
+        .map(|(kind, comment)| {
+            let next = next_token_start(source, &program.comments, comment.end);
+            let target = match kind {
+                AnnotationKind::Pure => targets.calls.get(&next),
+                AnnotationKind::NoSideEffects => targets.functions.get(&next),
+            };
+            AnnotationInfo {
+                kind,
+                start: comment.start,
+                end: comment.end,
+                target_start: target.map(|span| span.start),
+                target_end: target.map(|span| span.end),
             }
-            i += 1;
-            continue;
+        })
+        .collect()
+}
+
+/// Outermost calls and function declarations by start position
+#[derive(Default)]
+struct AnnotationTargets {
+    calls: HashMap<usize, Span>,
+    functions: HashMap<usize, Span>,
+}
+
+impl Visit for AnnotationTargets {
+    fn visit_stmt(&mut self, stmt: &Stmt) {
+        if declares_function(stmt) {
+            self.functions.entry(stmt.span().start).or_insert(stmt.span());
         }
-        
-        // Track braces
-        if bytes[i] == b'{' { brace_depth += 1; }
-        if bytes[i] == b'}' { brace_depth = brace_depth.saturating_sub(1); }
-        if bytes[i] == b'(' { paren_depth += 1; }
-        if bytes[i] == b')' { paren_depth = paren_depth.saturating_sub(1); }
-        
-        // Check for 'await' at top level (brace_depth == 0)
-        if brace_depth == 0 && i + 5 <= len {
-            if &bytes[i..i+5] == b"await" {
-                // Make sure it's not part of a larger word
-                let before_ok = i == 0 || !bytes[i-1].is_ascii_alphanumeric();
-                let after_ok = i + 5 >= len || !bytes[i+5].is_ascii_alphanumeric();
-                if before_ok && after_ok {
-                    return true;
-                }
+        walk_stmt(self, stmt);
+    }
+
+    fn visit_expr(&mut self, expr: &Expr) {
+        match expr {
+            Expr::Call(_) | Expr::New(_) => {
+                self.calls.entry(expr.span().start).or_insert(expr.span());
             }
+            Expr::Function(_) | Expr::Arrow(_) => {
+                self.functions.entry(expr.span().start).or_insert(expr.span());
+            }
+            _ => {}
         }
-        
-        i += 1;
+        walk_expr(self, expr);
     }
-    
-    false
 }
 
-/// Full parse
-pub fn parse_internal(source: &str) -> ParseResult {
-    let imports = extract_imports_internal(source);
-    let has_jsx = has_jsx_internal(source);
-    let has_top_level_await = has_top_level_await_internal(source);
-    let is_typescript = source.contains(": ") || source.contains("interface ") || 
-                        source.contains("type ") || source.contains("<T>");
-    
-    ParseResult {
-        imports,
-        has_jsx,
-        has_top_level_await,
-        is_typescript,
-        parse_time_us: 0, // Time measurement not available in WASM
+fn declares_function(stmt: &Stmt) -> bool {
+    match stmt {
+        Stmt::Function(_) => true,
+        Stmt::Var(decl) => decl
+            .decls
+            .iter()
+            .any(|d| matches!(d.init.as_ref().map(unparen), Some(Expr::Function(_) | Expr::Arrow(_)))),
+        Stmt::ExportDecl(decl) => declares_function(&decl.decl),
+        Stmt::ExportDefault(decl) => {
+            matches!(decl.decl, ast::DefaultDecl::Function(_) | ast::DefaultDecl::Expr(Expr::Arrow(_)))
+        }
+        _ => false,
+    }
+}
+
+/// First position at or after `pos` that is neither whitespace nor a comment
+fn next_token_start(source: &str, comments: &[Comment], mut pos: usize) -> usize {
+    loop {
+        pos += source[pos..].len() - source[pos..].trim_start().len();
+        let next = comments.partition_point(|c| c.start < pos);
+        match comments.get(next) {
+            Some(comment) if comment.start == pos => pos = comment.end,
+            _ => return pos,
+        }
+    }
+}
+
+fn program_has_jsx(program: &Program) -> bool {
+    program.body.iter().any(stmt_has_jsx)
+}
+
+pub(crate) fn stmt_has_jsx(stmt: &Stmt) -> bool {
+    struct JsxFinder(bool);
+
+    impl Visit for JsxFinder {
+        fn visit_stmt(&mut self, stmt: &Stmt) {
+            if !self.0 {
+                walk_stmt(self, stmt);
+            }
+        }
+
+        fn visit_jsx_element(&mut self, _: &JsxElement) {
+            self.0 = true;
+        }
+
+        fn visit_jsx_fragment(&mut self, _: &JsxFragment) {
+            self.0 = true;
+        }
+    }
+
+    let mut finder = JsxFinder(false);
+    finder.visit_stmt(stmt);
+    finder.0
+}
+
+fn program_has_top_level_await(program: &Program) -> bool {
+    program.body.iter().any(stmt_has_top_level_await)
+}
+
+pub(crate) fn stmt_has_top_level_await(stmt: &Stmt) -> bool {
+    /// Looks for `await`, `for await` and `await using` outside of function
+    /// bodies, class field initializers and static blocks
+    struct AwaitFinder(bool);
+
+    impl Visit for AwaitFinder {
+        fn visit_stmt(&mut self, stmt: &Stmt) {
+            match stmt {
+                Stmt::ForOf(ast::ForOfStmt { is_await: true, .. }) => self.0 = true,
+                Stmt::Var(ast::VarDecl { kind: ast::VarKind::AwaitUsing, .. }) => self.0 = true,
+                _ if !self.0 => walk_stmt(self, stmt),
+                _ => {}
+            }
+        }
+
+        fn visit_expr(&mut self, expr: &Expr) {
+            if let Expr::Await(_) = expr {
+                self.0 = true;
+            } else if !self.0 {
+                walk_expr(self, expr);
+            }
+        }
+
+        fn visit_function(&mut self, _: &Function) {}
+
+        fn visit_arrow(&mut self, _: &ArrowFunction) {}
+
+        /// Decorators, `extends` and computed keys run in the enclosing scope
+        fn visit_class(&mut self, class: &ast::Class) {
+            for decorator in &class.decorators {
+                self.visit_expr(&decorator.expr);
+            }
+            if let Some(super_class) = &class.super_class {
+                self.visit_expr(super_class);
+            }
+            for member in &class.body {
+                let (decorators, key) = match member {
+                    ast::ClassMember::Method(m) => (&m.decorators, &m.key),
+                    ast::ClassMember::Property(p) => (&p.decorators, &p.key),
+                    _ => continue,
+                };
+                decorators.iter().for_each(|d| self.visit_expr(&d.expr));
+                walk_prop_key(self, key);
+            }
+        }
     }
+
+    let mut finder = AwaitFinder(false);
+    finder.visit_stmt(stmt);
+    finder.0
 }
 
 #[cfg(test)]
@@ -703,6 +1505,17 @@ mod tests {
         assert!(!has_top_level_await_internal("async function f() { await x }"));
     }
 
+    #[test]
+    fn test_top_level_await_scopes() {
+        assert!(!has_top_level_await_internal("const f = async () => await x;"));
+        assert!(!has_top_level_await_internal("class A { x = async () => await y; static { } async m() { await z } }"));
+        assert!(!has_top_level_await_internal("const s = `${async () => await a}`; const r = /await x/; // await y"));
+        assert!(has_top_level_await_internal("if (ready) { for await (const chunk of stream) {} }"));
+        assert!(has_top_level_await_internal("class A { [await key]() {} }"));
+        assert!(has_top_level_await_internal("const s = `${await load()}`;"));
+        assert!(has_top_level_await_internal("await using res = open();"));
+    }
+
     #[test]
     fn test_multiple_imports() {
         let source = r#"
@@ -715,4 +1528,286 @@ mod tests {
         let imports = extract_imports_internal(source);
         assert_eq!(imports.len(), 5);
     }
+
+    #[test]
+    fn test_ignores_regex_and_strings() {
+        let source = r#"
+            const re = /import 'x'/;
+            const s = "require('y')";
+            // import 'z'
+            const t = `${require('a')}`;
+        "#;
+        let imports = extract_imports_internal(source);
+        assert_eq!(imports.len(), 1);
+        assert_eq!(imports[0].source, "a");
+    }
+
+    #[test]
+    fn test_unicode_identifiers() {
+        let source = "const café = require('./café');\nimport ñ from 'ñ';";
+        let sources: Vec<_> = extract_imports_internal(source).into_iter().map(|i| i.source).collect();
+        assert_eq!(sources, vec!["./café", "ñ"]);
+    }
+
+    #[test]
+    fn test_jsx_generic_and_comparison() {
+        assert!(!has_jsx_internal("const f = <T,>(x: T) => x;"));
+        assert!(!has_jsx_internal("if (a <b) { c = d > e }"));
+        assert!(has_jsx_internal("const el = <div className=\"a\">{x > 1}</div>;"));
+    }
+
+    #[test]
+    fn test_import_positions() {
+        let source = "// 😀\n  import a from 'a';";
+        let imports = extract_imports_internal(source);
+        assert_eq!((imports[0].line, imports[0].column), (2, 3));
+    }
+
+    #[test]
+    fn test_import_specifiers() {
+        let source = "import React, { useState as useS, type FC } from 'react';\nimport * as path from 'path';";
+        let imports = extract_imports_internal(source);
+        let specs: Vec<_> = imports[0]
+            .specifiers
+            .iter()
+            .map(|s| (s.kind, s.imported.as_str(), s.local.as_str(), s.is_type_only))
+            .collect();
+        assert_eq!(
+            specs,
+            vec![
+                (SpecifierKind::Default, "default", "React", false),
+                (SpecifierKind::Named, "useState", "useS", false),
+                (SpecifierKind::Named, "FC", "FC", true),
+            ]
+        );
+        assert_eq!(imports[1].specifiers[0].kind, SpecifierKind::Namespace);
+        assert_eq!(imports[1].specifiers[0].local, "path");
+    }
+
+    #[test]
+    fn test_export_star_vs_reexport() {
+        let source = "export * from './a';\nexport * as ns from './b';\nexport { x as y } from './c';";
+        let imports = extract_imports_internal(source);
+        assert_eq!(imports[0].kind, ImportKind::ExportAll);
+        assert!(imports[0].specifiers.is_empty());
+        assert_eq!(imports[1].kind, ImportKind::ExportAll);
+        assert_eq!(imports[1].specifiers[0].local, "ns");
+        assert_eq!(imports[2].kind, ImportKind::ReExport);
+        assert_eq!(imports[2].specifiers[0].imported, "x");
+        assert_eq!(imports[2].specifiers[0].local, "y");
+    }
+
+    #[test]
+    fn test_import_attributes() {
+        let source = r#"
+import data from './data.json' with { type: 'json' };
+import sheet from './a.css' assert { type: "css" };
+export { x } from './x.json' with { type: 'json' };
+const mod = await import('./b.json', { with: { type: 'json' } });
+import plain from './plain';
+"#;
+        let imports = extract_imports_internal(source);
+        let types: Vec<_> = imports.iter().map(|i| i.attributes.get("type").map(String::as_str)).collect();
+        assert_eq!(types, vec![Some("json"), Some("css"), Some("json"), Some("json"), None]);
+        assert!(imports[4].attributes.is_empty());
+    }
+
+    #[test]
+    fn test_url_dependencies() {
+        let source = r#"
+const logo = new URL('./logo.png', import.meta.url);
+const worker = new Worker(new URL('./w.ts', import.meta.url), { type: 'module' });
+new SharedWorker(new URL("./shared.js", import.meta.url));
+navigator.serviceWorker.register(new URL('./sw.js', import.meta.url));
+new URL('./ignored', location.href);
+"#;
+        let result = parse_internal(source);
+        let deps: Vec<_> = result
+            .dependencies
+            .iter()
+            .map(|d| (d.kind, d.specifier.as_str(), d.is_module))
+            .collect();
+        assert_eq!(
+            deps,
+            vec![
+                (DependencyKind::AssetUrl, "./logo.png", false),
+                (DependencyKind::Worker, "./w.ts", true),
+                (DependencyKind::SharedWorker, "./shared.js", false),
+                (DependencyKind::ServiceWorker, "./sw.js", false),
+            ]
+        );
+        let worker = &result.dependencies[1];
+        assert_eq!(&source[worker.specifier_start..worker.specifier_end], "'./w.ts'");
+        assert_eq!((worker.line, worker.column), (3, 16));
+    }
+
+    #[test]
+    fn test_magic_comments() {
+        let source = r#"
+import(/* webpackChunkName: "admin", webpackPrefetch: true */ './admin');
+import(/* @vite-ignore */ /* webpackExports: ["a", "b"] */ `./b.js`);
+import('./plain' /* trailing */);
+"#;
+        let imports = extract_imports_internal(source);
+        let comments: Vec<_> = imports
+            .iter()
+            .map(|i| i.magic_comments.iter().map(|c| (c.key.as_str(), c.value.as_deref())).collect::<Vec<_>>())
+            .collect();
+        assert_eq!(comments[0], vec![("webpackChunkName", Some("admin")), ("webpackPrefetch", Some("true"))]);
+        assert_eq!(comments[1], vec![("@vite-ignore", None), ("webpackExports", Some(r#"["a", "b"]"#))]);
+        assert!(comments[2].is_empty());
+
+        // Imports of a computed source are recorded without a specifier
+        let imports = extract_imports_internal("const m = await import(/* @vite-ignore */ base + name);");
+        assert_eq!(imports.len(), 1);
+        assert_eq!(imports[0].source, "");
+        assert!(imports[0].is_dynamic);
+        assert_eq!(imports[0].magic_comments[0].key, "@vite-ignore");
+    }
+
+    #[test]
+    fn test_directives_and_annotations() {
+        let source = r#"#!/usr/bin/env node
+'use client';
+"use strict";
+const a = /*#__PURE__*/ createStore();
+const b = /* @__PURE__ */ new Map();
+/*#__NO_SIDE_EFFECTS__*/ export function helper() {}
+const c = /*#__PURE__*/ 1;
+"not a directive";
+"#;
+        let result = parse_internal(source);
+        assert_eq!(result.hashbang.as_deref(), Some("#!/usr/bin/env node"));
+        let directives: Vec<_> = result.directives.iter().map(|d| d.value.as_str()).collect();
+        assert_eq!(directives, vec!["use client", "use strict"]);
+
+        let annotations: Vec<_> = result
+            .annotations
+            .iter()
+            .map(|a| (a.kind, a.target_start.zip(a.target_end).map(|(s, e)| &source[s..e])))
+            .collect();
+        assert_eq!(
+            annotations,
+            vec![
+                (AnnotationKind::Pure, Some("createStore()")),
+                (AnnotationKind::Pure, Some("new Map()")),
+                (AnnotationKind::NoSideEffects, Some("export function helper() {}")),
+                (AnnotationKind::Pure, None),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_language_classification() {
+        let language = |source: &str, filename: Option<&str>, hint: Option<&str>| {
+            let result = parse_file_internal(source, filename, hint);
+            (result.language, result.is_typescript, result.is_type_only)
+        };
+        // Object literals and `type` identifiers are plain JavaScript
+        let js = "const o = { a: 1, type: 'x' }; const type = 1;";
+        assert_eq!(language(js, None, None), (Language::Js, false, false));
+        assert_eq!(language("const el = <div />;", None, None), (Language::Jsx, false, false));
+        assert_eq!(language("const el = <div />;", Some("app.js"), None), (Language::Jsx, false, false));
+        assert_eq!(language("let x: number = 1;", None, None), (Language::Ts, true, false));
+        assert_eq!(language("let x: JSX.Element = <div />;", None, None), (Language::Tsx, true, false));
+        assert_eq!(language("const a = 1;", Some("a.ts"), None), (Language::Ts, true, false));
+        assert_eq!(language("const a = 1;", Some("a.ts"), Some("tsx")), (Language::Tsx, true, false));
+        assert_eq!(language("declare const a: number;", Some("src/a.d.ts"), None), (Language::Dts, true, true));
+        assert_eq!(
+            language("export interface A {}\nimport type { B } from './b';", None, None),
+            (Language::Ts, true, true)
+        );
+    }
+
+    #[test]
+    fn test_exports() {
+        let source = r#"
+export const { a, b: [c] } = obj;
+export function f() {}
+export default class {}
+export { f as g, a as default2 };
+export { x as y } from './x';
+export * as ns from './ns';
+export * from './all';
+export type T = string;
+"#;
+        let result = parse_internal(source);
+        let exports: Vec<_> = result
+            .exports
+            .iter()
+            .map(|e| (e.kind, e.exported.as_str(), e.local.as_deref(), e.source.as_deref(), e.is_type_only))
+            .collect();
+        assert_eq!(
+            exports,
+            vec![
+                (ExportKind::Declaration, "a", Some("a"), None, false),
+                (ExportKind::Declaration, "c", Some("c"), None, false),
+                (ExportKind::Declaration, "f", Some("f"), None, false),
+                (ExportKind::Default, "default", None, None, false),
+                (ExportKind::Named, "g", Some("f"), None, false),
+                (ExportKind::Named, "default2", Some("a"), None, false),
+                (ExportKind::ReExport, "y", Some("x"), Some("./x"), false),
+                (ExportKind::Namespace, "ns", None, Some("./ns"), false),
+                (ExportKind::Declaration, "T", Some("T"), None, true),
+            ]
+        );
+        assert_eq!((result.exports[0].line, result.exports[0].column), (2, 16));
+        assert_eq!(&source[result.exports[2].start..result.exports[2].end], "f");
+
+        let result = parse_internal("export default function named() {}\nexport = named;");
+        assert_eq!(result.exports[0].local.as_deref(), Some("named"));
+        assert_eq!(result.exports[1].kind, ExportKind::Assignment);
+        assert_eq!(result.exports[1].local.as_deref(), Some("named"));
+    }
+
+    #[test]
+    fn test_detect_cjs_exports() {
+        let source = r#"
+"use strict";
+Object.defineProperty(exports, "__esModule", { value: true });
+exports.b = exports.a = void 0;
+module.exports['c-d'] = 1;
+Object.defineProperty(exports, "e", { enumerable: true, get: function () { return lib_1.e; } });
+Object.defineProperty(exports, "skipped", { get() { return compute(); } });
+__exportStar(require("./star"), exports);
+"#;
+        let result = detect_cjs_exports_internal(source);
+        assert_eq!(result.exports, vec!["__esModule", "b", "a", "c-d", "e"]);
+        assert_eq!(result.reexports, vec!["./star"]);
+        assert!(result.is_es_module);
+
+        let result = detect_cjs_exports_internal("module.exports = { a, b: c.d, 'e': f, ...require('./g'), h: 1, i };");
+        assert_eq!(result.exports, vec!["a", "b", "e"]);
+        assert_eq!(result.reexports, vec!["./g"]);
+        assert!(!result.is_es_module);
+
+        let result = detect_cjs_exports_internal("module.exports = require('./impl');");
+        assert!(result.exports.is_empty());
+        assert_eq!(result.reexports, vec!["./impl"]);
+
+        // Babel's `export * from`
+        let source = r#"
+var _x = require("./x");
+Object.keys(_x).forEach(function (key) {
+  if (key === "default" || key === "__esModule") return;
+  if (key in exports && exports[key] === _x[key]) return;
+  Object.defineProperty(exports, key, { enumerable: true, get: function () { return _x[key]; } });
+});
+var _y = require("./y");
+Object.keys(_y).forEach(function (k) { exports[k] = _y[k]; });
+Object.keys(_y).forEach(function (k) { other[k] = _y[k]; });
+Object.keys(local).forEach(function (k) { exports[k] = local[k]; });
+"#;
+        assert_eq!(detect_cjs_exports_internal(source).reexports, vec!["./x", "./y"]);
+    }
+
+    #[test]
+    fn test_parse_diagnostics() {
+        let result = parse_internal("import a from 'a';\nconst = 1;");
+        assert_eq!(result.imports.len(), 1);
+        assert_eq!(result.diagnostics.len(), 1);
+        assert_eq!(result.diagnostics[0].line, 2);
+        assert!(result.diagnostics[0].code_frame.contains("> 2 | const = 1;"));
+        assert!(parse_internal("const ok = 1;").diagnostics.is_empty());
+    }
 }
@@ -1,14 +1,34 @@
 //! Fast TypeScript/JSX transformer
 //!
-//! Transforms:
-//! - TypeScript type annotations → removed
+//! Edits the original text using the syntax tree from `js_parser`, so code
+//! that is neither TypeScript nor JSX comes out byte for byte. Transforms:
+//! - TypeScript type annotations, assertions, overload signatures → removed
+//! - `import x = require(...)` → `const x = require(...)`; `export = x` → `module.exports = x`
+//! - TypeScript enums → IIFEs building the enum object; `const enum` members are inlined
+//! - TypeScript namespaces → IIFEs; ambient `declare` blocks → removed
+//! - TypeScript class modifiers → removed; parameter properties → assignments in the constructor
+//! - Decorators → `fuse_helpers_decorate` calls, legacy (`experimentalDecorators`) or 2023-05
+//! - Syntax newer than `target` → ES2015 equivalents, with `fuse_helpers_lower` for
+//!   async functions, async iteration and private members; library methods such as `at()` are not polyfilled,
+//!   and what cannot be lowered is reported in `diagnostics`
 //! - JSX → React.createElement / jsx calls
 //! - Import/export rewriting
 //! - Optional: minification
 
+use std::collections::{HashMap, HashSet};
+
 use wasm_bindgen::prelude::*;
 use serde::{Deserialize, Serialize};
 
+use crate::ast::*;
+use crate::compressor::{fold_binary, format_number, quote_string, to_int32, Literal};
+use crate::diagnostics::{Diagnostic, Severity};
+use crate::js_parser::{self, Syntax};
+use crate::jsx_text::{attr_value, clean_text, decode_entities, string_literal};
+use crate::lexer::{is_id_continue, is_id_start};
+use crate::parser::directive;
+use crate::utils::{apply_edits, Edit, LineIndex};
+
 /// Transform options
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[wasm_bindgen]
@@ -17,22 +37,48 @@ pub struct TransformOptions {
     pub jsx_runtime: JsxRuntime,
     #[wasm_bindgen(skip)]
     pub jsx_import_source: String,
+    /// Element factory of the classic runtime
+    #[wasm_bindgen(skip)]
+    pub jsx_factory: String,
+    /// Fragment component of the classic runtime
+    #[wasm_bindgen(skip)]
+    pub jsx_fragment: String,
     #[wasm_bindgen(skip)]
     pub remove_types: bool,
     #[wasm_bindgen(skip)]
     pub minify: bool,
     #[wasm_bindgen(skip)]
     pub target: Target,
+    /// Class fields use `[[Define]]` semantics; defaults to `target >= ES2022`
+    /// like TypeScript's `useDefineForClassFields`
+    #[wasm_bindgen(skip)]
+    pub use_define_for_class_fields: Option<bool>,
+    #[wasm_bindgen(skip)]
+    pub decorators: Decorators,
+    /// `design:*` metadata for legacy decorators, like TypeScript's `emitDecoratorMetadata`
+    #[wasm_bindgen(skip)]
+    pub emit_decorator_metadata: bool,
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
 pub enum JsxRuntime {
-    Classic,    // React.createElement
-    Automatic,  // jsx/jsxs from react/jsx-runtime
+    Classic,       // React.createElement
+    Automatic,     // jsx/jsxs from react/jsx-runtime
+    AutomaticDev,  // jsxDEV from react/jsx-dev-runtime, with __source and __self
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum Decorators {
+    Legacy,     // TypeScript experimentalDecorators
+    Standard,   // TC39 decorators, 2023-05
+    Preserve,   // left as written
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
 pub enum Target {
+    ES2015,
+    ES2017,
+    ES2018,
     ES2020,
     ES2021,
     ES2022,
@@ -44,9 +90,14 @@ impl Default for TransformOptions {
         Self {
             jsx_runtime: JsxRuntime::Automatic,
             jsx_import_source: "react".to_string(),
+            jsx_factory: "React.createElement".to_string(),
+            jsx_fragment: "React.Fragment".to_string(),
             remove_types: true,
             minify: false,
             target: Target::ES2020,
+            use_define_for_class_fields: None,
+            decorators: Decorators::Preserve,
+            emit_decorator_metadata: false,
         }
     }
 }
@@ -62,6 +113,7 @@ impl TransformOptions {
     pub fn set_jsx_runtime(&mut self, runtime: &str) {
         self.jsx_runtime = match runtime {
             "classic" => JsxRuntime::Classic,
+            "automatic-dev" => JsxRuntime::AutomaticDev,
             _ => JsxRuntime::Automatic,
         };
     }
@@ -71,6 +123,16 @@ impl TransformOptions {
         self.jsx_import_source = source;
     }
 
+    #[wasm_bindgen(setter)]
+    pub fn set_jsx_factory(&mut self, factory: String) {
+        self.jsx_factory = factory;
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_jsx_fragment(&mut self, fragment: String) {
+        self.jsx_fragment = fragment;
+    }
+
     #[wasm_bindgen(setter)]
     pub fn set_remove_types(&mut self, value: bool) {
         self.remove_types = value;
@@ -80,6 +142,52 @@ impl TransformOptions {
     pub fn set_minify(&mut self, value: bool) {
         self.minify = value;
     }
+
+    /// `"es2015"` to `"esnext"`; a year between targets picks the one before it
+    #[wasm_bindgen(setter)]
+    pub fn set_target(&mut self, target: &str) {
+        let target = target.to_ascii_lowercase();
+        let year = match target.as_str() {
+            "esnext" => u32::MAX,
+            "es5" | "es6" => 2015,
+            _ => target.strip_prefix("es").and_then(|y| y.parse().ok()).unwrap_or(2020),
+        };
+        self.target = match year {
+            0..=2016 => Target::ES2015,
+            2017 => Target::ES2017,
+            2018..=2019 => Target::ES2018,
+            2020 => Target::ES2020,
+            2021 => Target::ES2021,
+            2022..=2099 => Target::ES2022,
+            _ => Target::ESNext,
+        };
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_use_define_for_class_fields(&mut self, value: bool) {
+        self.use_define_for_class_fields = Some(value);
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_decorators(&mut self, mode: &str) {
+        self.decorators = match mode {
+            "legacy" => Decorators::Legacy,
+            "2023-05" => Decorators::Standard,
+            _ => Decorators::Preserve,
+        };
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_emit_decorator_metadata(&mut self, value: bool) {
+        self.emit_decorator_metadata = value;
+    }
+}
+
+impl TransformOptions {
+    /// Whether class fields stay fields rather than becoming constructor assignments
+    pub fn use_define_for_class_fields(&self) -> bool {
+        self.use_define_for_class_fields.unwrap_or(self.target >= Target::ES2022)
+    }
 }
 
 /// Transform result
@@ -88,6 +196,8 @@ pub struct TransformResult {
     pub code: String,
     pub had_jsx: bool,
     pub had_types: bool,
+    /// Syntax that could not be compiled for the chosen options
+    pub diagnostics: Vec<Diagnostic>,
 }
 
 /// Main transformer
@@ -121,886 +231,3977 @@ impl Transformer {
 
 /// Internal transform function
 pub fn transform_internal(source: &str, filename: &str, options: &TransformOptions) -> TransformResult {
-    let mut code = source.to_string();
-    let mut had_jsx = false;
-    let mut had_types = false;
+    let (program, syntax) = js_parser::parse(source, Some(filename));
 
-    // Detect file type
-    let is_tsx = filename.ends_with(".tsx");
-    let is_ts = filename.ends_with(".ts") || is_tsx;
-    let is_jsx = filename.ends_with(".jsx") || is_tsx;
+    // Remove TypeScript types and transform JSX in one pass over the tree
+    let strip_types = options.remove_types && syntax.typescript;
+    let mut result = emit(source, filename, &program, strip_types, options);
 
-    // Remove TypeScript types
-    if options.remove_types && is_ts {
-        let (new_code, found_types) = remove_typescript_types(&code);
-        code = new_code;
-        had_types = found_types;
+    // Minify if requested
+    if options.minify {
+        result.code = minify(&result.code);
     }
 
-    // Transform JSX
-    if is_jsx {
-        let (new_code, found_jsx) = transform_jsx(&code, options);
-        code = new_code;
-        had_jsx = found_jsx;
-    }
+    result
+}
 
-    // Add JSX runtime import if needed
-    if had_jsx && options.jsx_runtime == JsxRuntime::Automatic {
-        code = add_jsx_import(&code, options);
-    }
+/// Remove TypeScript syntax, leaving everything else untouched
+pub fn remove_typescript_types(source: &str) -> (String, bool) {
+    let program = js_parser::parse_program(source, Syntax { typescript: true, jsx: false });
+    let result = emit(source, "", &program, true, &TransformOptions::default());
+    (result.code, result.had_types)
+}
 
-    // Minify if requested
-    if options.minify {
-        code = quick_minify(&code);
+/// Transform JSX to JavaScript
+pub fn transform_jsx(source: &str, options: &TransformOptions) -> (String, bool) {
+    let program = js_parser::parse_program(source, Syntax { typescript: false, jsx: true });
+    let result = emit(source, "", &program, false, options);
+    (result.code, result.had_jsx)
+}
+
+/// Rewrite a parsed module
+fn emit(
+    source: &str,
+    filename: &str,
+    program: &Program,
+    strip_types: bool,
+    options: &TransformOptions,
+) -> TransformResult {
+    let pragma_options = jsx_pragma_options(program, source, options);
+    let options = pragma_options.as_ref().unwrap_or(options);
+    let dev = options.jsx_runtime == JsxRuntime::AutomaticDev;
+    let line_index = dev.then(|| LineIndex::new(source));
+    let mut emitter = Emitter::new(source, strip_types, options);
+    emitter.lowering.names = words(source);
+    emitter.jsx_source = line_index.as_ref().map(|index| (filename, index));
+    if strip_types {
+        emitter.collect_declarations(&program.body, None);
     }
+    walk_program(&mut emitter, program);
 
+    // Helper imports go after the hashbang and directives
+    let mut imports = jsx_imports(&emitter.jsx_helpers, options);
+    if emitter.had_jsx && dev {
+        imports.push(format!("var _jsxFileName = {};", quote_string(filename)));
+    }
+    if emitter.decorate_helpers {
+        imports.push(format!("import * as {} from \"fuse_helpers_decorate\";", DECORATE));
+    }
+    if emitter.lowering.helpers {
+        imports.push(format!("import * as {} from \"fuse_helpers_lower\";", LOWER));
+    }
+    // Temporaries of lowered syntax outside functions
+    imports.extend(emitter.leave_scope(Default::default()));
+    if !imports.is_empty() {
+        let prologue = program.body.iter().take_while(|stmt| directive(stmt, source).is_some()).last();
+        let code = match prologue.map(|stmt| stmt.span().end).or(program.hashbang.map(|h| h.end)) {
+            Some(pos) => Edit::new(pos, pos, format!("\n{}", imports.join("\n"))),
+            None => Edit::new(0, 0, format!("{}\n", imports.join("\n"))),
+        };
+        emitter.edits.push(code);
+    }
+    let diagnostics = match emitter.errors.is_empty() {
+        true => Vec::new(),
+        false => {
+            let line_index = LineIndex::new(source);
+            let error = |(span, code, message): &(Span, &str, String)| {
+                Diagnostic::new(Severity::Error, code, message, span.start, span.end, source, &line_index)
+            };
+            emitter.errors.iter().map(error).collect()
+        }
+    };
     TransformResult {
-        code,
-        had_jsx,
-        had_types,
+        code: apply_edits(source, 0, source.len(), emitter.edits),
+        had_jsx: emitter.had_jsx,
+        had_types: emitter.had_types,
+        diagnostics,
     }
 }
 
-/// Remove TypeScript type annotations
-fn remove_typescript_types(source: &str) -> (String, bool) {
-    let mut result = String::with_capacity(source.len());
-    let bytes = source.as_bytes();
-    let len = bytes.len();
-    let mut i = 0;
-    let mut had_types = false;
+/// Namespace import of the decorator helpers
+const DECORATE: &str = "__fuse_decorate";
 
-    while i < len {
-        // Skip string literals
-        if bytes[i] == b'"' || bytes[i] == b'\'' || bytes[i] == b'`' {
-            let quote = bytes[i];
-            result.push(bytes[i] as char);
-            i += 1;
-            while i < len && bytes[i] != quote {
-                if bytes[i] == b'\\' && i + 1 < len {
-                    result.push(bytes[i] as char);
-                    i += 1;
-                }
-                result.push(bytes[i] as char);
-                i += 1;
-            }
-            if i < len {
-                result.push(bytes[i] as char);
-                i += 1;
-            }
-            continue;
-        }
+/// Namespace import of the helpers of lowered syntax
+const LOWER: &str = "__fuse_lower";
 
-        // Skip comments
-        if bytes[i] == b'/' {
-            if i + 1 < len && bytes[i + 1] == b'/' {
-                // Single-line comment
-                while i < len && bytes[i] != b'\n' {
-                    result.push(bytes[i] as char);
-                    i += 1;
-                }
-                continue;
-            }
-            if i + 1 < len && bytes[i + 1] == b'*' {
-                // Multi-line comment
-                result.push('/');
-                result.push('*');
-                i += 2;
-                while i + 1 < len && !(bytes[i] == b'*' && bytes[i + 1] == b'/') {
-                    result.push(bytes[i] as char);
-                    i += 1;
-                }
-                if i + 1 < len {
-                    result.push('*');
-                    result.push('/');
-                    i += 2;
-                }
-                continue;
-            }
-        }
+/// A runtime function called by compiled JSX
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum JsxHelper {
+    Jsx,
+    Jsxs,
+    JsxDev,
+    Fragment,
+    /// `createElement` from the import source, for a `key` after a spread
+    CreateElement,
+}
 
-        // Remove type imports: import type { X } from 'y'
-        if i + 11 < len && &bytes[i..i+11] == b"import type" {
-            had_types = true;
-            // Skip entire import statement
-            while i < len && bytes[i] != b';' && bytes[i] != b'\n' {
-                i += 1;
-            }
-            if i < len && bytes[i] == b';' {
-                i += 1;
-            }
-            result.push('\n');
-            continue;
-        }
+/// A JSX attribute as an object property
+enum JsxProp {
+    Prop(String),
+    Spread(String),
+    /// Value of `key`, which the automatic runtime passes on its own
+    Key(String),
+}
 
-        // Remove type exports: export type { X }
-        if i + 11 < len && &bytes[i..i+11] == b"export type" {
-            // Check if it's "export type {" not "export type X ="
-            let mut j = i + 11;
-            while j < len && (bytes[j] == b' ' || bytes[j] == b'\t') {
-                j += 1;
-            }
-            if j < len && bytes[j] == b'{' {
-                had_types = true;
-                while i < len && bytes[i] != b';' && bytes[i] != b'\n' {
-                    i += 1;
-                }
-                if i < len && bytes[i] == b';' {
-                    i += 1;
-                }
-                result.push('\n');
-                continue;
-            }
+impl JsxProp {
+    fn into_prop(self) -> String {
+        match self {
+            JsxProp::Prop(prop) | JsxProp::Spread(prop) => prop,
+            JsxProp::Key(value) => format!("key: {}", value),
         }
+    }
+}
 
-        // Remove interface declarations
-        if i + 9 < len && &bytes[i..i+9] == b"interface" {
-            // Check it's a keyword (not part of identifier)
-            let before_ok = i == 0 || !bytes[i-1].is_ascii_alphanumeric();
-            let after_ok = i + 9 >= len || !bytes[i+9].is_ascii_alphanumeric();
-            if before_ok && after_ok {
-                had_types = true;
-                // Skip to end of interface block
-                let mut brace_depth = 0;
-                let mut found_brace = false;
-                while i < len {
-                    if bytes[i] == b'{' {
-                        brace_depth += 1;
-                        found_brace = true;
-                    } else if bytes[i] == b'}' {
-                        brace_depth -= 1;
-                        if found_brace && brace_depth == 0 {
-                            i += 1;
-                            break;
-                        }
-                    }
-                    i += 1;
-                }
-                result.push('\n');
-                continue;
-            }
+impl JsxHelper {
+    fn imported(self) -> &'static str {
+        match self {
+            JsxHelper::Jsx => "jsx",
+            JsxHelper::Jsxs => "jsxs",
+            JsxHelper::JsxDev => "jsxDEV",
+            JsxHelper::Fragment => "Fragment",
+            JsxHelper::CreateElement => "createElement",
         }
+    }
 
-        // Remove type alias: type X = ...
-        if i + 4 < len && &bytes[i..i+4] == b"type" {
-            let before_ok = i == 0 || !bytes[i-1].is_ascii_alphanumeric();
-            let after_ok = i + 4 < len && (bytes[i+4] == b' ' || bytes[i+4] == b'\t');
-            if before_ok && after_ok {
-                // Check if followed by identifier and =
-                let mut j = i + 4;
-                while j < len && (bytes[j] == b' ' || bytes[j] == b'\t') {
-                    j += 1;
-                }
-                // Skip identifier
-                let id_start = j;
-                while j < len && (bytes[j].is_ascii_alphanumeric() || bytes[j] == b'_') {
-                    j += 1;
-                }
-                if j > id_start {
-                    // Skip whitespace and generics
-                    while j < len && (bytes[j] == b' ' || bytes[j] == b'\t' || bytes[j] == b'<') {
-                        if bytes[j] == b'<' {
-                            let mut depth = 1;
-                            j += 1;
-                            while j < len && depth > 0 {
-                                if bytes[j] == b'<' { depth += 1; }
-                                if bytes[j] == b'>' { depth -= 1; }
-                                j += 1;
-                            }
-                        } else {
-                            j += 1;
-                        }
-                    }
-                    // Check for =
-                    if j < len && bytes[j] == b'=' {
-                        had_types = true;
-                        // Skip to semicolon or newline
-                        while i < len && bytes[i] != b';' && bytes[i] != b'\n' {
-                            // Handle nested braces/parens
-                            if bytes[i] == b'{' || bytes[i] == b'(' {
-                                let open = bytes[i];
-                                let close = if open == b'{' { b'}' } else { b')' };
-                                let mut depth = 1;
-                                i += 1;
-                                while i < len && depth > 0 {
-                                    if bytes[i] == open { depth += 1; }
-                                    if bytes[i] == close { depth -= 1; }
-                                    i += 1;
-                                }
-                                continue;
-                            }
-                            i += 1;
-                        }
-                        if i < len && bytes[i] == b';' {
-                            i += 1;
-                        }
-                        result.push('\n');
-                        continue;
-                    }
-                }
-            }
+    fn local(self) -> &'static str {
+        match self {
+            JsxHelper::Jsx => "_jsx",
+            JsxHelper::Jsxs => "_jsxs",
+            JsxHelper::JsxDev => "_jsxDEV",
+            JsxHelper::Fragment => "_Fragment",
+            JsxHelper::CreateElement => "_createElement",
         }
+    }
+}
 
-        // Remove type annotations after : (function params, variable declarations)
-        if bytes[i] == b':' {
-            // Check if this looks like a type annotation
-            // Skip if it's inside an object literal (key: value)
-            let mut j = i + 1;
-            while j < len && (bytes[j] == b' ' || bytes[j] == b'\t') {
-                j += 1;
-            }
-            
-            // Check for common type patterns
-            if j < len {
-                let is_type_annotation = 
-                    // Primitive types
-                    (j + 6 <= len && &bytes[j..j+6] == b"string") ||
-                    (j + 6 <= len && &bytes[j..j+6] == b"number") ||
-                    (j + 7 <= len && &bytes[j..j+7] == b"boolean") ||
-                    (j + 3 <= len && &bytes[j..j+3] == b"any") ||
-                    (j + 4 <= len && &bytes[j..j+4] == b"void") ||
-                    (j + 5 <= len && &bytes[j..j+5] == b"never") ||
-                    (j + 4 <= len && &bytes[j..j+4] == b"null") ||
-                    (j + 9 <= len && &bytes[j..j+9] == b"undefined") ||
-                    // Array/object types
-                    bytes[j] == b'{' ||
-                    bytes[j] == b'[' ||
-                    // Generic types (uppercase start)
-                    bytes[j].is_ascii_uppercase() ||
-                    // Union/intersection
-                    bytes[j] == b'(' ||
-                    // Typeof
-                    (j + 6 <= len && &bytes[j..j+6] == b"typeof");
-
-                if is_type_annotation {
-                    had_types = true;
-                    // Skip the type annotation
-                    i += 1;
-                    while i < len {
-                        let ch = bytes[i];
-                        // Stop at these characters
-                        if ch == b'=' || ch == b',' || ch == b')' || ch == b';' || 
-                           ch == b'{' || ch == b'\n' {
-                            break;
-                        }
-                        // Handle nested generics
-                        if ch == b'<' {
-                            let mut depth = 1;
-                            i += 1;
-                            while i < len && depth > 0 {
-                                if bytes[i] == b'<' { depth += 1; }
-                                if bytes[i] == b'>' { depth -= 1; }
-                                i += 1;
-                            }
-                            continue;
-                        }
-                        // Handle nested parens (function types)
-                        if ch == b'(' {
-                            let mut depth = 1;
-                            i += 1;
-                            while i < len && depth > 0 {
-                                if bytes[i] == b'(' { depth += 1; }
-                                if bytes[i] == b')' { depth -= 1; }
-                                i += 1;
-                            }
-                            continue;
-                        }
-                        // Handle array type []
-                        if ch == b'[' {
-                            i += 1;
-                            if i < len && bytes[i] == b']' {
-                                i += 1;
-                            }
-                            continue;
-                        }
-                        i += 1;
-                    }
-                    continue;
-                }
-            }
-        }
+/// Where a decorated class appears
+#[derive(Clone, Copy, PartialEq)]
+enum ClassSite {
+    Declaration,
+    Export,
+    ExportDefault,
+    Expression,
+}
 
-        // Remove generic type parameters from functions/classes
-        if bytes[i] == b'<' {
-            // Check if previous char suggests this is a generic
-            let prev_is_ident = i > 0 && (bytes[i-1].is_ascii_alphanumeric() || bytes[i-1] == b'_');
-            if prev_is_ident {
-                // Look ahead to see if this is a generic type parameter
-                let mut j = i + 1;
-                let mut depth = 1;
-                let mut looks_like_generic = true;
-                
-                while j < len && depth > 0 {
-                    if bytes[j] == b'<' { depth += 1; }
-                    if bytes[j] == b'>' { depth -= 1; }
-                    // If we see these, it's probably comparison not generic
-                    if bytes[j] == b';' || bytes[j] == b'\n' {
-                        looks_like_generic = false;
-                        break;
-                    }
-                    j += 1;
-                }
-                
-                if looks_like_generic && depth == 0 {
-                    // Check what follows the >
-                    while j < len && (bytes[j] == b' ' || bytes[j] == b'\t') {
-                        j += 1;
-                    }
-                    // If followed by ( or { or extends, it's a generic
-                    if j < len && (bytes[j] == b'(' || bytes[j] == b'{' || 
-                                   (j + 7 <= len && &bytes[j..j+7] == b"extends")) {
-                        had_types = true;
-                        // Skip the generic
-                        i += 1;
-                        depth = 1;
-                        while i < len && depth > 0 {
-                            if bytes[i] == b'<' { depth += 1; }
-                            if bytes[i] == b'>' { depth -= 1; }
-                            i += 1;
-                        }
-                        continue;
-                    }
-                }
-            }
-        }
+/// Collects the edits that erase TypeScript syntax and replace JSX with calls
+struct Emitter<'a> {
+    source: &'a str,
+    strip_types: bool,
+    options: &'a TransformOptions,
+    edits: Vec<Edit>,
+    had_types: bool,
+    had_jsx: bool,
+    enums: EnumScope,
+    namespaces: NamespaceScope,
+    /// JSX runtime helpers used
+    jsx_helpers: Vec<JsxHelper>,
+    /// File name and line index for `__source`, in development mode
+    jsx_source: Option<(&'a str, &'a LineIndex)>,
+    /// Whether `this` can be passed as `__self`; it cannot in derived constructors
+    this_allowed: bool,
+    /// Starts of the constructors of derived classes
+    derived_constructors: HashSet<usize>,
+    /// Whether the output calls the `fuse_helpers_decorate` helpers
+    decorate_helpers: bool,
+    /// Statements to run first in the constructor of the next class lowered
+    constructor_prologue: Vec<String>,
+    /// Start of the class declared by the statement being visited, and that statement
+    class_statement: Option<(usize, Span)>,
+    lowering: Lowering,
+    /// Syntax that cannot be compiled, with a diagnostic code and message
+    errors: Vec<(Span, &'static str, String)>,
+    /// Classes with legacy class decorators around the current node, innermost last
+    class_aliases: Vec<ClassAlias>,
+}
 
-        // Remove 'as' type assertions
-        if i + 2 < len && &bytes[i..i+2] == b"as" {
-            let before_ok = i > 0 && (bytes[i-1] == b' ' || bytes[i-1] == b')' || bytes[i-1] == b']');
-            let after_ok = i + 2 < len && bytes[i+2] == b' ';
-            if before_ok && after_ok {
-                had_types = true;
-                i += 2;
-                // Skip whitespace
-                while i < len && (bytes[i] == b' ' || bytes[i] == b'\t') {
-                    i += 1;
-                }
-                // Skip the type
-                while i < len {
-                    let ch = bytes[i];
-                    if ch == b';' || ch == b',' || ch == b')' || ch == b']' || 
-                       ch == b'\n' || ch == b'{' || ch == b'}' {
-                        break;
-                    }
-                    if ch == b'<' {
-                        let mut depth = 1;
-                        i += 1;
-                        while i < len && depth > 0 {
-                            if bytes[i] == b'<' { depth += 1; }
-                            if bytes[i] == b'>' { depth -= 1; }
-                            i += 1;
-                        }
-                        continue;
-                    }
-                    i += 1;
-                }
-                continue;
-            }
-        }
+/// A class whose legacy class decorators may replace it. Its body refers to
+/// the decorated class through an alias, as TypeScript's `A_1`.
+struct ClassAlias {
+    name: String,
+    alias: String,
+    used: bool,
+}
 
-        // Remove non-null assertion !
-        if bytes[i] == b'!' {
-            // Check if it's a non-null assertion (identifier! or )! or ]!)
-            if i > 0 {
-                let prev = bytes[i - 1];
-                if prev.is_ascii_alphanumeric() || prev == b'_' || prev == b')' || prev == b']' {
-                    // Check what follows
-                    if i + 1 < len {
-                        let next = bytes[i + 1];
-                        // If followed by . or [ or ) or , or ; it's likely non-null assertion
-                        if next == b'.' || next == b'[' || next == b')' || 
-                           next == b',' || next == b';' || next == b'\n' {
-                            had_types = true;
-                            i += 1;
-                            continue;
-                        }
-                    }
-                }
-            }
-        }
+/// Decorator calls of a class with legacy decorators, which follow the class
+/// and its static initializers
+struct LegacyCalls {
+    name: String,
+    /// Calls decorating the members
+    members: Vec<String>,
+    /// Class decorators, including those of constructor parameters
+    class: Vec<String>,
+    /// End of the class statement, and its indentation
+    end: usize,
+    indent: String,
+}
 
-        result.push(bytes[i] as char);
-        i += 1;
-    }
+/// What the emitter knows about the enums of the module
+#[derive(Default)]
+struct EnumScope {
+    /// Constant member values by enum name; merged declarations share an entry
+    values: HashMap<String, HashMap<String, Literal>>,
+    /// Names of `const enum`s, whose member accesses are inlined
+    consts: HashSet<String>,
+    /// Names exported from the module, so a `const enum` among them keeps its object
+    exported: HashSet<String>,
+    /// Enum name and member names while rendering a computed member initializer
+    members: Option<(String, HashSet<String>)>,
+}
 
-    (result, had_types)
+/// What the emitter knows about the namespaces of the module
+#[derive(Default)]
+struct NamespaceScope {
+    /// Members of each namespace by dotted path, across all its declarations
+    exports: HashMap<String, NamespaceExports>,
+    /// Enum and namespace declarations merging into an earlier declaration
+    /// of the same name, by start offset
+    merged: HashSet<usize>,
+    /// Namespace bodies and functions around the current node, innermost last
+    frames: Vec<Frame>,
 }
 
-/// Transform JSX to JavaScript
-fn transform_jsx(source: &str, options: &TransformOptions) -> (String, bool) {
-    let mut result = String::with_capacity(source.len() * 2);
-    let bytes = source.as_bytes();
-    let len = bytes.len();
-    let mut i = 0;
-    let mut had_jsx = false;
-
-    while i < len {
-        // Skip string literals
-        if bytes[i] == b'"' || bytes[i] == b'\'' || bytes[i] == b'`' {
-            let quote = bytes[i];
-            result.push(bytes[i] as char);
-            i += 1;
-            while i < len && bytes[i] != quote {
-                if bytes[i] == b'\\' && i + 1 < len {
-                    result.push(bytes[i] as char);
-                    i += 1;
-                }
-                result.push(bytes[i] as char);
-                i += 1;
-            }
-            if i < len {
-                result.push(bytes[i] as char);
-                i += 1;
-            }
-            continue;
-        }
-
-        // Skip comments
-        if bytes[i] == b'/' {
-            if i + 1 < len && bytes[i + 1] == b'/' {
-                while i < len && bytes[i] != b'\n' {
-                    result.push(bytes[i] as char);
-                    i += 1;
-                }
-                continue;
-            }
-            if i + 1 < len && bytes[i + 1] == b'*' {
-                result.push('/');
-                result.push('*');
-                i += 2;
-                while i + 1 < len && !(bytes[i] == b'*' && bytes[i + 1] == b'/') {
-                    result.push(bytes[i] as char);
-                    i += 1;
-                }
-                if i + 1 < len {
-                    result.push('*');
-                    result.push('/');
-                    i += 2;
-                }
-                continue;
-            }
-        }
+#[derive(Default)]
+struct NamespaceExports {
+    /// Exported variables, which only live on the namespace object
+    vars: HashSet<String>,
+    /// Exported functions, classes, enums and namespaces, with the start of
+    /// the block declaring them; that block also has a local binding
+    decls: Vec<(String, usize)>,
+}
 
-        // Detect JSX opening tag
-        if bytes[i] == b'<' {
-            if i + 1 < len {
-                let next = bytes[i + 1];
-                // <Component or <div or <>
-                if next.is_ascii_alphabetic() || next == b'>' {
-                    let (jsx_result, consumed) = parse_jsx_element(&bytes[i..], options);
-                    if consumed > 0 {
-                        had_jsx = true;
-                        result.push_str(&jsx_result);
-                        i += consumed;
-                        continue;
-                    }
-                }
-            }
+/// Names in scope in a namespace body or a function inside one
+#[derive(Default)]
+struct Frame {
+    /// Parameter holding the namespace object, for a namespace body
+    namespace: Option<String>,
+    /// Members read through the namespace object
+    members: HashSet<String>,
+    /// Local bindings, which shadow members of enclosing namespaces
+    locals: HashSet<String>,
+}
+
+/// What the emitter tracks while lowering syntax newer than the target
+#[derive(Default)]
+struct Lowering {
+    /// Temporaries of the function being emitted, declared at its start
+    temps: Vec<String>,
+    /// Statements to run first in the function being emitted
+    prologue: Vec<String>,
+    /// Words of the source, comments and strings included, and names generated
+    /// so far, which later names avoid
+    names: HashSet<String>,
+    /// Whether the output calls the `fuse_helpers_lower` helpers
+    helpers: bool,
+    /// Index of the next temporary name to try
+    next_name: usize,
+    /// Private names of the classes being lowered, innermost last
+    privates: Vec<HashMap<String, PrivateMember>>,
+    /// Replacement for `this` and for the inner name of a class expression,
+    /// in static initializers moved out of their class
+    class_this: Option<ClassThis>,
+    /// How `await` and `yield` change in the function being emitted
+    awaits: AsyncLowering,
+    /// Properties of `super` read in the lowered async function being emitted,
+    /// which reads them through functions declared outside its generator
+    super_props: Option<SuperProps>,
+    /// Names of those functions, `_super` and `_superIndex`
+    super_names: Option<(String, String)>,
+    /// Receivers of private members a destructuring assigns through setter
+    /// objects, evaluated into temporaries before it
+    receivers: Vec<String>,
+    /// Expressions in a position that takes any assignment expression, which
+    /// a conditional or `yield` can replace without parentheses
+    loose: HashSet<(usize, usize)>,
+    /// Temporaries holding computed keys that legacy decorator calls use,
+    /// by the start of the key
+    keys: HashMap<usize, String>,
+}
+
+/// An async function or generator becomes a generator run by a helper
+#[derive(Clone, Copy, Default, PartialEq)]
+enum AsyncLowering {
+    /// `await` and `yield` are kept
+    #[default]
+    None,
+    /// Run by `awaiter`: `await x` → `yield x`
+    Awaiter,
+    /// Run by `asyncGenerator`: `await x` → `yield awaitValue(x)`, and what is
+    /// yielded or returned is awaited first
+    Generator,
+}
+
+/// `super` properties of a lowered async function: the names read as
+/// `super.name`, and whether any is read as `super[key]`
+#[derive(Default)]
+struct SuperProps {
+    names: Vec<String>,
+    index: bool,
+}
+
+/// A lowered class as static initializers refer to it: the name standing for
+/// `this`, and the inner name of a class expression
+type ClassThis = (String, Option<String>);
+
+/// A private class member, lowered to `WeakMap`s, `WeakSet`s and functions
+#[derive(Clone)]
+struct PrivateMember {
+    kind: PrivateKind,
+    /// `WeakMap` of an instance field, `WeakSet` of instance methods and
+    /// accessors, or the class itself for static members
+    state: String,
+    /// Storage of a static field, function of a method, or getter of an accessor
+    value: Option<String>,
+    /// Setter of an accessor
+    setter: Option<String>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum PrivateKind {
+    Field,
+    Method,
+    Accessor,
+}
+
+impl PrivateMember {
+    fn kind(&self) -> &'static str {
+        match self.kind {
+            PrivateKind::Field => "f",
+            PrivateKind::Method => "m",
+            PrivateKind::Accessor => "a",
         }
+    }
 
-        result.push(bytes[i] as char);
-        i += 1;
+    /// Read the member of `receiver`
+    fn get(&self, receiver: &str) -> String {
+        match &self.value {
+            Some(f) => format!("{}.classPrivateFieldGet({}, {}, \"{}\", {})", LOWER, receiver, self.state, self.kind(), f),
+            None => format!("{}.classPrivateFieldGet({}, {}, \"{}\")", LOWER, receiver, self.state, self.kind()),
+        }
     }
 
-    (result, had_jsx)
+    /// Write `value` to the member of `receiver`
+    fn set(&self, receiver: &str, value: &str) -> String {
+        let f = match self.kind {
+            PrivateKind::Field => self.value.as_ref(),
+            PrivateKind::Method => None,
+            PrivateKind::Accessor => self.setter.as_ref(),
+        };
+        match f {
+            Some(f) => format!(
+                "{}.classPrivateFieldSet({}, {}, {}, \"{}\", {})",
+                LOWER, receiver, self.state, value, self.kind(), f
+            ),
+            None => format!("{}.classPrivateFieldSet({}, {}, {}, \"{}\")", LOWER, receiver, self.state, value, self.kind()),
+        }
+    }
 }
 
-/// Parse a single JSX element and return transformed code
-fn parse_jsx_element(bytes: &[u8], options: &TransformOptions) -> (String, usize) {
-    let len = bytes.len();
-    if len < 2 || bytes[0] != b'<' {
-        return (String::new(), 0);
+impl<'a> Emitter<'a> {
+    fn new(source: &'a str, strip_types: bool, options: &'a TransformOptions) -> Self {
+        Self {
+            source,
+            strip_types,
+            options,
+            edits: Vec::new(),
+            had_types: false,
+            had_jsx: false,
+            enums: EnumScope::default(),
+            namespaces: NamespaceScope::default(),
+            jsx_helpers: Vec::new(),
+            jsx_source: None,
+            this_allowed: true,
+            derived_constructors: HashSet::new(),
+            decorate_helpers: false,
+            constructor_prologue: Vec::new(),
+            class_statement: None,
+            lowering: Lowering::default(),
+            errors: Vec::new(),
+            class_aliases: Vec::new(),
+        }
     }
 
-    let mut i = 1;
+    fn erase(&mut self, span: Span) {
+        if span.start < span.end {
+            self.edits.push(Edit::delete(span.start, span.end));
+            self.had_types = true;
+        }
+    }
 
-    // Fragment <>
-    if bytes[i] == b'>' {
-        i += 1;
-        let (children, consumed) = parse_jsx_children(&bytes[i..], options);
-        i += consumed;
-        // Skip </>
-        if i + 2 < len && bytes[i] == b'<' && bytes[i+1] == b'/' && bytes[i+2] == b'>' {
-            i += 3;
+    fn erase_opt(&mut self, span: Option<Span>) {
+        if let Some(span) = span {
+            self.erase(span);
         }
-        
-        let result = if options.jsx_runtime == JsxRuntime::Automatic {
-            if children.is_empty() {
-                "_jsx(_Fragment, {})".to_string()
-            } else {
-                format!("_jsxs(_Fragment, {{ children: [{}] }})", children.join(", "))
-            }
-        } else {
-            format!("React.createElement(React.Fragment, null{})", 
-                    if children.is_empty() { "".to_string() } else { format!(", {}", children.join(", ")) })
+    }
+
+    fn erase_type_ann(&mut self, ann: &Option<TsTypeAnn>) {
+        self.erase_opt(ann.as_ref().map(|a| a.span));
+    }
+
+    /// Erase a list item along with the comma after it
+    fn erase_list_item(&mut self, span: Span) {
+        let rest = &self.source[span.end..];
+        let after = rest.trim_start();
+        let end = match after.starts_with(',') {
+            true => span.end + (rest.len() - after.len()) + 1,
+            false => span.end,
         };
-        return (result, i);
+        self.erase(Span::new(span.start, end));
     }
 
-    // Parse tag name
-    let tag_start = i;
-    while i < len && (bytes[i].is_ascii_alphanumeric() || bytes[i] == b'_' || bytes[i] == b'.' || bytes[i] == b'-') {
-        i += 1;
+    /// Erase `type` specifiers of an import or export, or the whole statement
+    /// when nothing else is left
+    fn erase_type_specifiers(&mut self, stmt: Span, specifiers: &[(Span, bool)]) {
+        let types: Vec<Span> = specifiers.iter().filter(|(_, t)| *t).map(|(s, _)| *s).collect();
+        if types.is_empty() {
+            return;
+        }
+        if types.len() == specifiers.len() {
+            self.erase(stmt);
+        } else {
+            types.into_iter().for_each(|span| self.erase_list_item(span));
+        }
     }
-    let tag_name = String::from_utf8_lossy(&bytes[tag_start..i]).to_string();
-    
-    if tag_name.is_empty() {
-        return (String::new(), 0);
+
+    /// Source of `expr` with the edits for its subtree applied
+    fn render(&mut self, expr: &Expr) -> String {
+        self.render_with(expr.span(), |inner| {
+            inner.loose(expr);
+            inner.visit_expr(expr);
+        })
     }
 
-    // Is it a component (uppercase) or HTML element (lowercase)?
-    let is_component = tag_name.chars().next().map(|c| c.is_uppercase()).unwrap_or(false);
-    let tag_ref = if is_component {
-        tag_name.clone()
-    } else {
-        format!("\"{}\"", tag_name)
-    };
+    /// Source of `span` with the edits made by `visit` applied
+    fn render_with(&mut self, span: Span, visit: impl FnOnce(&mut Emitter<'a>)) -> String {
+        let mut inner = Emitter::new(self.source, self.strip_types, self.options);
+        inner.enums = std::mem::take(&mut self.enums);
+        inner.namespaces = std::mem::take(&mut self.namespaces);
+        inner.jsx_helpers = std::mem::take(&mut self.jsx_helpers);
+        inner.jsx_source = self.jsx_source;
+        inner.this_allowed = self.this_allowed;
+        inner.derived_constructors = std::mem::take(&mut self.derived_constructors);
+        inner.lowering = std::mem::take(&mut self.lowering);
+        inner.class_aliases = std::mem::take(&mut self.class_aliases);
+        visit(&mut inner);
+        self.class_aliases = inner.class_aliases;
+        self.lowering = inner.lowering;
+        self.derived_constructors = inner.derived_constructors;
+        self.enums = inner.enums;
+        self.namespaces = inner.namespaces;
+        self.jsx_helpers = inner.jsx_helpers;
+        self.had_types |= inner.had_types;
+        self.had_jsx |= inner.had_jsx;
+        self.decorate_helpers |= inner.decorate_helpers;
+        self.errors.append(&mut inner.errors);
+        apply_edits(self.source, span.start, span.end, inner.edits)
+    }
 
-    // Parse attributes
-    let mut props: Vec<String> = Vec::new();
-    let mut key_prop: Option<String> = None;
-    
-    while i < len {
-        // Skip whitespace
-        while i < len && (bytes[i] == b' ' || bytes[i] == b'\t' || bytes[i] == b'\n') {
-            i += 1;
-        }
-
-        // End of opening tag
-        if i < len && bytes[i] == b'>' {
-            i += 1;
-            break;
-        }
-
-        // Self-closing tag
-        if i + 1 < len && bytes[i] == b'/' && bytes[i+1] == b'>' {
-            i += 2;
-            // Generate self-closing element
-            let result = generate_jsx_call(&tag_ref, &props, &key_prop, &[], options);
-            return (result, i);
-        }
-
-        // Spread attribute {...expr}
-        if i + 2 < len && bytes[i] == b'{' && bytes[i+1] == b'.' && bytes[i+2] == b'.' {
-            i += 3;
-            // Skip ...
-            if i < len && bytes[i] == b'.' {
-                i += 1;
-            }
-            let (expr, consumed) = parse_jsx_expression(&bytes[i..]);
-            props.push(format!("...{}", expr));
-            i += consumed;
-            // Skip closing }
-            if i < len && bytes[i] == b'}' {
-                i += 1;
-            }
-            continue;
-        }
-
-        // Parse attribute name
-        let attr_start = i;
-        while i < len && (bytes[i].is_ascii_alphanumeric() || bytes[i] == b'_' || bytes[i] == b'-') {
-            i += 1;
-        }
-        
-        if i == attr_start {
-            break; // No attribute found
-        }
-        
-        let attr_name = String::from_utf8_lossy(&bytes[attr_start..i]).to_string();
-        let js_attr_name = convert_jsx_attr_name(&attr_name);
-
-        // Skip whitespace
-        while i < len && (bytes[i] == b' ' || bytes[i] == b'\t') {
-            i += 1;
-        }
-
-        // Check for =
-        if i < len && bytes[i] == b'=' {
-            i += 1;
-            // Skip whitespace
-            while i < len && (bytes[i] == b' ' || bytes[i] == b'\t') {
-                i += 1;
-            }
-
-            // Parse attribute value
-            if i < len {
-                if bytes[i] == b'"' || bytes[i] == b'\'' {
-                    // String value
-                    let quote = bytes[i];
-                    i += 1;
-                    let value_start = i;
-                    while i < len && bytes[i] != quote {
-                        i += 1;
-                    }
-                    let value = String::from_utf8_lossy(&bytes[value_start..i]).to_string();
-                    i += 1; // Skip closing quote
-                    
-                    if js_attr_name == "key" {
-                        key_prop = Some(format!("\"{}\"", value));
-                    } else {
-                        props.push(format!("{}: \"{}\"", js_attr_name, value));
+    /// Evaluate enums and gather namespace members up front, so `const enum`
+    /// members and namespace exports used before their declaration are
+    /// rewritten too. `namespace` is the dotted path and block start of the
+    /// namespace body `body` belongs to.
+    fn collect_declarations(&mut self, body: &[Stmt], namespace: Option<(&str, usize)>) {
+        let mut declared = HashSet::new();
+        for stmt in body {
+            let (decl, export) = match stmt {
+                Stmt::ExportDecl(d) => (d.decl.as_ref(), true),
+                Stmt::ExportNamed(d) if d.source.is_none() => {
+                    self.enums.exported.extend(d.specifiers.iter().map(|s| s.local.name()));
+                    continue;
+                }
+                Stmt::ExportDefault(ExportDefault { decl: DefaultDecl::Expr(Expr::Ident(id)), .. }) => {
+                    self.enums.exported.insert(id.name.clone());
+                    continue;
+                }
+                stmt => (stmt, false),
+            };
+            if decl.is_type_only() {
+                // Members of ambient const enums are inlined all the same
+                if let Stmt::TsEnum(e) = decl {
+                    self.enum_body(e);
+                }
+                continue;
+            }
+            let names: Vec<String> = match decl {
+                Stmt::Var(_) | Stmt::Function(_) | Stmt::Class(_) => declared_names(decl),
+                Stmt::TsImportEquals(d) => {
+                    if let (true, Some((key, _))) = (d.is_export, namespace) {
+                        self.namespaces.exports.entry(key.to_string()).or_default().vars.insert(d.id.name.clone());
                     }
-                } else if bytes[i] == b'{' {
-                    // Expression value
-                    i += 1;
-                    let (expr, consumed) = parse_jsx_expression(&bytes[i..]);
-                    i += consumed;
-                    if i < len && bytes[i] == b'}' {
-                        i += 1;
+                    vec![d.id.name.clone()]
+                }
+                Stmt::TsEnum(e) => {
+                    if declared.contains(&e.id.name) {
+                        self.namespaces.merged.insert(e.span.start);
                     }
-                    
-                    if js_attr_name == "key" {
-                        key_prop = Some(expr);
-                    } else {
-                        props.push(format!("{}: {}", js_attr_name, expr));
+                    self.enum_body(e);
+                    vec![e.id.name.clone()]
+                }
+                Stmt::TsModule(m) => match (&m.name, &m.body) {
+                    (TsModuleName::Path(path), Some(block)) => {
+                        if declared.contains(&path[0].name) {
+                            self.namespaces.merged.insert(m.span.start);
+                        }
+                        let mut key = namespace.map(|(key, _)| key.to_string());
+                        for (i, id) in path.iter().enumerate() {
+                            // `namespace A.B` exports `B` from `A`
+                            if let (Some(key), true) = (&key, i > 0) {
+                                self.namespaces.exports.entry(key.clone()).or_default().decls.push((id.name.clone(), m.span.start));
+                            }
+                            key = Some(match key {
+                                Some(key) => format!("{}.{}", key, id.name),
+                                None => id.name.clone(),
+                            });
+                        }
+                        self.collect_declarations(&block.body, key.as_deref().map(|key| (key, block.span.start)));
+                        vec![path[0].name.clone()]
                     }
+                    _ => continue,
+                },
+                _ => continue,
+            };
+            if let (true, Some((key, block))) = (export, namespace) {
+                let exports = self.namespaces.exports.entry(key.to_string()).or_default();
+                match decl {
+                    Stmt::Var(_) => exports.vars.extend(names.iter().cloned()),
+                    _ => exports.decls.extend(names.iter().map(|name| (name.clone(), block))),
                 }
             }
-        } else {
-            // Boolean attribute (no value)
-            props.push(format!("{}: true", js_attr_name));
+            declared.extend(names);
         }
     }
 
-    // Parse children
-    let (children, consumed) = parse_jsx_children(&bytes[i..], options);
-    i += consumed;
+    /// Replace an enum declaration (`span`, with its `export` if any) by the
+    /// statements that build the enum object:
+    ///
+    /// ```text
+    /// var E;
+    /// (function (E) {
+    ///     E[E["A"] = 0] = "A";
+    /// })(E || (E = {}));
+    /// ```
+    ///
+    /// `keyword` declares the binding (`var`, `let` or `export var`) and
+    /// `parent` is the namespace that exports the enum, if any.
+    fn lower_enum(&mut self, decl: &TsEnumDecl, span: Span, keyword: &str, parent: Option<&str>) {
+        let name = decl.id.name.clone();
+        let lines = self.enum_body(decl);
+        let export = keyword.starts_with("export") || parent.is_some();
+        if decl.is_const && !export && !self.enums.exported.contains(&name) {
+            // Every use is inlined, so nothing needs the object at runtime
+            self.erase(span);
+            return;
+        }
 
-    // Skip closing tag </tagName>
-    if i + 2 < len && bytes[i] == b'<' && bytes[i+1] == b'/' {
-        i += 2;
-        // Skip tag name
-        while i < len && bytes[i] != b'>' {
-            i += 1;
+        let indent = self.line_indent(span.start);
+        let mut code = String::new();
+        if !self.namespaces.merged.contains(&decl.span.start) {
+            code.push_str(&format!("{} {};\n{}", keyword, name, indent));
         }
-        if i < len {
-            i += 1; // Skip >
+        code.push_str(&format!("(function ({}) {{\n", name));
+        for line in lines {
+            code.push_str(&format!("{}    {}\n", indent, line));
         }
+        code.push_str(&format!("{}}})({});", indent, iife_argument(&name, parent)));
+        self.edits.push(Edit::new(span.start, span.end, code));
+        self.had_types = true;
     }
 
-    let result = generate_jsx_call(&tag_ref, &props, &key_prop, &children, options);
-    (result, i)
-}
-
-/// Parse JSX children
-fn parse_jsx_children(bytes: &[u8], options: &TransformOptions) -> (Vec<String>, usize) {
-    let len = bytes.len();
-    let mut children: Vec<String> = Vec::new();
-    let mut i = 0;
-    let mut text_buffer = String::new();
-
-    while i < len {
-        // Check for closing tag
-        if i + 1 < len && bytes[i] == b'<' && bytes[i+1] == b'/' {
-            break;
-        }
+    /// Turn a namespace declaration into IIFEs that add the exported members
+    /// to the namespace object, one per name of a dotted path:
+    ///
+    /// ```text
+    /// var A;
+    /// (function (A) {
+    ///     A.x = 1;
+    /// })(A || (A = {}));
+    /// ```
+    ///
+    /// The body keeps its place in the source; `prefix` is the dotted path of
+    /// the enclosing namespace.
+    fn lower_namespace(&mut self, decl: &TsModuleDecl, span: Span, keyword: &str, parent: Option<&str>, prefix: &str) {
+        let (TsModuleName::Path(path), Some(body)) = (&decl.name, &decl.body) else {
+            self.erase(span);
+            return;
+        };
+        self.had_types = true;
+        let names: Vec<&str> = path.iter().map(|id| id.name.as_str()).collect();
+        let indent = self.line_indent(span.start);
 
-        // Check for child element
-        if bytes[i] == b'<' {
-            // Flush text buffer
-            if !text_buffer.trim().is_empty() {
-                children.push(format!("\"{}\"", escape_jsx_text(&text_buffer)));
+        let mut header = String::new();
+        let mut footer = String::new();
+        for (i, name) in names.iter().enumerate() {
+            let pad = format!("{}{}", indent, "    ".repeat(i));
+            match i {
+                0 if self.namespaces.merged.contains(&decl.span.start) => {}
+                0 => header.push_str(&format!("{} {};\n{}", keyword, name, indent)),
+                _ => header.push_str(&format!("\n{}let {};\n{}", pad, name, pad)),
             }
-            text_buffer.clear();
+            header.push_str(&format!("(function ({}) {{", name));
 
-            let (child, consumed) = parse_jsx_element(&bytes[i..], options);
-            if consumed > 0 {
-                children.push(child);
-                i += consumed;
-                continue;
-            }
+            let owner = if i == 0 { parent } else { Some(names[i - 1]) };
+            let close = format!("}})({});", iife_argument(name, owner));
+            footer = match i {
+                0 => close,
+                _ => format!("{}\n{}{}", close, pad.strip_suffix("    ").unwrap_or(&pad), footer),
+            };
         }
+        self.edits.push(Edit::new(span.start, body.span.start + 1, header));
+        self.edits.push(Edit::new(body.span.end - 1, body.span.end, footer));
 
-        // Check for expression {expr}
-        if bytes[i] == b'{' {
-            // Flush text buffer
-            if !text_buffer.trim().is_empty() {
-                children.push(format!("\"{}\"", escape_jsx_text(&text_buffer)));
+        // Members of the enclosing names of a dotted path, then of the body
+        let mut key = prefix.to_string();
+        for (i, name) in names.iter().enumerate() {
+            if !key.is_empty() {
+                key.push('.');
             }
-            text_buffer.clear();
-
-            i += 1;
-            let (expr, consumed) = parse_jsx_expression(&bytes[i..]);
-            children.push(expr);
-            i += consumed;
-            if i < len && bytes[i] == b'}' {
-                i += 1;
+            key.push_str(name);
+            let mut frame = Frame { namespace: Some(name.to_string()), ..Frame::default() };
+            if let Some(exports) = self.namespaces.exports.get(&key) {
+                let last = i == names.len() - 1;
+                frame.members.extend(exports.vars.iter().cloned());
+                let decls = exports.decls.iter().filter(|(_, block)| !last || *block != body.span.start);
+                frame.members.extend(decls.map(|(name, _)| name.clone()));
             }
-            continue;
+            self.namespaces.frames.push(frame);
+        }
+        let frame = self.namespaces.frames.last_mut().unwrap();
+        for stmt in body.body.iter().filter(|s| !matches!(s, Stmt::ExportDecl(_)) && !s.is_type_only()) {
+            frame.locals.extend(declared_names(stmt));
         }
 
-        // Regular text
-        text_buffer.push(bytes[i] as char);
-        i += 1;
-    }
-
-    // Flush remaining text
-    if !text_buffer.trim().is_empty() {
-        children.push(format!("\"{}\"", escape_jsx_text(&text_buffer)));
+        let namespace = names[names.len() - 1];
+        for stmt in &body.body {
+            self.namespace_stmt(stmt, namespace, &key);
+        }
+        self.namespaces.frames.truncate(self.namespaces.frames.len() - names.len());
     }
 
-    (children, i)
-}
-
-/// Parse a JSX expression (inside {})
-fn parse_jsx_expression(bytes: &[u8]) -> (String, usize) {
-    let len = bytes.len();
-    let mut i = 0;
-    let mut depth = 0;
-    let start = i;
-
-    while i < len {
-        match bytes[i] {
-            b'{' => depth += 1,
-            b'}' => {
-                if depth == 0 {
-                    break;
+    /// A statement directly inside the body of `namespace`, whose exports
+    /// become properties of the namespace object
+    fn namespace_stmt(&mut self, stmt: &Stmt, namespace: &str, path: &str) {
+        if stmt.is_type_only() {
+            self.erase(stmt.span());
+            return;
+        }
+        let Stmt::ExportDecl(export) = stmt else {
+            match stmt {
+                // `export import A = B.C` → `NS.A = B.C`
+                Stmt::TsImportEquals(decl) if decl.is_export => {
+                    let code = format!("{}.{} = {};", namespace, decl.id.name, self.module_reference(&decl.module_ref));
+                    self.edits.push(Edit::new(decl.span.start, decl.span.end, code));
                 }
-                depth -= 1;
+                Stmt::TsEnum(decl) => self.lower_enum(decl, decl.span, "let", None),
+                Stmt::TsModule(decl) => self.lower_namespace(decl, decl.span, "let", None, path),
+                _ => self.visit_stmt(stmt),
             }
-            b'"' | b'\'' | b'`' => {
-                // Skip string
-                let quote = bytes[i];
-                i += 1;
-                while i < len && bytes[i] != quote {
-                    if bytes[i] == b'\\' {
-                        i += 1;
+            return;
+        };
+        let decl = export.decl.as_ref();
+        let names = match decl {
+            Stmt::TsEnum(e) => return self.lower_enum(e, export.span, "let", Some(namespace)),
+            Stmt::TsModule(m) => return self.lower_namespace(m, export.span, "let", Some(namespace), path),
+            // `export const x = 1` → `NS.x = 1`
+            Stmt::Var(var) if var.decls.iter().all(|d| matches!(d.name, Pat::Ident(_))) => {
+                let mut assignments = Vec::new();
+                for d in &var.decls {
+                    if let (Pat::Ident(p), Some(init)) = (&d.name, &d.init) {
+                        assignments.push(format!("{}.{} = {};", namespace, p.id.name, self.render(init)));
                     }
-                    i += 1;
                 }
+                self.edits.push(Edit::new(export.span.start, export.span.end, assignments.join(" ")));
+                return;
             }
-            _ => {}
-        }
-        i += 1;
-    }
-
-    let expr = String::from_utf8_lossy(&bytes[start..i]).to_string();
-    (expr.trim().to_string(), i)
-}
-
-/// Generate the final JSX call
-fn generate_jsx_call(
-    tag: &str, 
-    props: &[String], 
-    key: &Option<String>,
-    children: &[String],
-    options: &TransformOptions
-) -> String {
-    if options.jsx_runtime == JsxRuntime::Automatic {
-        let has_children = !children.is_empty();
-        let func = if children.len() > 1 { "_jsxs" } else { "_jsx" };
-        
-        let mut all_props = props.to_vec();
-        if has_children {
-            if children.len() == 1 {
-                all_props.push(format!("children: {}", children[0]));
-            } else {
-                all_props.push(format!("children: [{}]", children.join(", ")));
-            }
-        }
-        
-        let props_obj = if all_props.is_empty() {
-            "{}".to_string()
-        } else {
-            format!("{{ {} }}", all_props.join(", "))
+            // Destructuring keeps its local bindings and copies them over
+            Stmt::Var(_) | Stmt::Function(_) | Stmt::Class(_) => declared_names(decl),
+            _ => Vec::new(),
         };
+        self.edits.push(Edit::delete(export.span.start, decl.span().start));
+        self.visit_stmt(decl);
+        let indent = self.line_indent(export.span.start);
+        let assignments: String = names
+            .iter()
+            .map(|name| format!("\n{}{}.{} = {};", indent, namespace, name, name))
+            .collect();
+        self.edits.push(Edit::new(export.span.end, export.span.end, assignments));
+    }
 
-        if let Some(k) = key {
-            format!("{}({}, {}, {})", func, tag, props_obj, k)
-        } else {
-            format!("{}({}, {})", func, tag, props_obj)
+    /// Value of an `import x = ...` declaration
+    fn module_reference(&self, module_ref: &TsModuleRef) -> String {
+        match module_ref {
+            TsModuleRef::Require(source) => format!("require({})", source.span.text(self.source)),
+            TsModuleRef::Entity(path) => path.iter().map(|id| id.name.as_str()).collect::<Vec<_>>().join("."),
         }
-    } else {
-        // Classic runtime
-        let props_obj = if props.is_empty() {
-            "null".to_string()
-        } else {
-            format!("{{ {} }}", props.join(", "))
-        };
+    }
 
-        let mut args = vec![tag.to_string(), props_obj];
-        args.extend(children.iter().cloned());
-        
-        format!("React.createElement({})", args.join(", "))
+    /// Erase TypeScript modifiers along with the space after them
+    fn erase_modifiers(&mut self, modifiers: &[Modifier]) {
+        for modifier in modifiers.iter().filter(|m| m.kind.is_typescript()) {
+            let end = self.after_space(modifier.span.end);
+            self.erase(Span::new(modifier.span.start, end));
+        }
     }
-}
 
-/// Convert JSX attribute name to JS property name
-fn convert_jsx_attr_name(name: &str) -> String {
-    match name {
-        "class" => "className".to_string(),
-        "for" => "htmlFor".to_string(),
-        "tabindex" => "tabIndex".to_string(),
-        "readonly" => "readOnly".to_string(),
-        "maxlength" => "maxLength".to_string(),
-        "cellpadding" => "cellPadding".to_string(),
-        "cellspacing" => "cellSpacing".to_string(),
-        "colspan" => "colSpan".to_string(),
-        "rowspan" => "rowSpan".to_string(),
-        "usemap" => "useMap".to_string(),
-        "frameborder" => "frameBorder".to_string(),
-        "contenteditable" => "contentEditable".to_string(),
-        "autocomplete" => "autoComplete".to_string(),
-        "autofocus" => "autoFocus".to_string(),
-        "autoplay" => "autoPlay".to_string(),
-        _ if name.starts_with("data-") || name.starts_with("aria-") => name.to_string(),
-        _ => name.to_string(),
-    }
-}
-
-/// Escape text for JSX
-fn escape_jsx_text(text: &str) -> String {
-    text.replace('\\', "\\\\")
-        .replace('"', "\\\"")
-        .replace('\n', "\\n")
-        .replace('\r', "\\r")
-        .replace('\t', "\\t")
-        .trim()
-        .to_string()
-}
-
-/// Add JSX runtime import
-fn add_jsx_import(source: &str, options: &TransformOptions) -> String {
-    let import_stmt = format!(
-        "import {{ jsx as _jsx, jsxs as _jsxs, Fragment as _Fragment }} from \"{}/jsx-runtime\";\n",
-        options.jsx_import_source
-    );
-    format!("{}{}", import_stmt, source)
-}
-
-/// Quick minification
-fn quick_minify(source: &str) -> String {
-    let mut result = source.to_string();
-    
-    // Remove single-line comments
-    let comment_re = regex::Regex::new(r"(?m)//[^\n]*$").unwrap();
-    result = comment_re.replace_all(&result, "").to_string();
-    
-    // Remove multi-line comments
-    let multi_comment_re = regex::Regex::new(r"/\*[\s\S]*?\*/").unwrap();
-    result = multi_comment_re.replace_all(&result, "").to_string();
-    
-    // Collapse whitespace
-    let whitespace_re = regex::Regex::new(r"\s+").unwrap();
-    result = whitespace_re.replace_all(&result, " ").to_string();
-    
-    // Remove spaces around operators
-    let operators_re = regex::Regex::new(r"\s*([=+\-*/<>!&|,;:{}()\[\]])\s*").unwrap();
-    result = operators_re.replace_all(&result, "$1").to_string();
-    
-    result.trim().to_string()
-}
+    /// Erase the TypeScript parts of class members, and assign parameter
+    /// properties (and, without `useDefineForClassFields`, instance field
+    /// initializers) in the constructor:
+    ///
+    /// ```text
+    /// class A {                              class A {
+    ///     y = 1;                                 constructor(x) {
+    ///     constructor(private x: number) {  →        this.x = x;
+    ///     }                                          this.y = 1;
+    /// }                                          }
+    ///                                        }
+    /// ```
+    ///
+    /// Before ES2022 all fields, static blocks and private members are lowered:
+    /// instance fields are assigned in the constructor, and static fields and
+    /// the storage of private members are set up after the class:
+    ///
+    /// ```text
+    /// class A {                  class A {
+    ///     static x = 1;              constructor() {
+    ///     #p = 2;            →           _A_p.set(this, 2);
+    /// }                              }
+    ///                            }
+    ///                            _A_p = new WeakMap();
+    ///                            A.x = 1;
+    /// ```
+    ///
+    /// Returns the starts of the members moved out of the class body.
+    fn lower_class_members(&mut self, class: &Class) -> HashSet<usize> {
+        let define = self.options.use_define_for_class_fields();
+        let statement = self.class_statement.take().filter(|(start, _)| *start == class.span.start).map(|(_, span)| span);
+        let keep = self.keeps_class_syntax(class);
+        let lowered = !keep
+            && class.body.iter().any(|member| match member {
+                ClassMember::Property(p) => !self.erased_field(p),
+                ClassMember::Method(m) => matches!(m.key, PropKey::Private(_)),
+                ClassMember::StaticBlock(_) => true,
+                _ => false,
+            });
+        let constructor = class.body.iter().find_map(|member| match member {
+            ClassMember::Method(m) if m.kind == MethodKind::Constructor && m.function.body.is_some() => Some(m),
+            _ => None,
+        });
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+        // `this.x = x` for parameter properties, then field initializers
+        let mut assignments = Vec::new();
+        let mut declarations = Vec::new();
+        for param in constructor.map_or(&[][..], |c| &c.function.params[..]) {
+            if !self.strip_types || !param.modifiers.iter().any(|m| m.kind.is_typescript()) {
+                continue;
+            }
+            self.erase_modifiers(&param.modifiers);
+            let id = match &param.pat {
+                Pat::Ident(p) => &p.id,
+                Pat::Assign(p) => match p.left.as_ref() {
+                    Pat::Ident(p) => &p.id,
+                    _ => continue,
+                },
+                _ => continue,
+            };
+            assignments.push(match define && !keep {
+                true => format!("{};", define_property("this", &quote_string(&id.name), &id.name)),
+                false => format!("this.{0} = {0};", id.name),
+            });
+            declarations.push(format!("{};", id.name));
+            self.had_types = true;
+        }
 
-    #[test]
-    fn test_remove_type_annotation() {
-        let source = "const x: number = 5;";
-        let (result, had_types) = remove_typescript_types(source);
-        assert!(had_types);
-        assert!(!result.contains(": number"));
+        // Code after the class: private storage and methods, then static
+        // initializers in order. Without a declared name to use there, the
+        // class becomes `(_a = class {}, ..., _a)`.
+        let after = lowered
+            && class.body.iter().any(|member| match member {
+                ClassMember::Property(p) => {
+                    !self.erased_field(p)
+                        && (p.is_static
+                            || matches!(p.key, PropKey::Private(_) | PropKey::Computed(_))
+                            || p.has_modifier(ModifierKind::Accessor))
+                }
+                ClassMember::Method(m) => matches!(m.key, PropKey::Private(_)),
+                ClassMember::StaticBlock(_) => true,
+                _ => false,
+            });
+        let alias = match (&class.id, statement) {
+            _ if !after => String::new(),
+            (Some(id), Some(_)) => id.name.clone(),
+            _ => self.temp(),
+        };
+        let inner_name = class.id.as_ref().filter(|_| statement.is_none()).map(|id| id.name.clone());
+        let class_this = (alias.clone(), inner_name);
+        let mut definitions = Vec::new();
+        let mut statics = Vec::new();
+        let mut moved = HashSet::new();
+        let (member_indent, _) = self.member_indent(class);
+        if lowered {
+            if let Some(brand) = self.bind_privates(class, &alias, &mut definitions) {
+                assignments.insert(0, format!("{}.add(this);", brand));
+            }
+        }
+
+        for member in &class.body {
+            match member {
+                ClassMember::Property(p) => {
+                    if self.erased_field(p) {
+                        self.erase(p.span);
+                        continue;
+                    }
+                    if self.lowers_accessor(p) {
+                        if lowered && matches!(p.key, PropKey::Private(_)) {
+                            let message = "Private auto-accessors need target \"esnext\"";
+                            self.errors.push((p.span, "unsupported-syntax", message.to_string()));
+                            continue;
+                        }
+                        let storage = lowered.then_some((&class_this, &mut definitions));
+                        match self.auto_accessor(p, &member_indent, storage) {
+                            Some(code) if p.is_static => statics.push(code),
+                            Some(code) => assignments.push(format!("{};", code)),
+                            None => {}
+                        }
+                        moved.insert(p.span.start);
+                        continue;
+                    }
+                    if lowered {
+                        match self.lower_field(p, &class_this, define, &mut definitions) {
+                            Some(code) if p.is_static => statics.push(code),
+                            Some(code) => assignments.push(format!("{};", code)),
+                            None => {}
+                        }
+                        self.edits.push(Edit::delete(p.span.start, p.span.end));
+                        moved.insert(p.span.start);
+                        continue;
+                    }
+                    if self.field_moved(p) {
+                        if let Some(value) = &p.value {
+                            let target = match &p.key {
+                                PropKey::Ident(id) => format!("this.{}", id.name),
+                                key => format!("this[{}]", key.span().text(self.source)),
+                            };
+                            assignments.push(format!("{} = {};", target, self.render(value)));
+                        }
+                        self.erase(p.span);
+                        continue;
+                    }
+                    if self.strip_types {
+                        self.erase_modifiers(&p.modifiers);
+                        self.erase_opt(p.optional);
+                        self.erase_opt(p.definite);
+                        self.erase_type_ann(&p.type_ann);
+                    }
+                    self.lower_key(&p.key);
+                }
+                ClassMember::Method(m) => {
+                    // Abstract methods and overload signatures have no body
+                    if self.strip_types && (m.has_modifier(ModifierKind::Abstract) || m.function.body.is_none()) {
+                        self.erase(m.span);
+                        continue;
+                    }
+                    if lowered && matches!(m.key, PropKey::Private(_)) {
+                        definitions.push(self.private_method(m));
+                        self.edits.push(Edit::delete(m.span.start, m.span.end));
+                        moved.insert(m.span.start);
+                        continue;
+                    }
+                    if self.strip_types {
+                        self.erase_modifiers(&m.modifiers);
+                        self.erase_opt(m.optional);
+                    }
+                    self.lower_key(&m.key);
+                    if self.lowers_async(&m.function) {
+                        self.erase_async(Span::new(m.span.start, m.key.span().start), m.function.is_generator);
+                    }
+                }
+                ClassMember::StaticBlock(b) if lowered => {
+                    self.lowering.class_this = Some(class_this.clone());
+                    let body = self.render_with(b.body.span, |inner| b.body.stmts.iter().for_each(|s| inner.visit_stmt(s)));
+                    self.lowering.class_this = None;
+                    statics.push(format!("(() => {})()", body));
+                    self.edits.push(Edit::delete(b.span.start, b.span.end));
+                    moved.insert(b.span.start);
+                }
+                ClassMember::IndexSignature(span) => self.erase(*span),
+                ClassMember::StaticBlock(_) | ClassMember::Empty(_) => {}
+            }
+        }
+
+        // Parameter properties are fields too under `[[Define]]` semantics
+        if define && keep && !declarations.is_empty() {
+            let code: String = declarations.iter().map(|d| format!("\n{}{}", member_indent, d)).collect();
+            self.edits.push(Edit::new(class.body_span.start + 1, class.body_span.start + 1, code));
+        }
+        let after: Vec<String> = definitions.into_iter().chain(statics).collect();
+        if !after.is_empty() {
+            match statement {
+                Some(stmt) if class.id.is_some() => {
+                    let indent = self.line_indent(stmt.start);
+                    let code: String = after.iter().map(|s| format!("\n{}{};", indent, s)).collect();
+                    self.edits.push(Edit::new(stmt.end, stmt.end, code));
+                }
+                _ => {
+                    self.edits.push(Edit::new(class.span.start, class.span.start, format!("({} = ", alias)));
+                    let code = format!(", {}, {})", after.join(", "), alias);
+                    self.edits.push(Edit::new(class.span.end, class.span.end, code));
+                }
+            }
+        }
+        self.add_to_constructor(class, assignments);
+        moved
+    }
+
+    /// Whether a class keeps its fields, static blocks and private members:
+    /// for ES2022 and up, and for classes with auto-accessors or decorators
+    /// other than legacy ones
+    fn keeps_class_syntax(&self, class: &Class) -> bool {
+        let decorated = !class.decorators.is_empty()
+            || class.body.iter().any(|member| match member {
+                ClassMember::Method(m) => !m.decorators.is_empty(),
+                ClassMember::Property(p) => !p.decorators.is_empty(),
+                _ => false,
+            });
+        self.options.target >= Target::ES2022 || decorated && self.options.decorators != Decorators::Legacy
+    }
+
+    /// Whether an `accessor` field becomes a getter and setter: below ESNext,
+    /// unless its decorators are applied or kept
+    fn lowers_accessor(&self, p: &ClassProperty) -> bool {
+        p.has_modifier(ModifierKind::Accessor)
+            && self.options.target < Target::ESNext
+            && (p.decorators.is_empty() || self.options.decorators == Decorators::Legacy)
+    }
+
+    /// Replace `accessor x = 1` with a getter and setter over private storage:
+    /// `#x_accessor_storage` from ES2022, and with `storage` (the replacement
+    /// for `this` in static initializers, and the code after the class) a
+    /// `WeakMap`, or `{ value }` for a static accessor. The storage
+    /// initializer is returned in that case.
+    fn auto_accessor(
+        &mut self,
+        p: &ClassProperty,
+        indent: &str,
+        storage: Option<(&ClassThis, &mut Vec<String>)>,
+    ) -> Option<String> {
+        let name = match &p.key {
+            PropKey::Private(id) => id.name.trim_start_matches('#').to_string(),
+            key => key.static_name().filter(|name| prop_key(name) == *name).unwrap_or_default(),
+        };
+        let (get_key, set_key, key_temp) = match &p.key {
+            PropKey::Computed(c) => {
+                let temp = self.key_temp(&c.expr);
+                (format!("[{} = {}]", temp, self.render(&c.expr)), format!("[{}]", temp), Some(temp))
+            }
+            key => {
+                let text = key.span().text(self.source).to_string();
+                (text.clone(), text, None)
+            }
+        };
+        let is_static = if p.is_static { "static " } else { "" };
+        let start = p.decorators.last().map_or(p.span.start, |d| self.after_space(d.span.end));
+        let Some((class_this, definitions)) = storage else {
+            // Keys that are not names share a base, which is made unique
+            let storage = match (key_temp, name.is_empty()) {
+                (Some(temp), _) => format!("#{}_accessor_storage", temp),
+                (None, true) => format!("#{}", self.unique_name("key_accessor_storage")),
+                (None, false) => format!("#{}_accessor_storage", name),
+            };
+            let value = p.value.as_ref().map(|value| format!(" = {}", self.render(value))).unwrap_or_default();
+            let code = format!(
+                "{0}{3}{4};\n{1}{0}get {2}() {{ return this.{3}; }}\n{1}{0}set {5}(value) {{ this.{3} = value; }}",
+                is_static, indent, get_key, storage, value, set_key
+            );
+            self.edits.push(Edit::new(start, p.span.end, code));
+            return None;
+        };
+
+        let base = class_this.0.trim_start_matches('_');
+        let name = if name.is_empty() { name } else { format!("{}_", name) };
+        let storage = self.unique_name(&format!("_{}_{}accessor_storage", base, name));
+        self.lowering.temps.push(storage.clone());
+        self.lowering.helpers = true;
+        if p.is_static {
+            self.lowering.class_this = Some(class_this.clone());
+        }
+        let value = p.value.as_ref().map_or("void 0".to_string(), |value| self.render(value));
+        self.lowering.class_this = None;
+        let (get, set, init) = match p.is_static {
+            true => (
+                format!("{}.classPrivateFieldGet(this, {}, \"f\", {})", LOWER, class_this.0, storage),
+                format!("{}.classPrivateFieldSet(this, {}, value, \"f\", {})", LOWER, class_this.0, storage),
+                format!("{} = {{ value: {} }}", storage, value),
+            ),
+            false => {
+                definitions.push(format!("{} = new WeakMap()", storage));
+                (
+                    format!("{}.classPrivateFieldGet(this, {}, \"f\")", LOWER, storage),
+                    format!("{}.classPrivateFieldSet(this, {}, value, \"f\")", LOWER, storage),
+                    format!("{}.set(this, {})", storage, value),
+                )
+            }
+        };
+        let code = format!(
+            "{0}get {2}() {{ return {3}; }}\n{1}{0}set {4}(value) {{ {5}; }}",
+            is_static, indent, get_key, get, set_key, set
+        );
+        self.edits.push(Edit::new(start, p.span.end, code));
+        Some(init)
+    }
+
+    /// Whether an instance field is assigned by the constructor, for
+    /// `[[Set]]` semantics
+    fn field_moved(&self, p: &ClassProperty) -> bool {
+        let movable = !matches!(p.key, PropKey::Computed(_) | PropKey::Private(_))
+            && !p.has_modifier(ModifierKind::Accessor);
+        let decorated = !p.decorators.is_empty() && self.options.decorators != Decorators::Legacy;
+        self.strip_types && !self.options.use_define_for_class_fields() && !p.is_static && movable && !decorated
+    }
+
+    /// Add statements to the start of the constructor, after `super()`,
+    /// creating the constructor if there is none. The pending constructor
+    /// prologue goes first.
+    fn add_to_constructor(&mut self, class: &Class, statements: Vec<String>) {
+        let mut statements = std::mem::take(&mut self.constructor_prologue).into_iter().chain(statements).peekable();
+        if statements.peek().is_none() {
+            return;
+        }
+        let constructor = class.body.iter().find_map(|member| match member {
+            ClassMember::Method(m) if m.kind == MethodKind::Constructor => Some((m.span, m.function.body.as_ref()?)),
+            _ => None,
+        });
+        let (member_indent, unit) = self.member_indent(class);
+        match constructor {
+            Some((span, body)) => {
+                // After `super()`, which must run before `this` is touched
+                let super_call = body.stmts.iter().find(|stmt| is_super_call(stmt));
+                if super_call.is_none() && class.super_class.is_some() {
+                    let mut calls = SuperCalls(false);
+                    body.stmts.iter().for_each(|stmt| calls.visit_stmt(stmt));
+                    if calls.0 {
+                        let message = "Initialized fields need `super()` at the top level of the constructor";
+                        self.errors.push((span, "unsupported-syntax", message.to_string()));
+                        return;
+                    }
+                }
+                let (pos, indent) = match (super_call, body.stmts.first()) {
+                    (Some(stmt), _) => (stmt.span().end, self.line_indent(stmt.span().start).to_string()),
+                    (None, Some(first)) => (body.span.start + 1, self.line_indent(first.span().start).to_string()),
+                    (None, None) => (body.span.start + 1, format!("{}{}", member_indent, unit)),
+                };
+                let mut code: String = match body.span.text(self.source).contains('\n') {
+                    true => statements.map(|a| format!("\n{}{}", indent, a)).collect(),
+                    // A body on one line stays on one line
+                    false if body.stmts.is_empty() => format!(" {} ", statements.collect::<Vec<_>>().join(" ")),
+                    false => statements.map(|a| format!(" {}", a)).collect(),
+                };
+                if super_call.is_some_and(|stmt| !stmt.span().text(self.source).ends_with(';')) {
+                    code.insert(0, ';');
+                }
+                self.edits.push(Edit::new(pos, pos, code));
+            }
+            None => {
+                let mut code = format!("\n{}constructor() {{", member_indent);
+                if class.super_class.is_some() {
+                    code.push_str(&format!("\n{}{}super(...arguments);", member_indent, unit));
+                }
+                for statement in statements {
+                    code.push_str(&format!("\n{}{}{}", member_indent, unit, statement));
+                }
+                code.push_str(&format!("\n{}}}", member_indent));
+                let body_start = class.body_span.start + 1;
+                self.edits.push(Edit::new(body_start, body_start, code));
+            }
+        }
+    }
+
+    /// Indentation of the members of a class, and the indentation unit
+    fn member_indent(&self, class: &Class) -> (String, String) {
+        let class_indent = self.line_indent(class.span.start);
+        let member_indent = match class.body.first().map(|m| self.line_indent(m.span().start)) {
+            Some(indent) if indent.len() > class_indent.len() => indent.to_string(),
+            _ => format!("{}    ", class_indent),
+        };
+        let unit = member_indent.strip_prefix(class_indent).unwrap_or("    ").to_string();
+        (member_indent, unit)
+    }
+
+    /// Lower the decorators of a class declaration or expression, if it has
+    /// any. Legacy decorator calls are returned, to be added once the class
+    /// has been visited.
+    fn lower_decorators(&mut self, class: &Class, span: Span, site: ClassSite) -> Option<LegacyCalls> {
+        match self.options.decorators {
+            Decorators::Legacy if site != ClassSite::Expression => return self.legacy_decorators(class, span),
+            Decorators::Standard => {
+                self.reject_param_decorators(class);
+                self.standard_decorators(class, span.start, site)
+            }
+            _ => {}
+        }
+        None
+    }
+
+    /// TC39 decorators don't apply to parameters; TypeScript rejects them
+    /// without `experimentalDecorators`. They are reported and erased.
+    fn reject_param_decorators(&mut self, class: &Class) {
+        for member in &class.body {
+            let ClassMember::Method(m) = member else { continue };
+            for param in &m.function.params {
+                let message = "Parameter decorators need the \"legacy\" decorators mode";
+                self.reject_decorators(&param.decorators, message, true);
+            }
+        }
+    }
+
+    /// Report decorators that cannot be lowered, erasing them unless they go
+    /// with the member they decorate
+    fn reject_decorators(&mut self, decorators: &[Decorator], message: &str, erase: bool) {
+        for decorator in decorators {
+            self.errors.push((decorator.span, "unsupported-decorator", message.to_string()));
+            if erase {
+                let end = self.after_space(decorator.span.end);
+                self.edits.push(Edit::delete(decorator.span.start, end));
+            }
+        }
+    }
+
+    /// Erase decorators along with the space after them, returning their expressions
+    fn take_decorators(&mut self, decorators: &[Decorator]) -> Vec<String> {
+        let mut exprs = Vec::new();
+        for decorator in decorators {
+            let end = self.after_space(decorator.span.end);
+            self.edits.push(Edit::delete(decorator.span.start, end));
+            exprs.push(self.render(&decorator.expr));
+        }
+        exprs
+    }
+
+    /// `__param` calls for the decorators of parameters, which are erased
+    fn param_decorators(&mut self, params: &[Param]) -> Vec<String> {
+        let mut calls = Vec::new();
+        let params = params.iter().filter(|p| !matches!(&p.pat, Pat::Ident(p) if p.id.name == "this"));
+        for (index, param) in params.enumerate() {
+            for decorator in self.take_decorators(&param.decorators) {
+                calls.push(format!("{}.p({}, {})", DECORATE, index, decorator));
+            }
+        }
+        calls
+    }
+
+    /// Position after the whitespace at `pos`
+    fn after_space(&self, pos: usize) -> usize {
+        let rest = &self.source[pos..];
+        pos + (rest.len() - rest.trim_start().len())
+    }
+
+    /// Start of the `class` keyword, after decorators, modifiers and `export`
+    fn class_keyword(&self, class: &Class) -> usize {
+        let from = class
+            .decorators
+            .iter()
+            .map(|d| d.span.end)
+            .chain(class.modifiers.iter().map(|m| m.span.end))
+            .fold(class.span.start, usize::max);
+        from + self.source[from..].find("class").unwrap_or(0)
+    }
+
+    /// Decorate a class like TypeScript's `experimentalDecorators`, with
+    /// helper calls after the declaration:
+    ///
+    /// ```text
+    /// @sealed                 class A {
+    /// class A {                   m() {}
+    ///     @log m() {}     →   }
+    /// }                       __fuse_decorate.d([log], A.prototype, "m", null);
+    ///                         A = __fuse_decorate.d([sealed], A);
+    /// ```
+    ///
+    /// With class decorators, references to the class in its body go through
+    /// an alias that the decorated class is assigned to as well.
+    fn legacy_decorators(&mut self, class: &Class, span: Span) -> Option<LegacyCalls> {
+        let metadata = self.options.emit_decorator_metadata;
+        let name = match &class.id {
+            Some(id) => id.name.clone(),
+            None => {
+                // `export default class {}` needs a binding to reassign
+                let pos = self.class_keyword(class) + "class".len();
+                self.edits.push(Edit::new(pos, pos, " _default"));
+                "_default".to_string()
+            }
+        };
+
+        let mut calls = Vec::new();
+        let mut class_decorators = self.take_decorators(&class.decorators);
+        for member in &class.body {
+            match member {
+                ClassMember::Property(p) if !p.decorators.is_empty() => {
+                    let erased = !self.keeps_class_syntax(class) && !p.has_modifier(ModifierKind::Accessor);
+                    if matches!(p.key, PropKey::Private(_)) {
+                        let message = "Legacy decorators cannot decorate private members";
+                        self.reject_decorators(&p.decorators, message, !self.field_moved(p) && !erased);
+                        continue;
+                    }
+                    let Some(key) = self.legacy_key(&p.key) else { continue };
+                    let mut decorators = match self.field_moved(p) || erased {
+                        // The field is erased with its decorators
+                        true => p.decorators.iter().map(|d| self.render(&d.expr)).collect(),
+                        false => self.take_decorators(&p.decorators),
+                    };
+                    if metadata {
+                        decorators.push(design_metadata("type", &metadata_type(p.type_ann.as_ref().map(|a| &a.ty))));
+                    }
+                    let target = legacy_target(&name, p.is_static);
+                    calls.push(format!("{}.d([{}], {}, {}, void 0);", DECORATE, decorators.join(", "), target, key));
+                }
+                ClassMember::Method(m) if m.function.body.is_some() => {
+                    let params = &m.function.params;
+                    if matches!(m.key, PropKey::Private(_)) {
+                        // Lowered private methods are erased with their decorators
+                        let message = "Legacy decorators cannot decorate private members";
+                        let kept = self.keeps_class_syntax(class);
+                        self.reject_decorators(&m.decorators, message, kept);
+                        for param in params {
+                            self.reject_decorators(&param.decorators, message, kept);
+                        }
+                        continue;
+                    }
+                    if m.kind == MethodKind::Constructor {
+                        // Constructor parameter decorators apply to the class
+                        class_decorators.extend(self.param_decorators(params));
+                        continue;
+                    }
+                    let mut decorators = self.take_decorators(&m.decorators);
+                    decorators.extend(self.param_decorators(params));
+                    let Some(key) = self.legacy_key(&m.key).filter(|_| !decorators.is_empty()) else { continue };
+                    if metadata {
+                        let return_type = m.function.return_type.as_ref().map(|a| &a.ty);
+                        match m.kind {
+                            MethodKind::Getter => decorators.push(design_metadata("type", &metadata_type(return_type))),
+                            MethodKind::Setter => {
+                                let ty = metadata_type(params.first().and_then(param_type));
+                                decorators.push(design_metadata("type", &ty));
+                                decorators.push(design_metadata("paramtypes", &format!("[{}]", ty)));
+                            }
+                            _ => {
+                                decorators.push(design_metadata("type", "Function"));
+                                decorators.push(design_metadata("paramtypes", &param_types(params)));
+                                let returns = match return_type {
+                                    None if m.function.is_async => "Promise".to_string(),
+                                    None => "void 0".to_string(),
+                                    ty => metadata_type(ty),
+                                };
+                                decorators.push(design_metadata("returntype", &returns));
+                            }
+                        }
+                    }
+                    let target = legacy_target(&name, m.is_static);
+                    calls.push(format!("{}.d([{}], {}, {}, null);", DECORATE, decorators.join(", "), target, key));
+                }
+                _ => {}
+            }
+        }
+        if !class_decorators.is_empty() {
+            let constructor = class.body.iter().find_map(|member| match member {
+                ClassMember::Method(m) if m.kind == MethodKind::Constructor && m.function.body.is_some() => Some(m),
+                _ => None,
+            });
+            if let Some(constructor) = constructor.filter(|_| metadata) {
+                class_decorators.push(design_metadata("paramtypes", &param_types(&constructor.function.params)));
+            }
+            if class.id.is_some() {
+                let alias = self.unique_name(&format!("{}_1", name));
+                self.class_aliases.push(ClassAlias { name: name.clone(), alias, used: false });
+            }
+        }
+        let indent = self.line_indent(span.start).to_string();
+        Some(LegacyCalls { name, members: calls, class: class_decorators, end: span.end, indent })
+    }
+
+    /// Add the calls of `legacy_decorators` after the class and the static
+    /// initializers moved out of it. `index` is the number of edits before
+    /// the class was visited, where the alias assignment goes.
+    fn finish_legacy_decorators(&mut self, calls: LegacyCalls, index: usize) {
+        let LegacyCalls { name, mut members, class, end, indent } = calls;
+        if !class.is_empty() {
+            let alias = match self.class_aliases.pop() {
+                Some(alias) if alias.used => alias.alias,
+                _ => String::new(),
+            };
+            let target = match alias.is_empty() {
+                true => name.clone(),
+                false => {
+                    self.lowering.temps.push(alias.clone());
+                    let code = format!("\n{}{} = {};", indent, alias, name);
+                    self.edits.insert(index, Edit::new(end, end, code));
+                    format!("{} = {}", name, alias)
+                }
+            };
+            members.push(format!("{} = {}.d([{}], {});", target, DECORATE, class.join(", "), name));
+        }
+        if members.is_empty() {
+            return;
+        }
+        self.decorate_helpers = true;
+        let code: String = members.iter().map(|call| format!("\n{}{}", indent, call)).collect();
+        self.edits.push(Edit::new(end, end, code));
+    }
+
+    /// Alias of a class with legacy class decorators whose body refers to it
+    fn class_alias(&mut self, name: &str) -> Option<String> {
+        let alias = self.class_aliases.iter_mut().rev().find(|alias| alias.name == name)?;
+        alias.used = true;
+        Some(alias.alias.clone())
+    }
+
+    /// Property name argument of a legacy decorator call. Computed keys are
+    /// assigned to a temporary where the class evaluates them.
+    fn legacy_key(&mut self, key: &PropKey) -> Option<String> {
+        Some(match key {
+            PropKey::Ident(id) => quote_string(&id.name),
+            PropKey::Str(_) | PropKey::Num(_) | PropKey::BigInt(_) => key.span().text(self.source).to_string(),
+            PropKey::Computed(c) if is_simple(&c.expr) || matches!(*c.expr, Expr::Lit(_)) => self.render(&c.expr),
+            PropKey::Computed(c) => {
+                let temp = self.temp();
+                let pos = c.expr.span().start;
+                self.edits.push(Edit::new(pos, pos, format!("{} = ", temp)));
+                self.lowering.keys.insert(pos, temp.clone());
+                temp
+            }
+            PropKey::Private(_) => return None,
+        })
+    }
+
+    /// Decorate a class with TC39 decorators (2023-05) the way TypeScript
+    /// does: the class is built inside an arrow function, and a static block
+    /// at the top of its body applies the decorators through `esDecorate`.
+    ///
+    /// ```text
+    ///                         let A = (() => {
+    ///                             let _classDecorators = [sealed];
+    /// @sealed                     ...
+    /// class A {           →       var A = class {
+    ///     @log m() {}                 static { _classThis = this; }
+    /// }                               static { ...esDecorate(...); }
+    ///                                 m() {}
+    ///                             };
+    ///                             return A = _classThis;
+    ///                         })();
+    /// ```
+    ///
+    /// Instance extra initializers run in the constructor. Decorators of
+    /// private methods and accessors are reported and erased.
+    fn standard_decorators(&mut self, class: &Class, start: usize, site: ClassSite) {
+        for member in &class.body {
+            match member {
+                ClassMember::Method(m) if matches!(m.key, PropKey::Private(_)) => {
+                    let message = "Decorators on private methods and accessors are not supported";
+                    self.reject_decorators(&m.decorators, message, true);
+                }
+                _ => {}
+            }
+        }
+        let members: Vec<&ClassMember> = class
+            .body
+            .iter()
+            .filter(|member| match member {
+                ClassMember::Property(p) => {
+                    !p.decorators.is_empty() && !p.has_modifier(ModifierKind::Declare) && !p.has_modifier(ModifierKind::Abstract)
+                }
+                ClassMember::Method(m) => {
+                    !m.decorators.is_empty()
+                        && m.kind != MethodKind::Constructor
+                        && m.function.body.is_some()
+                        && !matches!(m.key, PropKey::Private(_))
+                }
+                _ => false,
+            })
+            .collect();
+        let class_decorated = !class.decorators.is_empty();
+        if !class_decorated && members.is_empty() {
+            return;
+        }
+        // The decorated class relies on static blocks, fields and private names
+        if self.options.target < Target::ES2022 {
+            let span = Span::new(class.span.start, class.body_span.start);
+            let message = "2023-05 decorators need target \"es2022\" or later";
+            self.errors.push((span, "unsupported-decorator", message.to_string()));
+        }
+        self.decorate_helpers = true;
+        let indent = self.line_indent(start);
+        let (member_indent, unit) = self.member_indent(class);
+        let name = match (&class.id, site) {
+            (Some(id), _) => id.name.clone(),
+            (None, ClassSite::ExportDefault) => "_default".to_string(),
+            (None, _) => "_class".to_string(),
+        };
+
+        let mut lets = Vec::new();
+        if class_decorated {
+            let decorators: Vec<String> = class.decorators.iter().map(|d| self.render(&d.expr)).collect();
+            lets.push(format!("let _classDecorators = [{}];", decorators.join(", ")));
+            lets.push("let _classDescriptor;".to_string());
+            lets.push("let _classExtraInitializers = [];".to_string());
+            lets.push("let _classThis;".to_string());
+        }
+        let parent_metadata = match &class.super_class {
+            Some(super_class) => {
+                // Evaluated once, for `extends` and the inherited metadata
+                let span = super_class.span();
+                self.edits.push(Edit::new(span.start, span.start, "(_classSuper = "));
+                self.edits.push(Edit::new(span.end, span.end, ")"));
+                lets.push("let _classSuper;".to_string());
+                "_classSuper[Symbol.metadata] ?? null"
+            }
+            None => "null",
+        };
+
+        // Decorators apply to static then instance methods and accessors,
+        // then to static then instance fields
+        let order = |member: &&ClassMember| match member {
+            ClassMember::Property(p) if !p.has_modifier(ModifierKind::Accessor) => 2 + !p.is_static as u8,
+            ClassMember::Property(p) => !p.is_static as u8,
+            ClassMember::Method(m) => !m.is_static as u8,
+            _ => 0,
+        };
+        let mut members = members;
+        members.sort_by_key(order);
+
+        let mut names = HashSet::new();
+        let mut member_lets = Vec::new();
+        let mut calls = Vec::new();
+        let (mut statics, mut instances) = (false, false);
+        for member in members {
+            let (decorators, key, is_static) = match member {
+                ClassMember::Property(p) => (&p.decorators, &p.key, p.is_static),
+                ClassMember::Method(m) => (&m.decorators, &m.key, m.is_static),
+                _ => continue,
+            };
+            let (kind, prefix) = match member {
+                ClassMember::Property(p) if p.has_modifier(ModifierKind::Accessor) => ("accessor", ""),
+                ClassMember::Property(_) => ("field", ""),
+                ClassMember::Method(m) if m.kind == MethodKind::Getter => ("getter", "get_"),
+                ClassMember::Method(m) if m.kind == MethodKind::Setter => ("setter", "set_"),
+                _ => ("method", ""),
+            };
+            let var = decorator_variable(key, is_static, prefix, &mut names);
+            let exprs = self.take_decorators(decorators);
+            member_lets.push(format!("let {}_decorators = [{}];", var, exprs.join(", ")));
+
+            let (member_name, path) = match (member, key) {
+                // The accessor's storage takes the place of the key
+                (ClassMember::Property(p), _) if kind == "accessor" => self.lower_accessor(p, &var, &member_indent, &mut member_lets),
+                (_, PropKey::Computed(c)) => {
+                    let temp = format!("{}_key", var);
+                    member_lets.push(format!("let {};", temp));
+                    let pos = c.expr.span().start;
+                    self.edits.push(Edit::new(pos, pos, format!("{} = ", temp)));
+                    (temp.clone(), format!("[{}]", temp))
+                }
+                _ => member_access(key, self.source),
+            };
+            let has = match key {
+                PropKey::Private(id) => format!("has: obj => {} in obj", id.name),
+                _ => format!("has: obj => {} in obj", member_name),
+            };
+            let get = format!("get: obj => obj{}", path);
+            let set = format!("set: (obj, value) => {{ obj{} = value; }}", path);
+            let access = match kind {
+                "method" | "getter" => format!("{}, {}", has, get),
+                "setter" => format!("{}, {}", has, set),
+                _ => format!("{}, {}, {}", has, get, set),
+            };
+            let extra = match is_static {
+                true => "_staticExtraInitializers",
+                false => "_instanceExtraInitializers",
+            };
+            statics |= is_static;
+            instances |= !is_static;
+
+            let (receiver, initializers) = match member {
+                ClassMember::Property(p) => {
+                    let initializers = format!("{}_initializers", var);
+                    member_lets.push(format!("let {} = [];", initializers));
+                    let run = format!("{}.runInitializers(this, {}, ", DECORATE, initializers);
+                    match &p.value {
+                        Some(value) => {
+                            let span = value.span();
+                            self.edits.push(Edit::new(span.start, span.start, run));
+                            self.edits.push(Edit::new(span.end, span.end, ")"));
+                        }
+                        None => {
+                            let pos = [p.optional, p.definite, p.type_ann.as_ref().map(|a| a.span)]
+                                .into_iter()
+                                .flatten()
+                                .map(|s| s.end)
+                                .fold(key.span().end, usize::max);
+                            self.edits.push(Edit::new(pos, pos, format!(" = {}void 0)", run)));
+                        }
+                    }
+                    (if kind == "accessor" { "this" } else { "null" }, initializers)
+                }
+                _ => ("this", "null".to_string()),
+            };
+            calls.push(format!(
+                "{}.esDecorate({}, null, {}_decorators, {{ kind: \"{}\", name: {}, static: {}, private: {}, access: {{ {} }}, metadata: _metadata }}, {}, {});",
+                DECORATE,
+                receiver,
+                var,
+                kind,
+                member_name,
+                is_static,
+                matches!(key, PropKey::Private(_)),
+                access,
+                initializers,
+                extra,
+            ));
+        }
+        if statics {
+            lets.push("let _staticExtraInitializers = [];".to_string());
+        }
+        if instances {
+            lets.push("let _instanceExtraInitializers = [];".to_string());
+            let code = format!("{}.runInitializers(this, _instanceExtraInitializers);", DECORATE);
+            self.constructor_prologue.push(code);
+        }
+        lets.extend(member_lets);
+
+        // The static block running the decorators comes first in the body
+        let this_class = if class_decorated { "_classThis" } else { "this" };
+        let mut block = vec![format!(
+            "const _metadata = typeof Symbol === \"function\" && Symbol.metadata ? Object.create({}) : void 0;",
+            parent_metadata
+        )];
+        block.extend(calls);
+        if class_decorated {
+            block.push(format!(
+                "{}.esDecorate(null, _classDescriptor = {{ value: _classThis }}, _classDecorators, {{ kind: \"class\", name: _classThis.name, metadata: _metadata }}, null, _classExtraInitializers);",
+                DECORATE
+            ));
+            block.push(format!("{} = _classThis = _classDescriptor.value;", name));
+        }
+        block.push(format!(
+            "if (_metadata) Object.defineProperty({}, Symbol.metadata, {{ enumerable: true, configurable: true, writable: true, value: _metadata }});",
+            this_class
+        ));
+        if statics {
+            block.push(format!("{}.runInitializers(this, _staticExtraInitializers);", DECORATE));
+        }
+        let mut code = String::new();
+        if class_decorated {
+            code.push_str(&format!("\n{}static {{ _classThis = this; }}", member_indent));
+        }
+        code.push_str(&format!("\n{}static {{", member_indent));
+        for statement in &block {
+            code.push_str(&format!("\n{}{}{}", member_indent, unit, statement));
+        }
+        code.push_str(&format!("\n{}}}", member_indent));
+        let body_start = class.body_span.start + 1;
+        self.edits.push(Edit::new(body_start, body_start, code));
+
+        if class_decorated {
+            // Class extra initializers run once the class is complete
+            let pos = class.body.last().map_or(body_start, |m| m.span().end);
+            let code = format!(
+                "\n{}static {{ {}.runInitializers(_classThis, _classExtraInitializers); }}",
+                member_indent, DECORATE
+            );
+            self.edits.push(Edit::new(pos, pos, code));
+            // The class binding refers to the decorated class
+            if let Some(id) = &class.id {
+                let end = self.after_space(id.span.end);
+                self.edits.push(Edit::delete(id.span.start, end));
+            }
+        }
+
+        // Wrap the class in an arrow function, replacing `export` and decorators
+        let inner = format!("{}    ", indent);
+        let mut header = String::new();
+        match site {
+            ClassSite::Expression => header.push_str("(() => {"),
+            ClassSite::Export => header.push_str(&format!("export let {} = (() => {{", name)),
+            _ => header.push_str(&format!("let {} = (() => {{", name)),
+        }
+        for declaration in &lets {
+            header.push_str(&format!("\n{}{}", inner, declaration));
+        }
+        header.push_str(&format!("\n{}var {} = ", inner, name));
+        self.edits.push(Edit::new(start, self.class_keyword(class), header));
+
+        let result = if class_decorated { format!("{} = _classThis", name) } else { name.clone() };
+        let mut footer = format!(";\n{}return {};\n{}}})()", inner, result, indent);
+        match site {
+            ClassSite::Expression => {}
+            ClassSite::ExportDefault => footer.push_str(&format!(";\n{}export default {};", indent, name)),
+            _ => footer.push(';'),
+        }
+        self.edits.push(Edit::new(class.span.end, class.span.end, footer));
+    }
+
+    /// Replace an `accessor` field with a private storage field and a getter
+    /// and setter, returning the member name and access path
+    fn lower_accessor(&mut self, p: &ClassProperty, var: &str, indent: &str, lets: &mut Vec<String>) -> (String, String) {
+        let storage = format!("#{}_accessor_storage", var.trim_start_matches('_'));
+        let (name, path, get_key, set_key) = match &p.key {
+            PropKey::Computed(c) => {
+                let temp = format!("{}_key", var);
+                lets.push(format!("let {};", temp));
+                let get_key = format!("[{} = {}]", temp, self.render(&c.expr));
+                (temp.clone(), format!("[{}]", temp), get_key, format!("[{}]", temp))
+            }
+            key => {
+                let (name, path) = member_access(key, self.source);
+                let text = key.span().text(self.source).to_string();
+                (name, path, text.clone(), text)
+            }
+        };
+        for modifier in p.modifiers.iter().filter(|m| m.kind == ModifierKind::Accessor) {
+            let end = self.after_space(modifier.span.end);
+            self.edits.push(Edit::delete(modifier.span.start, end));
+        }
+        let key = p.key.span();
+        self.edits.push(Edit::new(key.start, key.end, storage.clone()));
+        let is_static = if p.is_static { "static " } else { "" };
+        let semicolon = if p.span.text(self.source).ends_with(';') { "" } else { ";" };
+        let code = format!(
+            "{0}\n{1}{2}get {3}() {{ return this.{5}; }}\n{1}{2}set {4}(value) {{ this.{5} = value; }}",
+            semicolon, indent, is_static, get_key, set_key, storage
+        );
+        self.edits.push(Edit::new(p.span.end, p.span.end, code));
+        (name, path)
+    }
+
+    /// Namespace object to read `name` from, unless a local binding shadows it
+    fn namespace_member(&self, name: &str) -> Option<&str> {
+        for frame in self.namespaces.frames.iter().rev() {
+            if frame.locals.contains(name) {
+                return None;
+            }
+            if frame.members.contains(name) {
+                return frame.namespace.as_deref();
+            }
+        }
+        None
+    }
+
+    /// Replace a reference to a namespace member with a property access
+    fn rewrite_member_reference(&mut self, id: &Ident) {
+        if let Some(namespace) = self.namespace_member(&id.name) {
+            let code = format!("{}.{}", namespace, id.name);
+            self.edits.push(Edit::new(id.span.start, id.span.end, code));
+        }
+    }
+
+    /// Enter a function inside a namespace; its bindings shadow namespace members
+    fn enter_function(&mut self, params: &[Param], body: &[Stmt]) -> bool {
+        if self.namespaces.frames.is_empty() {
+            return false;
+        }
+        let mut frame = Frame::default();
+        frame.locals.extend(params.iter().flat_map(|p| p.pat.bound_names()).map(|id| id.name.clone()));
+        let mut bindings = Bindings(&mut frame.locals);
+        body.iter().for_each(|stmt| bindings.visit_stmt(stmt));
+        self.namespaces.frames.push(frame);
+        true
+    }
+
+
+    /// The assignments that add each member to the enum object, recording the
+    /// values known at compile time
+    fn enum_body(&mut self, decl: &TsEnumDecl) -> Vec<String> {
+        let name = &decl.id.name;
+        if decl.is_const {
+            self.enums.consts.insert(name.clone());
+        }
+        let mut known = self.enums.values.remove(name).unwrap_or_default();
+        let mut lines = Vec::new();
+        let mut next = Some(0.0);
+        let mut previous: Option<String> = None;
+        for member in &decl.members {
+            let Some(key) = member.id.static_name() else {
+                continue;
+            };
+            let target = format!("{}[{}]", name, quote_string(&key));
+            let constant = match &member.init {
+                Some(init) => self.enum_constant(init, name, &known),
+                None => next.map(Literal::Number),
+            };
+            next = match &constant {
+                Some(Literal::Number(n)) => Some(n + 1.0),
+                _ => None,
+            };
+            let value = match (&constant, &member.init) {
+                (Some(value), _) => enum_value(value).unwrap_or_default(),
+                (None, Some(init)) => {
+                    let members = decl.members.iter().filter_map(|m| m.id.static_name()).collect();
+                    self.enums.members = Some((name.clone(), members));
+                    let code = self.render(init);
+                    self.enums.members = None;
+                    code
+                }
+                // Follows a member whose value is only known at runtime
+                (None, None) => format!("{}[{}] + 1", name, quote_string(previous.as_deref().unwrap_or_default())),
+            };
+            lines.push(match constant {
+                Some(Literal::String(_)) => format!("{} = {};", target, value),
+                _ => format!("{}[{} = {}] = {};", name, target, value, quote_string(&key)),
+            });
+            if let Some(value) = constant {
+                known.insert(key.clone(), value);
+            }
+            previous = Some(key);
+        }
+        self.enums.values.insert(name.clone(), known);
+        lines
+    }
+
+    /// Value of an enum member initializer, if it is a constant expression
+    /// over literals and members of enums declared so far
+    fn enum_constant(&self, expr: &Expr, enum_name: &str, members: &HashMap<String, Literal>) -> Option<Literal> {
+        let value = match expr {
+            Expr::Lit(Lit { value: LitValue::Num(n), .. }) => Literal::Number(*n),
+            Expr::Lit(Lit { value: LitValue::Str(s), .. }) => Literal::String(s.clone()),
+            Expr::Template(_) => Literal::String(expr.as_static_string(self.source)?),
+            Expr::Paren(p) => return self.enum_constant(&p.expr, enum_name, members),
+            Expr::Unary(u) => match (u.op, self.enum_constant(&u.arg, enum_name, members)?) {
+                (UnaryOp::Minus, Literal::Number(n)) => Literal::Number(-n),
+                (UnaryOp::Plus, Literal::Number(n)) => Literal::Number(n),
+                (UnaryOp::BitNot, Literal::Number(n)) => Literal::Number(!to_int32(n) as f64),
+                _ => return None,
+            },
+            Expr::Binary(b) => {
+                let left = self.enum_constant(&b.left, enum_name, members)?;
+                let right = self.enum_constant(&b.right, enum_name, members)?;
+                fold_binary(&left, b.op.as_str(), &right)?
+            }
+            Expr::Ident(id) => match members.get(&id.name) {
+                Some(value) => value.clone(),
+                None if id.name == "Infinity" => Literal::Number(f64::INFINITY),
+                None if id.name == "NaN" => Literal::Number(f64::NAN),
+                None => return None,
+            },
+            Expr::Member(m) => {
+                let object = &m.object.as_ident()?.name;
+                let property = m.property.static_name()?;
+                let values = match object == enum_name {
+                    true => members,
+                    false => self.enums.values.get(object)?,
+                };
+                values.get(property)?.clone()
+            }
+            _ => return None,
+        };
+        enum_value(&value).map(|_| value)
+    }
+
+    /// `E.A` of a `const enum`, replaced by its value
+    fn inline_enum_member(&self, member: &MemberExpr) -> Option<String> {
+        let object = &member.object.as_ident()?.name;
+        if !self.enums.consts.contains(object) {
+            return None;
+        }
+        let property = member.property.static_name()?;
+        let value = enum_value(self.enums.values.get(object)?.get(property)?)?;
+        let comment = format!("{}.{}", object, property).replace("*/", "*\\/");
+        Some(match value.starts_with('-') {
+            true => format!("({} /* {} */)", value, comment),
+            false => format!("{} /* {} */", value, comment),
+        })
+    }
+
+    /// Whitespace in front of `pos` on its line
+    fn line_indent(&self, pos: usize) -> &'a str {
+        let line_start = self.source[..pos].rfind('\n').map_or(0, |i| i + 1);
+        let prefix = &self.source[line_start..pos];
+        match prefix.trim_start().is_empty() {
+            true => prefix,
+            false => "",
+        }
+    }
+
+    /// `__source` object for JSX at `pos`, in development mode
+    fn jsx_source_location(&self, pos: usize) -> Option<String> {
+        let (_, line_index) = self.jsx_source?;
+        let (line, column) = line_index.position(self.source, pos);
+        Some(format!(
+            "{{ fileName: _jsxFileName, lineNumber: {}, columnNumber: {} }}",
+            line + 1,
+            column + 1
+        ))
+    }
+
+    /// `__self` argument
+    fn jsx_self(&self) -> &'static str {
+        match self.this_allowed {
+            true => "this",
+            false => "void 0",
+        }
+    }
+
+    /// Local name of a JSX runtime helper, recording that the module uses it
+    /// Whether object spread becomes `Object.assign`, before ES2018
+    fn assign_spread(&self) -> bool {
+        self.options.target < Target::ES2018
+    }
+
+    fn jsx_helper(&mut self, helper: JsxHelper) -> &'static str {
+        if !self.jsx_helpers.contains(&helper) {
+            self.jsx_helpers.push(helper);
+        }
+        helper.local()
+    }
+
+    fn jsx_element(&mut self, element: &JsxElement) -> String {
+        self.had_jsx = true;
+        let tag = jsx_tag(&element.opening.name);
+        let automatic = self.options.jsx_runtime != JsxRuntime::Classic;
+
+        let mut props = Vec::new();
+        for attr in &element.opening.attrs {
+            let attr = match attr {
+                JsxAttrOrSpread::Spread(spread) => {
+                    props.push(JsxProp::Spread(format!("...{}", self.render(&spread.expr))));
+                    continue;
+                }
+                JsxAttrOrSpread::Attr(attr) => attr,
+            };
+            let value = match &attr.value {
+                None => "true".to_string(),
+                Some(JsxAttrValue::Str(lit)) => string_literal(&attr_value(lit.value.as_str().unwrap_or_default())),
+                Some(JsxAttrValue::Expr(container)) => match &container.expr {
+                    Some(expr) => self.render(expr),
+                    None => continue,
+                },
+                Some(JsxAttrValue::Element(e)) => self.jsx_element(e),
+                Some(JsxAttrValue::Fragment(f)) => self.jsx_fragment(f),
+            };
+            let name = attr.name.name();
+            match name == "key" && automatic {
+                true => props.push(JsxProp::Key(value)),
+                false => props.push(JsxProp::Prop(format!("{}: {}", prop_key(&name), value))),
+            }
+        }
+        let children = self.jsx_children(&element.children);
+        if !automatic {
+            let props: Vec<String> = props.into_iter().map(JsxProp::into_prop).collect();
+            return create_element_call(&self.options.jsx_factory, &tag, &props, &children, self.assign_spread());
+        }
+
+        // Only `createElement` lets a `key` after a spread override the spread's
+        let first_spread = props.iter().position(|p| matches!(p, JsxProp::Spread(_)));
+        if first_spread.is_some_and(|i| props[i..].iter().any(|p| matches!(p, JsxProp::Key(_)))) {
+            let mut props: Vec<String> = props.into_iter().map(JsxProp::into_prop).collect();
+            if let Some(source) = self.jsx_source_location(element.span.start) {
+                props.push(format!("__source: {}", source));
+                props.push(format!("__self: {}", self.jsx_self()));
+            }
+            let callee = self.jsx_helper(JsxHelper::CreateElement);
+            return create_element_call(callee, &tag, &props, &children, self.assign_spread());
+        }
+        let mut key = None;
+        let mut rest = Vec::new();
+        for prop in props {
+            match prop {
+                JsxProp::Key(value) => key = key.or(Some(value)),
+                JsxProp::Prop(prop) | JsxProp::Spread(prop) => rest.push(prop),
+            }
+        }
+        let spread_children = element.children.iter().any(|c| matches!(c, JsxChild::Spread(_)));
+        self.jsx_call(element.span.start, &tag, rest, key, children, spread_children)
+    }
+
+    fn jsx_fragment(&mut self, fragment: &JsxFragment) -> String {
+        self.had_jsx = true;
+        let children = self.jsx_children(&fragment.children);
+        match self.options.jsx_runtime {
+            JsxRuntime::Automatic | JsxRuntime::AutomaticDev => {
+                let tag = self.jsx_helper(JsxHelper::Fragment);
+                let spread_children = fragment.children.iter().any(|c| matches!(c, JsxChild::Spread(_)));
+                self.jsx_call(fragment.span.start, tag, Vec::new(), None, children, spread_children)
+            }
+            JsxRuntime::Classic => {
+                create_element_call(&self.options.jsx_factory, &self.options.jsx_fragment, &[], &children, false)
+            }
+        }
+    }
+
+    /// `_jsx(tag, props, key)` with the children among the props; several
+    /// children make it `_jsxs`. In development mode it is
+    /// `_jsxDEV(tag, props, key, isStaticChildren, source, self)`.
+    fn jsx_call(
+        &mut self,
+        pos: usize,
+        tag: &str,
+        mut props: Vec<String>,
+        key: Option<String>,
+        children: Vec<String>,
+        spread: bool,
+    ) -> String {
+        let is_static = children.len() > 1 || spread;
+        match children.len() {
+            0 => {}
+            1 if !spread => props.push(format!("children: {}", children[0])),
+            _ => props.push(format!("children: [{}]", children.join(", "))),
+        }
+        let props = object_literal(&props, self.assign_spread());
+        if let Some(source) = self.jsx_source_location(pos) {
+            let callee = self.jsx_helper(JsxHelper::JsxDev);
+            let key = key.unwrap_or_else(|| "void 0".to_string());
+            let this = self.jsx_self();
+            return format!("{}({}, {}, {}, {}, {}, {})", callee, tag, props, key, is_static, source, this);
+        }
+        let callee = self.jsx_helper(if is_static { JsxHelper::Jsxs } else { JsxHelper::Jsx });
+        match key {
+            Some(key) => format!("{}({}, {}, {})", callee, tag, props, key),
+            None => format!("{}({}, {})", callee, tag, props),
+        }
+    }
+
+    fn jsx_children(&mut self, children: &[JsxChild]) -> Vec<String> {
+        let mut result = Vec::new();
+        for child in children {
+            match child {
+                JsxChild::Text(span) => {
+                    if let Some(text) = clean_text(&decode_entities(span.text(self.source))) {
+                        result.push(string_literal(&text));
+                    }
+                }
+                JsxChild::Expr(container) => {
+                    if let Some(expr) = &container.expr {
+                        result.push(self.render(expr));
+                    }
+                }
+                JsxChild::Spread(spread) => result.push(format!("...{}", self.render(&spread.expr))),
+                JsxChild::Element(e) => result.push(self.jsx_element(e)),
+                JsxChild::Fragment(f) => result.push(self.jsx_fragment(f)),
+            }
+        }
+        result
+    }
+
+    /// Mark `expr` as being in a position that takes any assignment expression
+    fn loose(&mut self, expr: &Expr) {
+        if self.options.target < Target::ES2020 {
+            let span = expr.span();
+            self.lowering.loose.insert((span.start, span.end));
+        }
+    }
+
+    /// Mark the operands of `expr` that take any assignment expression
+    fn loose_operands(&mut self, expr: &Expr) {
+        match expr {
+            Expr::Template(t) => t.exprs.iter().for_each(|e| self.loose(e)),
+            Expr::Array(a) => a.elems.iter().flatten().for_each(|e| self.loose(&e.expr)),
+            Expr::Object(o) => {
+                for prop in &o.props {
+                    match prop {
+                        Prop::KeyValue(p) => self.loose(&p.value),
+                        Prop::Spread(p) => self.loose(&p.expr),
+                        _ => {}
+                    }
+                }
+            }
+            Expr::Assign(e) => self.loose(&e.right),
+            Expr::Cond(e) => {
+                self.loose(&e.cons);
+                self.loose(&e.alt);
+            }
+            Expr::Call(e) => e.args.iter().for_each(|a| self.loose(&a.expr)),
+            Expr::New(e) => e.args.iter().flatten().for_each(|a| self.loose(&a.expr)),
+            Expr::Member(e) => {
+                if let MemberProp::Computed(c) = &e.property {
+                    self.loose(&c.expr);
+                }
+            }
+            Expr::Seq(e) => e.exprs.iter().for_each(|e| self.loose(e)),
+            Expr::Paren(e) => self.loose(&e.expr),
+            Expr::Yield(e) => e.arg.iter().for_each(|e| self.loose(e)),
+            Expr::Arrow(a) => {
+                if let ArrowBody::Expr(e) = &a.body {
+                    self.loose(e);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Mark the expressions of `stmt` that take any assignment expression
+    fn loose_statement(&mut self, stmt: &Stmt) {
+        match stmt {
+            Stmt::Expr(s) => self.loose(&s.expr),
+            Stmt::Return(s) => s.arg.iter().for_each(|e| self.loose(e)),
+            Stmt::Throw(s) => self.loose(&s.arg),
+            Stmt::If(s) => self.loose(&s.test),
+            Stmt::While(s) => self.loose(&s.test),
+            Stmt::DoWhile(s) => self.loose(&s.test),
+            Stmt::Switch(s) => {
+                self.loose(&s.discriminant);
+                s.cases.iter().flat_map(|c| &c.test).for_each(|e| self.loose(e));
+            }
+            Stmt::For(s) => {
+                if let Some(ForInit::Expr(e)) = &s.init {
+                    self.loose(e);
+                }
+                s.test.iter().chain(&s.update).for_each(|e| self.loose(e));
+            }
+            Stmt::ForIn(s) => self.loose(&s.right),
+            Stmt::ForOf(s) => self.loose(&s.right),
+            Stmt::Var(decl) => decl.decls.iter().flat_map(|d| &d.init).for_each(|e| self.loose(e)),
+            Stmt::ExportDefault(ExportDefault { decl: DefaultDecl::Expr(e), .. }) => self.loose(e),
+            _ => {}
+        }
+    }
+
+    /// `code` in place of the expression at `span`, parenthesized unless that
+    /// position takes any assignment expression
+    fn parenthesize(&self, span: Span, code: String) -> String {
+        match self.lowering.loose.contains(&(span.start, span.end)) {
+            true => code,
+            false => format!("({})", code),
+        }
+    }
+
+    /// A name the module does not use and no earlier generated name took
+    fn unique_name(&mut self, base: &str) -> String {
+        let mut name = base.to_string();
+        let mut n = 0;
+        while self.lowering.names.contains(&name) {
+            n += 1;
+            name = format!("{}_{}", base, n);
+        }
+        self.lowering.names.insert(name.clone());
+        name
+    }
+
+    /// `_a` to `_z`, then `_a1` and on, skipping names the module uses
+    fn fresh_name(&mut self) -> String {
+        loop {
+            let n = self.lowering.next_name;
+            self.lowering.next_name += 1;
+            let letter = (b'a' + (n % 26) as u8) as char;
+            let name = match n / 26 {
+                0 => format!("_{}", letter),
+                round => format!("_{}{}", letter, round),
+            };
+            if !self.lowering.names.contains(&name) {
+                self.lowering.names.insert(name.clone());
+                return name;
+            }
+        }
+    }
+
+    /// A temporary, declared at the start of the function being emitted
+    fn temp(&mut self) -> String {
+        let name = self.fresh_name();
+        self.lowering.temps.push(name.clone());
+        name
+    }
+
+    /// A temporary for a computed key moved out of its class, the one legacy
+    /// decorator calls use if there is one
+    fn key_temp(&mut self, key: &Expr) -> String {
+        match self.lowering.keys.remove(&key.span().start) {
+            Some(temp) => temp,
+            None => self.temp(),
+        }
+    }
+
+    /// `value` as it is first evaluated and as it is used again, through a
+    /// temporary unless it is simple
+    fn reuse(&mut self, expr: &Expr, value: String) -> (String, String) {
+        match is_simple(expr) {
+            true => (value.clone(), value),
+            false => {
+                let temp = self.temp();
+                (format!("{} = {}", temp, value), temp)
+            }
+        }
+    }
+
+    /// Start the temporaries of a function, returning those of the enclosing one
+    fn enter_scope(&mut self) -> (Vec<String>, Vec<String>) {
+        (std::mem::take(&mut self.lowering.temps), std::mem::take(&mut self.lowering.prologue))
+    }
+
+    /// Restore the enclosing function's temporaries, returning the statements
+    /// that go at the start of the function left
+    fn leave_scope(&mut self, (temps, prologue): (Vec<String>, Vec<String>)) -> Vec<String> {
+        let temps = std::mem::replace(&mut self.lowering.temps, temps);
+        let prologue = std::mem::replace(&mut self.lowering.prologue, prologue);
+        let declaration = (!temps.is_empty()).then(|| format!("var {};", temps.join(", ")));
+        declaration.into_iter().chain(prologue).collect()
+    }
+
+    /// Put statements at the start of a function body, after its directives
+    fn prepend(&mut self, body: &BlockStmt, statements: &[String]) {
+        let prologue = body.stmts.iter().take_while(|stmt| directive(stmt, self.source).is_some()).last();
+        let pos = prologue.map_or(body.span.start + 1, |stmt| stmt.span().end);
+        let code: String = match body.span.text(self.source).contains('\n') {
+            true => {
+                let indent = match body.stmts.first() {
+                    Some(first) => self.line_indent(first.span().start).to_string(),
+                    None => format!("{}    ", self.line_indent(body.span.start)),
+                };
+                statements.iter().map(|s| format!("\n{}{}", indent, s)).collect()
+            }
+            false => statements.iter().map(|s| format!(" {}", s)).collect(),
+        };
+        self.edits.push(Edit::new(pos, pos, code));
+    }
+
+    /// Replace `expr` if it is syntax newer than the target, returning
+    /// whether it was replaced
+    fn lower_expr(&mut self, expr: &Expr) -> bool {
+        let target = self.options.target;
+        let code = match expr {
+            Expr::Lit(lit) => {
+                self.strip_separators(lit);
+                self.reject_literal(lit);
+                return false;
+            }
+            Expr::This(_) => match &self.lowering.class_this {
+                Some((alias, _)) => alias.clone(),
+                None => return false,
+            },
+            Expr::Ident(id) => match &self.lowering.class_this {
+                Some((alias, Some(name))) if *name == id.name => alias.clone(),
+                _ => match self.class_alias(&id.name) {
+                    Some(alias) => alias,
+                    None => return false,
+                },
+            },
+            Expr::Binary(b) if b.op == BinaryOp::NullishCoalescing && target < Target::ES2020 => {
+                let left = self.render(&b.left);
+                let right = self.render(&b.right);
+                let code = self.nullish(is_simple(&b.left), left, &right);
+                self.parenthesize(b.span, code)
+            }
+            // `**` is ES2016
+            Expr::Binary(b) if b.op == BinaryOp::Exp && target < Target::ES2017 => {
+                format!("Math.pow({}, {})", self.render(&b.left), self.render(&b.right))
+            }
+            Expr::Binary(b) if b.op == BinaryOp::In => {
+                let private = match &b.left {
+                    Expr::PrivateName(id) => self.private_member(&id.name),
+                    _ => None,
+                };
+                match private {
+                    Some(private) => {
+                        let object = self.render(&b.right);
+                        format!("{}.classPrivateFieldIn({}, {})", LOWER, private.state, object)
+                    }
+                    None => return false,
+                }
+            }
+            Expr::Assign(a) => match self.lower_assign(a) {
+                Some(code) => code,
+                None => return false,
+            },
+            Expr::OptChain(chain) if target < Target::ES2020 => {
+                let code = self.lower_chain(&chain.expr, false);
+                self.parenthesize(chain.span, code)
+            }
+            Expr::Unary(u) if u.op == UnaryOp::Delete && target < Target::ES2020 => match &u.arg {
+                Expr::OptChain(chain) => {
+                    let code = self.lower_chain(&chain.expr, true);
+                    self.parenthesize(u.span, code)
+                }
+                _ => return false,
+            },
+            Expr::Object(o) if target < Target::ES2018 && o.props.iter().any(|p| matches!(p, Prop::Spread(_))) => {
+                let mut props = Vec::new();
+                for prop in &o.props {
+                    props.push(match prop {
+                        Prop::Spread(spread) => format!("...{}", self.render(&spread.expr)),
+                        prop => self.render_with(prop.span(), |inner| inner.visit_prop(prop)),
+                    });
+                }
+                object_literal(&props, true)
+            }
+            Expr::Member(m) => match self.super_member(m) {
+                Some(code) => code,
+                None => match self.private_of(expr) {
+                    Some((_, private)) => {
+                        let object = self.render(&m.object);
+                        private.get(&object)
+                    }
+                    None => return false,
+                },
+            },
+            Expr::Call(c) => {
+                let method = match c.callee.unwrapped() {
+                    Expr::Member(m) => self.super_member(m),
+                    _ => None,
+                };
+                match (method, self.private_of(&c.callee)) {
+                    (Some(method), _) => {
+                        let args = self.render_args(c.args_span, &c.args);
+                        format!("{}.call({})", method, join_args("this", &args))
+                    }
+                    (None, Some((m, private))) => {
+                        let object = self.render(&m.object);
+                        let (first, second) = self.reuse(&m.object, object);
+                        let args = self.render_args(c.args_span, &c.args);
+                        format!("{}.call({})", private.get(&first), join_args(&second, &args))
+                    }
+                    (None, None) => return false,
+                }
+            }
+            Expr::Update(u) => match self.private_of(&u.arg) {
+                Some((m, private)) => self.private_update(u, m, private),
+                None => return false,
+            },
+            _ => return false,
+        };
+        let span = expr.span();
+        self.edits.push(Edit::new(span.start, span.end, code));
+        true
+    }
+
+    /// `1_000` → `1000`, for targets before ES2021
+    fn strip_separators(&mut self, lit: &Lit) {
+        if self.options.target < Target::ES2021 && matches!(lit.value, LitValue::Num(_) | LitValue::BigInt(_)) {
+            let text = lit.span.text(self.source);
+            if text.contains('_') {
+                self.edits.push(Edit::new(lit.span.start, lit.span.end, text.replace('_', "")));
+            }
+        }
+    }
+
+    /// Report literals the target cannot express: BigInts before ES2020, and
+    /// regular expressions using ES2018 features before ES2018
+    fn reject_literal(&mut self, lit: &Lit) {
+        let target = self.options.target;
+        let message = match &lit.value {
+            LitValue::BigInt(_) if target < Target::ES2020 => "BigInt literals need target \"es2020\" or later",
+            LitValue::Regex { pattern, flags } if target < Target::ES2018 => {
+                match regex_es2018_feature(pattern, flags) {
+                    Some(message) => message,
+                    None => return,
+                }
+            }
+            _ => return,
+        };
+        self.errors.push((lit.span, "unsupported-syntax", message.to_string()));
+    }
+
+    /// Numeric separators of a member name
+    fn lower_key(&mut self, key: &PropKey) {
+        if let PropKey::Num(lit) | PropKey::BigInt(lit) = key {
+            self.strip_separators(lit);
+            self.reject_literal(lit);
+        }
+    }
+
+    /// `value ?? fallback` without `??`, through a temporary unless `value` is simple
+    fn nullish(&mut self, simple: bool, value: String, fallback: &str) -> String {
+        match simple {
+            true => format!("{0} !== null && {0} !== void 0 ? {0} : {1}", value, fallback),
+            false => {
+                let temp = self.temp();
+                format!("({0} = {1}) !== null && {0} !== void 0 ? {0} : {2}", temp, value, fallback)
+            }
+        }
+    }
+
+    /// Logical and exponent assignments the target lacks, and assignments to
+    /// the private members of lowered classes:
+    ///
+    /// ```text
+    /// a ||= b       →  a || (a = b)
+    /// o.p ??= b     →  (_a = o.p) !== null && _a !== void 0 ? _a : (o.p = b)
+    /// f()[k] **= 2  →  (_a = f())[_b = k] = Math.pow(_a[_b], 2)
+    /// ```
+    fn lower_assign(&mut self, a: &AssignExpr) -> Option<String> {
+        let target = self.options.target;
+        if let Pat::Expr(left) = &a.left {
+            if let Some((m, private)) = self.private_of(left) {
+                return Some(self.private_assign(a, m, private));
+            }
+            if matches!(left.unwrapped(), Expr::Member(m) if matches!(m.object, Expr::Super(_)))
+                && self.lowering.super_props.is_some()
+            {
+                let message = "Assignments to `super` properties are not supported in async functions below ES2017";
+                self.errors.push((a.span, "unsupported-syntax", message.to_string()));
+                return None;
+            }
+        }
+        if matches!(a.left, Pat::Array(_) | Pat::Object(_)) && self.has_private_target(&a.left) {
+            let right = self.render(&a.right);
+            return Some(self.assign_pattern(&a.left, &right));
+        }
+        let logical = matches!(a.op, AssignOp::OrAssign | AssignOp::AndAssign | AssignOp::NullishAssign);
+        if !(logical && target < Target::ES2021 || a.op == AssignOp::ExpAssign && target < Target::ES2017) {
+            return None;
+        }
+        let simple = match &a.left {
+            Pat::Ident(_) => true,
+            Pat::Expr(e) => matches!(e.unwrapped(), Expr::Ident(_)),
+            _ => false,
+        };
+        let (first, second) = self.reference(&a.left)?;
+        let right = self.render(&a.right);
+        Some(match a.op {
+            AssignOp::OrAssign => format!("{} || ({} = {})", first, second, right),
+            AssignOp::AndAssign => format!("{} && ({} = {})", first, second, right),
+            AssignOp::NullishAssign if target >= Target::ES2020 => format!("{} ?? ({} = {})", first, second, right),
+            AssignOp::NullishAssign => self.nullish(simple, first, &format!("({} = {})", second, right)),
+            _ => format!("{} = Math.pow({}, {})", first, second, right),
+        })
+    }
+
+    /// An assignment target as it is first evaluated and as it is assigned
+    /// after that: `(_a = o)[_b = k]` and `_a[_b]`
+    fn reference(&mut self, left: &Pat) -> Option<(String, String)> {
+        let expr = match left {
+            Pat::Ident(p) => return Some(self.identifier_reference(&p.id)),
+            Pat::Expr(expr) => expr.unwrapped(),
+            _ => return None,
+        };
+        match expr {
+            Expr::Ident(id) => Some(self.identifier_reference(id)),
+            Expr::Member(m) => {
+                let object = self.render(&m.object);
+                let (first, second) = match is_simple(&m.object) || matches!(m.object, Expr::Super(_)) {
+                    true => (object.clone(), object),
+                    false => {
+                        let temp = self.temp();
+                        (format!("({} = {})", temp, object), temp)
+                    }
+                };
+                let (key, again) = match &m.property {
+                    MemberProp::Ident(id) => (format!(".{}", id.name), format!(".{}", id.name)),
+                    MemberProp::Computed(c) if matches!(c.expr.as_ref(), Expr::Lit(_)) => {
+                        let key = format!("[{}]", self.render(&c.expr));
+                        (key.clone(), key)
+                    }
+                    MemberProp::Computed(c) => {
+                        let key = self.render(&c.expr);
+                        let temp = self.temp();
+                        (format!("[{} = {}]", temp, key), format!("[{}]", temp))
+                    }
+                    MemberProp::PrivateName(_) => return None,
+                };
+                Some((first + &key, second + &again))
+            }
+            _ => None,
+        }
+    }
+
+    fn identifier_reference(&self, id: &Ident) -> (String, String) {
+        let name = match self.namespace_member(&id.name) {
+            Some(namespace) => format!("{}.{}", namespace, id.name),
+            None => id.name.clone(),
+        };
+        (name.clone(), name)
+    }
+
+    /// A private member of a class being lowered
+    fn private_member(&mut self, name: &str) -> Option<PrivateMember> {
+        let private = self.lowering.privates.iter().rev().find_map(|names| names.get(name)).cloned();
+        self.lowering.helpers |= private.is_some();
+        private
+    }
+
+    /// `expr` if it reads a private member of a class being lowered
+    fn private_of<'e>(&mut self, expr: &'e Expr) -> Option<(&'e MemberExpr, PrivateMember)> {
+        match expr.unwrapped() {
+            Expr::Member(m) => match &m.property {
+                MemberProp::PrivateName(id) => self.private_member(&id.name).map(|private| (m.as_ref(), private)),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Whether `pat` assigns to a private member of a class being lowered
+    fn has_private_target(&mut self, pat: &Pat) -> bool {
+        match pat {
+            Pat::Expr(e) => self.private_of(e).is_some(),
+            Pat::Array(p) => p.elems.iter().flatten().any(|e| self.has_private_target(e)),
+            Pat::Object(p) => p.props.iter().any(|prop| match prop {
+                ObjectPatProp::KeyValue { value, .. } => self.has_private_target(value),
+                ObjectPatProp::Rest(r) => self.has_private_target(&r.arg),
+                ObjectPatProp::Assign { .. } => false,
+            }),
+            Pat::Rest(r) => self.has_private_target(&r.arg),
+            Pat::Assign(p) => self.has_private_target(&p.left),
+            Pat::Ident(_) | Pat::Invalid(_) => false,
+        }
+    }
+
+    /// `pat = value`, where private members of lowered classes are assigned
+    /// through setter objects whose receivers are evaluated first:
+    /// `[this.#x] = a` → `(_a = this, [({ set value(_b) { … } }).value] = a)`
+    fn assign_pattern(&mut self, pat: &Pat, value: &str) -> String {
+        let receivers = std::mem::take(&mut self.lowering.receivers);
+        let target = self.render_with(pat.span(), |inner| inner.visit_pat(pat));
+        let receivers = std::mem::replace(&mut self.lowering.receivers, receivers);
+        match receivers.is_empty() {
+            true => format!("{} = {}", target, value),
+            false => format!("({}, {} = {})", receivers.join(", "), target, value),
+        }
+    }
+
+    /// A private member as an assignment target, which is the `value` of a
+    /// setter object: `({ set value(_a) { __fuse_lower.classPrivateFieldSet(o, _A_x, _a, "f"); } }).value`.
+    /// The setter has its own `this`, so receivers other than variables go
+    /// through temporaries.
+    fn private_target(&mut self, m: &MemberExpr, private: PrivateMember) -> String {
+        let object = self.render(&m.object);
+        let receiver = match &m.object {
+            Expr::Ident(_) => object,
+            _ => {
+                let temp = self.temp();
+                self.lowering.receivers.push(format!("{} = {}", temp, object));
+                temp
+            }
+        };
+        let param = self.fresh_name();
+        format!("({{ set value({}) {{ {}; }} }}).value", param, private.set(&receiver, &param))
+    }
+
+    /// `for (this.#x of xs)` → `for (const _a of xs) { (_b = this, ({ set value(_c) { … } }).value = _a);`,
+    /// assigning private members of lowered classes in the loop body
+    fn private_for_head(&mut self, pat: &Pat, right: &Expr, body: &Stmt) {
+        let value = self.fresh_name();
+        let statement = expression_statement(&self.assign_pattern(pat, &value));
+        let span = pat.span();
+        self.edits.push(Edit::new(span.start, span.end, format!("const {}", value)));
+        self.prefix_body(body, &statement);
+        self.visit_expr(right);
+        self.visit_stmt(body);
+    }
+
+    /// Put `statement` first in a loop body, which becomes a block if it is not one
+    fn prefix_body(&mut self, body: &Stmt, statement: &str) {
+        let span = body.span();
+        match body {
+            Stmt::Block(_) => self.edits.push(Edit::new(span.start + 1, span.start + 1, format!(" {}", statement))),
+            _ => {
+                let space = if self.source[..span.start].ends_with(char::is_whitespace) { "" } else { " " };
+                self.edits.push(Edit::new(span.start, span.start, format!("{}{{ {} ", space, statement)));
+                self.edits.push(Edit::new(span.end, span.end, " }"));
+            }
+        }
+    }
+
+    /// Assignment to a private member: `this.#x += 1` → `set(this, get(this) + 1)`
+    fn private_assign(&mut self, a: &AssignExpr, m: &MemberExpr, private: PrivateMember) -> String {
+        let object = self.render(&m.object);
+        let right = self.render(&a.right);
+        if a.op == AssignOp::Assign {
+            return private.set(&object, &right);
+        }
+        let (first, second) = self.reuse(&m.object, object);
+        match a.op {
+            AssignOp::OrAssign => format!("{} || {}", private.get(&first), private.set(&second, &right)),
+            AssignOp::AndAssign => format!("{} && {}", private.get(&first), private.set(&second, &right)),
+            AssignOp::NullishAssign if self.options.target >= Target::ES2020 => {
+                format!("{} ?? {}", private.get(&first), private.set(&second, &right))
+            }
+            AssignOp::NullishAssign => {
+                let set = private.set(&second, &right);
+                self.nullish(false, private.get(&first), &set)
+            }
+            AssignOp::ExpAssign if self.options.target < Target::ES2017 => {
+                private.set(&first, &format!("Math.pow({}, {})", private.get(&second), right))
+            }
+            op => {
+                let right = match is_operand(&a.right) {
+                    true => right,
+                    false => format!("({})", right),
+                };
+                let operator = op.as_str().trim_end_matches('=');
+                private.set(&first, &format!("{} {} {}", private.get(&second), operator, right))
+            }
+        }
+    }
+
+    /// `++this.#x` → `set(this, (_a = get(this), ++_a))`; the postfix form
+    /// keeps the old value in a second temporary
+    fn private_update(&mut self, u: &UpdateExpr, m: &MemberExpr, private: PrivateMember) -> String {
+        let object = self.render(&m.object);
+        let (first, second) = self.reuse(&m.object, object);
+        let op = if u.increment { "++" } else { "--" };
+        let value = self.temp();
+        let read = private.get(&second);
+        match u.prefix {
+            true => private.set(&first, &format!("({0} = {1}, {2}{0})", value, read, op)),
+            false => {
+                let old = self.temp();
+                let update = format!("({0} = {1}, {2} = {0}{3}, {0})", value, read, old, op);
+                format!("({}, {})", private.set(&first, &update), old)
+            }
+        }
+    }
+
+    /// Arguments of a call without their parentheses and trailing comma
+    fn render_args(&mut self, span: Span, args: &[ExprOrSpread]) -> String {
+        let code = self.render_with(span, |inner| {
+            for arg in args {
+                inner.loose(&arg.expr);
+                inner.visit_expr(&arg.expr);
+            }
+        });
+        let code = code[1..code.len() - 1].trim();
+        code.strip_suffix(',').unwrap_or(code).trim_end().to_string()
+    }
+
+    /// An optional chain as a conditional, evaluating each link once:
+    ///
+    /// ```text
+    /// a?.b.c       →  a === null || a === void 0 ? void 0 : a.b.c
+    /// a.b?.(x)     →  (_a = a.b) === null || _a === void 0 ? void 0 : _a.call(a, x)
+    /// delete a?.b  →  a === null || a === void 0 ? true : delete a.b
+    /// ```
+    fn lower_chain(&mut self, expr: &Expr, delete: bool) -> String {
+        let mut links = Vec::new();
+        let mut base = expr;
+        loop {
+            match base {
+                Expr::Member(m) => {
+                    links.push(base);
+                    base = &m.object;
+                }
+                Expr::Call(c) if !matches!(c.callee, Expr::Super(_)) => {
+                    links.push(base);
+                    base = &c.callee;
+                }
+                Expr::TsNonNull(e) => base = &e.expr,
+                _ => break,
+            }
+        }
+        links.reverse();
+
+        let mut current = self.render(base);
+        let mut simple = is_simple(base) || matches!(base, Expr::Super(_));
+        let mut checks = Vec::new();
+        let mut receiver = None;
+        for (i, link) in links.iter().enumerate() {
+            let optional = match link {
+                Expr::Member(m) => m.optional,
+                Expr::Call(c) => c.optional,
+                _ => false,
+            };
+            if optional {
+                if !simple {
+                    let temp = self.temp();
+                    checks.push(format!("({0} = {1}) === null || {0} === void 0", temp, current));
+                    current = temp;
+                } else {
+                    checks.push(format!("{0} === null || {0} === void 0", current));
+                }
+            }
+            match link {
+                Expr::Member(m) => {
+                    // The object is the receiver of an optional call on the member
+                    let object = match links.get(i + 1) {
+                        Some(Expr::Call(c)) if c.optional => {
+                            let (object, again) = match simple {
+                                true => (current.clone(), current.clone()),
+                                false => {
+                                    let temp = self.temp();
+                                    (format!("({} = {})", temp, current), temp)
+                                }
+                            };
+                            receiver = Some(if again == "super" { "this".to_string() } else { again });
+                            object
+                        }
+                        _ => current.clone(),
+                    };
+                    current = match &m.property {
+                        MemberProp::Ident(id) => format!("{}.{}", object, id.name),
+                        MemberProp::Computed(c) => {
+                            let key = self.render_with(c.span, |inner| {
+                                inner.loose(&c.expr);
+                                inner.visit_expr(&c.expr);
+                            });
+                            format!("{}{}", object, key)
+                        }
+                        MemberProp::PrivateName(id) => match self.private_member(&id.name) {
+                            Some(private) => private.get(&object),
+                            None => format!("{}.#{}", object, id.name),
+                        },
+                    };
+                }
+                Expr::Call(c) => {
+                    let args = self.render_args(c.args_span, &c.args);
+                    current = match (c.optional, receiver.take()) {
+                        (true, Some(receiver)) => format!("{}.call({})", current, join_args(&receiver, &args)),
+                        _ => format!("{}({})", current, args),
+                    };
+                }
+                _ => {}
+            }
+            simple = false;
+        }
+        match (checks.is_empty(), delete) {
+            (true, _) => current,
+            (false, true) => format!("{} ? true : delete {}", checks.join(" || "), current),
+            (false, false) => format!("{} ? void 0 : {}", checks.join(" || "), current),
+        }
+    }
+
+    /// Visit an object literal member
+    fn visit_prop(&mut self, prop: &Prop) {
+        match prop {
+            Prop::Shorthand(id) => {
+                if let Some(alias) = self.class_alias(&id.name) {
+                    self.edits.push(Edit::new(id.span.end, id.span.end, format!(": {}", alias)));
+                } else if let Some(namespace) = self.namespace_member(&id.name) {
+                    let code = format!("{}: {}.{}", id.name, namespace, id.name);
+                    self.edits.push(Edit::new(id.span.start, id.span.end, code));
+                }
+            }
+            Prop::KeyValue(p) => {
+                self.lower_key(&p.key);
+                walk_prop_key(self, &p.key);
+                self.visit_expr(&p.value);
+            }
+            Prop::Assign(p) => self.visit_expr(&p.value),
+            Prop::Method(p) => {
+                self.lower_key(&p.key);
+                if self.lowers_async(&p.function) {
+                    self.erase_async(Span::new(p.span.start, p.key.span().start), p.function.is_generator);
+                }
+                walk_prop_key(self, &p.key);
+                self.visit_function(&p.function);
+            }
+            Prop::Spread(p) => self.visit_expr(&p.expr),
+        }
+    }
+
+    /// Declarators destructuring object rest, which ES2018 introduced:
+    /// `const { a, ...r } = f()` → `const _a = f(), { a } = _a, r = __fuse_lower.rest(_a, ["a"])`
+    fn lower_var_decl(&mut self, decl: &VarDecl) {
+        for d in &decl.decls {
+            let rest = match (&d.name, &d.init) {
+                (Pat::Object(pat), Some(init)) if self.options.target < Target::ES2018 => {
+                    object_rest(pat).map(|rest| (pat, init, rest))
+                }
+                _ => None,
+            };
+            match rest {
+                Some((pat, init, rest)) => {
+                    let value = self.render(init);
+                    let (binding, value) = match is_simple(init) {
+                        true => (String::new(), value),
+                        false => {
+                            let name = self.fresh_name();
+                            (format!("{} = {}, ", name, value), name)
+                        }
+                    };
+                    let code = binding + &self.lower_object_rest(pat, rest, &value);
+                    self.edits.push(Edit::new(d.span.start, d.span.end, code));
+                }
+                None => {
+                    self.visit_pat(&d.name);
+                    if let Some(init) = &d.init {
+                        self.visit_expr(init);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Object rest in parameters moves to the body: `({ a, ...r }) => {}` →
+    /// `(_a) => { var { a } = _a, r = __fuse_lower.rest(_a, ["a"]); }`.
+    /// Trailing commas go before ES2017.
+    fn lower_params(&mut self, params: &[Param], span: Span) {
+        if self.options.target < Target::ES2018 {
+            for param in params {
+                let pat = match &param.pat {
+                    Pat::Object(pat) => pat,
+                    Pat::Assign(p) => match p.left.as_ref() {
+                        Pat::Object(pat) => pat,
+                        _ => continue,
+                    },
+                    _ => continue,
+                };
+                let Some(rest) = object_rest(pat) else {
+                    continue;
+                };
+                let name = self.fresh_name();
+                let declaration = format!("var {};", self.lower_object_rest(pat, rest, &name));
+                self.lowering.prologue.push(declaration);
+                self.edits.push(Edit::new(pat.span.start, pat.span.end, name));
+            }
+        }
+        self.erase_trailing_comma(params.last().map(|p| p.span), span.end - 1);
+    }
+
+    /// `{ a } = value, r = __fuse_lower.rest(value, ["a"])` for the object
+    /// pattern `{ a, ...r }`, given the rest binding and the other keys;
+    /// `value` is simple
+    fn lower_object_rest(&mut self, pat: &ObjectPat, (rest, keys): (String, Vec<String>), value: &str) -> String {
+        self.lowering.helpers = true;
+        let rest = format!("{} = {}.rest({}, [{}])", rest, LOWER, value, keys.join(", "));
+        let (last, props) = match pat.props.split_last() {
+            Some((ObjectPatProp::Rest(last), props)) if !props.is_empty() => (last, props),
+            _ => return rest,
+        };
+        let code = self.render_with(Span::new(pat.span.start, last.span.start), |inner| {
+            for prop in props {
+                match prop {
+                    ObjectPatProp::KeyValue { value, .. } => inner.visit_pat(value),
+                    ObjectPatProp::Assign { value: Some(value), .. } => inner.visit_expr(value),
+                    _ => {}
+                }
+            }
+        });
+        let code = code.trim_end();
+        format!("{} }} = {}, {}", code.strip_suffix(',').unwrap_or(code), value, rest)
+    }
+
+    /// Whether an async function becomes a generator for the target: async
+    /// functions before ES2017, async generators before ES2018
+    fn lowers_async(&self, function: &Function) -> bool {
+        let since = if function.is_generator { Target::ES2018 } else { Target::ES2017 };
+        function.body.is_some() && function.is_async && self.options.target < since
+    }
+
+    /// Erase the `async` keyword within `span`, along with the space after it,
+    /// and the `*` of a generator
+    fn erase_async(&mut self, span: Span, generator: bool) {
+        let text = span.text(self.source);
+        let Some(i) = word_position(text, "async") else {
+            return;
+        };
+        let start = span.start + i;
+        let mut end = self.after_space(start + "async".len());
+        let star = text[i..].find('*').map(|j| start + j).filter(|_| generator);
+        match star {
+            // `async *m() {}`
+            Some(star) if star == end => end = self.after_space(star + 1),
+            // `async function* f() {}`
+            Some(star) => {
+                let joins =
+                    self.source[..star].ends_with(is_id_continue) && self.source[star + 1..].starts_with(is_id_start);
+                self.edits.push(Edit::new(star, star + 1, if joins { " " } else { "" }));
+            }
+            None => {}
+        }
+        self.edits.push(Edit::delete(start, end));
+    }
+
+    /// The call running the generator a lowered async function becomes, up to
+    /// that generator's body: `__fuse_lower.awaiter(this, arguments, void 0, function* () `,
+    /// or `__fuse_lower.asyncGenerator(this, arguments, function* () ` for an async generator
+    fn async_runner(&mut self, generator: bool, uses: &FunctionUses) -> String {
+        self.lowering.helpers = true;
+        if generator {
+            return format!("{}.asyncGenerator(this, arguments, function* () ", LOWER);
+        }
+        let arguments = if uses.arguments { "arguments" } else { "void 0" };
+        format!("{}.awaiter(this, {}, void 0, function* () ", LOWER, arguments)
+    }
+
+    /// Declarations of the functions reading `super` properties for a lowered
+    /// async function, which go before its generator:
+    /// `const _super = Object.create(null, { x: { get: () => super.x } });`
+    /// and `const _superIndex = name => super[name];`
+    fn super_declarations(&mut self, props: Option<SuperProps>) -> String {
+        let Some(props) = props else {
+            return String::new();
+        };
+        let (alias, index) = self.super_names();
+        let mut code = String::new();
+        if !props.names.is_empty() {
+            let getters: Vec<_> =
+                props.names.iter().map(|name| format!("{0}: {{ get: () => super.{0} }}", name)).collect();
+            code.push_str(&format!("const {} = Object.create(null, {{ {} }}); ", alias, getters.join(", ")));
+        }
+        if props.index {
+            code.push_str(&format!("const {} = name => super[name]; ", index));
+        }
+        code
+    }
+
+    /// `_super` and `_superIndex`, renamed if the module uses them
+    fn super_names(&mut self) -> (String, String) {
+        if self.lowering.super_names.is_none() {
+            let names = (self.unique_name("_super"), self.unique_name("_superIndex"));
+            self.lowering.super_names = Some(names);
+        }
+        self.lowering.super_names.clone().unwrap_or_default()
+    }
+
+    /// `super.x` → `_super.x` and `super[k]` → `_superIndex(k)` in a lowered
+    /// async function
+    fn super_member(&mut self, m: &MemberExpr) -> Option<String> {
+        if !matches!(m.object, Expr::Super(_)) || self.lowering.super_props.is_none() {
+            return None;
+        }
+        let (alias, index) = self.super_names();
+        match &m.property {
+            MemberProp::Ident(id) => {
+                let props = self.lowering.super_props.as_mut()?;
+                if !props.names.contains(&id.name) {
+                    props.names.push(id.name.clone());
+                }
+                Some(format!("{}.{}", alias, id.name))
+            }
+            MemberProp::Computed(c) => {
+                let key = self.render(&c.expr);
+                self.lowering.super_props.as_mut()?.index = true;
+                Some(format!("{}({})", index, key))
+            }
+            MemberProp::PrivateName(_) => None,
+        }
+    }
+
+    /// `await value` as the function being emitted is lowered
+    fn await_code(&mut self, value: &str) -> String {
+        match self.lowering.awaits {
+            AsyncLowering::None => format!("await {}", value),
+            AsyncLowering::Awaiter => format!("yield {}", value),
+            AsyncLowering::Generator => {
+                self.lowering.helpers = true;
+                format!("yield {}.awaitValue({})", LOWER, value)
+            }
+        }
+    }
+
+    /// Whether `for await`, which ES2018 introduced, is lowered for the target
+    fn lowers_for_await(&self, s: &ForOfStmt) -> bool {
+        s.is_await && self.options.target < Target::ES2018
+    }
+
+    /// `for await` as a loop over `__fuse_lower.asyncValues`, which awaits each
+    /// step and closes the iterator when the loop is left early:
+    ///
+    /// ```text
+    /// for await (const x of xs) f(x);
+    /// →
+    /// try { for (_a = true, _b = __fuse_lower.asyncValues(xs); _c = await _b.next(), _d = _c.done, !_d; _a = true)
+    /// { _e = _c.value; _a = false; const x = _e; f(x); } } catch (_h) { _f = { error: _h }; }
+    /// finally { try { if (!_a && !_d && (_g = _b.return)) await _g.call(_b); } finally { if (_f) throw _f.error; } }
+    /// ```
+    fn lower_for_await(&mut self, s: &ForOfStmt, label: Option<&Ident>) {
+        self.lowering.helpers = true;
+        let [idle, iterator, result, done, value, error, close] = std::array::from_fn(|_| self.temp());
+        let caught = self.fresh_name();
+        let right = self.render(&s.right);
+        let binding = match &s.left {
+            ForHead::Var(decl) => {
+                let head =
+                    self.render_with(decl.span, |inner| decl.decls.iter().for_each(|d| inner.visit_pat(&d.name)));
+                format!("{} = {};", head, value)
+            }
+            ForHead::Pat(pat) => expression_statement(&self.assign_pattern(pat, &value)),
+        };
+        let next = self.await_code(&format!("{}.next()", iterator));
+        let code = format!(
+            "try {{ {}for ({} = true, {} = {}.asyncValues({}); {} = {}, {} = {}.done, !{}; {} = true)",
+            label.map(|l| format!("{}: ", l.name)).unwrap_or_default(),
+            idle,
+            iterator,
+            LOWER,
+            right,
+            result,
+            next,
+            done,
+            result,
+            done,
+            idle
+        );
+        // Up to the parenthesis closing the head
+        let body = s.body.span().start;
+        let head_end = self.source[..body].rfind(')').map_or(body, |i| i + 1);
+        self.edits.push(Edit::new(s.span.start, head_end, code));
+        let prefix = format!("{} = {}.value; {} = false; {}", value, result, idle, binding);
+        self.prefix_body(&s.body, &prefix);
+        let close_iterator = self.await_code(&format!("{}.call({})", close, iterator));
+        let tail = format!(
+            " }} catch ({0}) {{ {1} = {{ error: {0} }}; }} \
+             finally {{ try {{ if (!{2} && !{3} && ({4} = {5}.return)) {6}; }} \
+             finally {{ if ({1}) throw {1}.error; }} }}",
+            caught, error, idle, done, close, iterator, close_iterator
+        );
+        self.edits.push(Edit::new(s.span.end, s.span.end, tail));
+        self.visit_stmt(&s.body);
+    }
+
+    /// Remove the trailing comma of a parameter or argument list, which
+    /// ES2017 introduced
+    fn erase_trailing_comma(&mut self, last: Option<Span>, close: usize) {
+        if self.options.target >= Target::ES2017 {
+            return;
+        }
+        // An arrow's lone parameter has no parentheses
+        if let Some(last) = last.filter(|last| last.end <= close) {
+            if let Some(i) = self.source[last.end..close].find(',') {
+                self.edits.push(Edit::delete(last.end + i, last.end + i + 1));
+            }
+        }
+    }
+
+    /// Module syntax and catch bindings the target lacks
+    fn lower_stmt(&mut self, stmt: &Stmt) {
+        match stmt {
+            // `export * as ns from "m"` is ES2020
+            Stmt::ExportAll(e) if self.options.target < Target::ES2020 && !e.type_only => {
+                if let Some(exported) = &e.exported {
+                    let base = match exported {
+                        ModuleExportName::Ident(id) => id.name.clone(),
+                        ModuleExportName::Str(_) => "_ns".to_string(),
+                    };
+                    let local = self.unique_name(&base);
+                    let from = self.source[e.source.span.start..e.span.end].trim_end_matches(';');
+                    let code = format!(
+                        "import * as {} from {}; export {{ {} as {} }};",
+                        local,
+                        from,
+                        local,
+                        exported.span().text(self.source)
+                    );
+                    self.edits.push(Edit::new(e.span.start, e.span.end, code));
+                }
+            }
+            // `catch {}` is ES2019
+            Stmt::Try(t) if self.options.target < Target::ES2020 => {
+                if let Some(handler) = t.handler.as_ref().filter(|h| h.param.is_none()) {
+                    let pos = handler.span.start + "catch".len();
+                    let name = self.unique_name("_unused");
+                    self.edits.push(Edit::new(pos, pos, format!(" ({})", name)));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Whether a field is only a TypeScript declaration
+    fn erased_field(&self, p: &ClassProperty) -> bool {
+        self.strip_types && (p.has_modifier(ModifierKind::Declare) || p.has_modifier(ModifierKind::Abstract))
+    }
+
+    /// Storage for the private members of a lowered class, with the code
+    /// creating it. Instance methods and accessors share a `WeakSet` of the
+    /// instances, which the constructor adds `this` to.
+    fn bind_privates(&mut self, class: &Class, alias: &str, definitions: &mut Vec<String>) -> Option<String> {
+        let base = alias.trim_start_matches('_');
+        let mut privates: HashMap<String, PrivateMember> = HashMap::new();
+        let mut brand = None;
+        for member in &class.body {
+            let (id, is_static, kind, method) = match member {
+                ClassMember::Property(p) => match &p.key {
+                    PropKey::Private(id) => (id, p.is_static, PrivateKind::Field, MethodKind::Method),
+                    _ => continue,
+                },
+                ClassMember::Method(m) => match &m.key {
+                    PropKey::Private(id) => {
+                        let kind = match m.kind {
+                            MethodKind::Getter | MethodKind::Setter => PrivateKind::Accessor,
+                            _ => PrivateKind::Method,
+                        };
+                        (id, m.is_static, kind, m.kind)
+                    }
+                    _ => continue,
+                },
+                _ => continue,
+            };
+            let state = match (is_static, kind) {
+                (true, _) => alias.to_string(),
+                (false, PrivateKind::Field) => {
+                    let map = self.unique_name(&format!("_{}_{}", base, id.name.trim_start_matches('#')));
+                    self.lowering.temps.push(map.clone());
+                    definitions.push(format!("{} = new WeakMap()", map));
+                    map
+                }
+                (false, _) => brand
+                    .get_or_insert_with(|| {
+                        let set = self.unique_name(&format!("_{}_instances", base));
+                        self.lowering.temps.push(set.clone());
+                        definitions.push(format!("{} = new WeakSet()", set));
+                        set
+                    })
+                    .clone(),
+            };
+            let private = privates.entry(id.name.clone()).or_insert(PrivateMember { kind, state, value: None, setter: None });
+            let suffix = match method {
+                MethodKind::Getter => "_get",
+                MethodKind::Setter => "_set",
+                _ => "",
+            };
+            if is_static || kind != PrivateKind::Field {
+                let var = self.unique_name(&format!("_{}_{}{}", base, id.name.trim_start_matches('#'), suffix));
+                self.lowering.temps.push(var.clone());
+                match method {
+                    MethodKind::Setter => private.setter = Some(var),
+                    _ => private.value = Some(var),
+                }
+            }
+        }
+        self.lowering.privates.push(privates);
+        brand
+    }
+
+    /// An instance field as a constructor statement, or a static field as an
+    /// expression run after the class
+    fn lower_field(
+        &mut self,
+        p: &ClassProperty,
+        class_this: &ClassThis,
+        define: bool,
+        definitions: &mut Vec<String>,
+    ) -> Option<String> {
+        let receiver = if p.is_static { class_this.0.as_str() } else { "this" };
+        // Static initializers run after the class, where `this` is the class
+        if p.is_static {
+            self.lowering.class_this = Some(class_this.clone());
+        }
+        let value = p.value.as_ref().map(|value| self.render(value));
+        self.lowering.class_this = None;
+        if let PropKey::Private(id) = &p.key {
+            let private = self.private_member(&id.name)?;
+            let value = value.as_deref().unwrap_or("void 0");
+            return Some(match (p.is_static, &private.value) {
+                (true, Some(storage)) => format!("{} = {{ value: {} }}", storage, value),
+                _ => format!("{}.set(this, {})", private.state, value),
+            });
+        }
+        let (name, access) = match &p.key {
+            PropKey::Computed(c) => {
+                let temp = self.key_temp(&c.expr);
+                definitions.push(format!("{} = {}", temp, self.render(&c.expr)));
+                (temp.clone(), format!("[{}]", temp))
+            }
+            key => {
+                self.lower_key(key);
+                member_access(key, self.source)
+            }
+        };
+        match (define, value) {
+            (true, value) => Some(define_property(receiver, &name, value.as_deref().unwrap_or("void 0"))),
+            (false, Some(value)) => Some(format!("{}{} = {}", receiver, access, value)),
+            (false, None) => None,
+        }
+    }
+
+    /// `#m() {}` as a function assigned after the class: `_A_m = function _A_m() {}`
+    fn private_method(&mut self, m: &ClassMethod) -> String {
+        let name = match &m.key {
+            PropKey::Private(id) => id.name.as_str(),
+            _ => "",
+        };
+        let private = self.lowering.privates.last().and_then(|names| names.get(name)).cloned();
+        let var = match (m.kind, private) {
+            (MethodKind::Setter, Some(private)) => private.setter,
+            (_, Some(private)) => private.value,
+            _ => None,
+        }
+        .unwrap_or_default();
+        let f = &m.function;
+        let lowered = self.lowers_async(f);
+        let keyword = if f.is_async && !lowered { "async " } else { "" };
+        let star = if f.is_generator && !lowered { "*" } else { "" };
+        let code = self.render_with(f.span, |inner| inner.visit_function(f));
+        format!("{} = {}function{} {}{}", var, keyword, star, var, code)
+    }
+}
+
+impl Visit for Emitter<'_> {
+    fn visit_stmt(&mut self, stmt: &Stmt) {
+        if self.strip_types {
+            if stmt.is_type_only() {
+                self.erase(stmt.span());
+                return;
+            }
+            match stmt {
+                Stmt::Import(decl) => {
+                    let specifiers: Vec<_> = decl
+                        .specifiers
+                        .iter()
+                        .map(|s| (s.span(), matches!(s, ImportSpecifier::Named { type_only: true, .. })))
+                        .collect();
+                    self.erase_type_specifiers(decl.span, &specifiers);
+                }
+                Stmt::ExportNamed(decl) => {
+                    let specifiers: Vec<_> = decl.specifiers.iter().map(|s| (s.span, s.type_only)).collect();
+                    self.erase_type_specifiers(decl.span, &specifiers);
+                }
+                Stmt::TsEnum(decl) => {
+                    self.lower_enum(decl, decl.span, "var", None);
+                    return;
+                }
+                Stmt::TsModule(decl) => {
+                    self.lower_namespace(decl, decl.span, "var", None, "");
+                    return;
+                }
+                Stmt::TsImportEquals(decl) => {
+                    let keyword = match (decl.is_export, &decl.module_ref) {
+                        (true, _) => "export var",
+                        (false, TsModuleRef::Require(_)) => "const",
+                        (false, TsModuleRef::Entity(_)) => "var",
+                    };
+                    let code = format!("{} {} = {};", keyword, decl.id.name, self.module_reference(&decl.module_ref));
+                    self.edits.push(Edit::new(decl.span.start, decl.span.end, code));
+                    self.had_types = true;
+                    return;
+                }
+                // `export = x` → `module.exports = x`
+                Stmt::TsExportAssignment(decl) => {
+                    self.edits.push(Edit::new(decl.span.start, decl.expr.span().start, "module.exports = "));
+                    self.had_types = true;
+                }
+                Stmt::ExportDecl(export) => match export.decl.as_ref() {
+                    Stmt::TsEnum(decl) => {
+                        self.lower_enum(decl, export.span, "export var", None);
+                        return;
+                    }
+                    Stmt::TsModule(decl) => {
+                        self.lower_namespace(decl, export.span, "export var", None, "");
+                        return;
+                    }
+                    _ => {}
+                },
+                _ => {}
+            }
+        }
+        self.class_statement = match stmt {
+            Stmt::Class(class) => Some(class),
+            Stmt::ExportDecl(export) => match export.decl.as_ref() {
+                Stmt::Class(class) => Some(class),
+                _ => None,
+            },
+            Stmt::ExportDefault(ExportDefault { decl: DefaultDecl::Class(class), .. }) => Some(class),
+            _ => None,
+        }
+        .map(|class| (class.span.start, stmt.span()));
+        self.loose_statement(stmt);
+        self.lower_stmt(stmt);
+        if self.options.decorators != Decorators::Preserve {
+            let class = match stmt {
+                Stmt::Class(class) => Some((class, ClassSite::Declaration)),
+                Stmt::ExportDecl(export) => match export.decl.as_ref() {
+                    Stmt::Class(class) => Some((class, ClassSite::Export)),
+                    _ => None,
+                },
+                Stmt::ExportDefault(export) => match &export.decl {
+                    DefaultDecl::Class(class) => Some((class, ClassSite::ExportDefault)),
+                    _ => None,
+                },
+                _ => None,
+            };
+            if let Some((class, site)) = class {
+                let outer = self.class_aliases.len();
+                let calls = self.lower_decorators(class, stmt.span(), site);
+                let index = self.edits.len();
+                self.visit_class(class);
+                if let Some(calls) = calls {
+                    self.finish_legacy_decorators(calls, index);
+                }
+                self.class_aliases.truncate(outer);
+                return;
+            }
+        }
+        match stmt {
+            Stmt::Var(decl) => self.lower_var_decl(decl),
+            Stmt::ExportAll(e) if e.exported.is_some() && self.options.target < Target::ES2020 => {}
+            Stmt::ForOf(s) if self.lowers_for_await(s) => self.lower_for_await(s, None),
+            Stmt::ForIn(ForInStmt { left: ForHead::Pat(pat), right, body, .. })
+            | Stmt::ForOf(ForOfStmt { left: ForHead::Pat(pat), right, body, .. })
+                if self.has_private_target(pat) =>
+            {
+                self.private_for_head(pat, right, body)
+            }
+            Stmt::Labeled(l) => match l.body.as_ref() {
+                Stmt::ForOf(s) if self.lowers_for_await(s) => {
+                    self.edits.push(Edit::delete(l.span.start, s.span.start));
+                    self.lower_for_await(s, Some(&l.label));
+                }
+                _ => walk_stmt(self, stmt),
+            },
+            // What a lowered async generator returns is awaited first
+            Stmt::Return(ReturnStmt { span, arg: Some(arg) }) if self.lowering.awaits == AsyncLowering::Generator => {
+                let code = format!("return yield {}.awaitValue(", LOWER);
+                self.edits.push(Edit::new(span.start, arg.span().start, code));
+                self.edits.push(Edit::new(arg.span().end, arg.span().end, ")"));
+                self.visit_expr(arg);
+            }
+            _ => walk_stmt(self, stmt),
+        }
+    }
+
+    fn visit_expr(&mut self, expr: &Expr) {
+        self.loose_operands(expr);
+        if self.lower_expr(expr) {
+            return;
+        }
+        match expr {
+            Expr::JsxElement(element) => {
+                let code = self.jsx_element(element);
+                self.edits.push(Edit::new(element.span.start, element.span.end, code));
+                return;
+            }
+            Expr::JsxFragment(fragment) => {
+                let code = self.jsx_fragment(fragment);
+                self.edits.push(Edit::new(fragment.span.start, fragment.span.end, code));
+                return;
+            }
+            Expr::Ident(id) => {
+                // Sibling member referenced from a computed enum initializer
+                match &self.enums.members {
+                    Some((name, members)) if members.contains(&id.name) => {
+                        let code = format!("{}.{}", name, id.name);
+                        self.edits.push(Edit::new(id.span.start, id.span.end, code));
+                    }
+                    _ => self.rewrite_member_reference(id),
+                }
+            }
+            Expr::Assign(assign) => {
+                if let Pat::Ident(p) = &assign.left {
+                    self.rewrite_member_reference(&p.id);
+                }
+            }
+            Expr::Object(object) => {
+                object.props.iter().for_each(|prop| self.visit_prop(prop));
+                return;
+            }
+            Expr::Class(class) => {
+                self.lower_decorators(class, class.span, ClassSite::Expression);
+            }
+            Expr::Member(member) => {
+                if let Some(code) = self.inline_enum_member(member) {
+                    self.edits.push(Edit::new(member.span.start, member.span.end, code));
+                    return;
+                }
+            }
+            Expr::Call(call) => self.erase_trailing_comma(call.args.last().map(|a| a.span()), call.args_span.end - 1),
+            Expr::New(new) => {
+                if let Some(args) = &new.args {
+                    self.erase_trailing_comma(args.last().map(|a| a.span()), new.span.end - 1);
+                }
+            }
+            // `await` in an async function lowered to a generator
+            Expr::Await(e) if self.lowering.awaits == AsyncLowering::Awaiter => {
+                let loose = self.lowering.loose.contains(&(e.span.start, e.span.end));
+                let keyword = if loose { "yield" } else { "(yield" };
+                self.edits.push(Edit::new(e.span.start, e.span.start + "await".len(), keyword));
+                if !loose {
+                    self.edits.push(Edit::new(e.span.end, e.span.end, ")"));
+                }
+            }
+            // `await x` → `yield __fuse_lower.awaitValue(x)` in a lowered async generator
+            Expr::Await(e) if self.lowering.awaits == AsyncLowering::Generator => {
+                let loose = self.lowering.loose.contains(&(e.span.start, e.span.end));
+                let code = format!("{}yield {}.awaitValue(", if loose { "" } else { "(" }, LOWER);
+                self.lowering.helpers = true;
+                self.edits.push(Edit::new(e.span.start, e.arg.span().start, code));
+                self.edits.push(Edit::new(e.span.end, e.span.end, if loose { ")" } else { "))" }));
+            }
+            // What an async generator yields is awaited first, and what it
+            // delegates to is async: `yield x` → `yield yield __fuse_lower.awaitValue(x)`
+            Expr::Yield(e) if self.lowering.awaits == AsyncLowering::Generator => {
+                self.lowering.helpers = true;
+                let (code, close) = match e.delegate {
+                    true => (format!("yield {0}.awaitValue(yield* {0}.asyncDelegator({0}.asyncValues(", LOWER), ")))"),
+                    false => (format!("yield yield {}.awaitValue(", LOWER), ")"),
+                };
+                match &e.arg {
+                    Some(arg) => {
+                        self.edits.push(Edit::new(e.span.start, arg.span().start, code));
+                        self.edits.push(Edit::new(e.span.end, e.span.end, close));
+                    }
+                    None => self.edits.push(Edit::new(e.span.start, e.span.end, format!("{}void 0{}", code, close))),
+                }
+            }
+            _ => {}
+        }
+        if self.strip_types {
+            match expr {
+                Expr::TsAs(e) | Expr::TsSatisfies(e) => self.erase(Span::new(e.expr.span().end, e.span.end)),
+                Expr::TsNonNull(e) => self.erase(Span::new(e.span.end - 1, e.span.end)),
+                Expr::TsTypeAssertion(e) => self.erase(e.type_span),
+                Expr::TsInstantiation(e) => self.erase(e.type_args.span),
+                Expr::Call(e) => self.erase_opt(e.type_args.as_ref().map(|a| a.span)),
+                Expr::New(e) => self.erase_opt(e.type_args.as_ref().map(|a| a.span)),
+                Expr::TaggedTemplate(e) => self.erase_opt(e.type_args.as_ref().map(|a| a.span)),
+                _ => {}
+            }
+        }
+        walk_expr(self, expr);
+    }
+
+    fn visit_pat(&mut self, pat: &Pat) {
+        if let Pat::Expr(e) = pat {
+            if let Some((m, private)) = self.private_of(e) {
+                let code = self.private_target(m, private);
+                self.edits.push(Edit::new(e.span().start, e.span().end, code));
+                return;
+            }
+        }
+        if self.strip_types {
+            match pat {
+                Pat::Ident(p) => {
+                    self.erase_opt(p.optional);
+                    self.erase_opt(p.definite);
+                    self.erase_type_ann(&p.type_ann);
+                }
+                Pat::Array(p) => {
+                    self.erase_opt(p.optional);
+                    self.erase_type_ann(&p.type_ann);
+                }
+                Pat::Object(p) => {
+                    self.erase_opt(p.optional);
+                    self.erase_type_ann(&p.type_ann);
+                }
+                Pat::Rest(p) => self.erase_type_ann(&p.type_ann),
+                _ => {}
+            }
+        }
+        walk_pat(self, pat);
+    }
+
+    fn visit_function(&mut self, function: &Function) {
+        if self.strip_types {
+            self.erase_opt(function.type_params.as_ref().map(|p| p.span));
+            self.erase_type_ann(&function.return_type);
+            // `this` parameter
+            if let Some(Param { span, pat: Pat::Ident(p), .. }) = function.params.first() {
+                if p.id.name == "this" {
+                    self.erase_list_item(*span);
+                }
+            }
+        }
+        let body = function.body.as_ref().map_or(&[][..], |b| &b.stmts[..]);
+        let entered = self.enter_function(&function.params, body);
+        let this_allowed = std::mem::replace(
+            &mut self.this_allowed,
+            !self.derived_constructors.contains(&function.span.start),
+        );
+        // Methods start at their parameters, after the `async` keyword
+        let lower_async = self.lowers_async(function);
+        if lower_async && self.source[function.span.start..].starts_with("async") {
+            self.erase_async(Span::new(function.span.start, function.params_span.start), function.is_generator);
+        }
+        let mut uses = FunctionUses::default();
+        if lower_async {
+            walk_function(&mut uses, function);
+        }
+        let scope = self.enter_scope();
+        self.lower_params(&function.params, function.params_span);
+        let awaits = match (lower_async, function.is_generator) {
+            (false, _) => AsyncLowering::None,
+            (true, false) => AsyncLowering::Awaiter,
+            (true, true) => AsyncLowering::Generator,
+        };
+        let awaits = std::mem::replace(&mut self.lowering.awaits, awaits);
+        let super_props = (lower_async && uses.super_props).then(SuperProps::default);
+        let super_props = std::mem::replace(&mut self.lowering.super_props, super_props);
+        let class_this = self.lowering.class_this.take();
+        walk_function(self, function);
+        self.lowering.class_this = class_this;
+        let super_props = std::mem::replace(&mut self.lowering.super_props, super_props);
+        self.lowering.awaits = awaits;
+        let statements = self.leave_scope(scope);
+        if let Some(body) = &function.body {
+            if !statements.is_empty() {
+                self.prepend(body, &statements);
+            }
+            if lower_async {
+                let declarations = self.super_declarations(super_props);
+                let runner = self.async_runner(function.is_generator, &uses);
+                let code = format!("{{ {}return {}", declarations, runner);
+                self.edits.push(Edit::new(body.span.start, body.span.start, code));
+                self.edits.push(Edit::new(body.span.end, body.span.end, "); }"));
+            }
+        }
+        self.this_allowed = this_allowed;
+        if entered {
+            self.namespaces.frames.pop();
+        }
+    }
+
+    fn visit_arrow(&mut self, arrow: &ArrowFunction) {
+        if self.strip_types {
+            self.erase_opt(arrow.type_params.as_ref().map(|p| p.span));
+            self.erase_type_ann(&arrow.return_type);
+        }
+        let body = match &arrow.body {
+            ArrowBody::Block(block) => &block.stmts[..],
+            ArrowBody::Expr(_) => &[],
+        };
+        let entered = self.enter_function(&arrow.params, body);
+        let body = match &arrow.body {
+            ArrowBody::Block(block) => block.span,
+            ArrowBody::Expr(expr) => expr.span(),
+        };
+        let lower_async = arrow.is_async && self.options.target < Target::ES2017;
+        if lower_async {
+            self.erase_async(Span::new(arrow.span.start, arrow.params_span.start), false);
+        }
+        let mut uses = FunctionUses::default();
+        if lower_async {
+            walk_arrow(&mut uses, arrow);
+        }
+        let scope = self.enter_scope();
+        self.lower_params(&arrow.params, arrow.params_span);
+        let awaits = if lower_async { AsyncLowering::Awaiter } else { AsyncLowering::None };
+        let awaits = std::mem::replace(&mut self.lowering.awaits, awaits);
+        // Within a lowered async function, `super` is read through that function's declarations
+        let own_super = lower_async && uses.super_props && self.lowering.super_props.is_none();
+        if own_super {
+            self.lowering.super_props = Some(SuperProps::default());
+        }
+        walk_arrow(self, arrow);
+        let super_props = if own_super { self.lowering.super_props.take() } else { None };
+        self.lowering.awaits = awaits;
+        let statements = self.leave_scope(scope);
+        let (open, close) = match lower_async {
+            true => {
+                let declarations = self.super_declarations(super_props);
+                let runner = self.async_runner(false, &uses);
+                match declarations.is_empty() {
+                    true => (runner, ")".to_string()),
+                    false => (format!("{{ {}return {}", declarations, runner), "); }".to_string()),
+                }
+            }
+            false => (String::new(), String::new()),
+        };
+        match &arrow.body {
+            ArrowBody::Block(block) => {
+                if !statements.is_empty() {
+                    self.prepend(block, &statements);
+                }
+                if lower_async {
+                    self.edits.push(Edit::new(body.start, body.start, open));
+                    self.edits.push(Edit::new(body.end, body.end, close));
+                }
+            }
+            // An expression body becomes a block for the statements
+            ArrowBody::Expr(_) if lower_async || !statements.is_empty() => {
+                let mut code = open;
+                code.push_str("{ ");
+                statements.iter().for_each(|s| code.push_str(&format!("{} ", s)));
+                code.push_str("return ");
+                self.edits.push(Edit::new(body.start, body.start, code));
+                self.edits.push(Edit::new(body.end, body.end, format!("; }}{}", close)));
+            }
+            ArrowBody::Expr(_) => {}
+        }
+        if entered {
+            self.namespaces.frames.pop();
+        }
+    }
+
+    fn visit_class(&mut self, class: &Class) {
+        if self.strip_types {
+            self.erase_opt(class.type_params.as_ref().map(|p| p.span));
+            self.erase_opt(class.super_type_args.as_ref().map(|a| a.span));
+            self.erase_opt(class.implements);
+            self.erase_modifiers(&class.modifiers);
+        }
+        let class_this = self.lowering.class_this.take();
+        let super_props = self.lowering.super_props.take();
+        let privates = self.lowering.privates.len();
+        let moved = self.lower_class_members(class);
+        if class.super_class.is_some() {
+            let constructor = class.body.iter().find_map(|member| match member {
+                ClassMember::Method(m) if m.kind == MethodKind::Constructor && m.function.body.is_some() => {
+                    Some(m.function.span.start)
+                }
+                _ => None,
+            });
+            self.derived_constructors.extend(constructor);
+        }
+        match moved.is_empty() {
+            true => walk_class(self, class),
+            // Members moved out of the class were visited as they were moved
+            false => {
+                let kept = class.body.iter().filter(|m| !moved.contains(&m.span().start)).cloned().collect();
+                walk_class(self, &Class { body: kept, ..class.clone() });
+            }
+        }
+        self.lowering.privates.truncate(privates);
+        self.lowering.super_props = super_props;
+        self.lowering.class_this = class_this;
+    }
+}
+
+/// Target of a legacy member decorator
+fn legacy_target(class: &str, is_static: bool) -> String {
+    match is_static {
+        true => class.to_string(),
+        false => format!("{}.prototype", class),
+    }
+}
+
+/// `__metadata("design:<key>", value)` call
+fn design_metadata(key: &str, value: &str) -> String {
+    format!("{}.m(\"design:{}\", {})", DECORATE, key, value)
+}
+
+/// Declared type of a parameter
+fn param_type(param: &Param) -> Option<&TsType> {
+    fn pat_type(pat: &Pat) -> Option<&TsType> {
+        let ann = match pat {
+            Pat::Ident(p) => &p.type_ann,
+            Pat::Array(p) => &p.type_ann,
+            Pat::Object(p) => &p.type_ann,
+            Pat::Rest(p) => &p.type_ann,
+            Pat::Assign(p) => return pat_type(&p.left),
+            _ => return None,
+        };
+        ann.as_ref().map(|a| &a.ty)
+    }
+    pat_type(&param.pat)
+}
+
+/// `design:paramtypes` array of a parameter list
+fn param_types(params: &[Param]) -> String {
+    let types: Vec<String> = params
+        .iter()
+        .filter(|p| !matches!(&p.pat, Pat::Ident(p) if p.id.name == "this"))
+        .map(|p| metadata_type(param_type(p)))
+        .collect();
+    format!("[{}]", types.join(", "))
+}
+
+/// Global constructors that `design:*` metadata refers to by name
+const METADATA_GLOBALS: &[&str] = &[
+    "RegExp", "Number", "String", "Boolean", "Function", "Object", "Symbol", "BigInt", "Error", "EvalError",
+    "RangeError", "ReferenceError", "SyntaxError", "TypeError", "URIError", "Date", "Array", "Int8Array",
+    "Uint8Array", "Uint8ClampedArray", "Int16Array", "Uint16Array", "Int32Array", "Uint32Array", "Float32Array",
+    "Float64Array", "BigInt64Array", "BigUint64Array", "Map", "Set", "WeakMap", "WeakSet", "ArrayBuffer",
+    "DataView", "Promise", "XMLHttpRequest",
+];
+
+/// Runtime value standing for a type in `design:*` metadata
+fn metadata_type(ty: Option<&TsType>) -> String {
+    let Some(ty) = ty else { return "Object".to_string() };
+    let name = match &ty.kind {
+        TsTypeKind::Keyword(keyword) => match keyword.as_str() {
+            "string" => "String",
+            "number" => "Number",
+            "boolean" => "Boolean",
+            "symbol" => "Symbol",
+            "bigint" => "BigInt",
+            "void" | "undefined" | "null" | "never" => "void 0",
+            _ => "Object",
+        },
+        TsTypeKind::Literal(LitKind::Str | LitKind::Template) => "String",
+        TsTypeKind::Literal(LitKind::Num) => "Number",
+        TsTypeKind::Literal(LitKind::Bool) => "Boolean",
+        TsTypeKind::Literal(LitKind::BigInt) => "BigInt",
+        TsTypeKind::Array(_) | TsTypeKind::Tuple => "Array",
+        TsTypeKind::Function | TsTypeKind::Constructor => "Function",
+        TsTypeKind::Paren(inner) => return metadata_type(Some(inner)),
+        TsTypeKind::Union(types) => {
+            // `T | null | undefined` is serialized as `T`
+            let mut serialized = types
+                .iter()
+                .filter(|t| !matches!(&t.kind, TsTypeKind::Keyword(k) if matches!(k.as_str(), "null" | "undefined" | "never")))
+                .map(|t| metadata_type(Some(t)));
+            return match serialized.next() {
+                Some(first) if serialized.all(|t| t == first) => first,
+                _ => "Object".to_string(),
+            };
+        }
+        TsTypeKind::Reference(path) if path.len() == 1 => {
+            let name = path[0].name.as_str();
+            return match name {
+                "VoidFunction" | "GeneratorFunction" | "FunctionConstructor" | "FunctionStringCallback" => "Function".to_string(),
+                _ if METADATA_GLOBALS.contains(&name) => name.to_string(),
+                // The type may have no value at runtime
+                _ => format!("typeof {0} === \"function\" ? {0} : Object", name),
+            };
+        }
+        _ => "Object",
+    };
+    name.to_string()
+}
+
+/// Name of the variables holding a decorated member's decorators and
+/// initializers, unique within the class
+fn decorator_variable(key: &PropKey, is_static: bool, prefix: &str, names: &mut HashSet<String>) -> String {
+    let name = match key {
+        PropKey::Ident(id) => id.name.clone(),
+        PropKey::Private(id) => format!("private_{}", id.name.trim_start_matches('#')),
+        PropKey::Str(lit) => lit.value.as_str().unwrap_or_default().to_string(),
+        PropKey::Num(_) | PropKey::BigInt(_) | PropKey::Computed(_) => "member".to_string(),
+    };
+    let name: String = name.chars().map(|c| if is_id_continue(c) { c } else { '_' }).collect();
+    let base = format!("_{}{}{}", if is_static { "static_" } else { "" }, prefix, name);
+    let mut variable = base.clone();
+    let mut n = 2;
+    while !names.insert(variable.clone()) {
+        variable = format!("{}_{}", base, n);
+        n += 1;
+    }
+    variable
+}
+
+/// Context `name` of a decorated member, and the property access reading it
+fn member_access(key: &PropKey, source: &str) -> (String, String) {
+    let text = key.span().text(source);
+    match key {
+        PropKey::Ident(id) | PropKey::Private(id) => (quote_string(&id.name), format!(".{}", id.name)),
+        PropKey::Str(_) => (text.to_string(), format!("[{}]", text)),
+        _ => (quote_string(text), format!("[{}]", text)),
+    }
+}
+
+/// Tag argument: intrinsic elements (lowercase or dashed names) and
+/// namespaced names as strings, components and member tags by reference
+fn jsx_tag(name: &JsxName) -> String {
+    match name {
+        JsxName::Ident(id) if id.name == "this" => id.name.clone(),
+        JsxName::Ident(id) if id.name.starts_with(|c: char| c.is_ascii_lowercase()) || id.name.contains('-') => {
+            string_literal(&id.name)
+        }
+        JsxName::Ident(id) => id.name.clone(),
+        JsxName::Member { .. } => name.name(),
+        JsxName::Namespaced { .. } => string_literal(&name.name()),
+    }
+}
+
+/// Argument of an enum or namespace IIFE: the existing object or a new one,
+/// shared with the `parent` namespace when it exports the binding
+fn iife_argument(name: &str, parent: Option<&str>) -> String {
+    match parent {
+        Some(parent) => format!("{0} = {1}.{0} || ({1}.{0} = {{}})", name, parent),
+        None => format!("{0} || ({0} = {{}})", name),
+    }
+}
+
+/// `super(...);`
+fn is_super_call(stmt: &Stmt) -> bool {
+    matches!(stmt, Stmt::Expr(ExprStmt { expr: Expr::Call(call), .. }) if matches!(call.callee, Expr::Super(_)))
+}
+
+/// Finds calls of `super()`, without entering nested functions and classes
+struct SuperCalls(bool);
+
+impl Visit for SuperCalls {
+    fn visit_expr(&mut self, expr: &Expr) {
+        if matches!(expr, Expr::Call(call) if matches!(call.callee, Expr::Super(_))) {
+            self.0 = true;
+        }
+        walk_expr(self, expr);
+    }
+
+    fn visit_function(&mut self, _: &Function) {}
+
+    fn visit_class(&mut self, _: &Class) {}
+}
+
+/// Names a statement declares in its scope
+fn declared_names(stmt: &Stmt) -> Vec<String> {
+    match stmt {
+        Stmt::Var(var) => var.decls.iter().flat_map(|d| d.name.bound_names()).map(|id| id.name.clone()).collect(),
+        Stmt::Function(Function { id: Some(id), .. }) | Stmt::Class(Class { id: Some(id), .. }) => vec![id.name.clone()],
+        Stmt::TsEnum(e) => vec![e.id.name.clone()],
+        Stmt::TsModule(TsModuleDecl { name: TsModuleName::Path(path), .. }) => vec![path[0].name.clone()],
+        _ => Vec::new(),
+    }
+}
+
+/// Collects the bindings a function body declares, without entering nested functions
+struct Bindings<'b>(&'b mut HashSet<String>);
+
+impl Visit for Bindings<'_> {
+    fn visit_stmt(&mut self, stmt: &Stmt) {
+        self.0.extend(declared_names(stmt));
+        walk_stmt(self, stmt);
+    }
+
+    fn visit_expr(&mut self, _: &Expr) {}
+
+    fn visit_function(&mut self, _: &Function) {}
+
+    fn visit_class(&mut self, _: &Class) {}
+}
+
+/// What a function refers to that its lowered async form passes on: its
+/// `arguments`, and properties of `super`. Arrows share these with the
+/// function around them; other functions and classes have their own.
+#[derive(Default)]
+struct FunctionUses {
+    arguments: bool,
+    super_props: bool,
+}
+
+impl Visit for FunctionUses {
+    fn visit_expr(&mut self, expr: &Expr) {
+        match expr {
+            Expr::Ident(id) if id.name == "arguments" => self.arguments = true,
+            Expr::Member(m) if matches!(m.object, Expr::Super(_)) => self.super_props = true,
+            _ => {}
+        }
+        walk_expr(self, expr);
+    }
+
+    fn visit_function(&mut self, _: &Function) {}
+
+    fn visit_class(&mut self, _: &Class) {}
+}
+
+/// Source text of an enum member value
+fn enum_value(value: &Literal) -> Option<String> {
+    match value {
+        Literal::Number(n) if n.is_nan() => Some("NaN".to_string()),
+        Literal::Number(n) if n.is_infinite() => Some(if *n > 0.0 { "Infinity" } else { "-Infinity" }.to_string()),
+        Literal::Number(n) => format_number(*n),
+        Literal::String(s) => Some(quote_string(s)),
+        _ => None,
+    }
+}
+
+/// Object literal key, quoted unless it is a valid identifier
+fn prop_key(name: &str) -> String {
+    let mut chars = name.chars();
+    let is_ident = chars.next().is_some_and(is_id_start) && chars.all(is_id_continue);
+    if is_ident {
+        name.to_string()
+    } else {
+        quote_string(name)
+    }
+}
+
+/// Generate the final JSX call
+/// `createElement(tag, props, ...children)`, the classic runtime call
+fn create_element_call(callee: &str, tag: &str, props: &[String], children: &[String], assign: bool) -> String {
+    let props = match props.is_empty() {
+        true => "null".to_string(),
+        false => object_literal(props, assign),
+    };
+    let mut args = vec![tag.to_string(), props];
+    args.extend(children.iter().cloned());
+    format!("{}({})", callee, args.join(", "))
+}
+
+/// `{ a, b }`; with `assign`, spread members (`...x`) become arguments of
+/// `Object.assign`, for targets before ES2018
+fn object_literal(props: &[String], assign: bool) -> String {
+    let literal = |props: &[String]| match props.is_empty() {
+        true => "{}".to_string(),
+        false => format!("{{ {} }}", props.join(", ")),
+    };
+    if !assign || !props.iter().any(|p| p.starts_with("...")) {
+        return literal(props);
+    }
+    let mut args = Vec::new();
+    let mut chunk = Vec::new();
+    for prop in props {
+        match prop.strip_prefix("...") {
+            Some(value) => {
+                if args.is_empty() || !chunk.is_empty() {
+                    args.push(literal(&chunk));
+                    chunk.clear();
+                }
+                args.push(value.to_string());
+            }
+            None => chunk.push(prop.clone()),
+        }
+    }
+    if !chunk.is_empty() {
+        args.push(literal(&chunk));
+    }
+    format!("Object.assign({})", args.join(", "))
+}
+
+/// A field defined with `[[Define]]` semantics, as `Object.defineProperty` does
+fn define_property(target: &str, key: &str, value: &str) -> String {
+    format!(
+        "Object.defineProperty({}, {}, {{ enumerable: true, configurable: true, writable: true, value: {} }})",
+        target, key, value
+    )
+}
+
+/// Whether evaluating `expr` again is harmless
+fn is_simple(expr: &Expr) -> bool {
+    matches!(expr, Expr::Ident(_) | Expr::This(_))
+}
+
+/// The ES2018 feature a regular expression uses, as a diagnostic message:
+/// the `s` flag, lookbehind, named groups or Unicode property escapes
+fn regex_es2018_feature(pattern: &str, flags: &str) -> Option<&'static str> {
+    if flags.contains('s') {
+        return Some("The regular expression flag `s` needs target \"es2018\" or later");
+    }
+    let unicode = flags.contains('u') || flags.contains('v');
+    let mut chars = pattern.chars().peekable();
+    let mut in_class = false;
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some('p' | 'P') if unicode => {
+                    return Some("Unicode property escapes need target \"es2018\" or later");
+                }
+                _ => {}
+            },
+            '[' => in_class = true,
+            ']' => in_class = false,
+            '(' if !in_class && chars.next_if_eq(&'?').is_some() && chars.next_if_eq(&'<').is_some() => {
+                return Some(match chars.peek() {
+                    Some('=' | '!') => "Lookbehind assertions need target \"es2018\" or later",
+                    _ => "Named capture groups need target \"es2018\" or later",
+                });
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Whether `expr` can be the operand of a binary operator as it is
+fn is_operand(expr: &Expr) -> bool {
+    matches!(
+        expr,
+        Expr::Ident(_)
+            | Expr::This(_)
+            | Expr::Lit(_)
+            | Expr::Template(_)
+            | Expr::Array(_)
+            | Expr::Object(_)
+            | Expr::Paren(_)
+            | Expr::Member(_)
+            | Expr::Call(_)
+    )
+}
+
+/// First occurrence of `word` in `text` as a whole identifier
+fn word_position(text: &str, word: &str) -> Option<usize> {
+    text.match_indices(word).map(|(i, _)| i).find(|&i| {
+        let before = text[..i].chars().next_back();
+        let after = text[i + word.len()..].chars().next();
+        !before.is_some_and(is_id_continue) && !after.is_some_and(is_id_continue)
+    })
+}
+
+/// The runs of identifier characters in `text`
+fn words(text: &str) -> HashSet<String> {
+    text.split(|c: char| !is_id_continue(c)).filter(|word| !word.is_empty()).map(str::to_string).collect()
+}
+
+/// `code;` as an expression statement, parenthesized if it would start like a block
+fn expression_statement(code: &str) -> String {
+    match code.starts_with('{') {
+        true => format!("({});", code),
+        false => format!("{};", code),
+    }
+}
+
+/// Arguments of `Function.prototype.call`
+fn join_args(receiver: &str, args: &str) -> String {
+    match args.is_empty() {
+        true => receiver.to_string(),
+        false => format!("{}, {}", receiver, args),
+    }
+}
+
+/// Binding of the rest ending an object pattern, with the other keys as
+/// strings; `None` without a rest or with computed keys
+fn object_rest(pat: &ObjectPat) -> Option<(String, Vec<String>)> {
+    let (ObjectPatProp::Rest(rest), props) = pat.props.split_last()? else {
+        return None;
+    };
+    let Pat::Ident(binding) = rest.arg.as_ref() else {
+        return None;
+    };
+    let mut keys = Vec::new();
+    for prop in props {
+        match prop {
+            ObjectPatProp::KeyValue { key, .. } => keys.push(quote_string(&key.static_name()?)),
+            ObjectPatProp::Assign { key, .. } => keys.push(quote_string(&key.name)),
+            ObjectPatProp::Rest(_) => return None,
+        }
+    }
+    Some((binding.id.name.clone(), keys))
+}
+
+/// The options for a module with `@jsx`, `@jsxFrag`, `@jsxImportSource` or
+/// `@jsxRuntime` pragmas, which override the JSX options for that module.
+/// `@jsx` and `@jsxFrag` switch to the classic runtime, `@jsxImportSource`
+/// to the automatic one, unless `@jsxRuntime` says otherwise.
+fn jsx_pragma_options(program: &Program, source: &str, options: &TransformOptions) -> Option<TransformOptions> {
+    let comments: Vec<&str> = program
+        .comments
+        .iter()
+        .map(|c| c.body(source))
+        .filter(|body| body.contains("@jsx"))
+        .collect();
+    if comments.is_empty() {
+        return None;
+    }
+    let find = |name: &str| comments.iter().find_map(|body| pragma(body, name));
+    let factory = find("jsx");
+    let fragment = find("jsxFrag");
+    let import_source = find("jsxImportSource");
+    let runtime = find("jsxRuntime");
+
+    let mut options = options.clone();
+    let classic = options.jsx_runtime == JsxRuntime::Classic;
+    options.jsx_runtime = match runtime {
+        Some("classic") => JsxRuntime::Classic,
+        Some("automatic") if classic => JsxRuntime::Automatic,
+        None if import_source.is_some() && classic => JsxRuntime::Automatic,
+        None if import_source.is_none() && (factory.is_some() || fragment.is_some()) => JsxRuntime::Classic,
+        _ => options.jsx_runtime,
+    };
+    if let Some(factory) = factory {
+        options.jsx_factory = factory.to_string();
+    }
+    if let Some(fragment) = fragment {
+        options.jsx_fragment = fragment.to_string();
+    }
+    if let Some(import_source) = import_source {
+        options.jsx_import_source = import_source.to_string();
+    }
+    Some(options)
+}
+
+/// Value of an `@name value` pragma at the start of a comment line, after
+/// an optional `*`
+fn pragma<'s>(comment: &'s str, name: &str) -> Option<&'s str> {
+    comment.lines().find_map(|line| {
+        let line = line.trim_start();
+        let rest = line.strip_prefix('*').unwrap_or(line).trim_start();
+        let rest = rest.strip_prefix('@')?.strip_prefix(name)?;
+        match rest.starts_with(char::is_whitespace) {
+            true => rest.split_whitespace().next(),
+            false => None,
+        }
+    })
+}
+
+/// Import declarations for the JSX runtime helpers a module uses
+fn jsx_imports(helpers: &[JsxHelper], options: &TransformOptions) -> Vec<String> {
+    let specifiers: Vec<String> = [JsxHelper::Jsx, JsxHelper::Jsxs, JsxHelper::JsxDev, JsxHelper::Fragment]
+        .into_iter()
+        .filter(|h| helpers.contains(h))
+        .map(|h| format!("{} as {}", h.imported(), h.local()))
+        .collect();
+    let mut imports = Vec::new();
+    if !specifiers.is_empty() {
+        let runtime = match options.jsx_runtime {
+            JsxRuntime::AutomaticDev => "jsx-dev-runtime",
+            _ => "jsx-runtime",
+        };
+        let source = format!("{}/{}", options.jsx_import_source, runtime);
+        imports.push(format!("import {{ {} }} from {};", specifiers.join(", "), quote_string(&source)));
+    }
+    if helpers.contains(&JsxHelper::CreateElement) {
+        let source = quote_string(&options.jsx_import_source);
+        imports.push(format!("import {{ createElement as _createElement }} from {};", source));
+    }
+    imports
+}
+
+/// Minify with the default minifier settings
+fn minify(source: &str) -> String {
+    crate::minifier::minify_internal(source, None, &crate::minifier::MinifyConfig::default()).code
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_remove_type_annotation() {
+        let source = "const x: number = 5;";
+        let (result, had_types) = remove_typescript_types(source);
+        assert!(had_types);
+        assert!(!result.contains(": number"));
         assert!(result.contains("const x"));
     }
 
@@ -1076,4 +4277,533 @@ mod tests {
         assert!(!result.code.contains("interface"));
         assert!(result.code.contains("_jsx"));
     }
+
+    #[test]
+    fn test_leaves_strings_and_regex_alone() {
+        let source = "const s: string = \"x: number\";\nconst re = /<div>/g;\nconst t = `<b>${a as any}</b>`;";
+        let (result, had_types) = remove_typescript_types(source);
+        assert!(had_types);
+        assert_eq!(result, "const s = \"x: number\";\nconst re = /<div>/g;\nconst t = `<b>${a}</b>`;");
+    }
+
+    #[test]
+    fn test_nested_jsx_in_expressions() {
+        let source = "const el = <ul>{items.map((i: Item) => <li key={i.id}>{i.name}</li>)}</ul>;";
+        let result = transform_internal(source, "list.tsx", &TransformOptions::default());
+        assert!(result.had_jsx);
+        assert!(result.code.contains(
+            "_jsx(\"ul\", { children: items.map((i) => _jsx(\"li\", { children: i.name }, i.id)) })"
+        ));
+    }
+
+    #[test]
+    fn test_jsx_key_and_spread() {
+        let options = TransformOptions::default();
+        let (result, _) = transform_jsx("\"use client\";\n<div key=\"a\" {...props} id=\"x\" />;", &options);
+        assert_eq!(
+            result,
+            "\"use client\";\nimport { jsx as _jsx } from \"react/jsx-runtime\";\n_jsx(\"div\", { ...props, id: \"x\" }, \"a\");"
+        );
+
+        // A key after a spread falls back to `createElement`
+        let (result, _) = transform_jsx("<div {...props} key=\"b\">text</div>", &options);
+        assert_eq!(
+            result,
+            "import { createElement as _createElement } from \"react\";\n\
+             _createElement(\"div\", { ...props, key: \"b\" }, \"text\")"
+        );
+
+        let (result, _) = transform_jsx("<><span key={i} />{x}</>", &options);
+        assert_eq!(
+            result,
+            "import { jsx as _jsx, jsxs as _jsxs, Fragment as _Fragment } from \"react/jsx-runtime\";\n\
+             _jsxs(_Fragment, { children: [_jsx(\"span\", {}, i), x] })"
+        );
+    }
+
+    #[test]
+    fn test_jsx_text_and_names() {
+        let options = TransformOptions::default();
+        let source = "<p title=\"a &amp;\n    b\">\n  Hello,&nbsp;{name}\n  &lt;World&#33;&gt;\n\n  again  \n</p>";
+        let (result, _) = transform_jsx(source, &options);
+        assert_eq!(
+            result,
+            "import { jsxs as _jsxs } from \"react/jsx-runtime\";\n\
+             _jsxs(\"p\", { title: \"a & b\", children: [\"Hello,\u{A0}\", name, \"<World!> again\"] })"
+        );
+
+        let source = "<Foo.Bar class='it\"s' xlink:href=\"#a\" icon=<svg:use />> a </Foo.Bar>";
+        let (result, _) = transform_jsx(source, &options);
+        assert_eq!(
+            result,
+            "import { jsx as _jsx } from \"react/jsx-runtime\";\n\
+             _jsx(Foo.Bar, { class: \"it\\\"s\", \"xlink:href\": \"#a\", icon: _jsx(\"svg:use\", {}), children: \" a \" })"
+        );
+    }
+
+    #[test]
+    fn test_jsx_dev_runtime() {
+        let mut options = TransformOptions::default();
+        options.set_jsx_runtime("automatic-dev");
+        let source = "const a = <div key=\"a\">hi</div>;\nclass A extends B {\n  constructor() { super(<p />); }\n}";
+        let result = transform_internal(source, "src/app.jsx", &options);
+        assert_eq!(
+            result.code,
+            "import { jsxDEV as _jsxDEV } from \"react/jsx-dev-runtime\";\nvar _jsxFileName = \"src/app.jsx\";\n\
+             const a = _jsxDEV(\"div\", { children: \"hi\" }, \"a\", false, \
+             { fileName: _jsxFileName, lineNumber: 1, columnNumber: 11 }, this);\nclass A extends B {\n  \
+             constructor() { super(_jsxDEV(\"p\", {}, void 0, false, \
+             { fileName: _jsxFileName, lineNumber: 3, columnNumber: 25 }, void 0)); }\n}"
+        );
+    }
+
+    #[test]
+    fn test_jsx_pragmas() {
+        let options = TransformOptions::default();
+        let source = "/** @jsx h */\n/** @jsxFrag Fragment */\nconst a = <><b /></>;";
+        let (result, _) = transform_jsx(source, &options);
+        assert_eq!(result, "/** @jsx h */\n/** @jsxFrag Fragment */\nconst a = h(Fragment, null, h(\"b\", null));");
+
+        let mut classic = TransformOptions::default();
+        classic.set_jsx_runtime("classic");
+        let source = "/**\n * @jsxImportSource @emotion/react\n */\nconst a = <div css={s} />;";
+        let (result, _) = transform_jsx(source, &classic);
+        assert_eq!(
+            result,
+            "import { jsx as _jsx } from \"@emotion/react/jsx-runtime\";\n/**\n * @jsxImportSource @emotion/react\n */\n\
+             const a = _jsx(\"div\", { css: s });"
+        );
+
+        // Only this file is affected, and `@jsxRuntime` wins
+        assert_eq!(classic.jsx_import_source, "react");
+        let (result, _) = transform_jsx("// @jsxRuntime classic @jsxImportSource preact\n<a />", &options);
+        assert_eq!(result, "// @jsxRuntime classic @jsxImportSource preact\nReact.createElement(\"a\", null)");
+    }
+
+    #[test]
+    fn test_enum_lowering() {
+        let source = "export enum E { A, B = 5, C, D = \"d\", F = B << 1, G = f(C) }\nenum E { H = 10 }";
+        let (result, had_types) = remove_typescript_types(source);
+        assert!(had_types);
+        assert_eq!(
+            result,
+            "export var E;\n(function (E) {\n    E[E[\"A\"] = 0] = \"A\";\n    E[E[\"B\"] = 5] = \"B\";\n    \
+             E[E[\"C\"] = 6] = \"C\";\n    E[\"D\"] = \"d\";\n    E[E[\"F\"] = 10] = \"F\";\n    \
+             E[E[\"G\"] = f(E.C)] = \"G\";\n})(E || (E = {}));\n\
+             (function (E) {\n    E[E[\"H\"] = 10] = \"H\";\n})(E || (E = {}));"
+        );
+
+        // Nested enums keep the indentation of their declaration
+        let (result, _) = remove_typescript_types("function f() {\n  enum Dir { Up = -1, Down = Up * -1 }\n}");
+        assert_eq!(
+            result,
+            "function f() {\n  var Dir;\n  (function (Dir) {\n      Dir[Dir[\"Up\"] = -1] = \"Up\";\n      \
+             Dir[Dir[\"Down\"] = 1] = \"Down\";\n  })(Dir || (Dir = {}));\n}"
+        );
+    }
+
+    #[test]
+    fn test_const_enum_inlining() {
+        let source = "const x = K.B + K[\"A\"] ** 2;\nconst enum K { A = -1, B = A + 3, S = `s` }\nlog(K.S, D.Q);\ndeclare const enum D { Q = 4 }";
+        let (result, _) = remove_typescript_types(source);
+        assert_eq!(
+            result,
+            "const x = 2 /* K.B */ + (-1 /* K.A */) ** 2;\n\nlog(\"s\" /* K.S */, 4 /* D.Q */);\n"
+        );
+
+        // An exported const enum keeps its object for other modules
+        let (result, _) = remove_typescript_types("const enum K { A }\nexport { K };\nuse(K.A);");
+        assert!(result.starts_with("var K;\n(function (K) {"));
+        assert!(result.ends_with("use(0 /* K.A */);"));
+    }
+
+    #[test]
+    fn test_namespace_lowering() {
+        let source = "function NS() {}\nnamespace NS {\n    export const x = 1;\n    const hidden = 2;\n    \
+                      export function f(x: number) { return x + hidden; }\n    export interface I {}\n}\n\
+                      namespace NS {\n    export const y = f(x), o = { x };\n}";
+        let (result, had_types) = remove_typescript_types(source);
+        assert!(had_types);
+        assert_eq!(
+            result,
+            "function NS() {}\n(function (NS) {\n    NS.x = 1;\n    const hidden = 2;\n    \
+             function f(x) { return x + hidden; }\n    NS.f = f;\n    \n})(NS || (NS = {}));\n\
+             (function (NS) {\n    NS.y = NS.f(NS.x); NS.o = { x: NS.x };\n})(NS || (NS = {}));"
+        );
+    }
+
+    #[test]
+    fn test_nested_and_ambient_namespaces() {
+        let source = "export namespace A.B { export namespace C { export const c = 1; } }\n\
+                      declare module \"pkg\" { export const v: number; }\ndeclare global { interface W {} }\n\
+                      namespace OnlyTypes { export type T = string; }";
+        let (result, _) = remove_typescript_types(source);
+        assert_eq!(
+            result,
+            "export var A;\n(function (A) {\n    let B;\n    (function (B) { let C;\n(function (C) { C.c = 1; })\
+             (C = B.C || (B.C = {})); })(B = A.B || (A.B = {}));\n})(A || (A = {}));\n\n\n"
+        );
+    }
+
+    #[test]
+    fn test_class_members() {
+        let source = "abstract class A extends B {\n    private cache = new Map<string, number>();\n    \
+                      declare meta: unknown;\n    name!: string;\n    static count: number = 0;\n    \
+                      abstract run(): void;\n    constructor(private readonly x: number, public y = 2) {\n        \
+                      super();\n    }\n    public override get value(): number { return this.x; }\n}";
+        let (result, had_types) = remove_typescript_types(source);
+        assert!(had_types);
+        assert_eq!(
+            result,
+            "class A extends B {\n    \n    \n    \n    \n    \n    \
+             constructor(x, y = 2) {\n        super();\n        this.x = x;\n        this.y = y;\n        \
+             this.cache = new Map();\n    }\n    get value() { return this.x; }\n}\nA.count = 0;"
+        );
+
+        // Without a constructor one is added
+        let (result, _) = remove_typescript_types("class C extends D {\n  items: string[] = [];\n}");
+        assert_eq!(
+            result,
+            "class C extends D {\n  constructor() {\n    super(...arguments);\n    this.items = [];\n  }\n  \n}"
+        );
+
+        // `super()` without a semicolon gets one before the assignments
+        let source = "class C extends D {\n  constructor(public x: number) { super() }\n}";
+        let (result, _) = remove_typescript_types(source);
+        assert_eq!(result, "class C extends D {\n  constructor(x) { super(); this.x = x; }\n}");
+
+        // Assignments cannot be placed after a `super()` nested in a statement
+        let source = "class C extends D {\n  constructor(public x: number) {\n    if (x) super(x);\n    \
+                      else super();\n  }\n}";
+        let result = transform_internal(source, "a.ts", &TransformOptions::default());
+        assert_eq!(result.diagnostics.len(), 1);
+        assert_eq!(result.diagnostics[0].code, "unsupported-syntax");
+    }
+
+    #[test]
+    fn test_use_define_for_class_fields() {
+        let mut options = TransformOptions::default();
+        assert!(!options.use_define_for_class_fields());
+        options.target = Target::ES2022;
+        assert!(options.use_define_for_class_fields());
+
+        let source = "class A {\n  y: number = 1;\n  z!: string;\n  constructor(public x: number) {}\n}";
+        let result = transform_internal(source, "a.ts", &options);
+        assert_eq!(
+            result.code,
+            "class A {\n  x;\n  y = 1;\n  z;\n  constructor(x) { this.x = x; }\n}"
+        );
+
+        // `accessor` fields keep their getter and setter
+        options.target = Target::ESNext;
+        options.set_use_define_for_class_fields(false);
+        let source = "class K {\n  accessor z = 1;\n  static accessor w = 2;\n  y = 3;\n}";
+        let result = transform_internal(source, "k.ts", &options);
+        assert_eq!(
+            result.code,
+            "class K {\n  constructor() {\n    this.y = 3;\n  }\n  accessor z = 1;\n  static accessor w = 2;\n  \n}"
+        );
+    }
+
+    #[test]
+    fn test_legacy_decorators() {
+        let mut options = TransformOptions::default();
+        options.set_decorators("legacy");
+        options.set_emit_decorator_metadata(true);
+        let source = "@Injectable()\nexport class S {\n  @Input() name: string | null;\n  \
+                      constructor(@Inject(T) private http: Http) {}\n  @Log() run(id: number): void {}\n}";
+        let result = transform_internal(source, "s.ts", &options);
+        assert_eq!(
+            result.code,
+            "import * as __fuse_decorate from \"fuse_helpers_decorate\";\nexport class S {\n  \n  \
+             constructor(http) { this.http = http; }\n  run(id) {}\n}\n\
+             __fuse_decorate.d([Input(), __fuse_decorate.m(\"design:type\", String)], S.prototype, \"name\", void 0);\n\
+             __fuse_decorate.d([Log(), __fuse_decorate.m(\"design:type\", Function), \
+             __fuse_decorate.m(\"design:paramtypes\", [Number]), __fuse_decorate.m(\"design:returntype\", void 0)], \
+             S.prototype, \"run\", null);\n\
+             S = __fuse_decorate.d([Injectable(), __fuse_decorate.p(0, Inject(T)), \
+             __fuse_decorate.m(\"design:paramtypes\", [typeof Http === \"function\" ? Http : Object])], S);"
+        );
+
+        // An anonymous default export gets a name to reassign
+        options.set_emit_decorator_metadata(false);
+        let result = transform_internal("export default @sealed class {}", "a.ts", &options);
+        assert!(result.code.ends_with("export default class _default {}\n_default = __fuse_decorate.d([sealed], _default);"));
+
+        // Static initializers run before the class decorators, which the class body sees through an alias
+        let source = "@seal\nclass B {\n  static count = 42;\n  m() { return B; }\n}";
+        let result = transform_internal(source, "b.ts", &options);
+        assert_eq!(
+            result.code,
+            "import * as __fuse_decorate from \"fuse_helpers_decorate\";\nvar B_1;\nclass B {\n  \n  \
+             m() { return B_1; }\n}\nB_1 = B;\nB.count = 42;\nB = B_1 = __fuse_decorate.d([seal], B);"
+        );
+
+        // Computed keys are evaluated once, into a temporary the decorator call uses
+        let result = transform_internal("class C {\n  @dec [\"a\" + k]() {}\n  @dec [j()] = 1;\n}", "c.js", &options);
+        assert_eq!(
+            result.code,
+            "import * as __fuse_decorate from \"fuse_helpers_decorate\";\nvar _a, _b;\nclass C {\n  \
+             constructor() {\n    this[_b] = 1;\n  }\n  [_a = \"a\" + k]() {}\n  \n}\n_b = j();\n\
+             __fuse_decorate.d([dec], C.prototype, _a, null);\n__fuse_decorate.d([dec], C.prototype, _b, void 0);"
+        );
+
+        // Private members cannot be decorated; the decorators are reported and erased
+        let source = "class P {\n  @dec #m() {}\n  @dec #f = 1;\n  run() { return this.#m(this.#f); }\n}";
+        let result = transform_internal(source, "p.js", &options);
+        assert!(!result.code.contains("@dec") && !result.code.contains("#m"));
+        assert_eq!(result.diagnostics.len(), 2);
+        assert!(result.diagnostics.iter().all(|d| d.code == "unsupported-decorator"));
+        options.target = Target::ES2022;
+        let result = transform_internal(source, "p.js", &options);
+        assert_eq!(result.code, "class P {\n  #m() {}\n  #f = 1;\n  run() { return this.#m(this.#f); }\n}");
+        assert_eq!(result.diagnostics.len(), 2);
+
+        // Decorators are kept by default
+        let result = transform_internal("@sealed class A {}", "a.ts", &TransformOptions::default());
+        assert_eq!(result.code, "@sealed class A {}");
+    }
+
+    #[test]
+    fn test_standard_decorators() {
+        let mut options = TransformOptions::default();
+        options.set_decorators("2023-05");
+        options.target = Target::ES2022;
+        let result = transform_internal("@sealed\nclass A {\n    @field x = 1;\n    @log m() {}\n}", "a.ts", &options);
+        assert_eq!(
+            result.code,
+            "import * as __fuse_decorate from \"fuse_helpers_decorate\";\nlet A = (() => {\n    \
+             let _classDecorators = [sealed];\n    let _classDescriptor;\n    let _classExtraInitializers = [];\n    \
+             let _classThis;\n    let _instanceExtraInitializers = [];\n    let _m_decorators = [log];\n    \
+             let _x_decorators = [field];\n    let _x_initializers = [];\n    var A = class {\n    \
+             static { _classThis = this; }\n    static {\n        \
+             const _metadata = typeof Symbol === \"function\" && Symbol.metadata ? Object.create(null) : void 0;\n        \
+             __fuse_decorate.esDecorate(this, null, _m_decorators, { kind: \"method\", name: \"m\", static: false, \
+             private: false, access: { has: obj => \"m\" in obj, get: obj => obj.m }, metadata: _metadata }, null, \
+             _instanceExtraInitializers);\n        \
+             __fuse_decorate.esDecorate(null, null, _x_decorators, { kind: \"field\", name: \"x\", static: false, \
+             private: false, access: { has: obj => \"x\" in obj, get: obj => obj.x, set: (obj, value) => { obj.x = value; } }, \
+             metadata: _metadata }, _x_initializers, _instanceExtraInitializers);\n        \
+             __fuse_decorate.esDecorate(null, _classDescriptor = { value: _classThis }, _classDecorators, \
+             { kind: \"class\", name: _classThis.name, metadata: _metadata }, null, _classExtraInitializers);\n        \
+             A = _classThis = _classDescriptor.value;\n        \
+             if (_metadata) Object.defineProperty(_classThis, Symbol.metadata, { enumerable: true, configurable: true, \
+             writable: true, value: _metadata });\n    }\n    constructor() {\n        \
+             __fuse_decorate.runInitializers(this, _instanceExtraInitializers);\n    }\n    \
+             x = __fuse_decorate.runInitializers(this, _x_initializers, 1);\n    m() {}\n    \
+             static { __fuse_decorate.runInitializers(_classThis, _classExtraInitializers); }\n};\n    \
+             return A = _classThis;\n})();"
+        );
+
+        // Parameter decorators are a TypeScript extension of legacy decorators
+        let source = "class S {\n  constructor(@Inject(TOKEN) svc) {}\n}";
+        let result = transform_internal(source, "s.ts", &options);
+        assert_eq!(result.code, "class S {\n  constructor(svc) {}\n}");
+        assert_eq!(result.diagnostics.len(), 1);
+        assert_eq!(result.diagnostics[0].code, "unsupported-decorator");
+        assert_eq!((result.diagnostics[0].line, result.diagnostics[0].column), (2, 15));
+
+        // So are decorators of private methods and accessors
+        let result = transform_internal("class P {\n  @p #q() {}\n  @p get #g() { return 1; }\n}", "p.js", &options);
+        assert_eq!(result.code, "class P {\n  #q() {}\n  get #g() { return 1; }\n}");
+        assert_eq!(result.diagnostics.len(), 2);
+        assert!(result.diagnostics.iter().all(|d| d.code == "unsupported-decorator"));
+
+        // `accessor` fields get private storage, and class expressions are wrapped in place
+        let result = transform_internal("const B = class {\n  @tracked accessor n = 0;\n};", "b.js", &options);
+        assert!(result.code.contains("const B = (() => {\n"));
+        assert!(result.code.contains(
+            "#n_accessor_storage = __fuse_decorate.runInitializers(this, _n_initializers, 0);\n  \
+             get n() { return this.#n_accessor_storage; }\n  set n(value) { this.#n_accessor_storage = value; }"
+        ));
+        assert!(result.code.ends_with("    return _class;\n})();"));
+    }
+
+    #[test]
+    fn test_set_target() {
+        let mut options = TransformOptions::default();
+        assert_eq!(options.target, Target::ES2020);
+        options.set_target("ES2015");
+        assert_eq!(options.target, Target::ES2015);
+        options.set_target("es2019");
+        assert_eq!(options.target, Target::ES2018);
+        options.set_target("es2023");
+        assert_eq!(options.target, Target::ES2022);
+        options.set_target("esnext");
+        assert_eq!(options.target, Target::ESNext);
+    }
+
+    #[test]
+    fn test_lower_es2021() {
+        let mut options = TransformOptions::default();
+        options.set_target("es2020");
+        let result = transform_internal("a ??= b.c;\nconst n = 1_000_000;", "a.js", &options);
+        assert_eq!(result.code, "a ?? (a = b.c);\nconst n = 1000000;");
+
+        options.set_target("es2021");
+        let result = transform_internal("a ??= b.c;", "a.js", &options);
+        assert_eq!(result.code, "a ??= b.c;");
+    }
+
+    #[test]
+    fn test_lower_es2017() {
+        let mut options = TransformOptions::default();
+        options.set_target("es2017");
+        let source = "const v = a?.b ?? c;\nconst o = { ...p, q: 1 };\nexport * as ns from \"./m\";\n\
+                      try { f(); } catch { g(); }\nconst x = 2 ** n;";
+        let result = transform_internal(source, "a.js", &options);
+        assert_eq!(
+            result.code,
+            "var _a;\nconst v = (_a = a === null || a === void 0 ? void 0 : a.b) !== null && _a !== void 0 ? _a : c;\n\
+             const o = Object.assign({}, p, { q: 1 });\nimport * as ns_1 from \"./m\"; export { ns_1 as ns };\n\
+             try { f(); } catch (_unused) { g(); }\nconst x = 2 ** n;"
+        );
+
+        // Object rest goes through the helper
+        let result = transform_internal("const { a, ...r } = o;", "a.js", &options);
+        assert_eq!(
+            result.code,
+            "import * as __fuse_lower from \"fuse_helpers_lower\";\n\
+             const { a } = o, r = __fuse_lower.rest(o, [\"a\"]);"
+        );
+    }
+
+    #[test]
+    fn test_lower_es2015() {
+        let mut options = TransformOptions::default();
+        options.set_target("es2015");
+        let source = "async function load(url) {\n  const res = await fetch(url);\n  return res.json();\n}\n\
+                      const y = 2 ** n;\nf(a, b,);";
+        let result = transform_internal(source, "a.js", &options);
+        assert_eq!(
+            result.code,
+            "import * as __fuse_lower from \"fuse_helpers_lower\";\n\
+             function load(url) { return __fuse_lower.awaiter(this, void 0, void 0, function* () {\n  \
+             const res = yield fetch(url);\n  return res.json();\n}); }\nconst y = Math.pow(2, n);\nf(a, b);"
+        );
+
+        // `super` is read through functions declared outside the generator
+        let source = "class A extends B {\n  async m() { return super.m(await x); }\n}";
+        let result = transform_internal(source, "a.js", &options);
+        assert_eq!(
+            result.code,
+            "import * as __fuse_lower from \"fuse_helpers_lower\";\nclass A extends B {\n  \
+             m() { const _super = Object.create(null, { m: { get: () => super.m } }); \
+             return __fuse_lower.awaiter(this, void 0, void 0, function* () \
+             { return _super.m.call(this, yield x); }); }\n}"
+        );
+        let result = transform_internal("class A extends B { async m() { super.x = 1; } }", "a.js", &options);
+        assert_eq!(result.diagnostics.len(), 1);
+        assert_eq!(result.diagnostics[0].code, "unsupported-syntax");
+
+        // Async generators and `for await` are ES2018
+        options.set_target("es2017");
+        let source = "async function* g(xs) {\n  for await (const x of xs) yield x;\n}";
+        let result = transform_internal(source, "a.js", &options);
+        assert_eq!(
+            result.code,
+            "import * as __fuse_lower from \"fuse_helpers_lower\";\n\
+             function g(xs) { return __fuse_lower.asyncGenerator(this, arguments, function* () {\n  \
+             var _a, _b, _c, _d, _e, _f, _g;\n  \
+             try { for (_a = true, _b = __fuse_lower.asyncValues(xs); \
+             _c = yield __fuse_lower.awaitValue(_b.next()), _d = _c.done, !_d; _a = true) \
+             { _e = _c.value; _a = false; const x = _e; yield yield __fuse_lower.awaitValue(x); } } \
+             catch (_h) { _f = { error: _h }; } \
+             finally { try { if (!_a && !_d && (_g = _b.return)) yield __fuse_lower.awaitValue(_g.call(_b)); } \
+             finally { if (_f) throw _f.error; } }\n}); }"
+        );
+        let result = transform_internal("async function f(xs) { for await (const x of xs); }", "a.js", &options);
+        assert_eq!(
+            result.code,
+            "import * as __fuse_lower from \"fuse_helpers_lower\";\n\
+             async function f(xs) { var _a, _b, _c, _d, _e, _f, _g; \
+             try { for (_a = true, _b = __fuse_lower.asyncValues(xs); \
+             _c = await _b.next(), _d = _c.done, !_d; _a = true) \
+             { _e = _c.value; _a = false; const x = _e; ; } } catch (_h) { _f = { error: _h }; } \
+             finally { try { if (!_a && !_d && (_g = _b.return)) await _g.call(_b); } \
+             finally { if (_f) throw _f.error; } } }"
+        );
+        options.set_target("es2018");
+        let source = "async function* g(xs) {\n  for await (const x of xs) yield x;\n}";
+        assert_eq!(transform_internal(source, "a.js", &options).code, source);
+
+        // BigInts and ES2018 regular expressions cannot be lowered
+        options.set_target("es2015");
+        let source = "const a = 10n;\nconst b = { 1n: 0 };\nconst c = /(?<y>\\d+)/;\nconst d = /a.b/s;\n\
+                      const e = /(?<=\\$)\\d/;\nconst f = /\\p{L}/u;\nconst g = /[(?<]\\(?<x/;";
+        let result = transform_internal(source, "a.js", &options);
+        let lines: Vec<_> = result.diagnostics.iter().map(|d| (d.code.as_str(), d.line)).collect();
+        assert_eq!(lines, (1..=6).map(|line| ("unsupported-syntax", line)).collect::<Vec<_>>());
+        options.set_target("es2018");
+        let result = transform_internal(source, "a.js", &options);
+        assert_eq!(result.diagnostics.len(), 2);
+    }
+
+    #[test]
+    fn test_lower_class_features() {
+        let mut options = TransformOptions::default();
+        options.set_target("es2021");
+        let source = "class A {\n  #n = 1;\n  static { init(); }\n  get n() { return this.#n; }\n}";
+        let result = transform_internal(source, "a.js", &options);
+        assert_eq!(
+            result.code,
+            "import * as __fuse_lower from \"fuse_helpers_lower\";\nvar _A_n;\nclass A {\n  constructor() {\n    \
+             _A_n.set(this, 1);\n  }\n  \n  \n  \
+             get n() { return __fuse_lower.classPrivateFieldGet(this, _A_n, \"f\"); }\n}\n\
+             _A_n = new WeakMap();\n(() => { init(); })();"
+        );
+
+        // Private members assigned by destructuring go through setter objects
+        let source = "class A {\n  #p;\n  m(o, xs) {\n    [this.#p] = o;\n    for (o.#p of xs);\n  }\n}";
+        let result = transform_internal(source, "a.js", &options);
+        assert_eq!(
+            result.code,
+            "import * as __fuse_lower from \"fuse_helpers_lower\";\nvar _A_p;\nclass A {\n  constructor() {\n    \
+             _A_p.set(this, void 0);\n  }\n  \n  m(o, xs) {\n    var _a;\n    \
+             (_a = this, [({ set value(_b) { __fuse_lower.classPrivateFieldSet(_a, _A_p, _b, \"f\"); } }).value] \
+             = o);\n    \
+             for (const _c of xs) \
+             { ({ set value(_d) { __fuse_lower.classPrivateFieldSet(o, _A_p, _d, \"f\"); } }).value = _c; ; }\n  }\n}\n\
+             _A_p = new WeakMap();"
+        );
+
+        // ES2022 keeps class fields, static blocks and private names
+        options.set_target("es2022");
+        let result = transform_internal(source, "a.js", &options);
+        assert_eq!(result.code, source);
+
+        // Auto-accessors get private storage, which is a `WeakMap` below ES2022
+        let source = "class K {\n  accessor z = 1;\n}";
+        let result = transform_internal(source, "k.js", &options);
+        assert_eq!(
+            result.code,
+            "class K {\n  #z_accessor_storage = 1;\n  get z() { return this.#z_accessor_storage; }\n  \
+             set z(value) { this.#z_accessor_storage = value; }\n}"
+        );
+        // Each computed key has its own storage
+        let result = transform_internal("class K {\n  accessor [a] = 1;\n  static accessor [b] = 2;\n}", "k.js", &options);
+        assert_eq!(
+            result.code,
+            "var _a, _b;\nclass K {\n  #_a_accessor_storage = 1;\n  get [_a = a]() { return this.#_a_accessor_storage; }\n  \
+             set [_a](value) { this.#_a_accessor_storage = value; }\n  static #_b_accessor_storage = 2;\n  \
+             static get [_b = b]() { return this.#_b_accessor_storage; }\n  \
+             static set [_b](value) { this.#_b_accessor_storage = value; }\n}"
+        );
+        options.set_target("es2021");
+        let result = transform_internal(source, "k.js", &options);
+        assert_eq!(
+            result.code,
+            "import * as __fuse_lower from \"fuse_helpers_lower\";\nvar _K_z_accessor_storage;\nclass K {\n  \
+             constructor() {\n    _K_z_accessor_storage.set(this, 1);\n  }\n  \
+             get z() { return __fuse_lower.classPrivateFieldGet(this, _K_z_accessor_storage, \"f\"); }\n  \
+             set z(value) { __fuse_lower.classPrivateFieldSet(this, _K_z_accessor_storage, value, \"f\"); }\n}\n\
+             _K_z_accessor_storage = new WeakMap();"
+        );
+
+        // 2023-05 decorators are not lowered below ES2022
+        options.set_decorators("2023-05");
+        let result = transform_internal("@sealed class A {}", "a.js", &options);
+        assert_eq!(result.diagnostics.len(), 1);
+        assert_eq!(result.diagnostics[0].code, "unsupported-decorator");
+    }
 }
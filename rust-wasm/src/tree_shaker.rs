@@ -7,6 +7,11 @@ use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
 use wasm_bindgen::prelude::*;
 
+use crate::ast::*;
+use crate::js_parser;
+use crate::parser::{self, ImportKind};
+use crate::utils::{apply_edits, Edit};
+
 /// Configuration for tree-shaking
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[wasm_bindgen]
@@ -245,90 +250,42 @@ pub struct ImportInfo {
 
 impl TreeShaker {
     fn analyze_module_internal(&self, code: &str, module_id: &str) -> ModuleAnalysis {
-        let mut exports = Vec::new();
-        let mut imports = Vec::new();
-        let mut has_side_effects = false;
-
-        // Simple regex-based analysis for common patterns
-        // In production, this would use SWC's full AST parsing
-        
-        // Find exports
-        let export_regex = regex::Regex::new(r"export\s+(?:const|let|var|function|class|default)\s+(\w+)").unwrap();
-        for cap in export_regex.captures_iter(code) {
-            if let Some(name) = cap.get(1) {
-                exports.push(name.as_str().to_string());
-            }
-        }
-
-        // Find named exports
-        let named_export_regex = regex::Regex::new(r"export\s*\{\s*([^}]+)\s*\}").unwrap();
-        for cap in named_export_regex.captures_iter(code) {
-            if let Some(names) = cap.get(1) {
-                for name in names.as_str().split(',') {
-                    let name = name.trim().split(" as ").next().unwrap_or("").trim();
-                    if !name.is_empty() {
-                        exports.push(name.to_string());
-                    }
-                }
-            }
-        }
-
-        // Find imports
-        let import_regex = regex::Regex::new(r#"import\s+(?:\{([^}]+)\}|(\w+))\s+from\s+['"]([^'"]+)['"]"#).unwrap();
-        for cap in import_regex.captures_iter(code) {
-            let source = cap.get(3).map(|m| m.as_str().to_string()).unwrap_or_default();
-            let mut specifiers = Vec::new();
-
-            if let Some(named) = cap.get(1) {
-                for spec in named.as_str().split(',') {
-                    let spec = spec.trim().split(" as ").next().unwrap_or("").trim();
-                    if !spec.is_empty() {
-                        specifiers.push(spec.to_string());
+        let (program, _) = js_parser::parse(code, None);
+        let exports = parser::collect_exports(&program, code)
+            .into_iter()
+            .filter(|export| !export.is_type_only)
+            .map(|export| export.exported)
+            .collect();
+
+        let imports = parser::collect_imports(&program, code)
+            .into_iter()
+            .filter(|import| !import.is_type_only && !import.source.is_empty())
+            .filter(|import| self.config.analyze_dynamic_imports || import.kind != ImportKind::Dynamic)
+            .map(|import| {
+                let specifiers = match import.kind {
+                    ImportKind::Import | ImportKind::ReExport => import
+                        .specifiers
+                        .iter()
+                        .filter(|spec| !spec.is_type_only)
+                        .map(|spec| spec.imported.clone())
+                        .collect(),
+                    // `export *` and `export * as ns` both need every export;
+                    // dynamic imports and `require` can reach any of them
+                    ImportKind::ExportAll | ImportKind::Dynamic | ImportKind::Require | ImportKind::ImportEquals => {
+                        vec!["*".to_string()]
                     }
+                };
+                ImportInfo {
+                    source: import.source,
+                    specifiers,
+                    is_dynamic: import.is_dynamic,
                 }
-            }
-            if let Some(default) = cap.get(2) {
-                specifiers.push(default.as_str().to_string());
-            }
-
-            imports.push(ImportInfo {
-                source,
-                specifiers,
-                is_dynamic: false,
-            });
-        }
+            })
+            .collect();
 
-        // Find dynamic imports
-        if self.config.analyze_dynamic_imports {
-            let dynamic_import_regex = regex::Regex::new(r#"import\s*\(\s*['"]([^'"]+)['"]\s*\)"#).unwrap();
-            for cap in dynamic_import_regex.captures_iter(code) {
-                if let Some(source) = cap.get(1) {
-                    imports.push(ImportInfo {
-                        source: source.as_str().to_string(),
-                        specifiers: vec!["*".to_string()],
-                        is_dynamic: true,
-                    });
-                }
-            }
-        }
-
-        // Check for side effects (top-level function calls, assignments to globals, etc.)
-        if self.config.preserve_side_effects {
-            let side_effect_patterns = [
-                r"^\s*\w+\s*\(",           // Top-level function calls
-                r"window\.",               // Window assignments
-                r"document\.",             // Document manipulation
-                r"globalThis\.",           // Global assignments
-                r"^\s*if\s*\(",            // Top-level conditionals
-            ];
-            
-            for pattern in &side_effect_patterns {
-                if regex::Regex::new(pattern).map(|r| r.is_match(code)).unwrap_or(false) {
-                    has_side_effects = true;
-                    break;
-                }
-            }
-        }
+        // Check for side effects (top-level calls, assignments, control flow, ...)
+        let has_side_effects = self.config.preserve_side_effects
+            && program.body.iter().any(|stmt| !is_pure_stmt(stmt));
 
         ModuleAnalysis {
             module_id: module_id.to_string(),
@@ -345,58 +302,43 @@ impl TreeShaker {
         _generate_source_map: bool,
     ) -> TreeShakeResult {
         let original_size = code.len();
-        let mut result_code = code.to_string();
         let mut removed_exports = Vec::new();
-        let mut removed_imports = Vec::new();
+        let removed_imports = Vec::new();
         let mut dead_blocks_removed = 0;
-
-        // Remove unused exports
-        let export_regex = regex::Regex::new(
-            r"export\s+(?:const|let|var)\s+(\w+)\s*=\s*[^;]+;"
-        ).unwrap();
-        
-        result_code = export_regex.replace_all(&result_code, |caps: &regex::Captures| {
-            let name = caps.get(1).map(|m| m.as_str()).unwrap_or("");
-            if !used_exports.contains(name) && !used_exports.contains("*") {
-                removed_exports.push(name.to_string());
-                dead_blocks_removed += 1;
-                String::new()
-            } else {
-                caps.get(0).map(|m| m.as_str().to_string()).unwrap_or_default()
+        let mut edits = Vec::new();
+
+        let (program, _) = js_parser::parse(code, None);
+        let keep_all = used_exports.contains("*") || !program.errors.is_empty();
+        let mut references = None;
+
+        for (index, stmt) in program.body.iter().enumerate() {
+            let (span, names, decl) = match stmt {
+                Stmt::ExportDecl(d) if !d.decl.is_type_only() => (d.span, declared_names(&d.decl), &*d.decl),
+                _ => continue,
+            };
+            if keep_all || names.is_empty() || names.iter().any(|name| used_exports.contains(name)) {
+                continue;
             }
-        }).to_string();
-
-        // Remove unused function exports
-        let fn_export_regex = regex::Regex::new(
-            r"export\s+function\s+(\w+)\s*\([^)]*\)\s*\{[^}]*\}"
-        ).unwrap();
-        
-        result_code = fn_export_regex.replace_all(&result_code, |caps: &regex::Captures| {
-            let name = caps.get(1).map(|m| m.as_str()).unwrap_or("");
-            if !used_exports.contains(name) && !used_exports.contains("*") {
-                removed_exports.push(name.to_string());
-                dead_blocks_removed += 1;
-                String::new()
+            removed_exports.extend(names.iter().cloned());
+
+            // Still used inside the module: keep the declaration, drop the export
+            let references = references.get_or_insert_with(|| statement_references(&program));
+            let referenced = references
+                .iter()
+                .enumerate()
+                .any(|(i, refs)| i != index && names.iter().any(|name| refs.contains(name)));
+            let export_keyword = Span::new(span.start, decl.span().start);
+            if referenced
+                || (self.config.preserve_side_effects && !is_pure_stmt(decl))
+            {
+                edits.push(Edit::delete(export_keyword.start, export_keyword.end));
             } else {
-                caps.get(0).map(|m| m.as_str().to_string()).unwrap_or_default()
-            }
-        }).to_string();
-
-        // Remove unused class exports
-        let class_export_regex = regex::Regex::new(
-            r"export\s+class\s+(\w+)\s*(?:extends\s+\w+\s*)?\{[^}]*\}"
-        ).unwrap();
-        
-        result_code = class_export_regex.replace_all(&result_code, |caps: &regex::Captures| {
-            let name = caps.get(1).map(|m| m.as_str()).unwrap_or("");
-            if !used_exports.contains(name) && !used_exports.contains("*") {
-                removed_exports.push(name.to_string());
+                edits.push(Edit::delete(span.start, span.end));
                 dead_blocks_removed += 1;
-                String::new()
-            } else {
-                caps.get(0).map(|m| m.as_str().to_string()).unwrap_or_default()
             }
-        }).to_string();
+        }
+
+        let mut result_code = apply_edits(code, 0, code.len(), edits);
 
         // Clean up empty lines
         let empty_lines_regex = regex::Regex::new(r"\n\s*\n\s*\n").unwrap();
@@ -430,6 +372,164 @@ impl TreeShaker {
     }
 }
 
+/// Names a declaration binds
+fn declared_names(decl: &Stmt) -> Vec<String> {
+    match decl {
+        Stmt::Var(d) => d
+            .decls
+            .iter()
+            .flat_map(|d| d.name.bound_names())
+            .map(|id| id.name.clone())
+            .collect(),
+        Stmt::Function(Function { id: Some(id), .. }) => vec![id.name.clone()],
+        Stmt::Class(Class { id: Some(id), .. }) => vec![id.name.clone()],
+        Stmt::TsEnum(e) => vec![e.id.name.clone()],
+        Stmt::TsModule(TsModuleDecl { name: TsModuleName::Path(path), .. }) => {
+            path.first().map(|id| vec![id.name.clone()]).unwrap_or_default()
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// Identifiers each top-level statement references
+fn statement_references(program: &Program) -> Vec<HashSet<String>> {
+    struct References(HashSet<String>);
+
+    impl Visit for References {
+        fn visit_stmt(&mut self, stmt: &Stmt) {
+            if let Stmt::ExportNamed(ExportNamed { source: None, specifiers, .. }) = stmt {
+                self.0.extend(specifiers.iter().map(|s| s.local.name()));
+            }
+            if let Stmt::ExportDefault(ExportDefault { decl: DefaultDecl::Expr(Expr::Ident(id)), .. }) = stmt {
+                self.0.insert(id.name.clone());
+            }
+            walk_stmt(self, stmt);
+        }
+
+        fn visit_expr(&mut self, expr: &Expr) {
+            match expr {
+                Expr::Ident(id) => {
+                    self.0.insert(id.name.clone());
+                }
+                Expr::Object(o) => {
+                    for prop in &o.props {
+                        if let Prop::Shorthand(id) = prop {
+                            self.0.insert(id.name.clone());
+                        }
+                    }
+                }
+                _ => {}
+            }
+            walk_expr(self, expr);
+        }
+
+        fn visit_pat(&mut self, pat: &Pat) {
+            // Targets of plain assignments (`x = 1`) are references too
+            if let Pat::Ident(p) = pat {
+                self.0.insert(p.id.name.clone());
+            }
+            walk_pat(self, pat);
+        }
+
+        fn visit_jsx_element(&mut self, element: &JsxElement) {
+            let mut name = &element.opening.name;
+            while let JsxName::Member { object, .. } = name {
+                name = object;
+            }
+            if let JsxName::Ident(id) = name {
+                self.0.insert(id.name.clone());
+            }
+            walk_jsx_element(self, element);
+        }
+    }
+
+    program
+        .body
+        .iter()
+        .map(|stmt| {
+            let mut references = References(HashSet::new());
+            references.visit_stmt(stmt);
+            references.0
+        })
+        .collect()
+}
+
+/// Can the statement be dropped without changing behavior?
+fn is_pure_stmt(stmt: &Stmt) -> bool {
+    match stmt {
+        Stmt::Empty(_)
+        | Stmt::Function(_)
+        | Stmt::Import(_)
+        | Stmt::ExportNamed(_)
+        | Stmt::ExportAll(_) => true,
+        Stmt::Var(d) => d.decls.iter().all(|d| d.init.as_ref().map_or(true, is_pure_expr)),
+        Stmt::Class(c) => is_pure_class(c),
+        Stmt::ExportDecl(d) => is_pure_stmt(&d.decl),
+        Stmt::ExportDefault(d) => match &d.decl {
+            DefaultDecl::Function(_) | DefaultDecl::TsInterface(_) => true,
+            DefaultDecl::Class(c) => is_pure_class(c),
+            DefaultDecl::Expr(e) => is_pure_expr(e),
+        },
+        // Directives and other bare literals
+        Stmt::Expr(s) => matches!(s.expr, Expr::Lit(_)),
+        _ => stmt.is_type_only(),
+    }
+}
+
+fn is_pure_class(class: &Class) -> bool {
+    class.decorators.is_empty()
+        && class.super_class.as_deref().map_or(true, is_pure_expr)
+        && class.body.iter().all(|member| match member {
+            ClassMember::Method(m) => m.decorators.is_empty() && is_pure_key(&m.key),
+            ClassMember::Property(p) => {
+                p.decorators.is_empty()
+                    && is_pure_key(&p.key)
+                    && (!p.is_static || p.value.as_ref().map_or(true, is_pure_expr))
+            }
+            ClassMember::StaticBlock(_) => false,
+            ClassMember::IndexSignature(_) | ClassMember::Empty(_) => true,
+        })
+}
+
+fn is_pure_key(key: &PropKey) -> bool {
+    match key {
+        PropKey::Computed(c) => is_pure_expr(&c.expr),
+        _ => true,
+    }
+}
+
+/// Can evaluating the expression have side effects? Calls, property reads
+/// (getters) and assignments are assumed to.
+fn is_pure_expr(expr: &Expr) -> bool {
+    match expr {
+        Expr::Ident(_)
+        | Expr::This(_)
+        | Expr::Lit(_)
+        | Expr::MetaProp(_)
+        | Expr::Function(_)
+        | Expr::Arrow(_) => true,
+        Expr::Template(t) => t.exprs.iter().all(is_pure_expr),
+        Expr::Array(a) => a.elems.iter().flatten().all(|e| e.spread.is_none() && is_pure_expr(&e.expr)),
+        Expr::Object(o) => o.props.iter().all(|prop| match prop {
+            Prop::Shorthand(_) => true,
+            Prop::KeyValue(p) => is_pure_key(&p.key) && is_pure_expr(&p.value),
+            Prop::Method(p) => is_pure_key(&p.key),
+            Prop::Assign(_) | Prop::Spread(_) => false,
+        }),
+        Expr::Class(c) => is_pure_class(c),
+        Expr::Unary(e) => e.op != UnaryOp::Delete && is_pure_expr(&e.arg),
+        Expr::Binary(e) => is_pure_expr(&e.left) && is_pure_expr(&e.right),
+        Expr::Cond(e) => is_pure_expr(&e.test) && is_pure_expr(&e.cons) && is_pure_expr(&e.alt),
+        Expr::Seq(e) => e.exprs.iter().all(is_pure_expr),
+        Expr::Paren(e) => is_pure_expr(&e.expr),
+        Expr::TsAs(e) | Expr::TsSatisfies(e) => is_pure_expr(&e.expr),
+        Expr::TsNonNull(e) => is_pure_expr(&e.expr),
+        Expr::TsTypeAssertion(e) => is_pure_expr(&e.expr),
+        Expr::TsInstantiation(e) => is_pure_expr(&e.expr),
+        _ => false,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -463,4 +563,36 @@ export const unused = 2;"#;
         assert!(result.code.contains("used"));
         assert!(!result.code.contains("unused"));
     }
+
+    #[test]
+    fn test_analyze_ignores_strings_and_comments() {
+        let shaker = TreeShaker::new(None);
+        let code = r#"
+            // import { fake } from './commented';
+            const s = "export const inString = 1";
+            import React, { useState as useS } from 'react';
+            export { a as b } from './re';
+            export default function App() {}
+        "#;
+
+        let analysis = shaker.analyze_module_internal(code, "test");
+        assert_eq!(analysis.exports, vec!["b".to_string(), "default".to_string()]);
+        assert_eq!(analysis.imports.len(), 2);
+        assert_eq!(analysis.imports[0].specifiers, vec!["default", "useState"]);
+        assert_eq!(analysis.imports[1].specifiers, vec!["a"]);
+        assert!(!analysis.has_side_effects);
+    }
+
+    #[test]
+    fn test_shake_keeps_locally_used_and_impure() {
+        let shaker = TreeShaker::new(None);
+        let code = "export function helper() { return 1; }\nexport const value = helper();\nexport class Unused { m() { return '}'; } }\n";
+
+        let result = shaker.shake_module_internal(code, &HashSet::new(), false);
+        assert!(result.code.contains("function helper()"));
+        assert!(!result.code.contains("export function helper"));
+        assert!(result.code.contains("const value = helper();"));
+        assert!(!result.code.contains("Unused"));
+        assert_eq!(result.stats.dead_blocks_removed, 1);
+    }
 }
@@ -5,3 +5,167 @@ pub fn set_panic_hook() {
     #[cfg(feature = "console_error_panic_hook")]
     console_error_panic_hook::set_once();
 }
+
+/// Current time in milliseconds (works both in WASM and natively)
+pub fn now_ms() -> f64 {
+    #[cfg(target_arch = "wasm32")]
+    {
+        js_sys::Date::now()
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs_f64() * 1000.0)
+            .unwrap_or(0.0)
+    }
+}
+
+/// Maps byte offsets to 0-based line and UTF-16 column positions
+pub struct LineIndex {
+    line_starts: Vec<usize>,
+    /// End of each non-ASCII character, with the bytes it and those before it
+    /// take beyond their UTF-16 length, so columns don't rescan the line
+    wide: Vec<(usize, usize)>,
+}
+
+impl LineIndex {
+    pub fn new(source: &str) -> Self {
+        let mut line_starts = vec![0];
+        let mut wide = Vec::new();
+        let mut excess = 0;
+        let bytes = source.as_bytes();
+        let mut i = 0;
+        while i < bytes.len() {
+            let (len, units) = match bytes[i] {
+                0..=0x7F => (1, 1),
+                0xF0..=0xFF => (4, 2),
+                0xE0..=0xEF => (3, 1),
+                _ => (2, 1),
+            };
+            if len > 1 {
+                excess += len - units;
+                wide.push((i + len, excess));
+            }
+            match bytes[i] {
+                b'\n' => line_starts.push(i + 1),
+                b'\r' if bytes.get(i + 1) != Some(&b'\n') => line_starts.push(i + 1),
+                // U+2028 / U+2029
+                0xE2 if bytes.get(i + 1) == Some(&0x80) && matches!(bytes.get(i + 2), Some(0xA8) | Some(0xA9)) => {
+                    line_starts.push(i + 3);
+                }
+                _ => {}
+            }
+            i += len;
+        }
+        Self { line_starts, wide }
+    }
+
+    /// 0-based (line, UTF-16 column) of a byte offset
+    pub fn position(&self, source: &str, offset: usize) -> (usize, usize) {
+        let offset = offset.min(source.len());
+        let line = match self.line_starts.binary_search(&offset) {
+            Ok(line) => line,
+            Err(next) => next - 1,
+        };
+        let line_start = self.line_starts[line];
+        let column = offset - line_start - (self.excess(offset) - self.excess(line_start));
+        (line, column)
+    }
+
+    /// Bytes beyond their UTF-16 length taken by the characters before `offset`
+    fn excess(&self, offset: usize) -> usize {
+        match self.wide.partition_point(|&(end, _)| end <= offset) {
+            0 => 0,
+            n => self.wide[n - 1].1,
+        }
+    }
+
+    pub fn line_count(&self) -> usize {
+        self.line_starts.len()
+    }
+
+    /// Byte range of a 0-based line, without its terminator
+    pub fn line_range(&self, source: &str, line: usize) -> std::ops::Range<usize> {
+        let start = self.line_starts[line];
+        let end = self.line_starts.get(line + 1).copied().unwrap_or(source.len());
+        let text = source[start..end].trim_end_matches(['\n', '\r', '\u{2028}', '\u{2029}']);
+        start..start + text.len()
+    }
+}
+
+/// Byte offset of a UTF-16 offset (a JavaScript string index); `None` past
+/// the end or between the halves of a surrogate pair
+pub fn byte_offset(source: &str, utf16: usize) -> Option<usize> {
+    let mut units = 0;
+    for (i, c) in source.char_indices() {
+        if units >= utf16 {
+            return (units == utf16).then_some(i);
+        }
+        units += c.len_utf16();
+    }
+    (units == utf16).then_some(source.len())
+}
+
+/// Replacement of the source text between two byte offsets
+#[derive(Debug, Clone)]
+pub struct Edit {
+    pub start: usize,
+    pub end: usize,
+    pub text: String,
+}
+
+impl Edit {
+    pub fn new(start: usize, end: usize, text: impl Into<String>) -> Self {
+        Self { start, end, text: text.into() }
+    }
+
+    pub fn delete(start: usize, end: usize) -> Self {
+        Self::new(start, end, "")
+    }
+}
+
+/// Apply edits to `source[start..end]`. Edits are applied in position order;
+/// one that overlaps an earlier edit is dropped.
+pub fn apply_edits(source: &str, start: usize, end: usize, mut edits: Vec<Edit>) -> String {
+    edits.sort_by_key(|e| (e.start, e.end));
+    let mut out = String::with_capacity(end - start);
+    let mut pos = start;
+    for edit in edits {
+        if edit.start < pos || edit.end > end {
+            continue;
+        }
+        out.push_str(&source[pos..edit.start]);
+        out.push_str(&edit.text);
+        pos = edit.end;
+    }
+    out.push_str(&source[pos..end]);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line_index_utf16_columns() {
+        let source = "a = \"é😀\";\u{2028}b = '中'; c\r\nd";
+        let index = LineIndex::new(source);
+        assert_eq!(index.line_count(), 3);
+        assert_eq!(index.position(source, source.find(';').unwrap()), (0, 9));
+        assert_eq!(index.position(source, source.find('b').unwrap()), (1, 0));
+        assert_eq!(index.position(source, source.find('c').unwrap()), (1, 9));
+        assert_eq!(index.position(source, source.len()), (2, 1));
+    }
+
+    #[test]
+    fn test_byte_offset() {
+        let source = "é😀x";
+        assert_eq!(byte_offset(source, 0), Some(0));
+        assert_eq!(byte_offset(source, 1), Some(2));
+        assert_eq!(byte_offset(source, 2), None);
+        assert_eq!(byte_offset(source, 3), Some(6));
+        assert_eq!(byte_offset(source, 4), Some(7));
+        assert_eq!(byte_offset(source, 5), None);
+    }
+}
@@ -0,0 +1,40 @@
+//! TypeScript erasure conformance corpus
+//!
+//! Every `conformance/<name>.ts` or `.tsx` file is transformed with the
+//! default options and must produce `conformance/<name>.js`, which in turn
+//! must parse as plain JavaScript.
+
+use std::fs;
+use std::path::Path;
+
+use kona_wasm::js_parser::{parse_program, Syntax};
+use kona_wasm::transformer::{transform_internal, TransformOptions};
+
+#[test]
+fn conformance() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/conformance");
+    let mut inputs: Vec<_> = fs::read_dir(&dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| matches!(path.extension().and_then(|e| e.to_str()), Some("ts" | "tsx")))
+        .collect();
+    inputs.sort();
+    assert!(!inputs.is_empty());
+
+    let mut failures = Vec::new();
+    for input in &inputs {
+        let name = input.file_name().unwrap().to_string_lossy().to_string();
+        let source = fs::read_to_string(input).unwrap();
+        let expected = fs::read_to_string(input.with_extension("js")).unwrap_or_default();
+        let result = transform_internal(&source, &name, &TransformOptions::default());
+        if result.code != expected {
+            failures.push(format!("{}: expected\n{}\nfound\n{}", name, expected, result.code));
+            continue;
+        }
+        let program = parse_program(&result.code, Syntax { typescript: false, jsx: false });
+        if let Some(error) = program.errors.first() {
+            failures.push(format!("{}: output does not parse: {}", name, error.message));
+        }
+    }
+    assert!(failures.is_empty(), "{}", failures.join("\n\n"));
+}
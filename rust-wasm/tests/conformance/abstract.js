@@ -0,0 +1,24 @@
+export class Shape {
+  
+  
+  
+
+  describe() {
+    return `${this.name}: ${this.area()}`;
+  }
+}
+
+class Square extends Shape {
+  
+  constructor(side) {
+    super();
+    this.side = side;
+    this.name = "square";
+  }
+  area() {
+    return this.side ** 2;
+  }
+  get sides() {
+    return 4;
+  }
+}
@@ -0,0 +1,22 @@
+export abstract class Shape {
+  abstract area(): number;
+  protected abstract readonly name: string;
+  abstract get sides(): number;
+
+  describe() {
+    return `${this.name}: ${this.area()}`;
+  }
+}
+
+class Square extends Shape {
+  protected readonly name = "square";
+  constructor(private side: number) {
+    super();
+  }
+  area() {
+    return this.side ** 2;
+  }
+  get sides() {
+    return 4;
+  }
+}
@@ -0,0 +1,6 @@
+const tuple = [1, 2];
+const nested = { a: { b: "c" } };
+const el = document.getElementById("root");
+const len = (input).length;
+const n = (value) + 1;
+let count = maybe.count;
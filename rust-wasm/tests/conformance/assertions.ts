@@ -0,0 +1,6 @@
+const tuple = [1, 2] as const;
+const nested = { a: { b: "c" } } as const;
+const el = document.getElementById("root")!;
+const len = (<string>input).length;
+const n = (value as unknown as number) + 1;
+let count = maybe!.count!;
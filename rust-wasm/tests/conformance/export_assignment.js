@@ -0,0 +1,5 @@
+class Server {
+  listen(port) {}
+}
+
+module.exports = Server;
@@ -0,0 +1,5 @@
+class Server {
+  listen(port: number): void {}
+}
+
+export = Server;
@@ -0,0 +1,5 @@
+const identity = <T,>(value: T): T => value;
+const pair = <K extends string, V>(key: K, value: V) => ({ key, value });
+const load = async <T,>(url: string): Promise<T> => (await fetch(url)).json();
+
+export const List = <T,>({ items }: { items: T[] }) => <ul>{items.map(identity)}</ul>;
@@ -0,0 +1,7 @@
+const fs = require("fs");
+
+export var path = require("path");
+var Alias = Outer.Inner;
+export var Shared = Outer.Shared;
+
+fs.readFileSync(path.join("a", "b"));
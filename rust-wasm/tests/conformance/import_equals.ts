@@ -0,0 +1,7 @@
+import fs = require("fs");
+import type Types = require("./types");
+export import path = require("path");
+import Alias = Outer.Inner;
+export import Shared = Outer.Shared;
+
+fs.readFileSync(path.join("a", "b"));
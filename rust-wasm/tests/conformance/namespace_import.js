@@ -0,0 +1,8 @@
+var Paths;
+(function (Paths) {
+  Paths.join = Node.path.join;
+  function resolve(a) {
+    return Paths.join(a, ".");
+  }
+  Paths.resolve = resolve;
+})(Paths || (Paths = {}));
@@ -0,0 +1,6 @@
+namespace Paths {
+  export import join = Node.path.join;
+  export function resolve(a: string): string {
+    return join(a, ".");
+  }
+}
@@ -0,0 +1,30 @@
+
+
+function pick(value) {
+  return value;
+}
+
+
+
+export function format(date, pattern) {
+  return pattern ?? date.toISOString();
+}
+
+
+
+
+export default function handler(req) {
+  return req;
+}
+
+class Parser {
+  
+  
+  constructor(source, options) {}
+
+  
+  
+  parse(input) {
+    return input;
+  }
+}
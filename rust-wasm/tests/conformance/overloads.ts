@@ -0,0 +1,30 @@
+function pick(value: string): string;
+function pick(value: number): number;
+function pick(value: any) {
+  return value;
+}
+
+export function format(date: Date): string;
+export function format(date: Date, pattern: string): string;
+export function format(date: Date, pattern?: string) {
+  return pattern ?? date.toISOString();
+}
+
+declare function external(): void;
+
+export default function handler(req: Request): Response;
+export default function handler(req: any) {
+  return req;
+}
+
+class Parser {
+  constructor(source: string);
+  constructor(source: string, options: Options);
+  constructor(source: string, options?: Options) {}
+
+  parse(input: string): Node;
+  parse(input: Buffer): Node;
+  parse(input: any) {
+    return input;
+  }
+}
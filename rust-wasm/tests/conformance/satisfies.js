@@ -0,0 +1,6 @@
+const config = { port: 8080, host: "localhost" };
+const palette = {
+  red: [255, 0, 0],
+};
+export default { name: "app" };
+call(value, other);
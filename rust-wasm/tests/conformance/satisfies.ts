@@ -0,0 +1,6 @@
+const config = { port: 8080, host: "localhost" } satisfies Config;
+const palette = {
+  red: [255, 0, 0],
+} satisfies Record<string, Color>;
+export default { name: "app" } satisfies Options;
+call(value satisfies Value, other);
@@ -0,0 +1,8 @@
+const items = useState([]);
+const map = new Map();
+const ref = useRef(null);
+const parsed = JSON.parse(text);
+const tagged = sql`select * from t`;
+const make = create;
+const compare = a < b && c > d;
+const shifted = x << 2 >> 1;
@@ -0,0 +1,8 @@
+const items = useState<string[]>([]);
+const map = new Map<string, Array<number>>();
+const ref = useRef<HTMLDivElement | null>(null);
+const parsed = JSON.parse<Result>(text);
+const tagged = sql<Row>`select * from t`;
+const make = create<A, B>;
+const compare = a < b && c > d;
+const shifted = x << 2 >> 1;
@@ -9,7 +9,7 @@ import { IRunProps } from './IRunProps';
 import { IStyleSheetProps } from './IStylesheetProps';
 import { INodeModuleLookup } from '../resolver/nodeModuleLookup';
 
-const ESSENTIAL_DEPENDENCIES = ['fuse_helpers_decorate'];
+const ESSENTIAL_DEPENDENCIES = ['fuse_helpers_decorate', 'fuse_helpers_lower'];
 
 export function Configuration(ctx: Context): IConfig {
   const self: IConfig = {
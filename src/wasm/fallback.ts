@@ -224,6 +224,10 @@ export class MinifierFallback {
               drop_console: this.config.dropConsole,
               drop_debugger: this.config.dropDebugger,
               passes: this.config.passes,
+              // `@` makes Terser parse the value as an expression
+              global_defs: Object.fromEntries(
+                Object.entries(this.config.define ?? {}).map(([key, value]) => [`@${key}`, value])
+              ),
             }
           : false,
         mangle: this.config.mangle
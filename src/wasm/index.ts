@@ -36,6 +36,7 @@ export interface MinifyConfigWasm {
   set_drop_console(value: boolean): void;
   set_drop_debugger(value: boolean): void;
   set_passes(value: number): void;
+  add_define(key: string, value: string): void;
 }
 
 export interface TreeShakerWasm {
@@ -124,6 +125,8 @@ export interface MinifyConfig {
   dropConsole?: boolean;
   dropDebugger?: boolean;
   passes?: number;
+  /** Global expressions replaced with source text before compressing */
+  define?: Record<string, string>;
 }
 
 // Singleton WASM module instance
@@ -294,6 +297,9 @@ export class Minifier {
     wasmConfig.set_drop_console(this.config.dropConsole ?? false);
     wasmConfig.set_drop_debugger(this.config.dropDebugger ?? true);
     wasmConfig.set_passes(this.config.passes ?? 2);
+    for (const [key, value] of Object.entries(this.config.define ?? {})) {
+      wasmConfig.add_define(key, value);
+    }
 
     this.wasmMinifier = new wasm.Minifier(wasmConfig);
     return this.wasmMinifier;